@@ -0,0 +1,31 @@
+import argparse
+import os
+
+
+def parse_args(argv):
+    parser = argparse.ArgumentParser(prog="tool")
+    parser.add_argument("path")
+    parser.add_argument("--all", action="store_true")
+    return parser.parse_args(argv[1:])
+
+
+class Report:
+    def __init__(self, path):
+        self.path = path
+        self.lines = []
+
+    def add(self, line):
+        self.lines.append(line)
+
+    def write(self):
+        with open(self.path, "w", encoding="utf-8") as f:
+            f.write("\n".join(self.lines) + "\n")
+
+
+def main(argv):
+    args = parse_args(argv)
+    report = Report(args.path)
+    for name in sorted(os.listdir(".")):
+        if args.all or not name.startswith("."):
+            report.add(name)
+    report.write()
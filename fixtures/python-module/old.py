@@ -0,0 +1,31 @@
+import os
+import sys
+
+
+def parse_args(argv):
+    if len(argv) < 2:
+        print("usage: tool <path>")
+        sys.exit(1)
+    return argv[1]
+
+
+class Report:
+    def __init__(self, path):
+        self.path = path
+        self.lines = []
+
+    def add(self, line):
+        self.lines.append(line)
+
+    def write(self):
+        with open(self.path, "w") as f:
+            for line in self.lines:
+                f.write(line + "\n")
+
+
+def main():
+    path = parse_args(sys.argv)
+    report = Report(path)
+    for name in os.listdir("."):
+        report.add(name)
+    report.write()
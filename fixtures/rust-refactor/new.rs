@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+pub struct Cache {
+    entries: HashMap<String, String>,
+    hits: usize,
+    misses: usize,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Cache { entries: HashMap::new(), hits: 0, misses: 0 }
+    }
+
+    pub fn get(&mut self, key: &str) -> Option<&String> {
+        let value = self.entries.get(key);
+        if value.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        value
+    }
+
+    pub fn load(path: &str) -> io::Result<Cache> {
+        let text = fs::read_to_string(path)?;
+        let mut cache = Cache::new();
+        for line in text.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                cache.entries.insert(key.to_string(), value.to_string());
+            }
+        }
+        Ok(cache)
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        self.hits as f64 / (self.hits + self.misses).max(1) as f64
+    }
+}
@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+use std::fs;
+
+pub struct Cache {
+    entries: HashMap<String, String>,
+    hits: usize,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Cache { entries: HashMap::new(), hits: 0 }
+    }
+
+    pub fn get(&mut self, key: &str) -> Option<&String> {
+        let value = self.entries.get(key);
+        if value.is_some() {
+            self.hits += 1;
+        }
+        value
+    }
+
+    pub fn load(path: &str) -> Cache {
+        let text = fs::read_to_string(path).unwrap();
+        let mut cache = Cache::new();
+        for line in text.lines() {
+            let parts: Vec<&str> = line.split('=').collect();
+            cache.entries.insert(parts[0].to_string(), parts[1].to_string());
+        }
+        cache
+    }
+}
+
+fn helper() -> usize {
+    42
+}
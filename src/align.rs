@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+/// Lines less similar than this are shown as a deletion plus an insertion rather than an edit
+pub const MIN_SIMILARITY: f32 = 0.5;
+
+/// Hunks with more line pairs than this are paired by index instead
+const MAX_PAIRS: usize = 250_000;
+
+/// Rows of a Modified hunk: `(Some(i), Some(j))` aligns original line `i` with modified line
+/// `j`, a `None` side is a line with no counterpart. Rows keep both sides in order, and pairing
+/// maximises total similarity, so an edited line stays next to its original even when lines were
+/// inserted or removed around it.
+pub fn align_lines(original: &[String], modified: &[String]) -> Vec<(Option<usize>, Option<usize>)> {
+    let (m, n) = (original.len(), modified.len());
+    if m * n > MAX_PAIRS {
+        return (0..m.max(n)).map(|i| ((i < m).then_some(i), (i < n).then_some(i))).collect();
+    }
+
+    let bigrams: Vec<_> = original.iter().map(|line| bigram_counts(line)).collect();
+    let modified_bigrams: Vec<_> = modified.iter().map(|line| bigram_counts(line)).collect();
+    let similar = |i: usize, j: usize| {
+        let score = if original[i].trim() == modified[j].trim() {
+            1.0
+        } else {
+            dice(&bigrams[i], &modified_bigrams[j])
+        };
+        (score >= MIN_SIMILARITY).then_some(score)
+    };
+
+    // best[i][j]: highest total similarity aligning the first i original and j modified lines
+    let mut best = vec![vec![0.0f32; n + 1]; m + 1];
+    for i in 1..=m {
+        for j in 1..=n {
+            let paired = similar(i - 1, j - 1).map_or(f32::MIN, |score| best[i - 1][j - 1] + score);
+            best[i][j] = paired.max(best[i - 1][j]).max(best[i][j - 1]);
+        }
+    }
+
+    let mut rows = Vec::new();
+    let (mut i, mut j) = (m, n);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && similar(i - 1, j - 1).is_some_and(|score| best[i][j] == best[i - 1][j - 1] + score) {
+            rows.push((Some(i - 1), Some(j - 1)));
+            i -= 1;
+            j -= 1;
+        } else if j > 0 && (i == 0 || best[i][j] == best[i][j - 1]) {
+            // Walking backwards, so taking insertions first puts deletions above them
+            rows.push((None, Some(j - 1)));
+            j -= 1;
+        } else {
+            rows.push((Some(i - 1), None));
+            i -= 1;
+        }
+    }
+    rows.reverse();
+    rows
+}
+
+/// Similarity of two lines from 0 to 1, ignoring surrounding whitespace
+pub fn line_similarity(a: &str, b: &str) -> f32 {
+    if a.trim() == b.trim() {
+        1.0
+    } else {
+        dice(&bigram_counts(a), &bigram_counts(b))
+    }
+}
+
+fn bigram_counts(line: &str) -> HashMap<(char, char), usize> {
+    let chars: Vec<char> = line.trim().chars().collect();
+    let mut counts = HashMap::new();
+    for pair in chars.windows(2) {
+        *counts.entry((pair[0], pair[1])).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Sørensen–Dice coefficient of two bigram multisets
+fn dice(a: &HashMap<(char, char), usize>, b: &HashMap<(char, char), usize>) -> f32 {
+    let total: usize = a.values().sum::<usize>() + b.values().sum::<usize>();
+    if total == 0 {
+        return 0.0;
+    }
+    let shared: usize = a.iter().map(|(bigram, count)| (*count).min(b.get(bigram).copied().unwrap_or(0))).sum();
+    2.0 * shared as f32 / total as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_align_lines() {
+        let original = lines("foo(a, b);\nreturn x;");
+        let modified = lines("let y = 0;\nlog(\"start\");\nfoo(a, b, c);\nreturn x + y;");
+        assert_eq!(
+            align_lines(&original, &modified),
+            vec![(None, Some(0)), (None, Some(1)), (Some(0), Some(2)), (Some(1), Some(3))]
+        );
+
+        // Unrelated lines are not paired at all
+        assert_eq!(align_lines(&lines("alpha"), &lines("zzz")), vec![(Some(0), None), (None, Some(0))]);
+    }
+}
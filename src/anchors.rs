@@ -0,0 +1,108 @@
+use crate::diff_core::LineChange;
+use crate::patience::{longest_increasing_run, unique_anchors};
+
+/// Shortest run of lines that can split a diff; shorter ones are too likely to be boilerplate
+pub const DEFAULT_MIN_ANCHOR_LINES: usize = 8;
+
+/// Lines `original_start..original_start + len` and `modified_start..modified_start + len` match
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnchorRun {
+    pub original_start: usize,
+    pub modified_start: usize,
+    pub len: usize,
+}
+
+/// Runs of at least `min_lines` matching lines whose content occurs once on each side, in order
+/// on both. Every window of `min_lines` lines is hashed, windows unique to both sides are kept
+/// in their longest order-preserving sequence, and overlapping windows on one diagonal are
+/// joined into runs.
+pub fn find_anchor_runs(
+    original_hashes: &[u64],
+    modified_hashes: &[u64],
+    same: &impl Fn(usize, usize) -> bool,
+    min_lines: usize,
+) -> Vec<AnchorRun> {
+    if min_lines == 0 || original_hashes.len() < min_lines || modified_hashes.len() < min_lines {
+        return Vec::new();
+    }
+    let windows = |hashes: &[u64]| -> Vec<u64> {
+        hashes
+            .windows(min_lines)
+            .map(|window| window.iter().fold(0xcbf2_9ce4_8422_2325u64, |h, line| (h ^ line).wrapping_mul(0x100_0000_01b3)))
+            .collect()
+    };
+    let candidates: Vec<(usize, usize)> = unique_anchors(&windows(original_hashes), &windows(modified_hashes))
+        .into_iter()
+        .filter(|&(i, j)| (0..min_lines).all(|k| same(i + k, j + k)))
+        .collect();
+
+    let mut runs: Vec<AnchorRun> = Vec::new();
+    for (i, j) in longest_increasing_run(&candidates) {
+        match runs.last_mut() {
+            Some(run) if i - run.original_start == j - run.modified_start && i <= run.original_start + run.len => {
+                run.len = i - run.original_start + min_lines;
+            }
+            // A window overlapping the previous run off its diagonal is dropped
+            Some(run) if i < run.original_start + run.len || j < run.modified_start + run.len => {}
+            _ => runs.push(AnchorRun { original_start: i, modified_start: j, len: min_lines }),
+        }
+    }
+    runs
+}
+
+/// Changes between sequences of length `m` and `n` split at `runs`: each gap between runs is
+/// passed to `diff_gap` as `(original_start, original_end, modified_start, modified_end)` and
+/// its changes, local to the gap, are shifted into place. Gaps share nothing, so they can be
+/// diffed in any order or at once, and a reorganization no longer leaves one table spanning it.
+pub fn anchored_changes(
+    m: usize,
+    n: usize,
+    runs: &[AnchorRun],
+    diff_gap: &mut dyn FnMut(usize, usize, usize, usize) -> Vec<LineChange>,
+) -> Vec<LineChange> {
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let ends = runs.iter().map(|run| (run.original_start, run.modified_start, run.len)).chain([(m, n, 0)]);
+    for (original_end, modified_end, len) in ends {
+        if (i, j) != (original_end, modified_end) {
+            for mut change in diff_gap(i, original_end, j, modified_end) {
+                change.original_start += i;
+                change.original_end += i;
+                change.modified_start += j;
+                change.modified_end += j;
+                changes.push(change);
+            }
+        }
+        (i, j) = (original_end + len, modified_end + len);
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{coarse_changes, verify};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_anchored_changes() {
+        // Two blocks swap places around a common header, and the tail changes
+        let original = lines("header\nfn a\none\ntwo\nend\nfn b\nthree\nfour\nend\ntail");
+        let modified = lines("header\nfn b\nthree\nfour\nend\nfn a\none\ntwo\nend\nnew tail");
+        let hash = |lines: &[String]| -> Vec<u64> {
+            lines.iter().map(|line| line.bytes().fold(5381u64, |h, b| h.wrapping_mul(33) ^ b as u64)).collect()
+        };
+        let same = |i: usize, j: usize| original[i] == modified[j];
+        let runs = find_anchor_runs(&hash(&original), &hash(&modified), &same, 3);
+        assert_eq!(runs, vec![AnchorRun { original_start: 5, modified_start: 1, len: 4 }]);
+
+        let mut gaps = Vec::new();
+        let changes = anchored_changes(original.len(), modified.len(), &runs, &mut |a0, a1, b0, b1| {
+            gaps.push((a0, a1, b0, b1));
+            coarse_changes(a1 - a0, b1 - b0, &|i, j| original[a0 + i] == modified[b0 + j])
+        });
+        assert_eq!(gaps, vec![(0, 5, 0, 1), (9, 10, 5, 10)]);
+        assert_eq!(verify(&original, &modified, &changes), Ok(()));
+        assert!(find_anchor_runs(&hash(&original), &hash(&modified), &same, 0).is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A file that looks like an earlier copy of the one being edited
+#[derive(Clone, Debug, PartialEq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// Which convention produced it, e.g. `*.bak` or `Emacs auto-save`
+    pub kind: &'static str,
+    pub modified: Option<SystemTime>,
+}
+
+/// Backup siblings of `path` (`.bak`, `.orig`, `~`, Emacs `#file#` / `.#file`, numbered
+/// `.~N~`) plus Vim/Neovim `backupdir` copies under `home`, newest first
+pub fn find_backups(path: &Path, home: Option<&Path>) -> Vec<Backup> {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Vec::new();
+    };
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+
+    let mut candidates: Vec<(PathBuf, &'static str)> = vec![
+        (dir.join(format!("{}.bak", name)), "*.bak"),
+        (dir.join(format!("{}.orig", name)), "*.orig"),
+        (dir.join(format!("{}.old", name)), "*.old"),
+        (dir.join(format!("{}~", name)), "*~"),
+        (dir.join(format!("#{}#", name)), "Emacs auto-save"),
+        // Usually a dangling lock symlink; only kept when it is a real file
+        (dir.join(format!(".#{}", name)), "Emacs .#file"),
+    ];
+    if let Some(stem) = Path::new(name).file_stem().and_then(|s| s.to_str()).filter(|stem| *stem != name) {
+        candidates.push((dir.join(format!("{}.bak", stem)), "*.bak"));
+    }
+
+    // Emacs numbered backups: `name.~1~`, `name.~2~`, ...
+    let numbered = format!("{}.~", name);
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if file_name.strip_prefix(&numbered).and_then(|rest| rest.strip_suffix('~')).is_some_and(is_number) {
+            candidates.push((entry.path(), "Emacs numbered backup"));
+        }
+    }
+
+    // With `backupdir=...//` Vim and Neovim name backups after the full path, `/` written as `%`
+    if let (Some(home), Ok(absolute)) = (home, fs::canonicalize(path)) {
+        let encoded = absolute.to_string_lossy().replace(['/', '\\'], "%");
+        for (backup_dir, kind) in [
+            (".local/state/nvim/backup", "Neovim backupdir"),
+            (".vim/backup", "Vim backupdir"),
+        ] {
+            candidates.push((home.join(backup_dir).join(format!("{}~", encoded)), kind));
+        }
+    }
+
+    let mut backups: Vec<Backup> = candidates
+        .into_iter()
+        .filter_map(|(path, kind)| {
+            let metadata = fs::symlink_metadata(&path).ok().filter(|m| m.is_file())?;
+            Some(Backup {
+                path,
+                kind,
+                modified: metadata.modified().ok(),
+            })
+        })
+        .collect();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.modified));
+    backups
+}
+
+fn is_number(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_find_backups() {
+        let dir = TempDir::new("backup");
+        let file = dir.join("notes.txt");
+        for name in ["notes.txt", "notes.txt.bak", "notes.txt~", "#notes.txt#", "notes.txt.~3~", "notes.bak", "other.bak"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+        // Make the numbered backup the newest
+        let newest = fs::File::options().write(true).open(dir.join("notes.txt.~3~")).unwrap();
+        newest.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+
+        let home = dir.join("home");
+        let encoded = fs::canonicalize(&file).unwrap().to_string_lossy().replace('/', "%");
+        fs::create_dir_all(home.join(".vim/backup")).unwrap();
+        fs::write(home.join(".vim/backup").join(format!("{}~", encoded)), "vim").unwrap();
+
+        let backups = find_backups(&file, Some(&home));
+        let kinds: Vec<_> = backups.iter().map(|b| b.kind).collect();
+        assert_eq!(backups.len(), 6);
+        assert_eq!(kinds[0], "Emacs numbered backup");
+        assert!(kinds.contains(&"Vim backupdir"));
+        assert!(!backups.iter().any(|b| b.path.ends_with("other.bak")));
+    }
+}
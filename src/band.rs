@@ -0,0 +1,148 @@
+use crate::clock::Clock;
+use crate::diff_core::{merge_adjacent_changes, ChangeType, LineChange};
+
+/// Diagonals kept on each side of the ones joining the start and end of the table
+pub const DEFAULT_BAND_WIDTH: usize = 64;
+
+/// The LCS table restricted to diagonals `lo..=hi` (`j - i`), one row of `hi - lo + 1` cells
+/// per line of the original
+struct BandTable {
+    lo: isize,
+    hi: isize,
+    rows: Vec<Vec<usize>>,
+}
+
+impl BandTable {
+    fn contains(&self, i: usize, j: usize) -> bool {
+        let diagonal = j as isize - i as isize;
+        self.lo <= diagonal && diagonal <= self.hi
+    }
+
+    fn get(&self, i: usize, j: usize) -> usize {
+        self.rows[i][(j as isize - i as isize - self.lo) as usize]
+    }
+}
+
+/// Diff of sequences of length `m` and `n` filling only the cells within `band_width` diagonals
+/// of the straight path, which is near-linear when the inputs differ in a few places. The
+/// result is minimal once the band holds every path as short as the one found; otherwise the
+/// band is widened to cover that path and filled again. `None` when the budget runs out or the
+/// band would need more than `max_cells` cells.
+pub(crate) fn band_changes(
+    m: usize,
+    n: usize,
+    same: &impl Fn(usize, usize) -> bool,
+    band_width: usize,
+    max_cells: u64,
+    clock: &dyn Clock,
+) -> Option<Vec<LineChange>> {
+    let offset = n as isize - m as isize;
+    // One diagonal either side at least, so every cell off the band's edge has a neighbour in it
+    let mut width = band_width.max(1);
+    loop {
+        let lo = offset.min(0) - width as isize;
+        let hi = offset.max(0) + width as isize;
+        if (m as u64 + 1) * (hi - lo + 1) as u64 > max_cells {
+            return None;
+        }
+        let table = fill_band(m, n, same, lo, hi, clock)?;
+
+        // Insertions and deletions of the path found; a shorter path never leaves the
+        // diagonals between them
+        let edits = (m + n - 2 * table.get(m, n)) as isize;
+        let (deletions, insertions) = ((edits - offset) / 2, (edits + offset) / 2);
+        if lo <= -deletions && insertions <= hi || (lo <= -(m as isize) && n as isize <= hi) {
+            return Some(backtrack_band(&table, m, n, same));
+        }
+        width = width.max(deletions.max(insertions) as usize);
+    }
+}
+
+fn fill_band(
+    m: usize,
+    n: usize,
+    same: &impl Fn(usize, usize) -> bool,
+    lo: isize,
+    hi: isize,
+    clock: &dyn Clock,
+) -> Option<BandTable> {
+    let mut table = BandTable { lo, hi, rows: vec![vec![0; (hi - lo + 1) as usize]; m + 1] };
+    for i in 1..=m {
+        if clock.expired((hi - lo + 1) as u64) {
+            return None;
+        }
+        let first = (i as isize + lo).max(1) as usize;
+        let last = (i as isize + hi).min(n as isize) as usize;
+        for j in first..=last {
+            let value = if same(i - 1, j - 1) {
+                table.get(i - 1, j - 1) + 1
+            } else {
+                let up = if table.contains(i - 1, j) { table.get(i - 1, j) } else { 0 };
+                let left = if table.contains(i, j - 1) { table.get(i, j - 1) } else { 0 };
+                up.max(left)
+            };
+            table.rows[i][(j as isize - i as isize - lo) as usize] = value;
+        }
+    }
+    Some(table)
+}
+
+fn backtrack_band(table: &BandTable, m: usize, n: usize, same: &impl Fn(usize, usize) -> bool) -> Vec<LineChange> {
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (m, n);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && same(i - 1, j - 1) {
+            (i, j) = (i - 1, j - 1);
+            continue;
+        }
+        let delete = i > 0 && (j == 0 || (table.contains(i - 1, j) && table.get(i - 1, j) == table.get(i, j)));
+        let (original_start, modified_start, change_type) =
+            if delete { (i - 1, j, ChangeType::Deleted) } else { (i, j - 1, ChangeType::Added) };
+        changes.push(LineChange {
+            original_start,
+            original_end: i,
+            modified_start,
+            modified_end: j,
+            change_type,
+            char_changes: None,
+            generated: false,
+            tags: Vec::new(),
+            id: String::new(),
+        });
+        (i, j) = (original_start, modified_start);
+    }
+    changes.reverse();
+    merge_adjacent_changes(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::HostClock;
+    use crate::diff_core::{backtrack_changes, compute_lcs_matrix, verify};
+
+    #[test]
+    fn test_band_changes() {
+        let original: Vec<String> = (0..300).map(|i| format!("line {}", i % 40)).collect();
+        let mut modified = original.clone();
+        modified[10] = "edited".to_string();
+        // As many lines deleted as inserted further down: the best path leaves the straight one
+        modified.drain(100..130);
+        modified.splice(170..170, (0..30).map(|i| format!("new {}", i)));
+        let same = |i: usize, j: usize| original[i] == modified[j];
+        let clock = HostClock::new(60_000);
+        let full = compute_lcs_matrix(original.len(), modified.len(), &same, &clock).unwrap();
+        let expected = backtrack_changes(&full, original.len(), modified.len(), &same);
+        let edits = |changes: &[LineChange]| -> usize {
+            changes.iter().map(|c| c.original_end - c.original_start + c.modified_end - c.modified_start).sum()
+        };
+
+        // A band of 2 cannot hold that path at first and is widened
+        for band_width in [2, DEFAULT_BAND_WIDTH] {
+            let changes = band_changes(original.len(), modified.len(), &same, band_width, u64::MAX, &clock).unwrap();
+            assert_eq!(verify(&original, &modified, &changes), Ok(()));
+            assert_eq!(edits(&changes), edits(&expected));
+        }
+        assert!(band_changes(original.len(), modified.len(), &same, 2, 1000, &clock).is_none());
+    }
+}
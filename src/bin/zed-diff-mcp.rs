@@ -0,0 +1,17 @@
+//! The diff tools (`compare_files`, `compare_with_revision`, `diffstat`) as an MCP context server
+//! over stdio, for Zed's assistant and agents. Register it under `context_servers` in Zed's
+//! settings:
+//!
+//! ```json
+//! "context_servers": {
+//!     "file-diff": { "command": { "path": "/path/to/zed-diff-mcp", "args": [] } }
+//! }
+//! ```
+
+fn main() {
+    let (stdin, stdout) = (std::io::stdin(), std::io::stdout());
+    if let Err(e) = zed_diff_plugin::tools::serve(stdin.lock(), stdout.lock()) {
+        eprintln!("zed-diff-mcp: {}", e);
+        std::process::exit(1);
+    }
+}
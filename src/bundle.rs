@@ -0,0 +1,128 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::diff_core::{compute_diff, LineChange};
+use crate::state::ComparisonState;
+
+/// Bumped whenever the bundle layout changes incompatibly
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// A comparison packed into one JSON document: both file snapshots, the options, the diff
+/// and its annotations, so another machine sees exactly the same view
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReviewBundle {
+    pub format_version: u32,
+    pub state: ComparisonState,
+    pub original: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl ReviewBundle {
+    pub fn new(state: ComparisonState, original: Vec<String>, modified: Vec<String>) -> Self {
+        Self {
+            format_version: BUNDLE_FORMAT_VERSION,
+            state,
+            original,
+            modified,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let bundle: Self = serde_json::from_str(text).map_err(|e| format!("invalid review bundle: {}", e))?;
+        if bundle.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(format!(
+                "review bundle format {} is newer than the supported format {}",
+                bundle.format_version, BUNDLE_FORMAT_VERSION
+            ));
+        }
+        if !bundle.state.diff_fits(bundle.original.len(), bundle.modified.len()) {
+            return Err("review bundle's hunks lie outside its snapshots".to_string());
+        }
+        Ok(bundle)
+    }
+
+    /// Whether re-diffing the snapshots with the recorded options gives the recorded hunks;
+    /// a mismatch means this build's engine differs from the author's
+    pub fn reproduces(&self) -> bool {
+        let changes = compute_diff(&self.original, &self.modified, self.state.options.clone());
+        let key = |c: &LineChange| {
+            (c.original_start, c.original_end, c.modified_start, c.modified_end, c.id.clone())
+        };
+        changes.iter().map(key).eq(self.state.diff_result.iter().map(key))
+    }
+
+    /// Write both snapshots into `dir` and return the comparison pointing at them
+    pub fn extract(&self, dir: &Path) -> io::Result<ComparisonState> {
+        fs::create_dir_all(dir)?;
+        let write = |side: &str, source: &str, lines: &[String]| -> io::Result<String> {
+            let name = Path::new(source).file_name().and_then(|n| n.to_str()).unwrap_or("file");
+            let path = dir.join(format!("{}-{}", side, name));
+            let mut text = lines.join("\n");
+            if !lines.is_empty() {
+                text.push('\n');
+            }
+            fs::write(&path, text)?;
+            Ok(path.to_string_lossy().into_owned())
+        };
+
+        let mut state = self.state.clone();
+        state.file1_path = write("original", &self.state.file1_path, &self.original)?;
+        state.file2_path = write("modified", &self.state.file2_path, &self.modified)?;
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::DiffOptions;
+    use crate::test_helpers::{lines, TempDir};
+
+    fn sample_bundle() -> ReviewBundle {
+        let (original, modified) = (lines("a\nb\nc"), lines("a\nB\nc\nD"));
+        let options = DiffOptions {
+            ignore_case: true,
+            ..DiffOptions::default()
+        };
+        let mut state = ComparisonState::new(
+            "/src/x.txt".to_string(),
+            "/src/y.txt".to_string(),
+            compute_diff(&original, &modified, options.clone()),
+        );
+        state.options = options;
+        state.add_annotation(0, "why D?".to_string()).unwrap();
+        ReviewBundle::new(state, original, modified)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bundle = sample_bundle();
+        let imported = ReviewBundle::from_json(&bundle.to_json()).unwrap();
+        assert!(imported.state.options.ignore_case);
+        assert_eq!(imported.state.annotations, bundle.state.annotations);
+        assert!(imported.reproduces());
+
+        let mut tampered = imported;
+        tampered.state.options.ignore_case = false;
+        assert!(!tampered.reproduces());
+        let newer = bundle.to_json().replace("\"format_version\": 1", "\"format_version\": 99");
+        assert!(ReviewBundle::from_json(&newer).is_err());
+
+        let mut out_of_range = sample_bundle();
+        out_of_range.state.diff_result[0].modified_end = 40;
+        assert!(ReviewBundle::from_json(&out_of_range.to_json()).unwrap_err().contains("outside its snapshots"));
+    }
+
+    #[test]
+    fn test_extract_snapshots() {
+        let dir = TempDir::new("bundle");
+        let state = sample_bundle().extract(&dir).unwrap();
+        assert!(state.file1_path.ends_with("original-x.txt"));
+        assert_eq!(fs::read_to_string(&state.file2_path).unwrap(), "a\nB\nc\nD\n");
+    }
+}
@@ -0,0 +1,142 @@
+use crate::diff_core::{compute_diff, ChangeType, DiffOptions};
+
+/// Entries added under one release (and optional subsection such as `### Added`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReleaseEntries {
+    /// Release heading as written, e.g. `## [1.2.0] - 2024-01-01`; empty before the first release
+    pub release: String,
+    pub section: Option<String>,
+    pub entries: Vec<String>,
+}
+
+/// `major.minor.patch` from a release heading such as `## [v1.2.0]`; missing components are 0
+pub fn heading_version(heading: &str) -> Option<(u64, u64, u64)> {
+    let start = heading.find(|c: char| c.is_ascii_digit())?;
+    let version: Vec<u64> = heading[start..]
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect();
+    match version.as_slice() {
+        [major] => Some((*major, 0, 0)),
+        [major, minor] => Some((*major, *minor, 0)),
+        [major, minor, patch, ..] => Some((*major, *minor, *patch)),
+        [] => None,
+    }
+}
+
+/// Top-level release headings (`#`/`##`) versus subsection headings (`###` and deeper)
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    (level > 0 && line[level..].starts_with(' ')).then_some(level)
+}
+
+/// Lines added in `modified`, grouped by the release and subsection they appear under.
+/// Deleted and rewritten-away lines are dropped; releases are ordered newest version first.
+pub fn whats_new(original: &[String], modified: &[String], options: DiffOptions) -> Vec<ReleaseEntries> {
+    let mut added = vec![false; modified.len()];
+    for change in compute_diff(original, modified, options) {
+        if change.change_type != ChangeType::Deleted {
+            added[change.modified_start..change.modified_end].fill(true);
+        }
+    }
+
+    // The first heading level seen is the release level; `# Changelog` titles sit above it
+    let release_level = modified
+        .iter()
+        .filter_map(|line| heading_level(line).map(|level| (level, line)))
+        .find(|(_, line)| heading_version(line).is_some() || line.to_lowercase().contains("unreleased"))
+        .map_or(2, |(level, _)| level);
+
+    let mut groups: Vec<ReleaseEntries> = Vec::new();
+    let mut release = String::new();
+    let mut section: Option<String> = None;
+    for (line, is_added) in modified.iter().zip(added) {
+        match heading_level(line) {
+            Some(level) if level <= release_level => {
+                release = if level == release_level { line.trim().to_string() } else { String::new() };
+                section = None;
+                continue;
+            }
+            Some(_) => {
+                section = Some(line.trim().to_string());
+                continue;
+            }
+            None => {}
+        }
+        if !is_added || line.trim().is_empty() {
+            continue;
+        }
+
+        match groups.last_mut() {
+            Some(group) if group.release == release && group.section == section => group.entries.push(line.clone()),
+            _ => groups.push(ReleaseEntries {
+                release: release.clone(),
+                section: section.clone(),
+                entries: vec![line.clone()],
+            }),
+        }
+    }
+
+    // Stable: headings without a version ("Unreleased") stay first, in file order
+    groups.sort_by_key(|group| std::cmp::Reverse(heading_version(&group.release).unwrap_or((u64::MAX, 0, 0))));
+    groups
+}
+
+pub fn format_whats_new(path: &str, rev_a: &str, rev_b: &str, groups: &[ReleaseEntries]) -> String {
+    let mut output = format!("# What's new in {} ({}..{})\n", path, rev_a, rev_b);
+    if groups.is_empty() {
+        output.push_str("\nNo new entries\n");
+        return output;
+    }
+
+    let mut release: Option<&str> = None;
+    for group in groups {
+        if release != Some(group.release.as_str()) {
+            release = Some(&group.release);
+            if !group.release.is_empty() {
+                output.push_str(&format!("\n{}\n", group.release));
+            }
+        }
+        if let Some(section) = &group.section {
+            output.push_str(&format!("\n{}\n", section));
+        } else {
+            output.push('\n');
+        }
+        for entry in &group.entries {
+            output.push_str(&format!("{}\n", entry));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_heading_version() {
+        assert_eq!(heading_version("## [v1.2.3] - 2024-01-01"), Some((1, 2, 3)));
+        assert_eq!(heading_version("## 2.0"), Some((2, 0, 0)));
+        assert_eq!(heading_version("## Unreleased"), None);
+    }
+
+    #[test]
+    fn test_whats_new_keeps_only_additions() {
+        let original = lines("# Changelog\n\n## [1.0.0]\n### Added\n- first\n- typo entry");
+        let modified = lines(
+            "# Changelog\n\n## Unreleased\n- pending\n\n## [1.1.0]\n### Added\n- feature\n### Fixed\n- bug\n\n## [1.0.0]\n### Added\n- first\n- backfilled",
+        );
+
+        let groups = whats_new(&original, &modified, DiffOptions::default());
+        let releases: Vec<_> = groups.iter().map(|g| g.release.as_str()).collect();
+        assert_eq!(releases, vec!["## Unreleased", "## [1.1.0]", "## [1.1.0]", "## [1.0.0]"]);
+        assert_eq!(groups[3].entries, vec!["- backfilled"]);
+
+        let text = format_whats_new("CHANGELOG.md", "v1.0.0", "v1.1.0", &groups);
+        assert!(text.contains("\n## [1.1.0]\n\n### Added\n- feature\n\n### Fixed\n- bug\n"));
+        assert!(!text.contains("typo entry"));
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use crate::diff_core::{compute_diff, DiffOptions, LineChange};
+
+/// One step of a changeset, walking the original and modified files together
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DeltaOp {
+    /// Keep this many lines unchanged
+    Retain(usize),
+    /// Drop these original lines; their text is kept so deltas can be checked and simplified
+    Delete(Vec<String>),
+    Insert(Vec<String>),
+}
+
+impl DeltaOp {
+    fn input_len(&self) -> usize {
+        match self {
+            DeltaOp::Retain(count) => *count,
+            DeltaOp::Delete(lines) => lines.len(),
+            DeltaOp::Insert(_) => 0,
+        }
+    }
+
+    fn output_len(&self) -> usize {
+        match self {
+            DeltaOp::Retain(count) => *count,
+            DeltaOp::Delete(_) => 0,
+            DeltaOp::Insert(lines) => lines.len(),
+        }
+    }
+
+    /// Split off the first `count` lines of the op, returning them and what is left
+    fn split(self, count: usize) -> (DeltaOp, Option<DeltaOp>) {
+        match self {
+            DeltaOp::Retain(total) if total > count => (DeltaOp::Retain(count), Some(DeltaOp::Retain(total - count))),
+            DeltaOp::Delete(mut lines) if lines.len() > count => {
+                let rest = lines.split_off(count);
+                (DeltaOp::Delete(lines), Some(DeltaOp::Delete(rest)))
+            }
+            DeltaOp::Insert(mut lines) if lines.len() > count => {
+                let rest = lines.split_off(count);
+                (DeltaOp::Insert(lines), Some(DeltaOp::Insert(rest)))
+            }
+            op => (op, None),
+        }
+    }
+}
+
+/// A self-contained edit from one version of a file to the next, unlike `LineChange` lists
+/// which only index into both versions
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Changeset {
+    pub ops: Vec<DeltaOp>,
+}
+
+impl Changeset {
+    /// The edit from `original` to `modified`
+    pub fn between(original: &[String], modified: &[String]) -> Self {
+        Self::from_changes(original, modified, &compute_diff(original, modified, DiffOptions::default()))
+    }
+
+    /// Capture a diff result together with the text it refers to
+    pub fn from_changes(original: &[String], modified: &[String], changes: &[LineChange]) -> Self {
+        let mut changeset = Self::default();
+        let mut position = 0;
+        for change in changes {
+            changeset.push(DeltaOp::Retain(change.original_start - position));
+            changeset.push(DeltaOp::Delete(original[change.original_start..change.original_end].to_vec()));
+            changeset.push(DeltaOp::Insert(modified[change.modified_start..change.modified_end].to_vec()));
+            position = change.original_end;
+        }
+        changeset.push(DeltaOp::Retain(original.len() - position));
+        changeset
+    }
+
+    /// Lines of the version the changeset starts from
+    pub fn input_len(&self) -> usize {
+        self.ops.iter().map(DeltaOp::input_len).sum()
+    }
+
+    /// Lines of the version the changeset produces
+    pub fn output_len(&self) -> usize {
+        self.ops.iter().map(DeltaOp::output_len).sum()
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.ops.iter().all(|op| matches!(op, DeltaOp::Retain(_)))
+    }
+
+    /// Append an op, merging it into the last one when they are the same kind
+    fn push(&mut self, op: DeltaOp) {
+        if op.input_len() == 0 && op.output_len() == 0 {
+            return;
+        }
+        match (self.ops.last_mut(), op) {
+            (Some(DeltaOp::Retain(last)), DeltaOp::Retain(count)) => *last += count,
+            (Some(DeltaOp::Delete(last)), DeltaOp::Delete(lines)) | (Some(DeltaOp::Insert(last)), DeltaOp::Insert(lines)) => {
+                last.extend(lines)
+            }
+            (_, op) => self.ops.push(op),
+        }
+    }
+
+    /// Replay the changeset on `original`, checking that deleted lines are the ones recorded
+    pub fn apply(&self, original: &[String]) -> Result<Vec<String>, String> {
+        if original.len() != self.input_len() {
+            return Err(format!("Changeset expects {} lines, got {}", self.input_len(), original.len()));
+        }
+        let mut result = Vec::with_capacity(self.output_len());
+        let mut position = 0;
+        for op in &self.ops {
+            match op {
+                DeltaOp::Retain(count) => result.extend_from_slice(&original[position..position + count]),
+                DeltaOp::Delete(lines) => {
+                    if original[position..position + lines.len()] != lines[..] {
+                        return Err(format!("Line {} does not match the changeset", position + 1));
+                    }
+                }
+                DeltaOp::Insert(lines) => result.extend_from_slice(lines),
+            }
+            position += op.input_len();
+        }
+        Ok(result)
+    }
+
+    /// The single changeset doing `self` then `next`. A line inserted by `self` and deleted by
+    /// `next` disappears from the result.
+    pub fn compose(&self, next: &Changeset) -> Result<Changeset, String> {
+        if self.output_len() != next.input_len() {
+            return Err(format!(
+                "Cannot compose: first changeset produces {} lines, second expects {}",
+                self.output_len(),
+                next.input_len()
+            ));
+        }
+        let mut first: VecDeque<DeltaOp> = self.ops.iter().cloned().collect();
+        let mut second: VecDeque<DeltaOp> = next.ops.iter().cloned().collect();
+        let mut composed = Changeset::default();
+        loop {
+            match (first.pop_front(), second.pop_front()) {
+                (None, None) => break,
+                // Deletions of the first and insertions of the second touch no shared line
+                (Some(DeltaOp::Delete(lines)), op) => {
+                    composed.push(DeltaOp::Delete(lines));
+                    requeue(&mut second, op);
+                }
+                (op, Some(DeltaOp::Insert(lines))) => {
+                    composed.push(DeltaOp::Insert(lines));
+                    requeue(&mut first, op);
+                }
+                (Some(a), Some(b)) => {
+                    let count = a.output_len().min(b.input_len());
+                    let ((a, a_rest), (b, b_rest)) = (a.split(count), b.split(count));
+                    requeue(&mut first, a_rest);
+                    requeue(&mut second, b_rest);
+                    match (a, b) {
+                        (DeltaOp::Retain(_), DeltaOp::Retain(_)) => composed.push(DeltaOp::Retain(count)),
+                        (DeltaOp::Retain(_), delete) => composed.push(delete),
+                        (insert, DeltaOp::Retain(_)) => composed.push(insert),
+                        // Inserted, then deleted again
+                        _ => {}
+                    }
+                }
+                // Lengths were checked above, so only empty ops can be left on one side
+                _ => {}
+            }
+        }
+        Ok(composed)
+    }
+
+    /// The same edit with no-ops removed: runs of deletions and insertions are merged, and lines
+    /// deleted and re-inserted unchanged at either end of a run become retained lines again
+    pub fn simplify(&self) -> Changeset {
+        let mut simplified = Changeset::default();
+        let (mut deleted, mut inserted) = (Vec::new(), Vec::new());
+        for op in self.ops.iter().chain([DeltaOp::Retain(0)].iter()) {
+            match op {
+                DeltaOp::Delete(lines) => deleted.extend(lines.iter().cloned()),
+                DeltaOp::Insert(lines) => inserted.extend(lines.iter().cloned()),
+                DeltaOp::Retain(count) => {
+                    let prefix = deleted.iter().zip(&inserted).take_while(|(a, b)| a == b).count();
+                    let suffix = deleted[prefix..]
+                        .iter()
+                        .rev()
+                        .zip(inserted[prefix..].iter().rev())
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    simplified.push(DeltaOp::Retain(prefix));
+                    simplified.push(DeltaOp::Delete(deleted[prefix..deleted.len() - suffix].to_vec()));
+                    simplified.push(DeltaOp::Insert(inserted[prefix..inserted.len() - suffix].to_vec()));
+                    simplified.push(DeltaOp::Retain(suffix + count));
+                    deleted.clear();
+                    inserted.clear();
+                }
+            }
+        }
+        simplified
+    }
+}
+
+/// Put the unconsumed rest of a split op back at the front of its queue
+fn requeue(queue: &mut VecDeque<DeltaOp>, op: Option<DeltaOp>) {
+    if let Some(op) = op {
+        queue.push_front(op);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_compose() {
+        let (a, b, c) = (lines("one\ntwo\nthree"), lines("one\n2\nthree\nfour"), lines("zero\none\n2\nthree"));
+        let (ab, bc) = (Changeset::between(&a, &b), Changeset::between(&b, &c));
+        let ac = ab.compose(&bc).unwrap();
+        assert_eq!(ac.apply(&a).unwrap(), c);
+        // `four` was added then removed again, so it never appears
+        assert!(!ac.ops.iter().any(|op| matches!(op, DeltaOp::Insert(lines) if lines.contains(&"four".to_string()))));
+        assert!(bc.compose(&ab).unwrap_err().starts_with("Cannot compose"));
+    }
+
+    #[test]
+    fn test_simplify() {
+        let changeset = Changeset {
+            ops: vec![
+                DeltaOp::Retain(1),
+                DeltaOp::Delete(lines("a\nb")),
+                DeltaOp::Insert(lines("a")),
+                DeltaOp::Delete(lines("c")),
+                DeltaOp::Insert(lines("x\nc")),
+            ],
+        };
+        let simplified = changeset.simplify();
+        assert_eq!(
+            simplified.ops,
+            vec![DeltaOp::Retain(2), DeltaOp::Delete(lines("b")), DeltaOp::Insert(lines("x")), DeltaOp::Retain(1)]
+        );
+        let original = lines("top\na\nb\nc");
+        assert_eq!(simplified.apply(&original).unwrap(), changeset.apply(&original).unwrap());
+        assert!(Changeset::between(&original, &original).simplify().is_identity());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::diff_core::{compute_diff, DiffOptions, LineChange};
+use crate::hunk_id::hunk_id;
+
+/// Lines of each side diffed per window; the LCS table of a window stays around 4M cells
+pub const DEFAULT_CHECKPOINT_LINES: usize = 2000;
+
+/// Progress of a windowed diff: everything before `original_done` and `modified_done` is final
+#[derive(Clone, Debug)]
+pub struct DiffCheckpoint<'a> {
+    pub original_done: usize,
+    pub modified_done: usize,
+    pub original_total: usize,
+    pub modified_total: usize,
+    /// Changes completed since the previous checkpoint, with whole-file line numbers
+    pub changes: &'a [LineChange],
+}
+
+impl DiffCheckpoint<'_> {
+    pub fn is_final(&self) -> bool {
+        self.original_done == self.original_total && self.modified_done == self.modified_total
+    }
+
+    /// Share of both inputs already compared, from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        let total = self.original_total + self.modified_total;
+        if total == 0 {
+            return 1.0;
+        }
+        (self.original_done + self.modified_done) as f32 / total as f32
+    }
+}
+
+/// Diff a window of `window` lines of each side at a time, passing each completed region to
+/// `on_checkpoint` before moving on, so the start of a huge diff can be shown while the rest
+/// is still being computed. A window's changes are final once an unchanged line follows them;
+/// the next window starts after the last such change. A window with no final change is doubled
+/// until it has one or covers the rest of both inputs.
+///
+/// Hunks never span windows they could have been matched across, so the result is always a
+/// correct diff, but may be longer than the one `compute_diff` finds for the whole inputs.
+pub fn diff_with_checkpoints(
+    original: &[String],
+    modified: &[String],
+    options: DiffOptions,
+    window: usize,
+    on_checkpoint: &mut dyn FnMut(&DiffCheckpoint),
+) -> Vec<LineChange> {
+    let window = window.max(1);
+    let mut all: Vec<LineChange> = Vec::new();
+    let (mut original_done, mut modified_done) = (0, 0);
+    let mut size = window;
+    loop {
+        let original_end = (original_done + size).min(original.len());
+        let modified_end = (modified_done + size).min(modified.len());
+        let last = original_end == original.len() && modified_end == modified.len();
+        let mut changes = compute_diff(
+            &original[original_done..original_end],
+            &modified[modified_done..modified_end],
+            options.clone(),
+        );
+        // Lines before the first change are unchanged, by whatever equality `options` define
+        let first = changes.first().map_or((original_end - original_done, modified_end - modified_done), |change| {
+            (change.original_start, change.modified_start)
+        });
+        if !last {
+            // Changes touching the window's end may continue past it
+            let kept = changes
+                .iter()
+                .take_while(|c| c.original_end < original_end - original_done && c.modified_end < modified_end - modified_done)
+                .count();
+            changes.truncate(kept);
+        }
+
+        // How far this window is final: after its last kept change, or through the unchanged
+        // lines before the first one
+        let (original_cut, modified_cut) = if last {
+            (original_end, modified_end)
+        } else if let Some(change) = changes.last() {
+            (original_done + change.original_end, modified_done + change.modified_end)
+        } else {
+            (original_done + first.0, modified_done + first.1)
+        };
+        if (original_cut, modified_cut) == (original_done, modified_done) && !last {
+            size *= 2;
+            continue;
+        }
+
+        let start = all.len();
+        for mut change in changes {
+            change.original_start += original_done;
+            change.original_end += original_done;
+            change.modified_start += modified_done;
+            change.modified_end += modified_done;
+            change.id = hunk_id(&change, original, modified);
+            all.push(change);
+        }
+        (original_done, modified_done) = (original_cut, modified_cut);
+        size = window;
+        on_checkpoint(&DiffCheckpoint {
+            original_done,
+            modified_done,
+            original_total: original.len(),
+            modified_total: modified.len(),
+            changes: &all[start..],
+        });
+        if last {
+            return all;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::verify;
+
+    #[test]
+    fn test_diff_with_checkpoints() {
+        let original: Vec<String> = (0..200).map(|i| format!("line {}", i)).collect();
+        let mut modified = original.clone();
+        modified[5] = "changed".to_string();
+        modified.insert(60, "inserted".to_string());
+        modified.drain(120..150);
+        modified.push("appended".to_string());
+
+        let mut checkpoints = Vec::new();
+        let mut streamed = Vec::new();
+        let options = DiffOptions { compute_char_changes: false, ..DiffOptions::default() };
+        let changes = diff_with_checkpoints(&original, &modified, options.clone(), 16, &mut |checkpoint| {
+            checkpoints.push((checkpoint.original_done, checkpoint.modified_done, checkpoint.is_final()));
+            streamed.extend(checkpoint.changes.iter().cloned());
+        });
+
+        assert!(verify(&original, &modified, &changes).is_ok());
+        let ranges = |changes: &[LineChange]| -> Vec<(usize, usize, usize, usize)> {
+            changes.iter().map(|c| (c.original_start, c.original_end, c.modified_start, c.modified_end)).collect()
+        };
+        assert_eq!(ranges(&changes), ranges(&compute_diff(&original, &modified, options)));
+        assert_eq!(ranges(&streamed), ranges(&changes));
+        assert!(checkpoints.len() > 5);
+        assert!(checkpoints.windows(2).all(|pair| pair[0].0 <= pair[1].0 && pair[0].1 <= pair[1].1));
+        assert_eq!(checkpoints.last(), Some(&(200, 172, true)));
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::diff_core::LineChange;
+use crate::messages::{text, Message};
+
+/// Records kept before the oldest are dropped
+pub const DEFAULT_MAX_RECORDS: usize = 10_000;
+
+/// The diffstat of one comparison of a file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChurnRecord {
+    pub path: String,
+    /// Seconds since the Unix epoch
+    pub recorded_at: u64,
+    pub hunks: usize,
+    pub added: usize,
+    pub deleted: usize,
+}
+
+impl ChurnRecord {
+    pub fn churn(&self) -> usize {
+        self.added + self.deleted
+    }
+}
+
+/// Totals for one file over the records queried
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileChurn {
+    pub path: String,
+    pub comparisons: usize,
+    pub added: usize,
+    pub deleted: usize,
+}
+
+/// Diffstats of past comparisons, oldest first, for finding the files that change most
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChurnStore {
+    pub max_records: usize,
+    pub records: Vec<ChurnRecord>,
+}
+
+impl Default for ChurnStore {
+    fn default() -> Self {
+        Self {
+            max_records: DEFAULT_MAX_RECORDS,
+            records: Vec::new(),
+        }
+    }
+}
+
+impl ChurnStore {
+    /// Add a comparison of `path`; identical files are not recorded
+    pub fn record(&mut self, path: &str, recorded_at: u64, changes: &[LineChange]) {
+        if changes.is_empty() {
+            return;
+        }
+        self.records.push(ChurnRecord {
+            path: path.to_string(),
+            recorded_at,
+            hunks: changes.len(),
+            added: changes.iter().map(|c| c.modified_end - c.modified_start).sum(),
+            deleted: changes.iter().map(|c| c.original_end - c.original_start).sum(),
+        });
+        let excess = self.records.len().saturating_sub(self.max_records);
+        self.records.drain(..excess);
+    }
+
+    /// Files recorded at or after `since`, most often changed first, then by total churn
+    pub fn hotspots(&self, since: u64, limit: usize) -> Vec<FileChurn> {
+        let mut files: BTreeMap<&str, FileChurn> = BTreeMap::new();
+        for record in self.records.iter().filter(|record| record.recorded_at >= since) {
+            let file = files.entry(&record.path).or_insert_with(|| FileChurn {
+                path: record.path.clone(),
+                comparisons: 0,
+                added: 0,
+                deleted: 0,
+            });
+            file.comparisons += 1;
+            file.added += record.added;
+            file.deleted += record.deleted;
+        }
+        let mut files: Vec<FileChurn> = files.into_values().collect();
+        files.sort_by_key(|file| std::cmp::Reverse((file.comparisons, file.added + file.deleted)));
+        files.truncate(limit);
+        files
+    }
+
+    /// The largest single comparisons recorded at or after `since`, largest first
+    pub fn biggest_changes(&self, since: u64, limit: usize) -> Vec<&ChurnRecord> {
+        let mut records: Vec<&ChurnRecord> = self.records.iter().filter(|record| record.recorded_at >= since).collect();
+        records.sort_by_key(|record| std::cmp::Reverse((record.churn(), record.recorded_at)));
+        records.truncate(limit);
+        records
+    }
+
+    pub fn save(&self, store_file: &Path) -> io::Result<()> {
+        let json = serde_json::to_string(self).map_err(io::Error::other)?;
+        let temp = store_file.with_extension("tmp");
+        fs::write(&temp, json)?;
+        fs::rename(&temp, store_file)
+    }
+
+    pub fn load(store_file: &Path) -> Result<Self, String> {
+        let text =
+            fs::read_to_string(store_file).map_err(|e| text(Message::ReadFailed, [&store_file.display(), &e]))?;
+        serde_json::from_str(&text).map_err(|e| format!("invalid churn store {}: {}", store_file.display(), e))
+    }
+}
+
+pub fn format_churn_report(hotspots: &[FileChurn], biggest: &[&ChurnRecord]) -> String {
+    if hotspots.is_empty() {
+        return "No changes recorded\n".to_string();
+    }
+    let mut output = "Most frequently changed files:\n".to_string();
+    for file in hotspots {
+        output.push_str(&format!(
+            "  {} ({} comparison(s), +{} -{})\n",
+            file.path, file.comparisons, file.added, file.deleted
+        ));
+    }
+    output.push_str("\nBiggest changes:\n");
+    for record in biggest {
+        output.push_str(&format!(
+            "  {} at {}: {} hunk(s), +{} -{}\n",
+            record.path, record.recorded_at, record.hunks, record.added, record.deleted
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::{lines, TempDir};
+
+    #[test]
+    fn test_churn_hotspots() {
+        let small = compute_diff(&lines("a\nb"), &lines("a\nc"), DiffOptions::default());
+        let large = compute_diff(&lines("a"), &lines("x\ny\nz\nw"), DiffOptions::default());
+        let mut store = ChurnStore { max_records: 4, ..ChurnStore::default() };
+        store.record("ancient.rs", 1, &large);
+        store.record("lib.rs", 10, &small);
+        store.record("lib.rs", 20, &small);
+        store.record("main.rs", 30, &large);
+        store.record("same.rs", 35, &[]);
+        store.record("lib.rs", 40, &small);
+        assert_eq!(store.records.len(), 4);
+        assert_eq!(store.records[0].path, "lib.rs");
+
+        let hotspots = store.hotspots(15, 10);
+        let summary: Vec<(&str, usize)> = hotspots.iter().map(|file| (file.path.as_str(), file.comparisons)).collect();
+        assert_eq!(summary, vec![("lib.rs", 2), ("main.rs", 1)]);
+        let biggest = store.biggest_changes(0, 1);
+        assert_eq!((biggest[0].path.as_str(), biggest[0].added, biggest[0].deleted), ("main.rs", 4, 1));
+
+        let dir = TempDir::new("churn");
+        let path = dir.join("churn.json");
+        store.save(&path).unwrap();
+        assert_eq!(ChurnStore::load(&path).unwrap().records, store.records);
+        assert!(format_churn_report(&hotspots, &biggest).contains("  lib.rs (2 comparison(s), +2 -2)\n"));
+    }
+}
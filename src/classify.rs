@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::diff_core::{ChangeType, LineChange};
+use crate::unicode_check::scan_changes;
+
+/// Higher-level category of a hunk, derived from its content
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChangeTag {
+    /// Only leading indentation changed
+    Reindent,
+    /// The same lines moved to a different position
+    Reorder,
+    /// Identifiers were consistently replaced, nothing else changed
+    Rename,
+    /// Only import/use/include lines are involved
+    ImportOnly,
+    /// Added lines carry bidi controls, invisible or look-alike characters; see `unicode_check`
+    SuspiciousUnicode,
+}
+
+impl ChangeTag {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChangeTag::Reindent => "reindent",
+            ChangeTag::Reorder => "reorder",
+            ChangeTag::Rename => "rename",
+            ChangeTag::ImportOnly => "import-only",
+            ChangeTag::SuspiciousUnicode => "suspicious-unicode",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        [
+            ChangeTag::Reindent,
+            ChangeTag::Reorder,
+            ChangeTag::Rename,
+            ChangeTag::ImportOnly,
+            ChangeTag::SuspiciousUnicode,
+        ]
+        .into_iter()
+            .find(|tag| tag.label() == label)
+    }
+}
+
+/// Attach classification tags to every change
+pub fn classify_changes(changes: &mut [LineChange], original_lines: &[String], modified_lines: &[String]) {
+    for change in changes.iter_mut() {
+        let original = &original_lines[change.original_start..change.original_end];
+        let modified = &modified_lines[change.modified_start..change.modified_end];
+
+        change.tags.clear();
+        if change.change_type == ChangeType::Modified {
+            if is_reindent(original, modified) {
+                change.tags.push(ChangeTag::Reindent);
+            } else if is_permutation(original, modified) {
+                change.tags.push(ChangeTag::Reorder);
+            } else if is_rename(original, modified) {
+                change.tags.push(ChangeTag::Rename);
+            }
+        }
+        let mut involved = original.iter().chain(modified).filter(|line| !line.trim().is_empty());
+        if involved.clone().next().is_some() && involved.all(|line| is_import_line(line)) {
+            change.tags.push(ChangeTag::ImportOnly);
+        }
+        if !scan_changes(modified_lines, std::slice::from_ref(change)).is_empty() {
+            change.tags.push(ChangeTag::SuspiciousUnicode);
+        }
+    }
+
+    tag_moved_blocks(changes, original_lines, modified_lines);
+}
+
+/// Keep only changes carrying none of the `excluded` tags
+pub fn filter_by_tags(changes: &[LineChange], excluded: &[ChangeTag]) -> Vec<LineChange> {
+    changes
+        .iter()
+        .filter(|change| !change.tags.iter().any(|tag| excluded.contains(tag)))
+        .cloned()
+        .collect()
+}
+
+fn is_reindent(original: &[String], modified: &[String]) -> bool {
+    original.len() == modified.len()
+        && original
+            .iter()
+            .zip(modified)
+            .all(|(a, b)| a.trim_start() == b.trim_start())
+}
+
+fn is_permutation(original: &[String], modified: &[String]) -> bool {
+    original.len() > 1 && original.len() == modified.len() && sorted(original) == sorted(modified)
+}
+
+fn sorted(lines: &[String]) -> Vec<&str> {
+    let mut lines: Vec<&str> = lines.iter().map(|line| line.trim()).collect();
+    lines.sort_unstable();
+    lines
+}
+
+fn is_rename(original: &[String], modified: &[String]) -> bool {
+    if original.len() != modified.len() {
+        return false;
+    }
+
+    let mut mapping: HashMap<&str, &str> = HashMap::new();
+    for (a, b) in original.iter().zip(modified) {
+        let (tokens_a, tokens_b) = (identifier_tokens(a), identifier_tokens(b));
+        if tokens_a.len() != tokens_b.len() {
+            return false;
+        }
+        for (ta, tb) in tokens_a.into_iter().zip(tokens_b) {
+            if ta == tb {
+                continue;
+            }
+            let is_identifier = |t: &str| t.starts_with(|c: char| c.is_alphabetic() || c == '_');
+            if !is_identifier(ta) || !is_identifier(tb) || *mapping.entry(ta).or_insert(tb) != tb {
+                return false;
+            }
+        }
+    }
+    !mapping.is_empty()
+}
+
+fn identifier_tokens(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (offset, c) in line.char_indices() {
+        let word = c.is_alphanumeric() || c == '_';
+        match (start, word) {
+            (None, true) => start = Some(offset),
+            (Some(s), false) => {
+                tokens.push(&line[s..offset]);
+                start = None;
+            }
+            _ => {}
+        }
+        if !word && !c.is_whitespace() {
+            tokens.push(&line[offset..offset + c.len_utf8()]);
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(&line[s..]);
+    }
+    tokens
+}
+
+pub fn is_import_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    ["use ", "pub use ", "import ", "from ", "#include", "require(", "extern crate "]
+        .iter()
+        .any(|prefix| trimmed.starts_with(prefix))
+}
+
+/// Tag a deletion and an insertion elsewhere with the same lines as a reorder
+fn tag_moved_blocks(changes: &mut [LineChange], original_lines: &[String], modified_lines: &[String]) {
+    for i in 0..changes.len() {
+        if changes[i].change_type != ChangeType::Deleted {
+            continue;
+        }
+        let deleted = &original_lines[changes[i].original_start..changes[i].original_end];
+
+        for j in 0..changes.len() {
+            let added_change = &changes[j];
+            if added_change.change_type != ChangeType::Added {
+                continue;
+            }
+            let added = &modified_lines[added_change.modified_start..added_change.modified_end];
+            if sorted(deleted) == sorted(added) {
+                for index in [i, j] {
+                    if !changes[index].tags.contains(&ChangeTag::Reorder) {
+                        changes[index].tags.push(ChangeTag::Reorder);
+                    }
+                }
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    fn tags(original: &str, modified: &str) -> Vec<Vec<ChangeTag>> {
+        compute_diff(&lines(original), &lines(modified), DiffOptions::default())
+            .into_iter()
+            .map(|change| change.tags)
+            .collect()
+    }
+
+    #[test]
+    fn test_reindent_and_rename() {
+        assert_eq!(tags("fn a() {\nx();\n}", "fn a() {\n    x();\n}"), vec![vec![ChangeTag::Reindent]]);
+        assert_eq!(
+            tags("let a = b + 1;\nprint(a);", "let c = b + 1;\nprint(c);"),
+            vec![vec![ChangeTag::Rename]]
+        );
+        assert_eq!(tags("let a = b + 1;", "let a = b - 1;"), vec![Vec::<ChangeTag>::new()]);
+    }
+
+    #[test]
+    fn test_reorder_and_imports() {
+        assert_eq!(
+            tags("one\ntwo\nthree\nfour", "two\nthree\nfour\none"),
+            vec![vec![ChangeTag::Reorder], vec![ChangeTag::Reorder]]
+        );
+        assert_eq!(
+            tags("use a;\nfn main() {}", "use a;\nuse b;\nfn main() {}"),
+            vec![vec![ChangeTag::ImportOnly]]
+        );
+    }
+
+    #[test]
+    fn test_filter_by_tags() {
+        let changes = compute_diff(
+            &lines("use a;\nfn x() {}"),
+            &lines("use b;\nfn y() {}"),
+            DiffOptions::default(),
+        );
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].tags, vec![ChangeTag::Rename]);
+        assert_eq!(filter_by_tags(&changes, &[ChangeTag::ImportOnly]).len(), 1);
+        assert!(filter_by_tags(&changes, &[ChangeTag::Rename]).is_empty());
+    }
+}
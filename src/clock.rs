@@ -0,0 +1,119 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use crate::planner::CELLS_PER_MS;
+
+/// Tells a long computation when its time budget is spent
+pub trait Clock {
+    /// Account for `work` more units about to be done, such as LCS cells or lines scanned, and
+    /// report whether the budget is already spent
+    fn expired(&self, work: u64) -> bool;
+
+    /// Time spent so far, measured or estimated from the work done
+    fn elapsed_ms(&self) -> u64;
+}
+
+/// Wall-clock time from the host; ignores the work reported
+pub struct HostClock {
+    start: Instant,
+    budget: Duration,
+}
+
+impl HostClock {
+    pub fn new(budget_ms: u64) -> Self {
+        Self { start: Instant::now(), budget: Duration::from_millis(budget_ms) }
+    }
+}
+
+impl Clock for HostClock {
+    fn expired(&self, _work: u64) -> bool {
+        self.start.elapsed() > self.budget
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+}
+
+/// Counts work instead of reading a clock, at `CELLS_PER_MS` units per millisecond of budget.
+/// Results do not depend on machine speed or load, which also makes them reproducible.
+pub struct OperationClock {
+    done: Cell<u64>,
+    budget: u64,
+}
+
+impl OperationClock {
+    pub fn new(budget_ms: u64) -> Self {
+        Self::with_limit(budget_ms.saturating_mul(CELLS_PER_MS))
+    }
+
+    /// A budget of `operations` units of work rather than milliseconds
+    pub fn with_limit(operations: u64) -> Self {
+        Self { done: Cell::new(0), budget: operations }
+    }
+}
+
+impl Clock for OperationClock {
+    fn expired(&self, work: u64) -> bool {
+        self.done.set(self.done.get().saturating_add(work));
+        self.done.get() > self.budget
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.done.get() / CELLS_PER_MS
+    }
+}
+
+/// A time budget and an operation budget together, spent as soon as either is
+pub struct CappedClock {
+    time: Box<dyn Clock>,
+    operations: OperationClock,
+}
+
+impl Clock for CappedClock {
+    fn expired(&self, work: u64) -> bool {
+        // Both are charged, so the operation count stays exact after the time runs out
+        let time = self.time.expired(work);
+        self.operations.expired(work) || time
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.time.elapsed_ms()
+    }
+}
+
+/// The clock budgets are measured with: host time natively, and counted operations in WASM,
+/// where `Instant` may be coarse, stop while the host is suspended, or not be provided. With
+/// `max_operations`, the work done is capped as well.
+pub fn budget_clock(budget_ms: u64, max_operations: Option<u64>) -> Box<dyn Clock> {
+    let time: Box<dyn Clock> = if cfg!(target_family = "wasm") {
+        Box::new(OperationClock::new(budget_ms))
+    } else {
+        Box::new(HostClock::new(budget_ms))
+    };
+    match max_operations {
+        Some(limit) => Box::new(CappedClock { time, operations: OperationClock::with_limit(limit) }),
+        None => time,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{backtrack_changes, compute_lcs_matrix};
+
+    #[test]
+    fn test_operation_clock() {
+        let clock = OperationClock::new(1);
+        assert!(!clock.expired(CELLS_PER_MS));
+        assert_eq!(clock.elapsed_ms(), 1);
+        assert!(clock.expired(1));
+        assert!(HostClock::new(0).elapsed_ms() < 1000);
+
+        // A table of 100x100 cells fits a budget of 10 000 operations, and not one of 5 000
+        let same = |i: usize, j: usize| i == j;
+        let table = compute_lcs_matrix(100, 100, &same, &OperationClock::with_limit(10_000)).unwrap();
+        assert!(backtrack_changes(&table, 100, 100, &same).is_empty());
+        assert!(compute_lcs_matrix(100, 100, &same, &OperationClock::with_limit(5_000)).is_none());
+        assert!(compute_lcs_matrix(100, 100, &same, budget_clock(60_000, Some(5_000)).as_ref()).is_none());
+    }
+}
@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::Path;
+use crate::git::WorktreeChange;
+
+/// Where GitHub and GitLab look for the file, in the order they look
+pub const CODEOWNERS_LOCATIONS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS", ".gitlab/CODEOWNERS"];
+
+/// One `pattern owner...` line
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnerRule {
+    pub pattern: String,
+    /// Empty when the pattern explicitly has no owner
+    pub owners: Vec<String>,
+}
+
+/// Rules in file order; comments, blank lines and GitLab `[Section]` headers are skipped
+pub fn parse_codeowners(text: &str) -> Vec<OwnerRule> {
+    text.lines()
+        .map(|line| line.split(" #").next().unwrap_or(line).trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('[') && !line.starts_with("^["))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next()?.to_string();
+            Some(OwnerRule { pattern, owners: fields.map(String::from).collect() })
+        })
+        .collect()
+}
+
+/// The first CODEOWNERS file found under `root`
+pub fn load_codeowners(root: &Path) -> Option<Vec<OwnerRule>> {
+    CODEOWNERS_LOCATIONS
+        .iter()
+        .find_map(|location| fs::read_to_string(root.join(location)).ok())
+        .map(|text| parse_codeowners(&text))
+}
+
+/// The owners of `path`; as in CODEOWNERS itself, the last matching rule wins
+pub fn owners_of<'a>(rules: &'a [OwnerRule], path: &str) -> &'a [String] {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| pattern_matches(&rule.pattern, path))
+        .map_or(&[], |rule| rule.owners.as_slice())
+}
+
+/// gitignore-style matching: a leading or inner `/` anchors the pattern at the root, a trailing
+/// `/` matches only directories, and a pattern matching a directory owns everything below it
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let directory_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+
+    // The path itself and each directory containing it
+    let mut candidates: Vec<&str> = path.match_indices('/').map(|(at, _)| &path[..at]).collect();
+    if !directory_only {
+        candidates.push(path);
+    }
+    candidates.iter().any(|candidate| {
+        if anchored {
+            return glob(trimmed.as_bytes(), candidate.as_bytes());
+        }
+        // Unanchored patterns may start at any segment
+        std::iter::once(0)
+            .chain(candidate.match_indices('/').map(|(at, _)| at + 1))
+            .any(|start| glob(trimmed.as_bytes(), &candidate.as_bytes()[start..]))
+    })
+}
+
+/// `*` and `?` stay within a path segment, `**` crosses segments
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|skip| glob(rest, &text[skip..]))
+        }
+        [b'*', rest @ ..] => {
+            let segment = text.iter().position(|&c| c == b'/').unwrap_or(text.len());
+            (0..=segment).any(|skip| glob(rest, &text[skip..]))
+        }
+        [b'?', rest @ ..] => text.first().is_some_and(|&c| c != b'/') && glob(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
+
+/// Changed files grouped by their owners, groups in the order their first file appears, with
+/// files nobody owns last
+pub fn group_by_owners<'a>(rules: &[OwnerRule], files: &'a [WorktreeChange]) -> Vec<(Vec<String>, Vec<&'a WorktreeChange>)> {
+    let mut groups: Vec<(Vec<String>, Vec<&WorktreeChange>)> = Vec::new();
+    for file in files {
+        let owners = owners_of(rules, &file.path).to_vec();
+        match groups.iter_mut().find(|(group, _)| *group == owners) {
+            Some((_, members)) => members.push(file),
+            None => groups.push((owners, vec![file])),
+        }
+    }
+    groups.sort_by_key(|(owners, _)| owners.is_empty());
+    groups
+}
+
+pub fn format_owner_report(groups: &[(Vec<String>, Vec<&WorktreeChange>)]) -> String {
+    let mut output = String::new();
+    for (owners, files) in groups {
+        let heading = if owners.is_empty() { "No owner".to_string() } else { owners.join(" ") };
+        output.push_str(&format!("{} ({} file(s))\n", heading, files.len()));
+        for file in files {
+            let added: usize = file.changes.iter().map(|c| c.modified_end - c.modified_start).sum();
+            let deleted: usize = file.changes.iter().map(|c| c.original_end - c.original_start).sum();
+            output.push_str(&format!("  {} {} +{} -{}\n", file.status, file.path, added, deleted));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owners_of() {
+        let rules = parse_codeowners(
+            "# Default\n*       @org/core\n*.md    @org/docs # prose\n/src/ui/ @alice @bob\nbuild/  @org/infra\n/vendor/\n[Frontend]\napps/**/test_*.js @carol",
+        );
+        assert_eq!(rules.len(), 6);
+        assert_eq!(owners_of(&rules, "src/lib.rs"), ["@org/core"]);
+        assert_eq!(owners_of(&rules, "docs/guide.md"), ["@org/docs"]);
+        assert_eq!(owners_of(&rules, "src/ui/button.rs"), ["@alice", "@bob"]);
+        assert_eq!(owners_of(&rules, "tools/build/run.sh"), ["@org/infra"]);
+        assert!(owners_of(&rules, "vendor/x.c").is_empty());
+        assert_eq!(owners_of(&rules, "apps/web/src/test_login.js"), ["@carol"]);
+        assert_eq!(owners_of(&rules, "x/src/ui/y.rs"), ["@org/core"]);
+    }
+
+    #[test]
+    fn test_group_by_owners() {
+        let file = |path: &str| WorktreeChange {
+            path: path.to_string(),
+            status: 'M',
+            binary: false,
+            submodule: false,
+            original: Vec::new(),
+            modified: Vec::new(),
+            changes: Vec::new(),
+        };
+        let rules = parse_codeowners("src/ @dev\n*.md @writer");
+        let files = vec![file("README.md"), file("notes.txt"), file("src/a.rs"), file("src/b.rs")];
+        let report = format_owner_report(&group_by_owners(&rules, &files));
+        assert_eq!(
+            report,
+            "@writer (1 file(s))\n  M README.md +0 -0\n@dev (2 file(s))\n  M src/a.rs +0 -0\n  M src/b.rs +0 -0\nNo owner (1 file(s))\n  M notes.txt +0 -0\n"
+        );
+    }
+}
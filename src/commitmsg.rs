@@ -0,0 +1,219 @@
+use std::collections::BTreeSet;
+use crate::classify::ChangeTag;
+use crate::diff_core::{compute_diff, DiffOptions, LineChange};
+use crate::git::WorktreeChange;
+use crate::structure::{summarize_functions, FunctionStatus};
+
+/// Share of a deleted file's lines an added file must keep to count as its rename
+const RENAME_SIMILARITY: f32 = 0.5;
+
+/// Names listed in a subject before it falls back to a count
+const SUBJECT_NAMES: usize = 2;
+
+/// A proposed conventional-commit message, to be edited rather than used as is
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommitSuggestion {
+    /// `feat`, `fix`, `refactor`, `style`, `docs`, `test`, `build` or `ci`
+    pub kind: &'static str,
+    pub scope: Option<String>,
+    pub subject: String,
+    /// One line per file, followed by detected renames
+    pub body: Vec<String>,
+}
+
+impl CommitSuggestion {
+    pub fn format(&self) -> String {
+        let scope = self.scope.as_ref().map(|scope| format!("({})", scope)).unwrap_or_default();
+        let mut output = format!("{}{}: {}\n", self.kind, scope, self.subject);
+        if !self.body.is_empty() {
+            output.push('\n');
+            for line in &self.body {
+                output.push_str(&format!("- {}\n", line));
+            }
+        }
+        output
+    }
+}
+
+/// Guess a commit message for `files` from what changed: which kinds of files, which definitions,
+/// how additions compare to deletions, whether hunks are only reformatting or renames, and
+/// which deleted files reappear under another name
+pub fn suggest_commit_message(files: &[WorktreeChange]) -> CommitSuggestion {
+    let renames = detect_renames(files);
+    let renamed: BTreeSet<&str> = renames.iter().flat_map(|(from, to)| [from.as_str(), to.as_str()]).collect();
+    let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    let (added, deleted) = files.iter().flat_map(|file| &file.changes).fold((0, 0), |(a, d), change| {
+        (a + change.modified_end - change.modified_start, d + change.original_end - change.original_start)
+    });
+
+    let mut new_definitions = Vec::new();
+    let mut changed_definitions = Vec::new();
+    for file in files.iter().filter(|file| !file.binary) {
+        for summary in summarize_functions(&file.original, &file.modified, &file.changes) {
+            match summary.status {
+                FunctionStatus::Added => new_definitions.push(summary.name),
+                FunctionStatus::Modified => changed_definitions.push(summary.name),
+                FunctionStatus::Removed => {}
+            }
+        }
+    }
+    let hunks: Vec<&LineChange> = files.iter().flat_map(|file| &file.changes).collect();
+    let only = |tags: &[ChangeTag]| {
+        !hunks.is_empty() && hunks.iter().all(|hunk| hunk.tags.iter().any(|tag| tags.contains(tag)))
+    };
+
+    let (kind, subject) = if paths.iter().all(|path| is_docs(path)) {
+        ("docs", format!("update {}", names(&file_names(&paths))))
+    } else if paths.iter().all(|path| is_test(path)) {
+        ("test", format!("cover {}", names(&file_names(&paths))))
+    } else if paths.iter().all(|path| path.starts_with(".github/")) {
+        ("ci", format!("update {}", names(&file_names(&paths))))
+    } else if paths.iter().all(|path| is_build(path)) {
+        ("build", format!("update {}", names(&file_names(&paths))))
+    } else if !renames.is_empty() && files.iter().all(|file| renamed.contains(file.path.as_str())) {
+        let (from, to) = &renames[0];
+        ("refactor", format!("move {} to {}", from, to))
+    } else if only(&[ChangeTag::Reindent]) {
+        ("style", format!("reformat {}", names(&file_names(&paths))))
+    } else if only(&[ChangeTag::Reindent, ChangeTag::Reorder, ChangeTag::Rename, ChangeTag::ImportOnly]) {
+        ("refactor", format!("tidy {}", names(&file_names(&paths))))
+    } else if !new_definitions.is_empty() && added >= 2 * deleted {
+        ("feat", format!("add {}", names(&new_definitions)))
+    } else if files.iter().all(|file| matches!(file.status, 'A' | '?')) {
+        ("feat", format!("add {}", names(&file_names(&paths))))
+    } else if deleted > 2 * added {
+        ("refactor", format!("remove unused code from {}", names(&file_names(&paths))))
+    } else if !changed_definitions.is_empty() {
+        ("fix", format!("correct {}", names(&changed_definitions)))
+    } else {
+        ("fix", format!("update {}", names(&file_names(&paths))))
+    };
+
+    let mut body: Vec<String> = files
+        .iter()
+        .filter(|file| !renamed.contains(file.path.as_str()))
+        .map(|file| {
+            let added: usize = file.changes.iter().map(|c| c.modified_end - c.modified_start).sum();
+            let deleted: usize = file.changes.iter().map(|c| c.original_end - c.original_start).sum();
+            format!("{}: +{} -{}", file.path, added, deleted)
+        })
+        .collect();
+    body.extend(renames.iter().map(|(from, to)| format!("rename {} -> {}", from, to)));
+
+    CommitSuggestion {
+        kind,
+        scope: scope(&paths),
+        subject,
+        body,
+    }
+}
+
+/// Deleted files whose content reappears in an added file, as `(old, new)` paths
+fn detect_renames(files: &[WorktreeChange]) -> Vec<(String, String)> {
+    let mut taken = BTreeSet::new();
+    let mut renames = Vec::new();
+    for deleted in files.iter().filter(|file| file.status == 'D' && !file.binary) {
+        let candidate = files
+            .iter()
+            .filter(|file| matches!(file.status, 'A' | '?') && !file.binary && !taken.contains(&file.path))
+            .map(|added| (added, kept_share(&deleted.original, &added.modified)))
+            .filter(|(_, share)| *share >= RENAME_SIMILARITY)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((added, _)) = candidate {
+            taken.insert(added.path.clone());
+            renames.push((deleted.path.clone(), added.path.clone()));
+        }
+    }
+    renames
+}
+
+fn kept_share(original: &[String], modified: &[String]) -> f32 {
+    let changes = compute_diff(original, modified, DiffOptions { compute_char_changes: false, ..DiffOptions::default() });
+    let removed: usize = changes.iter().map(|c| c.original_end - c.original_start).sum();
+    (original.len() - removed) as f32 / original.len().max(modified.len()).max(1) as f32
+}
+
+/// The directory every file is under, below a leading `src/`, or the file stem of a lone file
+fn scope(paths: &[&str]) -> Option<String> {
+    if let [path] = paths {
+        let name = path.rsplit('/').next()?;
+        return Some(name.split('.').next().unwrap_or(name).to_string());
+    }
+    let directory = |path: &str| {
+        let path = path.strip_prefix("src/").unwrap_or(path);
+        path.split_once('/').map(|(directory, _)| directory.to_string())
+    };
+    let first = directory(paths.first()?)?;
+    paths.iter().all(|path| directory(path).as_deref() == Some(first.as_str())).then_some(first)
+}
+
+fn file_names(paths: &[&str]) -> Vec<String> {
+    paths.iter().map(|path| path.rsplit('/').next().unwrap_or(path).to_string()).collect()
+}
+
+/// `a`, `a and b`, or `a, b and 3 more`
+fn names(names: &[String]) -> String {
+    match names {
+        [] => "files".to_string(),
+        [one] => one.clone(),
+        [a, b] => format!("{} and {}", a, b),
+        _ => format!("{} and {} more", names[..SUBJECT_NAMES].join(", "), names.len() - SUBJECT_NAMES),
+    }
+}
+
+fn is_docs(path: &str) -> bool {
+    path.starts_with("docs/") || [".md", ".rst", ".txt", ".adoc"].iter().any(|ext| path.ends_with(ext))
+}
+
+pub(crate) fn is_test(path: &str) -> bool {
+    path.starts_with("tests/") || path.contains("/tests/") || path.contains("_test.") || path.contains(".test.")
+        || path.contains(".spec.")
+}
+
+fn is_build(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    matches!(
+        name,
+        "Cargo.toml" | "Cargo.lock" | "package.json" | "package-lock.json" | "yarn.lock" | "Makefile" | "build.rs"
+            | "go.mod" | "go.sum" | "pyproject.toml" | "Dockerfile"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    fn file(path: &str, status: char, original: &str, modified: &str) -> WorktreeChange {
+        let (original, modified) = (lines(original), lines(modified));
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        WorktreeChange { path: path.to_string(), status, binary: false, submodule: false, original, modified, changes }
+    }
+
+    #[test]
+    fn test_suggest_feature_and_fix() {
+        let feature = suggest_commit_message(&[file(
+            "src/cache/store.rs",
+            'M',
+            "fn get() {\n    1\n}",
+            "fn get() {\n    1\n}\n\nfn evict() {\n    clear();\n}",
+        )]);
+        assert_eq!(feature.format(), "feat(store): add evict\n\n- src/cache/store.rs: +4 -0\n");
+
+        let fix = suggest_commit_message(&[
+            file("src/cache/a.rs", 'M', "fn get() {\n    1\n}", "fn get() {\n    2\n}"),
+            file("src/cache/b.rs", 'M', "x\ny", "x\nz"),
+        ]);
+        assert_eq!((fix.kind, fix.scope.as_deref(), fix.subject.as_str()), ("fix", Some("cache"), "correct get"));
+    }
+
+    #[test]
+    fn test_suggest_rename_and_docs() {
+        let body = "alpha\nbeta\ngamma\ndelta";
+        let moved = suggest_commit_message(&[file("old.rs", 'D', body, ""), file("lib/new.rs", '?', "", body)]);
+        assert_eq!(moved.format(), "refactor: move old.rs to lib/new.rs\n\n- rename old.rs -> lib/new.rs\n");
+
+        let docs = suggest_commit_message(&[file("README.md", 'M', "a", "b"), file("docs/guide.md", 'M', "a", "b")]);
+        assert_eq!((docs.kind, docs.subject.as_str()), ("docs", "update README.md and guide.md"));
+    }
+}
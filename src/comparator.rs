@@ -0,0 +1,142 @@
+use crate::diff_core::DiffOptions;
+use crate::logmask::mask_log_prefix;
+use crate::pattern::Pattern;
+
+/// Decides which lines count as unchanged. `key` gives a canonical form used for hashing:
+/// lines that compare equal must share a key.
+pub trait LineComparator {
+    fn key(&self, line: &str) -> String;
+
+    fn eq(&self, a: &str, b: &str) -> bool {
+        self.key(a) == self.key(b)
+    }
+
+    /// True when `eq` is stricter than key equality, so the diff must consult it for lines
+    /// whose keys match
+    fn refines_keys(&self) -> bool {
+        false
+    }
+}
+
+/// The built-in rules selected by `DiffOptions`: log masks, whitespace and case
+pub struct OptionsComparator {
+    mask_patterns: Vec<Pattern>,
+    ignore_whitespace: bool,
+    ignore_case: bool,
+}
+
+impl OptionsComparator {
+    pub fn new(options: &DiffOptions) -> Self {
+        Self {
+            // Invalid patterns are rejected when settings are loaded, skip them here
+            mask_patterns: options
+                .mask_patterns
+                .iter()
+                .filter_map(|source| Pattern::parse(source).ok())
+                .collect(),
+            ignore_whitespace: options.ignore_whitespace,
+            ignore_case: options.ignore_case,
+        }
+    }
+}
+
+impl LineComparator for OptionsComparator {
+    fn key(&self, line: &str) -> String {
+        let mut processed = if self.mask_patterns.is_empty() {
+            line.to_string()
+        } else {
+            mask_log_prefix(line, &self.mask_patterns)
+        };
+        if self.ignore_whitespace {
+            processed = processed.trim().to_string();
+        }
+        if self.ignore_case {
+            processed = processed.to_lowercase();
+        }
+        processed
+    }
+}
+
+/// Treats lines as equal when they differ only in numbers within `tolerance` of each other
+pub struct NumericTolerance {
+    pub tolerance: f64,
+}
+
+impl NumericTolerance {
+    /// Split a line into its non-numeric text (numbers replaced by `#`) and the numbers
+    fn split(line: &str) -> (String, Vec<f64>) {
+        let chars: Vec<char> = line.chars().collect();
+        let mut text = String::new();
+        let mut numbers = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let signed = chars[i] == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit);
+            if chars[i].is_ascii_digit() || signed {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                match number.parse() {
+                    Ok(value) => {
+                        numbers.push(value);
+                        text.push('#');
+                    }
+                    Err(_) => text.push_str(&number),
+                }
+            } else {
+                text.push(chars[i]);
+                i += 1;
+            }
+        }
+        (text, numbers)
+    }
+}
+
+impl LineComparator for NumericTolerance {
+    fn key(&self, line: &str) -> String {
+        Self::split(line).0
+    }
+
+    fn eq(&self, a: &str, b: &str) -> bool {
+        let (text_a, numbers_a) = Self::split(a);
+        let (text_b, numbers_b) = Self::split(b);
+        text_a == text_b
+            && numbers_a
+                .iter()
+                .zip(&numbers_b)
+                .all(|(x, y)| (x - y).abs() <= self.tolerance)
+    }
+
+    fn refines_keys(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_comparator() {
+        let comparator = OptionsComparator::new(&DiffOptions {
+            ignore_whitespace: true,
+            ignore_case: true,
+            ..DiffOptions::default()
+        });
+        assert_eq!(comparator.key("  Hello "), "hello");
+        assert!(comparator.eq("HELLO", "hello  "));
+        assert!(!comparator.refines_keys());
+    }
+
+    #[test]
+    fn test_numeric_tolerance() {
+        let comparator = NumericTolerance { tolerance: 0.01 };
+        assert_eq!(comparator.key("x = -1.5, y = 2"), "x = #, y = #");
+        assert!(comparator.eq("pi = 3.141", "pi = 3.14"));
+        assert!(!comparator.eq("pi = 3.2", "pi = 3.14"));
+        assert!(!comparator.eq("pi = 3.14", "tau = 3.14"));
+    }
+}
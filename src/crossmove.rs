@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::path::Path;
+use crate::dirdiff::{FileStatus, RelativeComparison};
+use crate::file_handler::read_file_lines;
+
+/// Non-blank lines a block needs before it is reported as moved; shorter runs match by chance
+pub const MIN_MOVED_LINES: usize = 3;
+
+/// A block of lines deleted from one file and added to another
+#[derive(Clone, Debug, PartialEq)]
+pub struct CrossFileMove {
+    pub from_path: String,
+    /// 1-based first line in the first root's copy of `from_path`
+    pub from_line: usize,
+    pub to_path: String,
+    /// 1-based first line in the second root's copy of `to_path`
+    pub to_line: usize,
+    pub lines: usize,
+}
+
+/// Lines one side of a comparison lost or gained, and where they start in their file
+struct Block {
+    path: String,
+    start: usize,
+    lines: Vec<String>,
+}
+
+/// Blocks deleted from one file that reappear, maybe reindented, among the lines added to
+/// another, in the order of the deleted lines. Files only in one root count as wholly deleted
+/// or added; moves within a file are left to the per-file diff.
+pub fn detect_cross_file_moves(root_a: &Path, root_b: &Path, results: &[RelativeComparison]) -> Vec<CrossFileMove> {
+    let (mut deleted, mut added) = (Vec::new(), Vec::new());
+    for result in results {
+        let read = |root: &Path| read_file_lines(&root.join(&result.path).to_string_lossy()).unwrap_or_default();
+        let block = |start: usize, lines: &[String]| Block { path: result.path.clone(), start, lines: lines.to_vec() };
+        match &result.status {
+            FileStatus::Modified(changes) => {
+                let (original, modified) = (read(root_a), read(root_b));
+                for change in changes {
+                    // Nested repositories report a synthetic commit line, not file content
+                    if let (Some(old), Some(new)) = (
+                        original.get(change.original_start..change.original_end),
+                        modified.get(change.modified_start..change.modified_end),
+                    ) {
+                        deleted.push(block(change.original_start, old));
+                        added.push(block(change.modified_start, new));
+                    }
+                }
+            }
+            FileStatus::OnlyInA => deleted.push(block(0, &read(root_a))),
+            FileStatus::OnlyInB => added.push(block(0, &read(root_b))),
+            FileStatus::Identical | FileStatus::EffectivelyIdentical(_) | FileStatus::Error(_) => {}
+        }
+    }
+
+    let mut index: HashMap<&str, Vec<(usize, usize)>> = HashMap::new();
+    for (block, target) in added.iter().enumerate() {
+        for (offset, line) in target.lines.iter().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            index.entry(line.trim()).or_default().push((block, offset));
+        }
+    }
+
+    let mut moves = Vec::new();
+    for source in &deleted {
+        let mut offset = 0;
+        while offset < source.lines.len() {
+            // The longest run of equal lines starting here, in any other file's added lines
+            let best = index
+                .get(source.lines[offset].trim())
+                .into_iter()
+                .flatten()
+                .filter(|(block, _)| added[*block].path != source.path)
+                .map(|&(block, at)| {
+                    let target = &added[block].lines;
+                    let length = source.lines[offset..]
+                        .iter()
+                        .zip(&target[at..])
+                        .take_while(|(a, b)| a.trim() == b.trim())
+                        .count();
+                    (length, block, at)
+                })
+                .max_by_key(|(length, _, _)| *length);
+            let Some((length, block, at)) = best else {
+                offset += 1;
+                continue;
+            };
+            let content = source.lines[offset..offset + length].iter().filter(|line| !line.trim().is_empty()).count();
+            if content < MIN_MOVED_LINES {
+                offset += 1;
+                continue;
+            }
+            moves.push(CrossFileMove {
+                from_path: source.path.clone(),
+                from_line: source.start + offset + 1,
+                to_path: added[block].path.clone(),
+                to_line: added[block].start + at + 1,
+                lines: length,
+            });
+            offset += length;
+        }
+    }
+    moves
+}
+
+/// One line per move, or nothing when there are none
+pub fn format_cross_file_moves(moves: &[CrossFileMove]) -> String {
+    if moves.is_empty() {
+        return String::new();
+    }
+    let mut output = format!("Moved between files ({}):\n", moves.len());
+    for moved in moves {
+        output.push_str(&format!(
+            "  {}:{}-{} → {}:{}-{} ({} lines)\n",
+            moved.from_path,
+            moved.from_line,
+            moved.from_line + moved.lines - 1,
+            moved.to_path,
+            moved.to_line,
+            moved.to_line + moved.lines - 1,
+            moved.lines
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::dirdiff::{compare_directories, DirectoryOptions};
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_detect_cross_file_moves() {
+        let root = TempDir::new("crossmove");
+        let (a, b) = (root.join("a"), root.join("b"));
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        let helper = "fn helper(x: u32) -> u32 {\n    let y = x * 2;\n    y + 1\n}\n";
+        fs::write(a.join("main.rs"), format!("{}\nfn main() {{\n    run();\n}}\n", helper)).unwrap();
+        fs::write(b.join("main.rs"), "fn main() {\n    run();\n}\n").unwrap();
+        fs::write(b.join("util.rs"), format!("mod inner {{\n{}\n}}\n", helper.replace("\n", "\n    ").trim_end())).unwrap();
+        fs::write(a.join("short.rs"), "}\n}\n").unwrap();
+
+        let results = compare_directories(&a, &b, &DirectoryOptions::default());
+        let moves = detect_cross_file_moves(&a, &b, &results);
+        assert_eq!(
+            moves,
+            vec![CrossFileMove { from_path: "main.rs".to_string(), from_line: 1, to_path: "util.rs".to_string(), to_line: 2, lines: 4 }]
+        );
+        assert_eq!(format_cross_file_moves(&moves), "Moved between files (1):\n  main.rs:1-4 → util.rs:2-5 (4 lines)\n");
+    }
+}
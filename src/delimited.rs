@@ -0,0 +1,206 @@
+use std::path::Path;
+use crate::diff_core::{compute_diff, DiffOptions};
+use crate::messages::{text, Message};
+use crate::token_diff::{compute_token_diff, Token};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnChange {
+    /// 1-based position in the modified file
+    Added { position: usize, name: String },
+    /// 1-based position in the original file
+    Removed { position: usize, name: String },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DelimitedDiff {
+    pub delimiter: char,
+    pub columns: Vec<ColumnChange>,
+    /// Row changes computed on the columns both files share, with 1-based line numbers
+    pub rows: Vec<RowChange>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowChange {
+    pub removed: Vec<(usize, String)>,
+    pub added: Vec<(usize, String)>,
+}
+
+/// Delimiter implied by the extension, or the more frequent of tab and comma in the header
+pub fn detect_delimiter(path: &str, header: &str) -> char {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("tsv") | Some("tab") => '\t',
+        Some("csv") => ',',
+        _ if header.matches('\t').count() >= header.matches(',').count() => '\t',
+        _ => ',',
+    }
+}
+
+pub fn is_delimited_path(path: &str) -> bool {
+    matches!(
+        Path::new(path).extension().and_then(|e| e.to_str()),
+        Some("tsv") | Some("tab") | Some("csv")
+    )
+}
+
+/// Split one record, honouring double-quoted fields with `""` escapes
+pub fn split_record(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Detect inserted and removed columns from the header rows, then diff the data rows
+/// projected onto the shared columns so a new column does not mark every row modified
+pub fn compare_delimited(
+    original: &[String],
+    modified: &[String],
+    delimiter: char,
+    options: &DiffOptions,
+) -> DelimitedDiff {
+    let header = |lines: &[String]| -> Vec<Token> {
+        lines
+            .first()
+            .map(|line| split_record(line, delimiter))
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| Token::new(name, i..i + 1))
+            .collect()
+    };
+    let (original_header, modified_header) = (header(original), header(modified));
+
+    let mut columns = Vec::new();
+    let mut dropped_original = Vec::new();
+    let mut dropped_modified = Vec::new();
+    for change in compute_token_diff(&original_header, &modified_header) {
+        for i in change.original.clone() {
+            dropped_original.push(i);
+            columns.push(ColumnChange::Removed {
+                position: i + 1,
+                name: original_header[i].text.clone(),
+            });
+        }
+        for j in change.modified.clone() {
+            dropped_modified.push(j);
+            columns.push(ColumnChange::Added {
+                position: j + 1,
+                name: modified_header[j].text.clone(),
+            });
+        }
+    }
+
+    // Rows are compared on shared columns only; a renamed column counts as removed + added
+    let project = |lines: &[String], dropped: &[usize]| -> Vec<String> {
+        lines
+            .iter()
+            .skip(1)
+            .map(|line| {
+                split_record(line, delimiter)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| !dropped.contains(i))
+                    .map(|(_, field)| field)
+                    .collect::<Vec<_>>()
+                    .join(&delimiter.to_string())
+            })
+            .collect()
+    };
+    let original_rows = project(original, &dropped_original);
+    let modified_rows = project(modified, &dropped_modified);
+
+    let rows = compute_diff(&original_rows, &modified_rows, options.clone())
+        .into_iter()
+        .map(|change| {
+            let numbered = |rows: &[String], start: usize, end: usize| -> Vec<(usize, String)> {
+                (start..end).map(|i| (i + 2, rows[i].clone())).collect()
+            };
+            RowChange {
+                removed: numbered(&original_rows, change.original_start, change.original_end),
+                added: numbered(&modified_rows, change.modified_start, change.modified_end),
+            }
+        })
+        .collect();
+
+    DelimitedDiff {
+        delimiter,
+        columns,
+        rows,
+    }
+}
+
+pub fn format_delimited_diff(file1_path: &str, file2_path: &str, diff: &DelimitedDiff) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", file1_path, file2_path);
+    if diff.columns.is_empty() && diff.rows.is_empty() {
+        output.push_str(&format!("\n{}\n", text(Message::FilesIdentical, [])));
+        return output;
+    }
+
+    if !diff.columns.is_empty() {
+        output.push('\n');
+        for change in &diff.columns {
+            output.push_str(&match change {
+                ColumnChange::Added { position, name } => format!("column {} \"{}\" added\n", position, name),
+                ColumnChange::Removed { position, name } => format!("column {} \"{}\" removed\n", position, name),
+            });
+        }
+    }
+
+    for change in &diff.rows {
+        output.push('\n');
+        for (line, row) in &change.removed {
+            output.push_str(&format!("-{:>5}: {}\n", line, row.replace(diff.delimiter, " | ")));
+        }
+        for (line, row) in &change.added {
+            output.push_str(&format!("+{:>5}: {}\n", line, row.replace(diff.delimiter, " | ")));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_split_record() {
+        assert_eq!(split_record("a,\"b,c\",\"say \"\"hi\"\"\"", ','), vec!["a", "b,c", "say \"hi\""]);
+        assert_eq!(detect_delimiter("data.txt", "a\tb\tc"), '\t');
+    }
+
+    #[test]
+    fn test_added_column_does_not_modify_rows() {
+        let original = lines("id\tname\n1\tann\n2\tbob\n3\tcy");
+        let modified = lines("id\tname\tage\n1\tann\t30\n2\tbo\t40\n3\tcy\t50");
+
+        let diff = compare_delimited(&original, &modified, '\t', &DiffOptions::default());
+        assert_eq!(
+            diff.columns,
+            vec![ColumnChange::Added {
+                position: 3,
+                name: "age".to_string()
+            }]
+        );
+        assert_eq!(diff.rows.len(), 1);
+        assert_eq!(diff.rows[0].removed, vec![(3, "2\tbob".to_string())]);
+
+        let text = format_delimited_diff("a.tsv", "b.tsv", &diff);
+        assert!(text.contains("column 3 \"age\" added\n"));
+        assert!(text.contains("-    3: 2 | bob\n+    3: 2 | bo\n"));
+    }
+}
@@ -1,15 +1,110 @@
-use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::classify::{classify_changes, ChangeTag};
+use crate::clock::{budget_clock, Clock};
+use crate::comparator::{LineComparator, OptionsComparator};
+use crate::error::{DiffError, VerifyError};
+use crate::generated::mark_generated_changes;
+use crate::hunk_id::assign_hunk_ids;
+use crate::imports::normalize_import_blocks;
+use crate::embedded::{detect_embedded_regions, granularity_for_language, region_at};
+use crate::anchors::{anchored_changes, find_anchor_runs, DEFAULT_MIN_ANCHOR_LINES};
+use crate::band::{band_changes, DEFAULT_BAND_WIDTH};
+use crate::patience::{patience_changes, PATIENCE_LEAF_CELLS};
+use crate::trace::{self, Category, Level};
+use crate::planner::{plan_diff, DiffAlgorithm, DiffStats, MemoryEstimate, MIN_FULL_CELLS};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DiffOptions {
     pub ignore_whitespace: bool,
     pub ignore_case: bool,
     pub max_computation_time_ms: u64,
     pub compute_char_changes: bool,
+    pub granularity: Granularity,
+    /// Pick the intra-line granularity per embedded-language region
+    pub detect_embedded_languages: bool,
+    /// Sort import/use/include blocks before diffing; positions inside a block refer to sorted order
+    pub normalize_imports: bool,
+    /// Patterns for leading log noise (timestamps, PIDs) stripped before comparing lines
+    #[serde(default)]
+    pub mask_patterns: Vec<String>,
+    /// Upper bound on the estimated working set of a diff; `None` means unlimited
+    #[serde(default)]
+    pub max_memory_bytes: Option<u64>,
+    /// Upper bound on the work of a diff, counted in LCS cells and lines compared, on top of
+    /// `max_computation_time_ms`; unlike time it gives the same result on every machine
+    #[serde(default)]
+    pub max_operations: Option<u64>,
+    /// Check every result with `verify` against the preprocessed lines
+    #[serde(default)]
+    pub verify: bool,
+    /// Diagonals either side of the straight path searched first for near-identical inputs;
+    /// `None` means `DEFAULT_BAND_WIDTH`, 0 never bands
+    #[serde(default)]
+    pub band_width: Option<usize>,
+    /// Shortest run of lines unique to both sides that splits a large diff into independent
+    /// gaps; `None` means `DEFAULT_MIN_ANCHOR_LINES`, 0 never splits
+    #[serde(default)]
+    pub min_anchor_lines: Option<usize>,
 }
 
-#[derive(Clone, Debug)]
+/// Token pairs one intra-line diff may compare (its LCS table size); past this a minified line
+/// is diffed by words, then as a single change
+pub const MAX_CHAR_DIFF_PAIRS: usize = 4_000_000;
+
+/// Leaves headroom below the 4 GiB address space of a wasm32 sandbox
+pub const DEFAULT_MAX_MEMORY_BYTES: u64 = 1 << 30;
+
+/// Unit used when computing intra-line changes
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Granularity {
+    Char,
+    Word,
+    /// No intra-line detail, the whole range is one change
+    Line,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            ignore_whitespace: false,
+            ignore_case: false,
+            max_computation_time_ms: 5000,
+            compute_char_changes: true,
+            granularity: Granularity::Char,
+            detect_embedded_languages: false,
+            normalize_imports: false,
+            mask_patterns: Vec::new(),
+            max_memory_bytes: Some(DEFAULT_MAX_MEMORY_BYTES),
+            max_operations: None,
+            verify: cfg!(debug_assertions),
+            band_width: None,
+            min_anchor_lines: None,
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Whether diffing with `other` instead could give a different result. A diff that ran out
+    /// of its time, operation or memory budget fell back to a coarser one, and a cached diff does
+    /// not record whether it did, so budget changes count too; only `verify` is left out, as it
+    /// changes nothing unless the engine is wrong.
+    pub fn changes_result(&self, other: &DiffOptions) -> bool {
+        self.ignore_whitespace != other.ignore_whitespace
+            || self.ignore_case != other.ignore_case
+            || self.compute_char_changes != other.compute_char_changes
+            || self.granularity != other.granularity
+            || self.detect_embedded_languages != other.detect_embedded_languages
+            || self.normalize_imports != other.normalize_imports
+            || self.mask_patterns != other.mask_patterns
+            || self.min_anchor_lines != other.min_anchor_lines
+            || self.band_width != other.band_width
+            || self.max_computation_time_ms != other.max_computation_time_ms
+            || self.max_operations != other.max_operations
+            || self.max_memory_bytes != other.max_memory_bytes
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LineChange {
     pub original_start: usize,
     pub original_end: usize,
@@ -17,16 +112,23 @@ pub struct LineChange {
     pub modified_end: usize,
     pub change_type: ChangeType,
     pub char_changes: Option<Vec<CharChange>>,
+    /// Entirely inside `@generated` marker regions
+    pub generated: bool,
+    #[serde(default)]
+    pub tags: Vec<ChangeTag>,
+    /// Content-derived identifier, stable across re-diffs (see `hunk_id`)
+    #[serde(default)]
+    pub id: String,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ChangeType {
     Added,
     Deleted,
     Modified,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CharChange {
     pub original_start: usize,
     pub original_length: usize,
@@ -34,62 +136,326 @@ pub struct CharChange {
     pub modified_length: usize,
 }
 
-/// Compute diff between two sets of lines using Myers algorithm
+/// Compute diff between two sets of lines with the algorithm `plan_diff` picks: the full LCS
+/// table when the inputs fit the time and memory budgets, anchoring on unique lines when they
+/// do not but are similar, and otherwise a coarse diff, one change spanning everything between
+/// the common prefix and suffix. Large inputs are first split at long runs of lines unique to
+/// both sides, and only the gaps between them are diffed. Results failing `verify` are
+/// coarsened too.
 pub fn compute_diff(
     original_lines: &[String],
     modified_lines: &[String],
     options: DiffOptions,
 ) -> Vec<LineChange> {
-    let start_time = Instant::now();
-    let timeout = Duration::from_millis(options.max_computation_time_ms);
-
-    // Preprocess lines based on options
-    let processed_original = preprocess_lines(original_lines, &options);
-    let processed_modified = preprocess_lines(modified_lines, &options);
-
-    // Build line hash map for faster comparison
-    let original_hashes = hash_lines(&processed_original);
-    let modified_hashes = hash_lines(&processed_modified);
-
-    // Compute LCS using Myers algorithm with DP
-    let lcs_matrix = compute_lcs_matrix(
-        &original_hashes,
-        &modified_hashes,
-        start_time,
-        timeout,
-    );
-
-    // Backtrack to find changes
-    let changes = backtrack_changes(
-        &lcs_matrix,
-        original_lines,
-        modified_lines,
-        &original_hashes,
-        &modified_hashes,
-    );
-
-    // Compute character-level changes if requested
-    if options.compute_char_changes {
-        compute_character_changes(changes, original_lines, modified_lines)
+    let comparator = OptionsComparator::new(&options);
+    compute_diff_with(original_lines, modified_lines, options, &comparator)
+}
+
+/// `compute_diff` with custom line equivalence in place of the whitespace, case and mask options
+pub fn compute_diff_with(
+    original_lines: &[String],
+    modified_lines: &[String],
+    options: DiffOptions,
+    comparator: &dyn LineComparator,
+) -> Vec<LineChange> {
+    // Cannot fail when falling back to the coarse diff
+    diff_within_budget(original_lines, modified_lines, options, comparator, true).map(|(changes, _)| changes).unwrap_or_default()
+}
+
+/// Like `compute_diff`, but fails with a `DiffError` instead of coarsening
+pub fn try_compute_diff(
+    original_lines: &[String],
+    modified_lines: &[String],
+    options: DiffOptions,
+) -> Result<Vec<LineChange>, DiffError> {
+    let comparator = OptionsComparator::new(&options);
+    diff_within_budget(original_lines, modified_lines, options, &comparator, false).map(|(changes, _)| changes)
+}
+
+/// `compute_diff`, also reporting which algorithm the planner picked and why
+pub fn compute_diff_with_stats(
+    original_lines: &[String],
+    modified_lines: &[String],
+    options: DiffOptions,
+) -> (Vec<LineChange>, DiffStats) {
+    let comparator = OptionsComparator::new(&options);
+    // Cannot fail when falling back to the coarse diff
+    diff_within_budget(original_lines, modified_lines, options, &comparator, true).unwrap_or_else(|_| unreachable!())
+}
+
+fn diff_within_budget(
+    original_lines: &[String],
+    modified_lines: &[String],
+    options: DiffOptions,
+    comparator: &dyn LineComparator,
+    coarse_over_budget: bool,
+) -> Result<(Vec<LineChange>, DiffStats), DiffError> {
+    let clock = budget_clock(options.max_computation_time_ms, options.max_operations);
+    let clock = clock.as_ref();
+    let memory = MemoryEstimate {
+        full: estimate_memory(original_lines, modified_lines, &options),
+        patience: estimate_patience_memory(original_lines, modified_lines, &options),
+        band: estimate_band_memory(original_lines, modified_lines, &options),
+    };
+
+    let normalized;
+    let (original_lines, modified_lines) = if options.normalize_imports {
+        normalized = (
+            normalize_import_blocks(original_lines),
+            normalize_import_blocks(modified_lines),
+        );
+        (&normalized.0[..], &normalized.1[..])
     } else {
-        changes
+        (original_lines, modified_lines)
+    };
+
+    // Canonical keys and their hashes for fast comparison
+    let original = HashedLines::new(original_lines, comparator);
+    let modified = HashedLines::new(modified_lines, comparator);
+    let same = |i: usize, j: usize| original.same_line(i, &modified, j);
+
+    let plan = plan_diff(&original.hashes, &modified.hashes, memory, &options);
+    if let (DiffAlgorithm::Coarse, Some(limit)) = (plan.algorithm, options.max_memory_bytes) {
+        if memory.full > limit && !coarse_over_budget {
+            return Err(DiffError::MemoryLimit { required: memory.full, limit });
+        }
     }
-}
+    trace::log(Level::Info, Category::Algorithm, || {
+        format!("{} diff of {} and {} lines: {}", plan.algorithm.name(), original.len(), modified.len(), plan.reason)
+    });
+    let mut stats = DiffStats {
+        algorithm: plan.algorithm,
+        reason: plan.reason,
+        similarity: plan.similarity,
+        original_lines: original.len(),
+        modified_lines: modified.len(),
+        fell_back: false,
+        anchor_runs: 0,
+        elapsed_ms: 0,
+    };
 
-fn preprocess_lines(lines: &[String], options: &DiffOptions) -> Vec<String> {
-    lines
-        .iter()
-        .map(|line| {
-            let mut processed = line.clone();
-            if options.ignore_whitespace {
-                processed = processed.trim().to_string();
-            }
-            if options.ignore_case {
-                processed = processed.to_lowercase();
+    // Long runs of lines unique to both sides split large inputs into gaps diffed on their own
+    let cells = original.len() as u64 * modified.len() as u64;
+    let min_anchor_lines = options.min_anchor_lines.unwrap_or(DEFAULT_MIN_ANCHOR_LINES);
+    let runs = if cells > MIN_FULL_CELLS {
+        find_anchor_runs(&original.hashes, &modified.hashes, &same, min_anchor_lines)
+    } else {
+        Vec::new()
+    };
+    stats.anchor_runs = runs.len();
+    trace::log(Level::Debug, Category::Algorithm, || format!("split at {} unique runs of common lines", runs.len()));
+    let band_width = options.band_width.unwrap_or(DEFAULT_BAND_WIDTH);
+    // Whatever the budget leaves for a band's cells once everything else is held
+    let word = std::mem::size_of::<usize>() as u64;
+    let overhead = memory.band - band_cells(original.len(), modified.len(), band_width) * word;
+    let max_cells = options.max_memory_bytes.map_or(u64::MAX, |limit| limit.saturating_sub(overhead) / word);
+
+    // Past the time budget the full table gives way to a coarse but still correct diff
+    let mut diff_gap = |a0: usize, a1: usize, b0: usize, b1: usize| -> Vec<LineChange> {
+        let (m, n) = (a1 - a0, b1 - b0);
+        let (original_hashes, modified_hashes) = (&original.hashes[a0..a1], &modified.hashes[b0..b1]);
+        let same = |i: usize, j: usize| same(a0 + i, b0 + j);
+        match plan.algorithm {
+            DiffAlgorithm::Full => match compute_lcs_matrix(m, n, &same, clock) {
+                Some(lcs_matrix) => backtrack_changes(&lcs_matrix, m, n, &same),
+                None => {
+                    stats.fell_back = true;
+                    coarse_changes(m, n, &same)
+                }
+            },
+            // A band that times out or must widen past the memory budget gives way to anchoring
+            DiffAlgorithm::Banded => band_changes(m, n, &same, band_width, max_cells, clock).unwrap_or_else(|| {
+                stats.fell_back = true;
+                patience_changes(original_hashes, modified_hashes, &same, clock)
+            }),
+            DiffAlgorithm::Patience => patience_changes(original_hashes, modified_hashes, &same, clock),
+            DiffAlgorithm::Coarse => coarse_changes(m, n, &same),
+        }
+    };
+    let mut changes = anchored_changes(original.len(), modified.len(), &runs, &mut diff_gap);
+    if options.verify {
+        if let Err(error) = verify(&original.keys, &modified.keys, &changes) {
+            if !coarse_over_budget {
+                return Err(DiffError::Verify(error));
             }
-            processed
-        })
-        .collect()
+            trace::log(Level::Warn, Category::Algorithm, || format!("diff failed verification ({}), coarsened", error));
+            stats.fell_back = true;
+            changes = coarse_changes(original.len(), modified.len(), &same);
+        }
+    }
+    mark_generated_changes(&mut changes, original_lines, modified_lines);
+    classify_changes(&mut changes, original_lines, modified_lines);
+    assign_hunk_ids(&mut changes, original_lines, modified_lines);
+
+    // Compute character-level changes if requested; skipped for a planned coarse diff since
+    // its change can span the whole file
+    if options.compute_char_changes && plan.algorithm != DiffAlgorithm::Coarse {
+        changes = compute_character_changes(changes, original_lines, modified_lines, &options);
+    }
+    stats.elapsed_ms = clock.elapsed_ms();
+    if stats.fell_back {
+        trace::log(Level::Warn, Category::Algorithm, || format!("{} diff ran out of budget and fell back", plan.algorithm.name()));
+    }
+    Ok((changes, stats))
+}
+
+/// Check that applying `changes` to `original` reproduces `modified`: hunks are ordered and
+/// in bounds, and every line outside them is identical on both sides
+pub fn verify(original: &[String], modified: &[String], changes: &[LineChange]) -> Result<(), VerifyError> {
+    let (mut original_position, mut modified_position) = (0, 0);
+
+    for (hunk, change) in changes.iter().enumerate() {
+        if change.original_start < original_position
+            || change.modified_start < modified_position
+            || change.original_end < change.original_start
+            || change.modified_end < change.modified_start
+        {
+            return Err(VerifyError::OutOfOrder { hunk });
+        }
+        if change.original_end > original.len() || change.modified_end > modified.len() {
+            return Err(VerifyError::OutOfBounds { hunk });
+        }
+        verify_unchanged(
+            &original[original_position..change.original_start],
+            &modified[modified_position..change.modified_start],
+            (original_position, modified_position),
+            Some(hunk),
+        )?;
+        original_position = change.original_end;
+        modified_position = change.modified_end;
+    }
+
+    verify_unchanged(
+        &original[original_position..],
+        &modified[modified_position..],
+        (original_position, modified_position),
+        None,
+    )
+}
+
+fn verify_unchanged(
+    original: &[String],
+    modified: &[String],
+    (original_offset, modified_offset): (usize, usize),
+    hunk: Option<usize>,
+) -> Result<(), VerifyError> {
+    if original.len() != modified.len() {
+        return Err(VerifyError::GapMismatch { hunk });
+    }
+    match original.iter().zip(modified).position(|(a, b)| a != b) {
+        Some(index) => Err(VerifyError::LineMismatch {
+            original_line: original_offset + index,
+            modified_line: modified_offset + index,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Approximate peak bytes held by a line diff: the input lines and their preprocessed
+/// copies, line hashes, and the LCS matrix
+pub fn estimate_memory(original_lines: &[String], modified_lines: &[String], options: &DiffOptions) -> u64 {
+    let (m, n) = (original_lines.len() as u64, modified_lines.len() as u64);
+    let word = std::mem::size_of::<usize>() as u64;
+    let row = std::mem::size_of::<Vec<usize>>() as u64;
+
+    input_memory(original_lines, modified_lines, options) + (m + 1) * ((n + 1) * word + row)
+}
+
+/// As `estimate_memory` for the patience algorithm: the inputs, an anchor table entry per
+/// line, and the LCS matrix of its largest gap
+pub fn estimate_patience_memory(original_lines: &[String], modified_lines: &[String], options: &DiffOptions) -> u64 {
+    let lines = (original_lines.len() + modified_lines.len()) as u64;
+    let word = std::mem::size_of::<usize>() as u64;
+    input_memory(original_lines, modified_lines, options) + lines * 5 * word + PATIENCE_LEAF_CELLS as u64 * word
+}
+
+/// As `estimate_memory` for a band of `options.band_width` diagonals before any widening
+pub fn estimate_band_memory(original_lines: &[String], modified_lines: &[String], options: &DiffOptions) -> u64 {
+    let band_width = options.band_width.unwrap_or(DEFAULT_BAND_WIDTH);
+    let word = std::mem::size_of::<usize>() as u64;
+    let row = std::mem::size_of::<Vec<usize>>() as u64;
+    let cells = band_cells(original_lines.len(), modified_lines.len(), band_width);
+    input_memory(original_lines, modified_lines, options) + cells * word + (original_lines.len() as u64 + 1) * row
+}
+
+/// Cells of the band `band_changes` starts from
+fn band_cells(m: usize, n: usize, band_width: usize) -> u64 {
+    (m as u64 + 1) * (m.abs_diff(n) + 2 * band_width.max(1) + 1) as u64
+}
+
+/// The input lines, their preprocessed copies, and line hashes
+fn input_memory(original_lines: &[String], modified_lines: &[String], options: &DiffOptions) -> u64 {
+    let text = |lines: &[String]| -> u64 {
+        lines
+            .iter()
+            .map(|line| (line.len() + std::mem::size_of::<String>()) as u64)
+            .sum()
+    };
+    let copies = if options.normalize_imports { 3 } else { 2 };
+    copies * (text(original_lines) + text(modified_lines)) + 8 * (original_lines.len() + modified_lines.len()) as u64
+}
+
+/// A single change covering everything between the common prefix and suffix of sequences of
+/// length `m` and `n`, where `same(i, j)` compares their elements
+pub(crate) fn coarse_changes(m: usize, n: usize, same: &impl Fn(usize, usize) -> bool) -> Vec<LineChange> {
+    let prefix = (0..m.min(n)).take_while(|&i| same(i, i)).count();
+    let suffix = (0..(m - prefix).min(n - prefix))
+        .take_while(|&k| same(m - 1 - k, n - 1 - k))
+        .count();
+    let original_end = m - suffix;
+    let modified_end = n - suffix;
+
+    let change_type = match (prefix == original_end, prefix == modified_end) {
+        (true, true) => return Vec::new(),
+        (true, false) => ChangeType::Added,
+        (false, true) => ChangeType::Deleted,
+        (false, false) => ChangeType::Modified,
+    };
+    vec![LineChange {
+        original_start: prefix,
+        original_end,
+        modified_start: prefix,
+        modified_end,
+        change_type,
+        char_changes: None,
+        generated: false,
+        tags: Vec::new(),
+        id: String::new(),
+    }]
+}
+
+/// Lines with their canonical keys and key hashes; hashes reject most mismatches cheaply,
+/// keys settle collisions, and refining comparators get the final say
+struct HashedLines<'a> {
+    lines: &'a [String],
+    keys: Vec<String>,
+    hashes: Vec<u64>,
+    refine: Option<&'a dyn LineComparator>,
+}
+
+impl<'a> HashedLines<'a> {
+    fn new(lines: &'a [String], comparator: &'a dyn LineComparator) -> Self {
+        let keys: Vec<String> = lines.iter().map(|line| comparator.key(line)).collect();
+        let hashes = hash_lines(&keys);
+        Self {
+            lines,
+            keys,
+            hashes,
+            refine: comparator.refines_keys().then_some(comparator),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn same_line(&self, i: usize, other: &HashedLines, j: usize) -> bool {
+        self.hashes[i] == other.hashes[j]
+            && self.keys[i] == other.keys[j]
+            && self
+                .refine
+                .is_none_or(|comparator| comparator.eq(&self.lines[i], &other.lines[j]))
+    }
 }
 
 fn hash_lines(lines: &[String]) -> Vec<u64> {
@@ -106,27 +472,26 @@ fn hash_lines(lines: &[String]) -> Vec<u64> {
         .collect()
 }
 
-fn compute_lcs_matrix(
-    original_hashes: &[u64],
-    modified_hashes: &[u64],
-    start_time: Instant,
-    timeout: Duration,
-) -> Vec<Vec<usize>> {
-    let m = original_hashes.len();
-    let n = modified_hashes.len();
+/// `None` when the clock's budget runs out before the matrix is complete
+pub(crate) fn compute_lcs_matrix(
+    m: usize,
+    n: usize,
+    same: &impl Fn(usize, usize) -> bool,
+    clock: &dyn Clock,
+) -> Option<Vec<Vec<usize>>> {
 
     // Create DP matrix (m+1) x (n+1)
     let mut dp = vec![vec![0; n + 1]; m + 1];
 
     // Fill DP matrix
     for i in 1..=m {
-        // Check timeout
-        if start_time.elapsed() > timeout {
-            break;
+        // Check the budget, charging the row about to be filled
+        if clock.expired(n as u64) {
+            return None;
         }
 
         for j in 1..=n {
-            if original_hashes[i - 1] == modified_hashes[j - 1] {
+            if same(i - 1, j - 1) {
                 dp[i][j] = dp[i - 1][j - 1] + 1;
             } else {
                 dp[i][j] = dp[i - 1][j].max(dp[i][j - 1]);
@@ -134,21 +499,21 @@ fn compute_lcs_matrix(
         }
     }
 
-    dp
+    Some(dp)
 }
-fn backtrack_changes(
+
+pub(crate) fn backtrack_changes(
     lcs_matrix: &[Vec<usize>],
-    original_lines: &[String],
-    modified_lines: &[String],
-    original_hashes: &[u64],
-    modified_hashes: &[u64],
+    m: usize,
+    n: usize,
+    same: &impl Fn(usize, usize) -> bool,
 ) -> Vec<LineChange> {
     let mut changes = Vec::new();
-    let mut i = original_hashes.len();
-    let mut j = modified_hashes.len();
+    let mut i = m;
+    let mut j = n;
 
     while i > 0 || j > 0 {
-        if i > 0 && j > 0 && original_hashes[i - 1] == modified_hashes[j - 1] {
+        if i > 0 && j > 0 && same(i - 1, j - 1) {
             // Lines match, no change
             i -= 1;
             j -= 1;
@@ -161,6 +526,9 @@ fn backtrack_changes(
                 modified_end: j,
                 change_type: ChangeType::Deleted,
                 char_changes: None,
+                generated: false,
+                tags: Vec::new(),
+                id: String::new(),
             });
             i -= 1;
         } else if j > 0 {
@@ -172,6 +540,9 @@ fn backtrack_changes(
                 modified_end: j,
                 change_type: ChangeType::Added,
                 char_changes: None,
+                generated: false,
+                tags: Vec::new(),
+                id: String::new(),
             });
             j -= 1;
         }
@@ -181,7 +552,7 @@ fn backtrack_changes(
     merge_adjacent_changes(changes)
 }
 
-fn merge_adjacent_changes(changes: Vec<LineChange>) -> Vec<LineChange> {
+pub(crate) fn merge_adjacent_changes(changes: Vec<LineChange>) -> Vec<LineChange> {
     if changes.is_empty() {
         return changes;
     }
@@ -193,8 +564,7 @@ fn merge_adjacent_changes(changes: Vec<LineChange>) -> Vec<LineChange> {
         if should_merge(&current, &change) {
             current.original_end = change.original_end;
             current.modified_end = change.modified_end;
-            if current.change_type == ChangeType::Deleted 
-                && change.change_type == ChangeType::Added {
+            if current.change_type != change.change_type {
                 current.change_type = ChangeType::Modified;
             }
         } else {
@@ -207,25 +577,35 @@ fn merge_adjacent_changes(changes: Vec<LineChange>) -> Vec<LineChange> {
 }
 
 fn should_merge(a: &LineChange, b: &LineChange) -> bool {
-    // Merge adjacent deletions and insertions into modifications
-    (a.change_type == ChangeType::Deleted && b.change_type == ChangeType::Added)
-        || (a.change_type == b.change_type 
-            && a.original_end == b.original_start 
-            && a.modified_end == b.modified_start)
+    // Merge touching changes; a deletion next to an insertion becomes a modification
+    a.original_end == b.original_start && a.modified_end == b.modified_start
 }
 
 fn compute_character_changes(
     mut changes: Vec<LineChange>,
     original_lines: &[String],
     modified_lines: &[String],
+    options: &DiffOptions,
 ) -> Vec<LineChange> {
+    let regions = if options.detect_embedded_languages {
+        detect_embedded_regions(original_lines)
+    } else {
+        Vec::new()
+    };
+
+    // Intra-line diffs share one time budget of their own; pairs past it become whole changes
+    let clock = budget_clock(options.max_computation_time_ms, options.max_operations);
     for change in &mut changes {
         if change.change_type == ChangeType::Modified {
+            let granularity = region_at(&regions, change.original_start)
+                .map(|region| granularity_for_language(&region.language))
+                .unwrap_or(options.granularity);
+
             // Compute character-level diff for modified lines
             let orig_text = get_line_range(original_lines, change.original_start, change.original_end);
             let mod_text = get_line_range(modified_lines, change.modified_start, change.modified_end);
 
-            change.char_changes = Some(compute_char_diff(&orig_text, &mod_text));
+            change.char_changes = Some(compute_char_diff(&orig_text, &mod_text, granularity, clock.as_ref()));
         }
     }
     changes
@@ -235,10 +615,15 @@ fn get_line_range(lines: &[String], start: usize, end: usize) -> String {
     lines[start..end].join("\n")
 }
 
-fn compute_char_diff(original: &str, modified: &str) -> Vec<CharChange> {
-    // Simplified character-level diff
-    let orig_chars: Vec<char> = original.chars().collect();
-    let mod_chars: Vec<char> = modified.chars().collect();
+fn compute_char_diff(
+    original: &str,
+    modified: &str,
+    granularity: Granularity,
+    clock: &dyn Clock,
+) -> Vec<CharChange> {
+    // Simplified LCS over tokens of the requested granularity
+    let (orig_chars, orig_offsets) = tokenize(original, granularity);
+    let (mod_chars, mod_offsets) = tokenize(modified, granularity);
 
     let m = orig_chars.len();
     let n = mod_chars.len();
@@ -247,95 +632,218 @@ fn compute_char_diff(original: &str, modified: &str) -> Vec<CharChange> {
         return Vec::new();
     }
 
-    // Simple character-level LCS
-    let mut dp = vec![vec![0; n + 1]; m + 1];
-
-    for i in 1..=m {
-        for j in 1..=n {
-            if orig_chars[i - 1] == mod_chars[j - 1] {
-                dp[i][j] = dp[i - 1][j - 1] + 1;
-            } else {
-                dp[i][j] = dp[i - 1][j].max(dp[i][j - 1]);
-            }
+    if m.saturating_mul(n) > MAX_CHAR_DIFF_PAIRS {
+        // Minified lines: words are far fewer than characters, so try those before giving up
+        if granularity == Granularity::Char {
+            return compute_char_diff(original, modified, Granularity::Word, clock);
         }
+        return whole_char_change(original, modified);
     }
 
+    let same = |i: usize, j: usize| orig_chars[i] == mod_chars[j];
+    let Some(dp) = compute_lcs_matrix(m, n, &same, clock) else {
+        trace::log(Level::Debug, Category::Algorithm, || "intra-line budget spent, pair shown as one change".to_string());
+        return whole_char_change(original, modified);
+    };
+
     // Backtrack to find character changes
     let mut char_changes = Vec::new();
     let mut i = m;
     let mut j = n;
-    let mut del_start = None;
-    let mut ins_start = None;
+    let mut del_end = None;
+    let mut ins_end = None;
 
     while i > 0 || j > 0 {
         if i > 0 && j > 0 && orig_chars[i - 1] == mod_chars[j - 1] {
             // Flush pending changes
-            if let (Some(ds), Some(is)) = (del_start, ins_start) {
-                char_changes.push(CharChange {
-                    original_start: ds,
-                    original_length: i - ds,
-                    modified_start: is,
-                    modified_length: j - is,
-                });
-                del_start = None;
-                ins_start = None;
-            }
+            flush_char_change(&mut char_changes, &mut del_end, &mut ins_end, i, j);
             i -= 1;
             j -= 1;
         } else if i > 0 && (j == 0 || dp[i][j] == dp[i - 1][j]) {
-            if del_start.is_none() {
-                del_start = Some(i - 1);
+            if del_end.is_none() {
+                del_end = Some(i);
             }
             i -= 1;
         } else {
-            if ins_start.is_none() {
-                ins_start = Some(j - 1);
+            if ins_end.is_none() {
+                ins_end = Some(j);
             }
             j -= 1;
         }
     }
 
-    if let (Some(ds), Some(is)) = (del_start, ins_start) {
-        char_changes.push(CharChange {
-            original_start: ds,
-            original_length: i - ds,
-            modified_start: is,
-            modified_length: j - is,
-        });
-    }
+    flush_char_change(&mut char_changes, &mut del_end, &mut ins_end, i, j);
 
     char_changes.reverse();
+    // Convert token indices back to character offsets
+    for change in &mut char_changes {
+        let original_end = orig_offsets[change.original_start + change.original_length];
+        let modified_end = mod_offsets[change.modified_start + change.modified_length];
+        change.original_start = orig_offsets[change.original_start];
+        change.original_length = original_end - change.original_start;
+        change.modified_start = mod_offsets[change.modified_start];
+        change.modified_length = modified_end - change.modified_start;
+    }
     char_changes
 }
 
+/// One change spanning everything between the common prefix and suffix of the two texts
+fn whole_char_change(original: &str, modified: &str) -> Vec<CharChange> {
+    let (original, modified): (Vec<char>, Vec<char>) = (original.chars().collect(), modified.chars().collect());
+    let prefix = original.iter().zip(&modified).take_while(|(a, b)| a == b).count();
+    let suffix = original[prefix..]
+        .iter()
+        .rev()
+        .zip(modified[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (original_length, modified_length) = (original.len() - prefix - suffix, modified.len() - prefix - suffix);
+    if original_length == 0 && modified_length == 0 {
+        return Vec::new();
+    }
+    vec![CharChange {
+        original_start: prefix,
+        original_length,
+        modified_start: prefix,
+        modified_length,
+    }]
+}
+
+/// Split `text` into tokens, along with the char offset of each token plus a final end offset
+fn tokenize(text: &str, granularity: Granularity) -> (Vec<&str>, Vec<usize>) {
+    let mut tokens = Vec::new();
+    let mut offsets = Vec::new();
+    let mut char_offset = 0;
+    let mut token_start: Option<(usize, usize)> = None;
+    let mut previous_class = None;
+
+    for (byte_offset, c) in text.char_indices() {
+        let class = match granularity {
+            Granularity::Char => None,
+            Granularity::Word if c.is_alphanumeric() || c == '_' => Some(0),
+            Granularity::Word if c.is_whitespace() => Some(1),
+            Granularity::Word => None,
+            Granularity::Line => Some(2),
+        };
+
+        // Word and whitespace runs continue a token, everything else starts a new one
+        let continues = class.is_some() && class == previous_class;
+        if !continues {
+            if let Some((start_byte, start_char)) = token_start {
+                tokens.push(&text[start_byte..byte_offset]);
+                offsets.push(start_char);
+            }
+            token_start = Some((byte_offset, char_offset));
+        }
+        previous_class = class;
+        char_offset += 1;
+    }
+
+    if let Some((start_byte, start_char)) = token_start {
+        tokens.push(&text[start_byte..]);
+        offsets.push(start_char);
+    }
+    offsets.push(char_offset);
+
+    (tokens, offsets)
+}
+
+fn flush_char_change(
+    char_changes: &mut Vec<CharChange>,
+    del_end: &mut Option<usize>,
+    ins_end: &mut Option<usize>,
+    i: usize,
+    j: usize,
+) {
+    if del_end.is_none() && ins_end.is_none() {
+        return;
+    }
+
+    char_changes.push(CharChange {
+        original_start: i,
+        original_length: del_end.take().unwrap_or(i) - i,
+        modified_start: j,
+        modified_length: ins_end.take().unwrap_or(j) - j,
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::{HostClock, OperationClock};
 
     #[test]
     fn test_identical_files() {
         let lines1 = vec!["line1".to_string(), "line2".to_string()];
         let lines2 = vec!["line1".to_string(), "line2".to_string()];
         let options = DiffOptions {
-            ignore_whitespace: false,
-            ignore_case: false,
-            max_computation_time_ms: 5000,
             compute_char_changes: false,
+            ..DiffOptions::default()
         };
 
         let changes = compute_diff(&lines1, &lines2, options);
         assert_eq!(changes.len(), 0);
     }
 
+    #[test]
+    fn test_memory_limit() {
+        let lines1: Vec<String> = "a\nb\nc\nd".lines().map(String::from).collect();
+        let lines2: Vec<String> = "a\nB\nc\nD".lines().map(String::from).collect();
+        let options = DiffOptions {
+            max_memory_bytes: Some(64),
+            ..DiffOptions::default()
+        };
+
+        let error = try_compute_diff(&lines1, &lines2, options.clone()).unwrap_err();
+        assert!(matches!(error, DiffError::MemoryLimit { limit: 64, .. }));
+
+        let changes = compute_diff(&lines1, &lines2, options);
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].original_start, changes[0].original_end), (1, 4));
+        assert_eq!(compute_diff(&lines1, &lines2, DiffOptions::default()).len(), 2);
+    }
+
+    #[test]
+    fn test_verify() {
+        let lines1: Vec<String> = "a\nb\nc".lines().map(String::from).collect();
+        let lines2: Vec<String> = "a\nB\nc\nd".lines().map(String::from).collect();
+        let mut changes = compute_diff(&lines1, &lines2, DiffOptions::default());
+        assert_eq!(verify(&lines1, &lines2, &changes), Ok(()));
+
+        changes.remove(0);
+        assert_eq!(
+            verify(&lines1, &lines2, &changes),
+            Err(VerifyError::LineMismatch {
+                original_line: 1,
+                modified_line: 1
+            })
+        );
+        assert_eq!(
+            verify(&lines1, &lines2, &[]),
+            Err(VerifyError::GapMismatch { hunk: None })
+        );
+    }
+
+    #[test]
+    fn test_custom_comparator() {
+        use crate::comparator::NumericTolerance;
+
+        let lines1: Vec<String> = "a = 1.00\nb = 2\nc = 3".lines().map(String::from).collect();
+        let lines2: Vec<String> = "a = 1.001\nb = 2\nc = 4".lines().map(String::from).collect();
+        let comparator = NumericTolerance { tolerance: 0.01 };
+
+        let changes = compute_diff_with(&lines1, &lines2, DiffOptions::default(), &comparator);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].original_start, 2);
+    }
+
     #[test]
     fn test_simple_addition() {
         let lines1 = vec!["line1".to_string()];
         let lines2 = vec!["line1".to_string(), "line2".to_string()];
         let options = DiffOptions {
-            ignore_whitespace: false,
-            ignore_case: false,
-            max_computation_time_ms: 5000,
             compute_char_changes: false,
+            ..DiffOptions::default()
         };
 
         let changes = compute_diff(&lines1, &lines2, options);
@@ -348,14 +856,218 @@ mod tests {
         let lines1 = vec!["line1".to_string(), "line2".to_string()];
         let lines2 = vec!["line1".to_string()];
         let options = DiffOptions {
-            ignore_whitespace: false,
-            ignore_case: false,
-            max_computation_time_ms: 5000,
             compute_char_changes: false,
+            ..DiffOptions::default()
         };
 
         let changes = compute_diff(&lines1, &lines2, options);
         assert_eq!(changes.len(), 1);
         assert_eq!(changes[0].change_type, ChangeType::Deleted);
     }
+
+    #[test]
+    fn test_replacement_is_modified() {
+        let lines1 = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let lines2 = vec!["a".to_string(), "x".to_string(), "c".to_string()];
+
+        let changes = compute_diff(&lines1, &lines2, DiffOptions::default());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].change_type, ChangeType::Modified);
+        assert_eq!((changes[0].original_start, changes[0].original_end), (1, 2));
+
+        let char_changes = changes[0].char_changes.as_ref().unwrap();
+        assert_eq!(char_changes.len(), 1);
+        assert_eq!(char_changes[0].original_length, 1);
+        assert_eq!(char_changes[0].modified_length, 1);
+    }
+
+    #[test]
+    fn test_distant_changes_stay_separate() {
+        let lines1 = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let lines2 = vec!["b".to_string(), "c".to_string(), "d".to_string()];
+
+        let changes = compute_diff(&lines1, &lines2, DiffOptions::default());
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].change_type, ChangeType::Deleted);
+        assert_eq!(changes[1].change_type, ChangeType::Added);
+    }
+
+    #[test]
+    fn test_touching_changes_merge() {
+        let lines1 = vec!["a".to_string(), "b".to_string(), "c".to_string(), "d".to_string()];
+        let lines2 = vec!["a".to_string(), "x".to_string(), "d".to_string()];
+
+        // Two deleted lines touching one inserted line make a single modification
+        let changes = compute_diff(&lines1, &lines2, DiffOptions::default());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].change_type, ChangeType::Modified);
+        assert_eq!((changes[0].original_start, changes[0].original_end), (1, 3));
+        assert_eq!((changes[0].modified_start, changes[0].modified_end), (1, 2));
+    }
+
+    #[test]
+    fn test_one_sided_char_runs() {
+        // Runs longer than one character, with nothing on the other side, are kept whole
+        let changes = compute_diff(&["abcdef".to_string()], &["abef".to_string()], DiffOptions::default());
+        let char_changes = changes[0].char_changes.as_ref().unwrap();
+        assert_eq!(char_changes.len(), 1);
+        assert_eq!((char_changes[0].original_start, char_changes[0].original_length), (2, 2));
+        assert_eq!(char_changes[0].modified_length, 0);
+    }
+
+    #[test]
+    fn test_word_granularity() {
+        let lines1 = vec!["let value = compute(a);".to_string()];
+        let lines2 = vec!["let result = compute(a);".to_string()];
+        let options = DiffOptions {
+            granularity: Granularity::Word,
+            ..DiffOptions::default()
+        };
+
+        let changes = compute_diff(&lines1, &lines2, options);
+        let char_changes = changes[0].char_changes.as_ref().unwrap();
+        assert_eq!(char_changes.len(), 1);
+        assert_eq!((char_changes[0].original_start, char_changes[0].original_length), (4, 5));
+        assert_eq!((char_changes[0].modified_start, char_changes[0].modified_length), (4, 6));
+
+        let options = DiffOptions {
+            granularity: Granularity::Line,
+            ..DiffOptions::default()
+        };
+        let changes = compute_diff(&lines1, &lines2, options);
+        assert_eq!(changes[0].char_changes.as_ref().unwrap()[0].original_length, 23);
+    }
+
+    #[test]
+    fn test_embedded_region_granularity() {
+        let lines1: Vec<String> = ["Some prose here", "```rust", "let a = b;", "```"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut lines2 = lines1.clone();
+        lines2[0] = "Some text here".to_string();
+        lines2[2] = "let a = c;".to_string();
+        let options = DiffOptions {
+            granularity: Granularity::Word,
+            detect_embedded_languages: true,
+            ..DiffOptions::default()
+        };
+
+        let changes = compute_diff(&lines1, &lines2, options);
+        assert_eq!(changes.len(), 2);
+        // Prose keeps word granularity, the Rust block falls back to characters
+        assert_eq!(changes[0].char_changes.as_ref().unwrap()[0].original_length, 5);
+        assert_eq!(changes[1].char_changes.as_ref().unwrap()[0].original_length, 1);
+    }
+
+    #[test]
+    fn test_mask_patterns() {
+        let lines1 = vec!["10:00:00 [12] started".to_string(), "10:00:01 [12] done".to_string()];
+        let lines2 = vec!["11:30:00 [98] started".to_string(), "11:30:02 [98] failed".to_string()];
+        let options = DiffOptions {
+            mask_patterns: vec![r"\d\d:\d\d:\d\d".to_string(), r"\[\d+\]".to_string()],
+            ..DiffOptions::default()
+        };
+
+        let changes = compute_diff(&lines1, &lines2, options);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].original_start, 1);
+    }
+
+    #[test]
+    fn test_planned_algorithm() {
+        let original: Vec<String> = (0..1500).map(|i| format!("line {}", i)).collect();
+        let mut modified = original.clone();
+        modified[700] = "edited".to_string();
+        modified.insert(1200, "added".to_string());
+        // The full table needs about 18 MiB here, anchoring well under 12
+        let options = DiffOptions { max_memory_bytes: Some(12 << 20), band_width: Some(0), ..DiffOptions::default() };
+        assert!(try_compute_diff(&original, &modified, options.clone()).is_ok());
+
+        let (changes, stats) = compute_diff_with_stats(&original, &modified, options.clone());
+        assert_eq!((stats.algorithm, stats.fell_back), (DiffAlgorithm::Patience, false));
+        assert_eq!((stats.original_lines, stats.modified_lines, stats.anchor_runs), (1500, 1501, 3));
+        assert_eq!(verify(&original, &modified, &changes), Ok(()));
+        assert_eq!(changes.len(), 2);
+
+        let (_, stats) = compute_diff_with_stats(&original[..10], &modified[..10], options.clone());
+        assert_eq!(stats.algorithm, DiffAlgorithm::Full);
+
+        // Near-identical inputs search a narrow band instead
+        let banded = DiffOptions { band_width: None, ..options };
+        let (banded_changes, stats) = compute_diff_with_stats(&original, &modified, banded);
+        assert_eq!((stats.algorithm, stats.fell_back), (DiffAlgorithm::Banded, false));
+        let ranges = |changes: &[LineChange]| -> Vec<(usize, usize, usize, usize)> {
+            changes.iter().map(|c| (c.original_start, c.original_end, c.modified_start, c.modified_end)).collect()
+        };
+        assert_eq!(ranges(&banded_changes), ranges(&changes));
+    }
+
+    #[test]
+    fn test_anchor_runs() {
+        // Two halves swap places; one of them anchors and the other's gaps are diffed alone
+        let original: Vec<String> = (0..1200).map(|i| format!("line {}", i)).collect();
+        let modified: Vec<String> = original[600..].iter().chain(&original[..600]).cloned().collect();
+        let options = DiffOptions { compute_char_changes: false, ..DiffOptions::default() };
+        let (changes, stats) = compute_diff_with_stats(&original, &modified, options.clone());
+        assert_eq!(stats.anchor_runs, 1);
+        assert_eq!(verify(&original, &modified, &changes), Ok(()));
+        assert_eq!(changes.len(), 2);
+
+        let unanchored = DiffOptions { min_anchor_lines: Some(0), ..options };
+        let (_, stats) = compute_diff_with_stats(&original, &modified, unanchored);
+        assert_eq!(stats.anchor_runs, 0);
+    }
+
+    #[test]
+    fn test_operation_budget() {
+        let original: Vec<String> = (0..200).map(|i| format!("line {}", i)).collect();
+        let mut modified = original.clone();
+        modified[20] = "edited".to_string();
+        modified[150] = "edited too".to_string();
+
+        // 40 000 cells do not fit 10 000 operations on any machine: one coarse change instead
+        let options = DiffOptions { max_operations: Some(10_000), ..DiffOptions::default() };
+        let (changes, stats) = compute_diff_with_stats(&original, &modified, options);
+        assert_eq!((stats.algorithm, stats.fell_back), (DiffAlgorithm::Full, true));
+        assert_eq!((changes.len(), changes[0].original_start, changes[0].original_end), (1, 20, 151));
+
+        let options = DiffOptions { max_operations: Some(100_000), ..DiffOptions::default() };
+        let (changes, stats) = compute_diff_with_stats(&original, &modified, options);
+        assert_eq!((changes.len(), stats.fell_back), (2, false));
+    }
+
+    #[test]
+    fn test_changes_result() {
+        let options = DiffOptions::default();
+        let verify_only = DiffOptions { verify: !options.verify, ..options.clone() };
+        assert!(!options.changes_result(&verify_only));
+        let budget = DiffOptions { max_computation_time_ms: 10, ..options.clone() };
+        assert!(options.changes_result(&budget));
+        let band = DiffOptions { band_width: Some(0), ..options.clone() };
+        assert!(options.changes_result(&band));
+        let whitespace = DiffOptions { ignore_whitespace: true, ..options.clone() };
+        assert!(options.changes_result(&whitespace));
+    }
+
+    #[test]
+    fn test_char_diff_budget() {
+        let clock = HostClock::new(60_000);
+        // Minified: thousands of tiny tokens either way, so only the edited span is reported
+        let minified: String = (0..3000).map(|i| format!("v{};", i)).collect();
+        let edited = minified.replacen("v1500;", "w1500;", 1);
+        let changes = compute_char_diff(&minified, &edited, Granularity::Char, &clock);
+        let at = minified.find("v1500;").unwrap();
+        assert_eq!((changes.len(), changes[0].original_start, changes[0].original_length), (1, at, 1));
+
+        // Long words fit the budget as word tokens
+        let words: String = (0..300).map(|i| format!("{:0>20} ", i)).collect();
+        let edited = words.replacen(&format!("{:0>20}", 7), "seven", 1);
+        let changes = compute_char_diff(&words, &edited, Granularity::Char, &clock);
+        assert_eq!((changes[0].original_start, changes[0].original_length), (7 * 21, 20));
+
+        // An exhausted budget reports the pair as one change
+        let changes = compute_char_diff("abc", "aXc", Granularity::Char, &OperationClock::new(0));
+        assert_eq!((changes[0].original_start, changes[0].original_length, changes[0].modified_length), (1, 1, 1));
+    }
 }
@@ -0,0 +1,199 @@
+use std::fs;
+use crate::diff_core::{try_compute_diff, DiffOptions};
+use crate::eol::{detect_line_ending, has_final_newline, join_lines};
+use crate::file_handler::read_file_lines;
+use crate::merge::{merge_three_way, MergeOptions};
+use crate::messages::{text, Message};
+use crate::ui::format_unified_diff;
+
+/// The files git hands a `difftool` or `mergetool` command (`$LOCAL`, `$REMOTE`, `$BASE`, `$MERGED`)
+#[derive(Clone, Debug, PartialEq)]
+pub enum ToolInvocation {
+    Diff {
+        local: String,
+        remote: String,
+    },
+    Merge {
+        local: String,
+        remote: String,
+        /// Absent for add/add conflicts, where git passes an empty or missing base
+        base: Option<String>,
+        merged: String,
+    },
+}
+
+/// Result of a tool run, with git's exit-code convention: 0 for success, 1 for unresolved
+/// conflicts or failure
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToolOutcome {
+    pub exit_code: i32,
+    pub report: String,
+}
+
+impl ToolInvocation {
+    /// `--local=.. --remote=.. [--base=..] [--merged=..]`, or positional `LOCAL REMOTE [BASE MERGED]`
+    /// as written in a `difftool.<name>.cmd` / `mergetool.<name>.cmd` line
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let (mut local, mut remote, mut base, mut merged) = (None, None, None, None);
+        let mut positional = Vec::new();
+        for arg in args {
+            if let Some(path) = arg.strip_prefix("--local=") {
+                local = Some(path.to_string());
+            } else if let Some(path) = arg.strip_prefix("--remote=") {
+                remote = Some(path.to_string());
+            } else if let Some(path) = arg.strip_prefix("--base=") {
+                base = Some(path.to_string());
+            } else if let Some(path) = arg.strip_prefix("--merged=") {
+                merged = Some(path.to_string());
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+        let mut positional = positional.into_iter();
+        let local = local.or_else(|| positional.next());
+        let remote = remote.or_else(|| positional.next());
+        let base = base.or_else(|| positional.next());
+        let merged = merged.or_else(|| positional.next());
+        if positional.next().is_some() {
+            return Err("too many arguments".to_string());
+        }
+
+        let (Some(local), Some(remote)) = (local, remote) else {
+            return Err("Usage: LOCAL REMOTE [BASE MERGED]".to_string());
+        };
+        Ok(match merged {
+            Some(merged) => ToolInvocation::Merge {
+                local,
+                remote,
+                base: base.filter(|path| !path.is_empty()),
+                merged,
+            },
+            None if base.is_some() => return Err("a BASE needs a MERGED path".to_string()),
+            None => ToolInvocation::Diff { local, remote },
+        })
+    }
+
+    /// Read the paths from git's `LOCAL`/`REMOTE`/`BASE`/`MERGED` environment variables
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let args: Vec<String> = [("local", "LOCAL"), ("remote", "REMOTE"), ("base", "BASE"), ("merged", "MERGED")]
+            .iter()
+            .filter_map(|(flag, name)| var(name).map(|value| format!("--{}={}", flag, value)))
+            .collect();
+        Self::from_args(&args)
+    }
+}
+
+/// Diff or merge as the invocation asks. A merge writes MERGED and succeeds only without conflicts,
+/// so git leaves the file unresolved otherwise (with `trustExitCode = true`)
+pub fn run_tool(invocation: &ToolInvocation, options: &DiffOptions, merge_options: &MergeOptions) -> ToolOutcome {
+    let result = match invocation {
+        ToolInvocation::Diff { local, remote } => run_diff(local, remote, options),
+        ToolInvocation::Merge {
+            local,
+            remote,
+            base,
+            merged,
+        } => run_merge(local, remote, base.as_deref(), merged, options, merge_options),
+    };
+    result.unwrap_or_else(|e| ToolOutcome {
+        exit_code: 1,
+        report: format!("{}\n", e),
+    })
+}
+
+fn read(path: &str) -> Result<Vec<String>, String> {
+    read_file_lines(path).map_err(|e| text(Message::ReadFailed, [&path, &e]))
+}
+
+fn run_diff(local: &str, remote: &str, options: &DiffOptions) -> Result<ToolOutcome, String> {
+    let changes = try_compute_diff(&read(local)?, &read(remote)?, options.clone())
+        .map_err(|e| text(Message::CompareFailed, [&e]))?;
+    Ok(ToolOutcome {
+        exit_code: 0,
+        report: format_unified_diff(local, remote, &changes),
+    })
+}
+
+fn run_merge(
+    local: &str,
+    remote: &str,
+    base: Option<&str>,
+    merged: &str,
+    options: &DiffOptions,
+    merge_options: &MergeOptions,
+) -> Result<ToolOutcome, String> {
+    // A missing base file is an add/add conflict: both sides merge against nothing
+    let base = match base {
+        Some(path) if fs::metadata(path).is_ok() => read(path)?,
+        _ => Vec::new(),
+    };
+    let result = merge_three_way(&base, &read(local)?, &read(remote)?, options.clone(), merge_options);
+
+    // Written the way LOCAL is: its line endings and whether it ends in one
+    let text = join_lines(&result.lines, detect_line_ending(local), has_final_newline(local));
+    fs::write(merged, text).map_err(|e| format!("Failed to write {}: {}", merged, e))?;
+
+    Ok(if result.conflict_count == 0 {
+        ToolOutcome {
+            exit_code: 0,
+            report: format!("Merged cleanly into {}\n", merged),
+        }
+    } else {
+        ToolOutcome {
+            exit_code: 1,
+            report: format!("{} conflict(s) left in {}\n", result.conflict_count, merged),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_from_args() {
+        assert_eq!(
+            ToolInvocation::from_args(&args("a b")).unwrap(),
+            ToolInvocation::Diff {
+                local: "a".to_string(),
+                remote: "b".to_string()
+            }
+        );
+        let merge = ToolInvocation::from_args(&args("--merged=m --base= l r")).unwrap();
+        assert!(matches!(merge, ToolInvocation::Merge { base: None, ref merged, .. } if merged == "m"));
+        assert!(ToolInvocation::from_args(&args("a b c")).is_err());
+
+        let env = ToolInvocation::from_env(|name| (name != "BASE").then(|| name.to_lowercase())).unwrap();
+        assert!(matches!(env, ToolInvocation::Merge { ref local, base: None, .. } if local == "local"));
+    }
+
+    #[test]
+    fn test_merge_writes_result() {
+        let dir = TempDir::new("tool");
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        fs::write(path("base"), "a\nb\nc\n").unwrap();
+        fs::write(path("local"), "A\nb\nc\n").unwrap();
+        fs::write(path("remote"), "a\nb\nC\n").unwrap();
+
+        let invocation = ToolInvocation::from_args(&[path("local"), path("remote"), path("base"), path("merged")]).unwrap();
+        let outcome = run_tool(&invocation, &DiffOptions::default(), &MergeOptions::default());
+        assert_eq!(outcome.exit_code, 0);
+        assert_eq!(fs::read_to_string(path("merged")).unwrap(), "A\nb\nC\n");
+
+        fs::write(path("remote"), "X\nb\nc\n").unwrap();
+        let outcome = run_tool(&invocation, &DiffOptions::default(), &MergeOptions::default());
+        assert_eq!(outcome.exit_code, 1);
+        assert!(fs::read_to_string(path("merged")).unwrap().contains("<<<<<<< ours"));
+
+        // LOCAL's CRLF endings and missing final newline carry over
+        fs::write(path("local"), "A\r\nb\r\nc").unwrap();
+        fs::write(path("remote"), "a\nb\nC\n").unwrap();
+        run_tool(&invocation, &DiffOptions::default(), &MergeOptions::default());
+        assert_eq!(fs::read_to_string(path("merged")).unwrap(), "A\r\nb\r\nC");
+    }
+}
@@ -0,0 +1,610 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path};
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use crate::diff_core::{compute_diff, try_compute_diff, DiffOptions, LineChange};
+use crate::file_handler::read_file_lines;
+use crate::generated::is_generated_path;
+use crate::git::run_git;
+use crate::messages::{text, Message};
+use crate::ui::format_unified_diff;
+use crate::walk::{walk_dir, WalkStep};
+
+/// How one relative path compares between two roots
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum FileStatus {
+    Identical,
+    Modified(Vec<LineChange>),
+    /// Changed in whitespace only, in few enough lines to count as identical; see
+    /// `DirectoryOptions::max_trivial_ratio`
+    EffectivelyIdentical(Vec<LineChange>),
+    OnlyInA,
+    OnlyInB,
+    /// Missing from both roots, unreadable, or not a plain relative path
+    Error(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RelativeComparison {
+    /// Path relative to both roots, with `/` separators
+    pub path: String,
+    pub status: FileStatus,
+}
+
+/// Diff the copies of `path` under two roots, e.g. a staging and a production checkout
+pub fn compare_relative(path: &str, root_a: &Path, root_b: &Path, options: &DiffOptions) -> RelativeComparison {
+    RelativeComparison {
+        path: path.to_string(),
+        status: relative_status(path, root_a, root_b, options, None).unwrap_or_else(FileStatus::Error),
+    }
+}
+
+/// `compare_relative` over a list of paths, one result per path in the same order
+pub fn compare_relative_batch(
+    paths: &[String],
+    root_a: &Path,
+    root_b: &Path,
+    options: &DiffOptions,
+) -> Vec<RelativeComparison> {
+    paths.iter().map(|path| compare_relative(path, root_a, root_b, options)).collect()
+}
+
+/// What to do with nested git repositories and submodules met while walking a tree
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum NestedRepoMode {
+    /// Leave them out of the comparison
+    #[default]
+    Skip,
+    /// Compare only the commit each one has checked out, shown as a `Subproject commit` line
+    Revision,
+    /// Walk into them like ordinary directories
+    Recurse,
+}
+
+impl NestedRepoMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "skip" => Some(Self::Skip),
+            "revision" => Some(Self::Revision),
+            "recurse" => Some(Self::Recurse),
+            _ => None,
+        }
+    }
+}
+
+/// Restricts a directory comparison to some files; a path is compared when its copy in either
+/// root passes every filter that is set
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DirectoryFilter {
+    /// Extensions without the dot, e.g. `rs`
+    pub extensions: Vec<String>,
+    /// `*` wildcard patterns matched against the relative path or the file name
+    pub globs: Vec<String>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub modified_after: Option<SystemTime>,
+    pub modified_before: Option<SystemTime>,
+}
+
+impl DirectoryFilter {
+    /// Build a filter from `--ext=rs,toml`, `--include=<glob>`, `--min-size=10k`,
+    /// `--max-size=2M`, `--newer-than=7d` and `--older-than=12h` arguments
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for arg in args {
+            let (flag, value) = arg.split_once('=').ok_or_else(|| format!("Expected --flag=value, got {}", arg))?;
+            match flag {
+                "--ext" => filter
+                    .extensions
+                    .extend(value.split(',').map(|ext| ext.trim_start_matches('.').to_string())),
+                "--include" => filter.globs.push(value.to_string()),
+                "--min-size" => filter.min_size = Some(parse_size(value)?),
+                "--max-size" => filter.max_size = Some(parse_size(value)?),
+                "--newer-than" => filter.modified_after = Some(parse_age(value)?),
+                "--older-than" => filter.modified_before = Some(parse_age(value)?),
+                _ => return Err(format!("Unknown filter: {}", flag)),
+            }
+        }
+        Ok(filter)
+    }
+
+    /// The extension and glob filters alone
+    pub fn matches_path(&self, path: &str) -> bool {
+        let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or_default();
+        (self.extensions.is_empty() || self.extensions.iter().any(|ext| ext == extension))
+            && (self.globs.is_empty() || is_generated_path(path, &self.globs))
+    }
+
+    pub fn matches(&self, path: &str, metadata: &fs::Metadata) -> bool {
+        let size = metadata.len();
+        let modified = metadata.modified().ok();
+        let within = |bound: Option<SystemTime>, ok: fn(SystemTime, SystemTime) -> bool| {
+            bound.is_none_or(|bound| modified.is_some_and(|time| ok(time, bound)))
+        };
+        self.matches_path(path)
+            && self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+            && within(self.modified_after, |time, bound| time >= bound)
+            && within(self.modified_before, |time, bound| time < bound)
+    }
+}
+
+/// `512`, `10k`, `2M` or `1G` bytes
+fn parse_size(text: &str) -> Result<u64, String> {
+    let (digits, multiplier) = match text.char_indices().last() {
+        Some((at, 'k' | 'K')) => (&text[..at], 1 << 10),
+        Some((at, 'm' | 'M')) => (&text[..at], 1 << 20),
+        Some((at, 'g' | 'G')) => (&text[..at], 1 << 30),
+        _ => (text, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid size: {}", text))
+}
+
+/// The time `30s`, `15m`, `12h`, `7d` or `2w` ago
+fn parse_age(text: &str) -> Result<SystemTime, String> {
+    let invalid = || format!("Invalid age: {}", text);
+    let (at, unit) = text.char_indices().last().ok_or_else(invalid)?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        'w' => 7 * 86_400,
+        _ => return Err(invalid()),
+    };
+    let count: u64 = text[..at].parse().map_err(|_| invalid())?;
+    let age = count.checked_mul(seconds).ok_or_else(invalid)?;
+    SystemTime::now().checked_sub(Duration::from_secs(age)).ok_or_else(invalid)
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DirectoryOptions {
+    pub diff: DiffOptions,
+    pub nested_repos: NestedRepoMode,
+    pub filter: DirectoryFilter,
+    /// Report modified files as effectively identical when every change is whitespace only and
+    /// the changed lines are at most this fraction of both files' lines
+    pub max_trivial_ratio: Option<f64>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TreeEntry {
+    File,
+    /// A nested repository, compared by its checked-out commit
+    Repository,
+}
+
+/// Compare every file under two roots, sorted by relative path; `.git` directories are never
+/// compared themselves
+pub fn compare_directories(root_a: &Path, root_b: &Path, options: &DirectoryOptions) -> Vec<RelativeComparison> {
+    compare_directory_entries(&directory_paths(root_a, root_b, options), root_a, root_b, options)
+}
+
+/// Compare several paths from `directory_paths`; files whose bytes hash the same are reported
+/// identical without running the line diff
+pub fn compare_directory_entries(
+    paths: &[String],
+    root_a: &Path,
+    root_b: &Path,
+    options: &DirectoryOptions,
+) -> Vec<RelativeComparison> {
+    paths
+        .iter()
+        .zip(identical_by_content(paths, root_a, root_b))
+        .map(|(path, identical)| {
+            if identical {
+                RelativeComparison {
+                    path: path.clone(),
+                    status: FileStatus::Identical,
+                }
+            } else {
+                compare_directory_entry(path, root_a, root_b, options)
+            }
+        })
+        .collect()
+}
+
+/// For each path, whether both copies are regular files with the same bytes. Sizes are checked
+/// first; only same-size pairs are read, spread over the available cores.
+pub fn identical_by_content(paths: &[String], root_a: &Path, root_b: &Path) -> Vec<bool> {
+    let same_size = |path: &String| {
+        let size = |root: &Path| fs::metadata(root.join(path)).ok().filter(|m| m.is_file()).map(|m| m.len());
+        size(root_a).is_some_and(|a| Some(a) == size(root_b))
+    };
+    let candidates: Vec<usize> = (0..paths.len()).filter(|&i| same_size(&paths[i])).collect();
+    let compare_pair = |&index: &usize| same_bytes(&root_a.join(&paths[index]), &root_b.join(&paths[index]));
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = candidates.len().div_ceil(threads).max(1);
+    let matches: Vec<bool> = std::thread::scope(|scope| {
+        let handles: Vec<_> = candidates
+            .chunks(chunk_size)
+            .map(|chunk| {
+                // Hosts without threads (such as the extension's WASM runtime) hash inline
+                std::thread::Builder::new()
+                    .spawn_scoped(scope, move || chunk.iter().map(compare_pair).collect::<Vec<_>>())
+                    .map(|handle| (handle, chunk))
+                    .map_err(|_| chunk)
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| match handle {
+                // A chunk that panicked counts as different, keeping later results in place
+                Ok((handle, chunk)) => handle.join().unwrap_or_else(|_| vec![false; chunk.len()]),
+                Err(chunk) => chunk.iter().map(compare_pair).collect(),
+            })
+            .collect()
+    });
+
+    let mut identical = vec![false; paths.len()];
+    for (index, matched) in candidates.into_iter().zip(matches) {
+        identical[index] = matched;
+    }
+    identical
+}
+
+/// Whether two files of the same size hold the same bytes; unreadable files never match
+fn same_bytes(a: &Path, b: &Path) -> bool {
+    use std::io::Read;
+
+    let (Ok(mut file_a), Ok(mut file_b)) = (fs::File::open(a), fs::File::open(b)) else {
+        return false;
+    };
+    let (mut buffer_a, mut buffer_b) = (vec![0u8; 64 * 1024], vec![0u8; 64 * 1024]);
+    loop {
+        let Ok(read) = file_a.read(&mut buffer_a) else {
+            return false;
+        };
+        if read == 0 {
+            return file_b.read(&mut buffer_b[..1]).is_ok_and(|more| more == 0);
+        }
+        if file_b.read_exact(&mut buffer_b[..read]).is_err() || buffer_a[..read] != buffer_b[..read] {
+            return false;
+        }
+    }
+}
+
+/// The sorted relative paths `compare_directories` visits, after nested-repository handling and
+/// filters
+pub fn directory_paths(root_a: &Path, root_b: &Path, options: &DirectoryOptions) -> Vec<String> {
+    let (mut entries_a, mut entries_b) = (BTreeMap::new(), BTreeMap::new());
+    walk_tree(root_a, "", options.nested_repos, &mut entries_a);
+    walk_tree(root_b, "", options.nested_repos, &mut entries_b);
+    let mut paths: Vec<String> = entries_a.keys().chain(entries_b.keys()).cloned().collect();
+    paths.sort();
+    paths.dedup();
+    let included = |root: &Path, entries: &BTreeMap<String, TreeEntry>, path: &str| match entries.get(path) {
+        Some(TreeEntry::File) => fs::metadata(root.join(path)).is_ok_and(|m| options.filter.matches(path, &m)),
+        Some(TreeEntry::Repository) => options.filter.matches_path(path),
+        None => false,
+    };
+    paths.retain(|path| included(root_a, &entries_a, path) || included(root_b, &entries_b, path));
+    paths
+}
+
+/// Compare one path from `directory_paths`
+pub fn compare_directory_entry(path: &str, root_a: &Path, root_b: &Path, options: &DirectoryOptions) -> RelativeComparison {
+    let entry = |root: &Path| {
+        let full = root.join(path);
+        if full.join(".git").exists() {
+            Some(TreeEntry::Repository)
+        } else {
+            full.is_file().then_some(TreeEntry::File)
+        }
+    };
+    let status = match (entry(root_a), entry(root_b)) {
+        (Some(TreeEntry::Repository), Some(TreeEntry::Repository)) => {
+            let commit = |root: &Path| {
+                let rev = run_git(&root.join(path), &["rev-parse", "HEAD"]).unwrap_or_else(|_| "(no commit)".to_string());
+                vec![format!("Subproject commit {}", rev)]
+            };
+            let (a, b) = (commit(root_a), commit(root_b));
+            if a == b {
+                FileStatus::Identical
+            } else {
+                FileStatus::Modified(compute_diff(&a, &b, options.diff.clone()))
+            }
+        }
+        (Some(TreeEntry::File), Some(TreeEntry::File)) | (None, None) => {
+            relative_status(path, root_a, root_b, &options.diff, options.max_trivial_ratio).unwrap_or_else(FileStatus::Error)
+        }
+        (Some(_), None) => FileStatus::OnlyInA,
+        (None, Some(_)) => FileStatus::OnlyInB,
+        _ => FileStatus::Error("a file in one root and a nested repository in the other".to_string()),
+    };
+    RelativeComparison {
+        path: path.to_string(),
+        status,
+    }
+}
+
+fn walk_tree(root: &Path, prefix: &str, nested_repos: NestedRepoMode, entries: &mut BTreeMap<String, TreeEntry>) {
+    walk_dir(root, prefix, |path, entry, is_dir| {
+        if entry.file_name() == ".git" {
+            return WalkStep::Skip;
+        }
+        if !is_dir {
+            if entry.path().is_file() {
+                entries.insert(path, TreeEntry::File);
+            }
+            return WalkStep::Skip;
+        }
+
+        // Submodules have a `.git` file pointing into the superproject, clones a `.git` directory
+        if entry.path().join(".git").exists() {
+            match nested_repos {
+                NestedRepoMode::Skip => return WalkStep::Skip,
+                NestedRepoMode::Revision => {
+                    entries.insert(path, TreeEntry::Repository);
+                    return WalkStep::Skip;
+                }
+                NestedRepoMode::Recurse => {}
+            }
+        }
+        WalkStep::Descend
+    });
+}
+
+fn relative_status(
+    path: &str,
+    root_a: &Path,
+    root_b: &Path,
+    options: &DiffOptions,
+    max_trivial_ratio: Option<f64>,
+) -> Result<FileStatus, String> {
+    // Absolute paths or `..` would step outside the roots being compared
+    if !Path::new(path).components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(format!("{} is not a path inside the compared roots", path));
+    }
+    let (file_a, file_b) = (root_a.join(path), root_b.join(path));
+    let read = |file: &Path| -> Result<Vec<String>, String> {
+        read_file_lines(&file.to_string_lossy()).map_err(|e| text(Message::ReadFailed, [&file.display(), &e]))
+    };
+
+    match (file_a.is_file(), file_b.is_file()) {
+        (false, false) => Err(format!("{} exists in neither root", path)),
+        (true, false) => Ok(FileStatus::OnlyInA),
+        (false, true) => Ok(FileStatus::OnlyInB),
+        (true, true) => {
+            let (original, modified) = (read(&file_a)?, read(&file_b)?);
+            let changes = try_compute_diff(&original, &modified, options.clone())
+                .map_err(|e| format!("Failed to compare {}: {}", path, e))?;
+            Ok(if changes.is_empty() {
+                FileStatus::Identical
+            } else if max_trivial_ratio.is_some_and(|ratio| is_trivial(&original, &modified, &changes, ratio)) {
+                FileStatus::EffectivelyIdentical(changes)
+            } else {
+                FileStatus::Modified(changes)
+            })
+        }
+    }
+}
+
+/// Whether `changes` only touch whitespace, in at most `max_ratio` of the lines of both files
+pub fn is_trivial(original: &[String], modified: &[String], changes: &[LineChange], max_ratio: f64) -> bool {
+    let (added, deleted) = change_counts(changes);
+    let total = original.len() + modified.len();
+    let squeeze = |lines: &[String]| -> String { lines.concat().chars().filter(|c| !c.is_whitespace()).collect() };
+    (added + deleted) as f64 <= max_ratio * total as f64
+        && changes.iter().all(|c| {
+            squeeze(&original[c.original_start..c.original_end]) == squeeze(&modified[c.modified_start..c.modified_end])
+        })
+}
+
+/// One status line per path, then the diff of every modified file
+pub fn format_relative_report(root_a: &str, root_b: &str, results: &[RelativeComparison]) -> String {
+    format_grouped_report(root_a, root_b, &[(None, results.iter().collect())])
+}
+
+/// `format_relative_report` with the status lines under a heading per labelled group, and the
+/// diffs in the same order
+pub fn format_grouped_report(root_a: &str, root_b: &str, groups: &[(Option<String>, Vec<&RelativeComparison>)]) -> String {
+    let mut output = format!("# {} ↔ {}\n\n", root_a, root_b);
+    for (label, results) in groups {
+        if let Some(label) = label {
+            output.push_str(&format!("## {} ({} file(s))\n", label, results.len()));
+        }
+        for result in results {
+            output.push_str(&match &result.status {
+                FileStatus::Identical => format!("= {}\n", result.path),
+                FileStatus::Modified(changes) => {
+                    let (added, deleted) = change_counts(changes);
+                    format!("M {} (+{} -{})\n", result.path, added, deleted)
+                }
+                FileStatus::EffectivelyIdentical(changes) => {
+                    format!("≈ {}: {}\n", result.path, text(Message::EffectivelyIdentical, [&changes.len()]))
+                }
+                FileStatus::OnlyInA => format!("- {} (only in {})\n", result.path, root_a),
+                FileStatus::OnlyInB => format!("+ {} (only in {})\n", result.path, root_b),
+                FileStatus::Error(message) => format!("! {}: {}\n", result.path, message),
+            });
+        }
+    }
+
+    for result in groups.iter().flat_map(|(_, results)| results) {
+        if let FileStatus::Modified(changes) = &result.status {
+            output.push('\n');
+            output.push_str(&format_unified_diff(
+                &format!("{}/{}", root_a.trim_end_matches('/'), result.path),
+                &format!("{}/{}", root_b.trim_end_matches('/'), result.path),
+                changes,
+            ));
+        }
+    }
+    output
+}
+
+/// Lines added and deleted by `changes`
+pub fn change_counts(changes: &[LineChange]) -> (usize, usize) {
+    let added = changes.iter().map(|c| c.modified_end - c.modified_start).sum();
+    let deleted = changes.iter().map(|c| c.original_end - c.original_start).sum();
+    (added, deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_compare_relative_batch() {
+        let root = TempDir::new("relative");
+        let (a, b) = (root.join("staging"), root.join("production"));
+        fs::create_dir_all(a.join("conf")).unwrap();
+        fs::create_dir_all(b.join("conf")).unwrap();
+        fs::write(a.join("conf/app.ini"), "x=1\ny=2\n").unwrap();
+        fs::write(b.join("conf/app.ini"), "x=1\ny=3\n").unwrap();
+        fs::write(a.join("same.txt"), "s\n").unwrap();
+        fs::write(b.join("same.txt"), "s\n").unwrap();
+        fs::write(a.join("new.txt"), "n\n").unwrap();
+
+        let paths: Vec<String> = ["conf/app.ini", "same.txt", "new.txt", "none.txt", "../escape"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let results = compare_relative_batch(&paths, &a, &b, &DiffOptions::default());
+        assert!(matches!(&results[0].status, FileStatus::Modified(changes) if changes.len() == 1));
+        assert!(matches!(results[1].status, FileStatus::Identical));
+        assert!(matches!(results[2].status, FileStatus::OnlyInA));
+        assert!(matches!(results[3].status, FileStatus::Error(_)));
+        assert!(matches!(&results[4].status, FileStatus::Error(message) if message.contains("not a path inside")));
+
+        let report = format_relative_report("staging", "production", &results);
+        assert!(report.contains("M conf/app.ini (+1 -1)\n= same.txt\n- new.txt (only in staging)\n"));
+        assert!(report.contains("--- staging/conf/app.ini\n+++ production/conf/app.ini\n"));
+    }
+
+    #[test]
+    fn test_effectively_identical() {
+        let root = TempDir::new("trivial");
+        let (a, b) = (root.join("a"), root.join("b"));
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        let text: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        fs::write(a.join("spaces.txt"), &text).unwrap();
+        fs::write(b.join("spaces.txt"), text.replace("line 7\n", "line  7 \n")).unwrap();
+        fs::write(a.join("words.txt"), &text).unwrap();
+        fs::write(b.join("words.txt"), text.replace("line 7\n", "line 8\n")).unwrap();
+
+        let options = DirectoryOptions { max_trivial_ratio: Some(0.01), ..DirectoryOptions::default() };
+        let results = compare_directories(&a, &b, &options);
+        assert!(matches!(&results[0].status, FileStatus::EffectivelyIdentical(changes) if changes.len() == 1));
+        assert!(matches!(results[1].status, FileStatus::Modified(_)));
+        let report = format_relative_report("a", "b", &results);
+        assert!(report.contains("≈ spaces.txt: effectively identical (1 trivial difference(s))\nM words.txt (+1 -1)\n"));
+        assert!(!report.contains("+++ b/spaces.txt"));
+
+        let strict = DirectoryOptions { max_trivial_ratio: Some(0.001), ..DirectoryOptions::default() };
+        assert!(matches!(compare_directories(&a, &b, &strict)[0].status, FileStatus::Modified(_)));
+    }
+
+    #[test]
+    fn test_compare_directories_nested_repos() {
+        let root = TempDir::new("nested");
+        for (side, content) in [("a", "v1\n"), ("b", "v2\n")] {
+            let vendor = root.join(side).join("vendor/lib");
+            fs::create_dir_all(&vendor).unwrap();
+            fs::write(root.join(side).join("top.txt"), "same\n").unwrap();
+            fs::write(vendor.join("lib.txt"), content).unwrap();
+            for args in [&["init", "-q"][..], &["add", "."], &["-c", "user.name=T", "-c", "user.email=t@e", "commit", "-qm", content]] {
+                run_git(&vendor, args).unwrap();
+            }
+        }
+        let (a, b) = (root.join("a"), root.join("b"));
+        let compare = |nested_repos| {
+            let options = DirectoryOptions { nested_repos, ..DirectoryOptions::default() };
+            compare_directories(&a, &b, &options)
+        };
+
+        let skipped = compare(NestedRepoMode::Skip);
+        assert_eq!(skipped.len(), 1);
+        assert!(matches!(skipped[0].status, FileStatus::Identical));
+
+        let revisions = compare(NestedRepoMode::Revision);
+        assert_eq!(revisions[1].path, "vendor/lib");
+        assert!(matches!(&revisions[1].status, FileStatus::Modified(changes) if changes.len() == 1));
+
+        let recursed = compare(NestedRepoMode::Recurse);
+        let paths: Vec<&str> = recursed.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["top.txt", "vendor/lib/lib.txt"]);
+    }
+
+    #[test]
+    fn test_identical_by_content() {
+        let root = TempDir::new("prehash");
+        let (a, b) = (root.join("a"), root.join("b"));
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        for (name, left, right) in [("same", "abc\n", "abc\n"), ("edit", "abc\n", "abd\n"), ("grow", "a\n", "ab\n")] {
+            fs::write(a.join(name), left).unwrap();
+            fs::write(b.join(name), right).unwrap();
+        }
+        fs::write(a.join("only"), "x").unwrap();
+
+        let paths: Vec<String> = ["edit", "grow", "only", "same"].iter().map(|p| p.to_string()).collect();
+        assert_eq!(identical_by_content(&paths, &a, &b), vec![false, false, false, true]);
+        let results = compare_directories(&a, &b, &DirectoryOptions::default());
+        assert!(matches!(results[3].status, FileStatus::Identical));
+        assert!(matches!(results[0].status, FileStatus::Modified(_)));
+    }
+
+    #[test]
+    fn test_directory_filter() {
+        let root = TempDir::new("filter");
+        let (a, b) = (root.join("a"), root.join("b"));
+        for (dir, big) in [(&a, "x".repeat(2048)), (&b, "y".repeat(2048))] {
+            fs::create_dir_all(dir.join("src")).unwrap();
+            fs::write(dir.join("src/lib.rs"), "fn a() {}\n").unwrap();
+            fs::write(dir.join("src/big.rs"), &big).unwrap();
+            fs::write(dir.join("notes.md"), "n\n").unwrap();
+        }
+        let old = SystemTime::now() - Duration::from_secs(30 * 86_400);
+        fs::File::options().write(true).open(a.join("src/lib.rs")).unwrap().set_modified(old).unwrap();
+        fs::File::options().write(true).open(b.join("src/lib.rs")).unwrap().set_modified(old).unwrap();
+
+        let args: Vec<String> = ["--ext=rs", "--newer-than=7d", "--max-size=1k"].iter().map(|a| a.to_string()).collect();
+        let filter = DirectoryFilter::from_args(&args).unwrap();
+        assert_eq!(filter.max_size, Some(1024));
+        let options = DirectoryOptions { filter, ..DirectoryOptions::default() };
+        // lib.rs is too old, big.rs too big and notes.md has the wrong extension
+        assert!(compare_directories(&a, &b, &options).is_empty());
+
+        let filter = DirectoryFilter::from_args(&["--include=src/*".to_string(), "--min-size=1k".to_string()]).unwrap();
+        let options = DirectoryOptions { filter, ..DirectoryOptions::default() };
+        let paths: Vec<String> = compare_directories(&a, &b, &options).into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["src/big.rs"]);
+        assert!(DirectoryFilter::from_args(&["--newer-than=7x".to_string()]).is_err());
+        let overflow = DirectoryFilter::from_args(&["--max-size=99999999999999999G".to_string()]);
+        assert_eq!(overflow.unwrap_err(), "Invalid size: 99999999999999999G");
+        assert!(DirectoryFilter::from_args(&["--older-than=9999999999999999w".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_deep_tree_on_small_stack() {
+        let root = TempDir::new("deep");
+        let deep = root.join(["d"; 300].join("/"));
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(deep.join("leaf"), "x").unwrap();
+
+        // Far below a WASM module's stack: a walk recursing per directory level would abort here
+        let walked = root.to_path_buf();
+        let entries = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || {
+                let mut entries = BTreeMap::new();
+                walk_tree(&walked, "", NestedRepoMode::Skip, &mut entries);
+                entries
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(entries.keys().collect::<Vec<_>>(), vec![&format!("{}/leaf", ["d"; 300].join("/"))]);
+    }
+}
@@ -0,0 +1,113 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::dirdiff::{compare_directory_entries, directory_paths, DirectoryOptions, RelativeComparison};
+use crate::messages::{text, Message};
+
+/// Files compared between two saves of the job file
+pub const CHECKPOINT_INTERVAL: usize = 100;
+
+/// A directory comparison that can be saved part-way and resumed: the walk's path list is fixed
+/// when the job starts, and results accumulate in the same order
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DirectoryJob {
+    pub root_a: String,
+    pub root_b: String,
+    pub options: DirectoryOptions,
+    pub paths: Vec<String>,
+    /// One result per path, for the first `results.len()` paths
+    pub results: Vec<RelativeComparison>,
+}
+
+impl DirectoryJob {
+    /// Walk both roots and plan the comparison; no file is diffed yet
+    pub fn start(root_a: &str, root_b: &str, options: DirectoryOptions) -> Self {
+        Self {
+            paths: directory_paths(Path::new(root_a), Path::new(root_b), &options),
+            root_a: root_a.to_string(),
+            root_b: root_b.to_string(),
+            options,
+            results: Vec::new(),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.results.len() >= self.paths.len()
+    }
+
+    /// Compare up to `count` of the remaining paths and return how many were compared
+    pub fn step(&mut self, count: usize) -> usize {
+        let (root_a, root_b) = (Path::new(&self.root_a), Path::new(&self.root_b));
+        let start = self.results.len();
+        let end = (start + count).min(self.paths.len());
+        self.results
+            .extend(compare_directory_entries(&self.paths[start..end], root_a, root_b, &self.options));
+        end - start
+    }
+
+    /// Finish the job, saving progress to `job_file` every `CHECKPOINT_INTERVAL` files so an
+    /// interrupted run loses at most that much work; the file is removed once the job is done
+    pub fn run(&mut self, job_file: &Path) -> io::Result<()> {
+        while !self.is_complete() {
+            self.step(CHECKPOINT_INTERVAL);
+            self.save(job_file)?;
+        }
+        match fs::remove_file(job_file) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write the job through a temporary file, so a crash mid-write keeps the last checkpoint
+    pub fn save(&self, job_file: &Path) -> io::Result<()> {
+        let json = serde_json::to_string(self).map_err(io::Error::other)?;
+        let temp = job_file.with_extension("tmp");
+        fs::write(&temp, json)?;
+        fs::rename(&temp, job_file)
+    }
+
+    pub fn load(job_file: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(job_file).map_err(|e| text(Message::ReadFailed, [&job_file.display(), &e]))?;
+        serde_json::from_str(&text).map_err(|e| format!("invalid directory job {}: {}", job_file.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dirdiff::FileStatus;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_resume_after_interruption() {
+        let root = TempDir::new("dirjob");
+        let (a, b) = (root.join("a"), root.join("b"));
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        for i in 0..5 {
+            fs::write(a.join(format!("{}.txt", i)), "x\n").unwrap();
+            fs::write(b.join(format!("{}.txt", i)), if i == 3 { "y\n" } else { "x\n" }).unwrap();
+        }
+        let job_file = root.join("job.json");
+        let (a, b) = (a.to_string_lossy().into_owned(), b.to_string_lossy().into_owned());
+
+        // Interrupted after two files
+        let mut job = DirectoryJob::start(&a, &b, DirectoryOptions::default());
+        assert_eq!(job.step(2), 2);
+        job.save(&job_file).unwrap();
+
+        let mut resumed = DirectoryJob::load(&job_file).unwrap();
+        assert_eq!(resumed.results.len(), 2);
+        resumed.run(&job_file).unwrap();
+        assert!(resumed.is_complete());
+        assert!(!job_file.exists());
+        let modified: Vec<&str> = resumed
+            .results
+            .iter()
+            .filter(|r| matches!(r.status, FileStatus::Modified(_)))
+            .map(|r| r.path.as_str())
+            .collect();
+        assert_eq!(modified, vec!["3.txt"]);
+    }
+}
@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use serde::Serialize;
+use crate::diff_core::{compute_diff, verify, ChangeType, DiffOptions};
+use crate::settings::ExtensionSettings;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum CheckStatus {
+    Ok,
+    /// Works, but some features are unavailable
+    Warning,
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HealthReport {
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.status != CheckStatus::Failed)
+    }
+
+    pub fn format(&self) -> String {
+        let problems = self.checks.iter().filter(|check| check.status != CheckStatus::Ok).count();
+        let mut text = match problems {
+            0 => "Extension health: OK\n\n".to_string(),
+            n => format!("Extension health: {} problem(s)\n\n", n),
+        };
+        for check in &self.checks {
+            let mark = match check.status {
+                CheckStatus::Ok => "✓",
+                CheckStatus::Warning => "⚠",
+                CheckStatus::Failed => "✗",
+            };
+            text.push_str(&format!("{} {}: {}\n", mark, check.name, check.detail));
+        }
+        text
+    }
+}
+
+/// Check what the extension depends on: git, the settings and external tool they configure,
+/// that `work_dir` and the churn file's directory are writable, and that a known diff comes
+/// out as expected
+pub fn run_checks(settings: &ExtensionSettings, work_dir: &Path) -> HealthReport {
+    let mut checks = vec![check_git()];
+    checks.push(match settings.validate() {
+        Ok(()) => HealthCheck { name: "settings", status: CheckStatus::Ok, detail: "valid".to_string() },
+        Err(e) => HealthCheck { name: "settings", status: CheckStatus::Failed, detail: e },
+    });
+    checks.push(check_external_tool(settings, work_dir));
+    checks.push(check_writable("working directory", work_dir));
+    if let Some(churn_file) = &settings.churn_file {
+        let directory = Path::new(churn_file).parent().filter(|parent| !parent.as_os_str().is_empty());
+        checks.push(check_writable("churn file directory", directory.unwrap_or(Path::new("."))));
+    }
+    checks.push(check_known_diff());
+    HealthReport { checks }
+}
+
+fn check_git() -> HealthCheck {
+    match Command::new("git").arg("--version").output() {
+        Ok(output) if output.status.success() => HealthCheck {
+            name: "git",
+            status: CheckStatus::Ok,
+            detail: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        },
+        Ok(output) => HealthCheck {
+            name: "git",
+            status: CheckStatus::Warning,
+            detail: format!("git --version failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+        },
+        Err(e) => HealthCheck {
+            name: "git",
+            status: CheckStatus::Warning,
+            detail: format!("not available ({}); worktree, HEAD and branch comparisons will fail", e),
+        },
+    }
+}
+
+/// Run the configured tool on two small files that differ in one line
+fn check_external_tool(settings: &ExtensionSettings, work_dir: &Path) -> HealthCheck {
+    let Some(tool) = &settings.external_tool else {
+        return HealthCheck { name: "external tool", status: CheckStatus::Ok, detail: "not configured".to_string() };
+    };
+    let failed = |detail: String| HealthCheck { name: "external tool", status: CheckStatus::Failed, detail };
+    if !tool.args.iter().any(|arg| arg.contains("{original}")) || !tool.args.iter().any(|arg| arg.contains("{modified}")) {
+        return failed(format!("{} arguments lack {{original}} or {{modified}}", tool.command));
+    }
+    let (original, modified) = (work_dir.join(".zed-diff-doctor-a"), work_dir.join(".zed-diff-doctor-b"));
+    let result = fs::write(&original, "same\nold\n")
+        .and_then(|_| fs::write(&modified, "same\nnew\n"))
+        .map_err(|e| format!("cannot write probe files: {}", e))
+        .and_then(|_| tool.run(&original.to_string_lossy(), &modified.to_string_lossy()));
+    let _ = (fs::remove_file(&original), fs::remove_file(&modified));
+    match result {
+        Ok(changes) if changes.len() == 1 => {
+            HealthCheck { name: "external tool", status: CheckStatus::Ok, detail: format!("{} works", tool.command) }
+        }
+        Ok(changes) => failed(format!("{} reported {} changes for a one-line edit", tool.command, changes.len())),
+        Err(e) => failed(e),
+    }
+}
+
+fn check_writable(name: &'static str, directory: &Path) -> HealthCheck {
+    let probe = directory.join(".zed-diff-doctor");
+    match fs::write(&probe, "").and_then(|_| fs::remove_file(&probe)) {
+        Ok(()) => HealthCheck { name, status: CheckStatus::Ok, detail: format!("{} is writable", directory.display()) },
+        Err(e) => HealthCheck { name, status: CheckStatus::Failed, detail: format!("cannot write to {}: {}", directory.display(), e) },
+    }
+}
+
+/// The engine itself, on an edit with a known answer
+fn check_known_diff() -> HealthCheck {
+    let original: Vec<String> = ["a", "b", "c"].iter().map(|line| line.to_string()).collect();
+    let modified: Vec<String> = ["a", "x", "c", "d"].iter().map(|line| line.to_string()).collect();
+    let changes = compute_diff(&original, &modified, DiffOptions::default());
+    let shape: Vec<(usize, usize, usize, usize, ChangeType)> = changes
+        .iter()
+        .map(|c| (c.original_start, c.original_end, c.modified_start, c.modified_end, c.change_type.clone()))
+        .collect();
+    let expected = vec![(1, 2, 1, 2, ChangeType::Modified), (3, 3, 3, 4, ChangeType::Added)];
+    if shape == expected && verify(&original, &modified, &changes).is_ok() {
+        HealthCheck { name: "diff engine", status: CheckStatus::Ok, detail: "known diff reproduced".to_string() }
+    } else {
+        HealthCheck { name: "diff engine", status: CheckStatus::Failed, detail: format!("unexpected result for a known diff: {:?}", shape) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::external::ExternalTool;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_run_checks() {
+        let dir = TempDir::new("doctor");
+        let status = |report: &HealthReport, name: &str| report.checks.iter().find(|c| c.name == name).map(|c| c.status);
+
+        let report = run_checks(&ExtensionSettings::default(), &dir);
+        assert_eq!(status(&report, "settings"), Some(CheckStatus::Ok));
+        assert_eq!(status(&report, "external tool"), Some(CheckStatus::Ok));
+        assert_eq!(status(&report, "working directory"), Some(CheckStatus::Ok));
+        assert_eq!(status(&report, "diff engine"), Some(CheckStatus::Ok));
+        assert!(report.healthy());
+
+        let tool = ExternalTool {
+            command: "zed-diff-no-such-tool".to_string(),
+            args: vec!["{original}".to_string(), "{modified}".to_string()],
+            ..ExternalTool::default()
+        };
+        let settings = ExtensionSettings {
+            log_level: Some("loud".to_string()),
+            external_tool: Some(tool),
+            churn_file: Some(dir.join("missing/churn.json").to_string_lossy().into_owned()),
+            ..ExtensionSettings::default()
+        };
+        let report = run_checks(&settings, &dir);
+        assert_eq!(status(&report, "settings"), Some(CheckStatus::Failed));
+        assert_eq!(status(&report, "external tool"), Some(CheckStatus::Failed));
+        assert_eq!(status(&report, "churn file directory"), Some(CheckStatus::Failed));
+        assert!(!report.healthy());
+        assert!(report.format().contains("✗ settings: Unknown log level: loud\n"));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::diff_core::LineChange;
+use crate::git::WorktreeChange;
+
+/// Files that must share a hunk before it is collapsed into one entry
+pub const DEFAULT_MIN_DUPLICATE_FILES: usize = 3;
+
+/// One hunk made identically in several files, such as a copyright bump or a copy-pasted fix
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateChange {
+    pub removed: Vec<String>,
+    pub added: Vec<String>,
+    /// Files with the hunk, in the order given, each listed once
+    pub paths: Vec<String>,
+}
+
+/// Hunks whose removed and added lines are the same in at least `min_files` files, in the order
+/// they first appear, and `files` with those hunks taken out; files left with no hunks of their
+/// own are dropped, as every change they have is in a duplicate. A `min_files` of 0 collapses
+/// nothing.
+pub fn collapse_duplicates(files: Vec<WorktreeChange>, min_files: usize) -> (Vec<DuplicateChange>, Vec<WorktreeChange>) {
+    let mut groups: Vec<DuplicateChange> = Vec::new();
+    for file in files.iter().filter(|file| !file.binary) {
+        for change in &file.changes {
+            let (removed, added) = hunk_body(&file.original, &file.modified, change);
+            match groups.iter_mut().find(|group| group.removed == removed && group.added == added) {
+                Some(group) if group.paths.last() != Some(&file.path) => group.paths.push(file.path.clone()),
+                Some(_) => {}
+                None => groups.push(DuplicateChange {
+                    removed: removed.to_vec(),
+                    added: added.to_vec(),
+                    paths: vec![file.path.clone()],
+                }),
+            }
+        }
+    }
+    groups.retain(|group| min_files > 0 && group.paths.len() >= min_files.max(2));
+
+    let remaining = files
+        .into_iter()
+        .filter_map(|mut file| {
+            if file.binary || file.changes.is_empty() {
+                return Some(file);
+            }
+            file.changes.retain(|change| {
+                let (removed, added) = hunk_body(&file.original, &file.modified, change);
+                !groups.iter().any(|group| group.removed == removed && group.added == added)
+            });
+            (!file.changes.is_empty()).then_some(file)
+        })
+        .collect();
+    (groups, remaining)
+}
+
+/// The lines a hunk removes and the lines it adds
+fn hunk_body<'a>(original: &'a [String], modified: &'a [String], change: &LineChange) -> (&'a [String], &'a [String]) {
+    (
+        &original[change.original_start..change.original_end],
+        &modified[change.modified_start..change.modified_end],
+    )
+}
+
+/// `Same change in N files`, followed by the hunk once and the files it was made in
+pub fn format_duplicate_change(duplicate: &DuplicateChange) -> String {
+    let mut output = format!("{}\n", duplicate_label(duplicate));
+    for line in &duplicate.removed {
+        output.push_str(&format!("-{}\n", line));
+    }
+    for line in &duplicate.added {
+        output.push_str(&format!("+{}\n", line));
+    }
+    output.push_str("Files:\n");
+    for path in &duplicate.paths {
+        output.push_str(&format!("  {}\n", path));
+    }
+    output
+}
+
+pub fn duplicate_label(duplicate: &DuplicateChange) -> String {
+    format!("Same change in {} files", duplicate.paths.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    fn file(path: &str, original: &str, modified: &str) -> WorktreeChange {
+        let (original, modified) = (lines(original), lines(modified));
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        WorktreeChange { path: path.to_string(), status: 'M', binary: false, submodule: false, original, modified, changes }
+    }
+
+    #[test]
+    fn test_collapse_duplicates() {
+        let files = || vec![
+            file("a.rs", "// (c) 2025\nfn a() {}", "// (c) 2026\nfn a() {}"),
+            file("b.rs", "// (c) 2025\nfn b() {}\nx", "// (c) 2026\nfn b() {}\ny"),
+            file("c.rs", "use x;\n// (c) 2025", "use x;\n// (c) 2026"),
+            file("d.rs", "// (c) 2024", "// (c) 2026"),
+        ];
+        let (duplicates, remaining) = collapse_duplicates(files(), 3);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].paths, ["a.rs", "b.rs", "c.rs"]);
+        let remaining: Vec<(&str, usize)> = remaining.iter().map(|file| (file.path.as_str(), file.changes.len())).collect();
+        assert_eq!(remaining, vec![("b.rs", 1), ("d.rs", 1)]);
+        assert_eq!(
+            format_duplicate_change(&duplicates[0]),
+            "Same change in 3 files\n-// (c) 2025\n+// (c) 2026\nFiles:\n  a.rs\n  b.rs\n  c.rs\n"
+        );
+        assert!(collapse_duplicates(files(), 4).0.is_empty());
+        assert_eq!(collapse_duplicates(files(), 0).1.len(), 4);
+    }
+}
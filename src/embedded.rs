@@ -0,0 +1,105 @@
+use crate::diff_core::Granularity;
+
+/// A range of lines written in a different language than the surrounding file
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmbeddedRegion {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub language: String,
+}
+
+const SQL_KEYWORDS: &[&str] = &["SELECT ", "INSERT INTO ", "UPDATE ", "DELETE FROM ", "CREATE TABLE "];
+
+/// Detect fenced Markdown code blocks, HTML `<script>`/`<style>` blocks and SQL in string literals
+pub fn detect_embedded_regions(lines: &[String]) -> Vec<EmbeddedRegion> {
+    let mut regions = Vec::new();
+    let mut open: Option<(usize, String, &str)> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+
+        if let Some((start, language, closer)) = &open {
+            if trimmed.starts_with(closer) {
+                regions.push(EmbeddedRegion {
+                    start_line: *start,
+                    end_line: index,
+                    language: language.clone(),
+                });
+                open = None;
+            }
+            continue;
+        }
+
+        if let Some(info) = trimmed.strip_prefix("```").or_else(|| trimmed.strip_prefix("~~~")) {
+            let fence = if trimmed.starts_with("```") { "```" } else { "~~~" };
+            let language = info.split_whitespace().next().unwrap_or("text").to_lowercase();
+            open = Some((index + 1, language, fence));
+        } else if trimmed.starts_with("<script") && !trimmed.contains("</script>") {
+            open = Some((index + 1, "javascript".to_string(), "</script>"));
+        } else if trimmed.starts_with("<style") && !trimmed.contains("</style>") {
+            open = Some((index + 1, "css".to_string(), "</style>"));
+        } else if contains_sql_string(line) {
+            regions.push(EmbeddedRegion {
+                start_line: index,
+                end_line: index + 1,
+                language: "sql".to_string(),
+            });
+        }
+    }
+
+    regions
+}
+
+fn contains_sql_string(line: &str) -> bool {
+    line.split(['"', '\''])
+        .skip(1)
+        .step_by(2)
+        .any(|literal| {
+            let upper = literal.trim_start().to_uppercase();
+            SQL_KEYWORDS.iter().any(|keyword| upper.starts_with(keyword))
+        })
+}
+
+pub fn region_at(regions: &[EmbeddedRegion], line: usize) -> Option<&EmbeddedRegion> {
+    regions
+        .iter()
+        .find(|region| region.start_line <= line && line < region.end_line)
+}
+
+/// Prose-like languages read best word by word, code benefits from exact character edits
+pub fn granularity_for_language(language: &str) -> Granularity {
+    match language {
+        "text" | "markdown" | "md" | "html" | "sql" => Granularity::Word,
+        _ => Granularity::Char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_detect_markdown_fence() {
+        let regions = detect_embedded_regions(&lines("# Title\n```rust\nfn main() {}\n```\ntext"));
+        assert_eq!(
+            regions,
+            vec![EmbeddedRegion {
+                start_line: 2,
+                end_line: 3,
+                language: "rust".to_string()
+            }]
+        );
+        assert_eq!(region_at(&regions, 2).unwrap().language, "rust");
+        assert!(region_at(&regions, 4).is_none());
+    }
+
+    #[test]
+    fn test_detect_script_and_sql() {
+        let regions = detect_embedded_regions(&lines(
+            "<script>\nlet x = 1;\n</script>\nquery(\"select * from users\")",
+        ));
+        assert_eq!(regions[0].language, "javascript");
+        assert_eq!((regions[1].start_line, regions[1].language.as_str()), (3, "sql"));
+    }
+}
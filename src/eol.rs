@@ -0,0 +1,119 @@
+use std::fs;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// Which line endings formatted output and patches are written with. Lines are read without
+/// their endings, so output is `\n`-separated unless one of these says otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputLineEndings {
+    /// Whatever the original file uses
+    MatchOriginal,
+    /// Whatever the modified file uses
+    MatchModified,
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl OutputLineEndings {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "original" | "file1" => Some(Self::MatchOriginal),
+            "modified" | "file2" => Some(Self::MatchModified),
+            "lf" => Some(Self::Lf),
+            "crlf" => Some(Self::Crlf),
+            _ => None,
+        }
+    }
+
+    pub fn resolve(self, file1_path: &str, file2_path: &str) -> LineEnding {
+        match self {
+            Self::MatchOriginal => detect_line_ending(file1_path),
+            Self::MatchModified => detect_line_ending(file2_path),
+            Self::Lf => LineEnding::Lf,
+            Self::Crlf => LineEnding::Crlf,
+        }
+    }
+}
+
+/// The ending of a file's first line; files that are unreadable or a single line count as LF
+pub fn detect_line_ending(path: &str) -> LineEnding {
+    let Ok(bytes) = fs::read(path) else {
+        return LineEnding::Lf;
+    };
+    match bytes.iter().position(|&b| b == b'\n') {
+        Some(at) if at > 0 && bytes[at - 1] == b'\r' => LineEnding::Crlf,
+        _ => LineEnding::Lf,
+    }
+}
+
+/// Whether a file's last line ends in a line break; unreadable and empty files count as ending
+/// in one
+pub fn has_final_newline(path: &str) -> bool {
+    match fs::read(path) {
+        Ok(bytes) => bytes.last().is_none_or(|&b| b == b'\n'),
+        Err(_) => true,
+    }
+}
+
+/// `lines` joined with `ending`, the last one too when `final_newline` is set
+pub fn join_lines(lines: &[String], ending: LineEnding, final_newline: bool) -> String {
+    let mut text = lines.join(ending.as_str());
+    if final_newline && !lines.is_empty() {
+        text.push_str(ending.as_str());
+    }
+    text
+}
+
+/// `text` with every line ending, LF or CRLF, replaced by `ending`
+pub fn with_line_endings(text: &str, ending: LineEnding) -> String {
+    let normalized = text.replace("\r\n", "\n");
+    match ending {
+        LineEnding::Lf => normalized,
+        LineEnding::Crlf => normalized.replace('\n', ending.as_str()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_line_endings() {
+        let dir = TempDir::new("eol");
+        let (windows, unix) = (dir.join("windows.txt"), dir.join("unix.txt"));
+        fs::write(&windows, "a\r\nb\r\n").unwrap();
+        fs::write(&unix, "a\nb\n").unwrap();
+        let (windows, unix) = (windows.to_string_lossy(), unix.to_string_lossy());
+
+        assert_eq!(OutputLineEndings::MatchOriginal.resolve(&windows, &unix), LineEnding::Crlf);
+        assert_eq!(OutputLineEndings::MatchModified.resolve(&windows, &unix), LineEnding::Lf);
+        assert_eq!(OutputLineEndings::Crlf.resolve(&unix, &unix), LineEnding::Crlf);
+        assert_eq!(detect_line_ending("no-such-file"), LineEnding::Lf);
+
+        assert_eq!(with_line_endings("-a\n+b\r\n", LineEnding::Crlf), "-a\r\n+b\r\n");
+        assert_eq!(with_line_endings("-a\r\n+b\n", LineEnding::Lf), "-a\n+b\n");
+
+        let lines = vec!["a".to_string(), "b".to_string()];
+        assert!(has_final_newline(&windows));
+        fs::write(dir.join("open.txt"), "a\nb").unwrap();
+        assert!(!has_final_newline(&dir.join("open.txt").to_string_lossy()));
+        assert_eq!(join_lines(&lines, LineEnding::Crlf, true), "a\r\nb\r\n");
+        assert_eq!(join_lines(&lines, LineEnding::Lf, false), "a\nb");
+    }
+}
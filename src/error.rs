@@ -0,0 +1,82 @@
+use std::fmt;
+
+/// Failures of the diff computation itself, as opposed to I/O around it
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffError {
+    /// The estimated working set exceeds `DiffOptions::max_memory_bytes`
+    MemoryLimit { required: u64, limit: u64 },
+    /// The computed changes do not reproduce the modified file (see `DiffOptions::verify`)
+    Verify(VerifyError),
+}
+
+/// Why a change list does not transform the original lines into the modified ones
+#[derive(Clone, Debug, PartialEq)]
+pub enum VerifyError {
+    /// Hunk overlaps or precedes the previous one, or has a negative range
+    OutOfOrder { hunk: usize },
+    /// Hunk range extends past the end of either file
+    OutOfBounds { hunk: usize },
+    /// Unchanged stretches before a hunk (or at the end, `hunk == None`) differ in length
+    GapMismatch { hunk: Option<usize> },
+    /// Lines outside every hunk differ
+    LineMismatch { original_line: usize, modified_line: usize },
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffError::MemoryLimit { required, limit } => write!(
+                f,
+                "diff would need about {} MiB, over the {} MiB limit (max_memory_bytes)",
+                required.div_ceil(1 << 20),
+                limit / (1 << 20)
+            ),
+            DiffError::Verify(error) => write!(f, "diff failed verification: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for DiffError {}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::OutOfOrder { hunk } => write!(f, "hunk {} overlaps the previous hunk", hunk + 1),
+            VerifyError::OutOfBounds { hunk } => write!(f, "hunk {} extends past the end of the file", hunk + 1),
+            VerifyError::GapMismatch { hunk: Some(hunk) } => {
+                write!(f, "unchanged lines before hunk {} differ in length", hunk + 1)
+            }
+            VerifyError::GapMismatch { hunk: None } => {
+                write!(f, "unchanged lines after the last hunk differ in length")
+            }
+            VerifyError::LineMismatch {
+                original_line,
+                modified_line,
+            } => write!(
+                f,
+                "unchanged line {} differs from modified line {}",
+                original_line + 1,
+                modified_line + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_limit_message() {
+        let error = DiffError::MemoryLimit {
+            required: 3 << 20,
+            limit: 2 << 20,
+        };
+        assert_eq!(
+            error.to_string(),
+            "diff would need about 3 MiB, over the 2 MiB limit (max_memory_bytes)"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::diff_core::LineChange;
+use crate::messages::{text, Message};
+use crate::state::ComparisonState;
+use crate::structure::{summarize_functions, FunctionStatus};
+
+/// Export a comparison, with its review annotations, as a Markdown document
+pub fn export_markdown(
+    state: &ComparisonState,
+    original_lines: &[String],
+    modified_lines: &[String],
+) -> String {
+    let mut output = format!("# `{}` → `{}`\n", state.file1_path, state.file2_path);
+
+    if state.diff_result.is_empty() {
+        output.push_str(&format!("\n{}\n", text(Message::FilesIdentical, [])));
+        return output;
+    }
+
+    let summaries = summarize_functions(original_lines, modified_lines, &state.diff_result);
+    if !summaries.is_empty() {
+        output.push_str("\n| Definition | Change | Lines |\n| --- | --- | --- |\n");
+        for summary in &summaries {
+            let status = match summary.status {
+                FunctionStatus::Added => "added",
+                FunctionStatus::Removed => "removed",
+                FunctionStatus::Modified => "modified",
+            };
+            output.push_str(&format!(
+                "| {} `{}` | {} | +{} −{} |\n",
+                summary.kind, summary.name, status, summary.added_lines, summary.removed_lines
+            ));
+        }
+    }
+
+    for (index, change) in state.diff_result.iter().enumerate() {
+        output.push_str(&format!(
+            "\n## Hunk {} `{}`: {}\n\n```diff\n",
+            index + 1,
+            change.id,
+            hunk_header(change)
+        ));
+        for line in hunk_body(change, original_lines, modified_lines) {
+            output.push_str(&line);
+            output.push('\n');
+        }
+        output.push_str("```\n");
+
+        for annotation in state.annotations_for_hunk(index) {
+            output.push_str(&format!("\n> **Note {}:** {}\n", annotation.id, annotation.text));
+        }
+    }
+
+    output
+}
+
+/// Export a comparison, with its review annotations, as a standalone HTML page
+pub fn export_html(
+    state: &ComparisonState,
+    original_lines: &[String],
+    modified_lines: &[String],
+) -> String {
+    let title = format!("{} → {}", escape_html(&state.file1_path), escape_html(&state.file2_path));
+    let mut output = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>.add{{background:#e6ffed}} .del{{background:#ffeef0}} .note{{border-left:3px solid #888;padding-left:8px}}</style>\n\
+         </head>\n<body>\n<h1>{}</h1>\n",
+        title, title
+    );
+
+    if state.diff_result.is_empty() {
+        output.push_str(&format!("<p>{}</p>\n", escape_html(&text(Message::FilesIdentical, []))));
+    }
+
+    for (index, change) in state.diff_result.iter().enumerate() {
+        output.push_str(&format!(
+            "<h2 id=\"hunk-{}\"><a href=\"#hunk-{}\">Hunk {}</a>: {}</h2>\n<pre>\n",
+            change.id,
+            change.id,
+            index + 1,
+            escape_html(&hunk_header(change))
+        ));
+        for line in hunk_body(change, original_lines, modified_lines) {
+            let class = if line.starts_with('-') { "del" } else { "add" };
+            output.push_str(&format!("<span class=\"{}\">{}</span>\n", class, escape_html(&line)));
+        }
+        output.push_str("</pre>\n");
+
+        for annotation in state.annotations_for_hunk(index) {
+            output.push_str(&format!(
+                "<p class=\"note\"><strong>Note {}:</strong> {}</p>\n",
+                annotation.id,
+                escape_html(&annotation.text)
+            ));
+        }
+    }
+
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+fn hunk_header(change: &LineChange) -> String {
+    format!(
+        "@@ -{},{} +{},{} @@",
+        change.original_start + 1,
+        change.original_end - change.original_start,
+        change.modified_start + 1,
+        change.modified_end - change.modified_start
+    )
+}
+
+fn hunk_body(change: &LineChange, original_lines: &[String], modified_lines: &[String]) -> Vec<String> {
+    original_lines[change.original_start..change.original_end]
+        .iter()
+        .map(|line| format!("-{}", line))
+        .chain(
+            modified_lines[change.modified_start..change.modified_end]
+                .iter()
+                .map(|line| format!("+{}", line)),
+        )
+        .collect()
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::redact::{default_redaction_patterns, Redactor};
+
+    #[test]
+    fn test_exports_include_annotations() {
+        let original = vec!["a".to_string(), "<b>".to_string()];
+        let modified = vec!["a".to_string(), "c".to_string()];
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let mut state = ComparisonState::new("x.txt".to_string(), "y.txt".to_string(), changes);
+        state.add_annotation(0, "renamed <b>".to_string()).unwrap();
+
+        let markdown = export_markdown(&state, &original, &modified);
+        assert!(markdown.contains("```diff\n-<b>\n+c\n```"));
+        assert!(markdown.contains("> **Note 1:** renamed <b>"));
+
+        let html = export_html(&state, &original, &modified);
+        assert!(html.contains("<span class=\"del\">-&lt;b&gt;</span>"));
+        assert!(html.contains("renamed &lt;b&gt;"));
+        assert!(html.contains(&format!("id=\"hunk-{}\"", state.diff_result[0].id)));
+        assert!(markdown.contains(&format!("## Hunk 1 `{}`", state.diff_result[0].id)));
+    }
+
+    #[test]
+    fn test_markdown_function_summary() {
+        let original = vec!["fn a() {".to_string(), "    1".to_string(), "}".to_string()];
+        let modified = vec!["fn a() {".to_string(), "    2".to_string(), "}".to_string()];
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let state = ComparisonState::new("x.rs".to_string(), "y.rs".to_string(), changes);
+
+        let markdown = export_markdown(&state, &original, &modified);
+        assert!(markdown.contains("| function `a` | modified | +1 −1 |"));
+    }
+
+    #[test]
+    fn test_redacted_export() {
+        let original = vec!["host = db".to_string(), "password = hunter2".to_string()];
+        let modified = vec!["host = db".to_string(), "password = correct-horse".to_string()];
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let state = ComparisonState::new("x.env".to_string(), "y.env".to_string(), changes);
+
+        let redactor = Redactor::new(&default_redaction_patterns()).unwrap();
+        let markdown = export_markdown(&state, &redactor.redact_lines(&original), &redactor.redact_lines(&modified));
+        assert!(markdown.contains("-password = [REDACTED]\n+password = [REDACTED]"));
+        assert!(!markdown.contains("hunter2") && !markdown.contains("correct-horse"));
+    }
+}
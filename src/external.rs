@@ -0,0 +1,256 @@
+use std::fs;
+use std::process::Command;
+use zed_extension_api::serde_json::Value;
+use crate::classify::classify_changes;
+use crate::diff_core::{verify, ChangeType, DiffOptions, LineChange};
+use crate::generated::{is_generated_path, mark_generated_changes};
+use crate::hunk_id::assign_hunk_ids;
+
+/// An external diff program used for files the built-in engine should not or cannot handle
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExternalTool {
+    pub command: String,
+    /// Argument template; `{original}` and `{modified}` are replaced by the file paths
+    pub args: Vec<String>,
+    /// File name patterns always delegated to the tool
+    pub file_patterns: Vec<String>,
+    /// Files at least this large are delegated to the tool
+    pub min_size_bytes: Option<u64>,
+}
+
+impl ExternalTool {
+    /// `{"command": "diff", "args": ["-u", "{original}", "{modified}"], ...}`; `None` without a command
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let strings = |key: &str| -> Vec<String> {
+            value
+                .get(key)
+                .and_then(Value::as_array)
+                .map(|items| items.iter().filter_map(Value::as_str).map(String::from).collect())
+                .unwrap_or_default()
+        };
+        let command = value.get("command").and_then(Value::as_str)?.to_string();
+        let args = match strings("args") {
+            args if args.is_empty() => vec!["-u".to_string(), "{original}".to_string(), "{modified}".to_string()],
+            args => args,
+        };
+        Some(Self {
+            command,
+            args,
+            file_patterns: strings("file_patterns"),
+            min_size_bytes: value.get("min_size_bytes").and_then(Value::as_u64),
+        })
+    }
+
+    /// Whether either file matches a pattern or reaches the size threshold
+    pub fn applies_to(&self, file1_path: &str, file2_path: &str) -> bool {
+        [file1_path, file2_path].iter().any(|path| {
+            let too_large = self.min_size_bytes.is_some_and(|limit| {
+                fs::metadata(path).map(|meta| meta.len() >= limit).unwrap_or(false)
+            });
+            is_generated_path(path, &self.file_patterns) || too_large
+        })
+    }
+
+    /// Run the tool and normalize its output into line changes
+    pub fn run(&self, file1_path: &str, file2_path: &str) -> Result<Vec<LineChange>, String> {
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| arg.replace("{original}", file1_path).replace("{modified}", file2_path))
+            .collect();
+        let output = Command::new(&self.command)
+            .args(&args)
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", self.command, e))?;
+
+        // diff(1) convention: 0 means identical, 1 means different, anything else is trouble
+        if !matches!(output.status.code(), Some(0) | Some(1)) {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("{} failed: {}", self.command, stderr.trim()));
+        }
+        parse_diff_output(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// Line changes from unified (`@@ -a,b +c,d @@`) or normal (`3,4c3`) diff output
+pub fn parse_diff_output(text: &str) -> Result<Vec<LineChange>, String> {
+    if text.lines().any(|line| line.starts_with("@@ ")) {
+        parse_unified_output(text)
+    } else {
+        parse_normal_output(text)
+    }
+}
+
+fn change(original: (usize, usize), modified: (usize, usize)) -> LineChange {
+    let change_type = match (original.0 == original.1, modified.0 == modified.1) {
+        (true, _) => ChangeType::Added,
+        (false, true) => ChangeType::Deleted,
+        (false, false) => ChangeType::Modified,
+    };
+    LineChange {
+        original_start: original.0,
+        original_end: original.1,
+        modified_start: modified.0,
+        modified_end: modified.1,
+        change_type,
+        char_changes: None,
+        generated: false,
+        tags: Vec::new(),
+        id: String::new(),
+    }
+}
+
+/// `a,b` (or `a`) as a 0-based start and line count; an empty range names the line it follows
+fn hunk_range(range: &str) -> Result<(usize, usize), String> {
+    let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+    let start: usize = start.parse().map_err(|_| format!("invalid hunk range: {}", range))?;
+    let count: usize = count.parse().map_err(|_| format!("invalid hunk range: {}", range))?;
+    Ok((if count == 0 { start } else { start.saturating_sub(1) }, count))
+}
+
+fn parse_unified_output(text: &str) -> Result<Vec<LineChange>, String> {
+    let mut changes = Vec::new();
+    let (mut original, mut modified) = (0, 0);
+    // Lines of the current hunk still to read on each side, from its header
+    let (mut original_left, mut modified_left) = (0, 0);
+    // Start of the current run of `-`/`+` lines
+    let mut run: Option<(usize, usize)> = None;
+
+    let close = |run: &mut Option<(usize, usize)>, changes: &mut Vec<LineChange>, original, modified| {
+        if let Some((o, m)) = run.take() {
+            changes.push(change((o, original), (m, modified)));
+        }
+    };
+
+    for line in text.lines() {
+        if original_left == 0 && modified_left == 0 {
+            close(&mut run, &mut changes, original, modified);
+            let Some(header) = line.strip_prefix("@@ ") else {
+                continue;
+            };
+            let mut ranges = header.split_whitespace();
+            let old = ranges.next().and_then(|r| r.strip_prefix('-'));
+            let new = ranges.next().and_then(|r| r.strip_prefix('+'));
+            let (Some(old), Some(new)) = (old, new) else {
+                return Err(format!("invalid hunk header: {}", line));
+            };
+            (original, original_left) = hunk_range(old)?;
+            (modified, modified_left) = hunk_range(new)?;
+            continue;
+        }
+        match line.chars().next() {
+            Some('\\') => {}
+            Some('-') if original_left > 0 => {
+                run.get_or_insert((original, modified));
+                original += 1;
+                original_left -= 1;
+            }
+            Some('+') if modified_left > 0 => {
+                run.get_or_insert((original, modified));
+                modified += 1;
+                modified_left -= 1;
+            }
+            // Some tools strip the space from empty context lines
+            Some(' ') | None if original_left > 0 && modified_left > 0 => {
+                close(&mut run, &mut changes, original, modified);
+                original += 1;
+                modified += 1;
+                original_left -= 1;
+                modified_left -= 1;
+            }
+            _ => return Err(format!("unexpected line in hunk: {}", line)),
+        }
+    }
+    if original_left > 0 || modified_left > 0 {
+        return Err("diff output ends inside a hunk".to_string());
+    }
+    close(&mut run, &mut changes, original, modified);
+    Ok(changes)
+}
+
+fn parse_normal_output(text: &str) -> Result<Vec<LineChange>, String> {
+    let mut changes = Vec::new();
+    for line in text.lines() {
+        if line.starts_with(['<', '>', '-', '\\']) || line.trim().is_empty() {
+            continue;
+        }
+        let Some(at) = line.find(['a', 'c', 'd']) else {
+            return Err(format!("unrecognized diff output: {}", line));
+        };
+        let parse = |range: &str| -> Result<(usize, usize), String> {
+            let (start, end) = range.split_once(',').unwrap_or((range, range));
+            let start = start.parse().map_err(|_| format!("unrecognized diff output: {}", line))?;
+            let end = end.parse().map_err(|_| format!("unrecognized diff output: {}", line))?;
+            Ok((start, end))
+        };
+        let (left, right) = (parse(&line[..at])?, parse(&line[at + 1..])?);
+        // `a` and `d` name the line the insertion or deletion follows on the other side
+        changes.push(match &line[at..at + 1] {
+            "a" => change((left.0, left.0), (right.0.saturating_sub(1), right.1)),
+            "d" => change((left.0.saturating_sub(1), left.1), (right.0, right.0)),
+            _ => change((left.0.saturating_sub(1), left.1), (right.0.saturating_sub(1), right.1)),
+        });
+    }
+    Ok(changes)
+}
+
+/// Run the tool and finish its changes like the built-in engine's: generated marking,
+/// classification, hunk ids, and verification when the options ask for it
+pub fn external_diff(
+    tool: &ExternalTool,
+    file1_path: &str,
+    file2_path: &str,
+    original_lines: &[String],
+    modified_lines: &[String],
+    options: &DiffOptions,
+) -> Result<Vec<LineChange>, String> {
+    let mut changes = tool.run(file1_path, file2_path)?;
+    if options.verify {
+        verify(original_lines, modified_lines, &changes)
+            .map_err(|e| format!("{} output does not match the files: {}", tool.command, e))?;
+    }
+    mark_generated_changes(&mut changes, original_lines, modified_lines);
+    classify_changes(&mut changes, original_lines, modified_lines);
+    assign_hunk_ids(&mut changes, original_lines, modified_lines);
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::json;
+
+    fn ranges(changes: &[LineChange]) -> Vec<(usize, usize, usize, usize)> {
+        changes
+            .iter()
+            .map(|c| (c.original_start, c.original_end, c.modified_start, c.modified_end))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_unified_output() {
+        let text = "--- a\n+++ b\n@@ -1,3 +1,4 @@\n a\n-b\n+B\n+x\n c\n@@ -0,0 +6 @@\n+tail\n@@ -9 +10,0 @@\n--- sql comment\n\\ No newline at end of file\n";
+        let changes = parse_diff_output(text).unwrap();
+        assert_eq!(ranges(&changes), vec![(1, 2, 1, 3), (0, 0, 5, 6), (8, 9, 10, 10)]);
+        assert_eq!(changes[0].change_type, ChangeType::Modified);
+        assert_eq!(changes[2].change_type, ChangeType::Deleted);
+        assert!(parse_diff_output("@@ -1,2 +1,2 @@\n-a\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_normal_output() {
+        let text = "2c2,3\n< b\n---\n> B\n> x\n5a7\n> y\n8,9d9\n< p\n< q\n";
+        let changes = parse_diff_output(text).unwrap();
+        assert_eq!(ranges(&changes), vec![(1, 2, 1, 3), (5, 5, 6, 7), (7, 9, 9, 9)]);
+        assert!(parse_diff_output("garbage line").is_err());
+    }
+
+    #[test]
+    fn test_tool_settings() {
+        let tool = ExternalTool::from_json(&json!({ "command": "difft", "file_patterns": ["*.bin"] })).unwrap();
+        assert_eq!(tool.args, vec!["-u", "{original}", "{modified}"]);
+        assert!(tool.applies_to("data/blob.bin", "other"));
+        assert!(!tool.applies_to("a.txt", "b.txt"));
+        assert!(ExternalTool::from_json(&json!({ "args": [] })).is_none());
+    }
+}
@@ -1,6 +1,6 @@
 use std::fs;
-use std::path::Path;
-use crate::diff_core::{compute_diff, DiffOptions, LineChange};
+use std::path::{Component, Path, PathBuf};
+use crate::diff_core::{try_compute_diff, DiffOptions, LineChange};
 
 pub fn read_file_lines(path: &str) -> Result<Vec<String>, std::io::Error> {
     let content = fs::read_to_string(Path::new(path))?;
@@ -15,15 +15,42 @@ pub fn compare_files(
     let lines1 = read_file_lines(file1_path)?;
     let lines2 = read_file_lines(file2_path)?;
 
-    Ok(compute_diff(&lines1, &lines2, options))
+    Ok(try_compute_diff(&lines1, &lines2, options)?)
+}
+
+/// `path` with `.` and `..` resolved lexically, so a prefix check sees where it really points
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_helpers::TempDir;
 
     #[test]
     fn test_read_file_lines() {
-        assert!(true);
+        let dir = TempDir::new("read");
+        let path = dir.join("read.txt");
+        fs::write(&path, "first\nsecond\n").unwrap();
+
+        let lines = read_file_lines(path.to_str().unwrap()).unwrap();
+        assert_eq!(lines, vec!["first".to_string(), "second".to_string()]);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path(Path::new("/work/./src/../a.txt")), Path::new("/work/a.txt"));
+        assert!(!normalize_path(Path::new("/work/../outside.txt")).starts_with("/work"));
     }
 }
@@ -0,0 +1,130 @@
+use std::path::Path;
+use std::time::SystemTime;
+use crate::walk::{walk_dir, WalkStep};
+
+/// Stop enumerating after this many files, so a huge worktree can't stall a completion
+const MAX_FILES: usize = 20_000;
+
+/// Directories never offered as comparison targets
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target"];
+
+/// A file that could be chosen as a comparison target
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileCandidate {
+    /// Relative to the root, `/`-separated
+    pub path: String,
+    pub modified: Option<SystemTime>,
+}
+
+/// Files under `root`, skipping hidden directories, `.git`, build output and dependencies
+pub fn list_files(root: &Path) -> Vec<FileCandidate> {
+    let mut files = Vec::new();
+    walk(root, "", &mut files);
+    files
+}
+
+fn walk(root: &Path, prefix: &str, files: &mut Vec<FileCandidate>) {
+    walk_dir(root, prefix, |path, entry, is_dir| {
+        if files.len() >= MAX_FILES {
+            return WalkStep::Stop;
+        }
+        if is_dir {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_str()) {
+                return WalkStep::Skip;
+            }
+        } else if entry.path().is_file() {
+            let modified = entry.metadata().and_then(|metadata| metadata.modified()).ok();
+            files.push(FileCandidate { path, modified });
+        }
+        WalkStep::Descend
+    });
+}
+
+/// How well `path` matches `query` as a case-insensitive subsequence, or `None` if it doesn't.
+/// Matches at the start of a path segment or word and runs of consecutive matches score higher,
+/// as do matches in the file name.
+pub fn fuzzy_score(query: &str, path: &str) -> Option<i64> {
+    let path_chars: Vec<char> = path.chars().collect();
+    let name_start = path_chars.iter().rposition(|&c| c == '/').map_or(0, |at| at + 1);
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.chars().flat_map(char::to_lowercase) {
+        let at = next + path_chars[next..].iter().position(|c| c.to_lowercase().eq([wanted]))?;
+        score += 1;
+        if at == 0 || matches!(path_chars[at - 1], '/' | '_' | '-' | '.' | ' ') {
+            score += 8;
+        }
+        if previous.is_some_and(|previous| previous + 1 == at) {
+            score += 5;
+        }
+        if at >= name_start {
+            score += 2;
+        }
+        previous = Some(at);
+        next = at + 1;
+    }
+    // Shorter paths win ties between otherwise equal matches
+    Some(score * 1000 - path_chars.len() as i64)
+}
+
+/// The best `limit` files under `root` for `query`, best match first and, among equal matches,
+/// most recently modified first; `exclude` (e.g. the first file of a comparison) is left out
+pub fn pick_files(root: &Path, query: &str, exclude: Option<&str>, limit: usize) -> Vec<FileCandidate> {
+    rank_candidates(list_files(root), query, exclude, limit)
+}
+
+fn rank_candidates(files: Vec<FileCandidate>, query: &str, exclude: Option<&str>, limit: usize) -> Vec<FileCandidate> {
+    let mut scored: Vec<(i64, FileCandidate)> = files
+        .into_iter()
+        .filter(|file| Some(file.path.as_str()) != exclude)
+        .filter_map(|file| Some((fuzzy_score(query, &file.path)?, file)))
+        .collect();
+    // An empty query matches everything equally, leaving recency to decide
+    let by_score = !query.is_empty();
+    scored.sort_by(|(a_score, a), (b_score, b)| {
+        let score = if by_score { b_score.cmp(a_score) } else { std::cmp::Ordering::Equal };
+        score.then_with(|| b.modified.cmp(&a.modified)).then_with(|| a.path.cmp(&b.path))
+    });
+    scored.into_iter().take(limit).map(|(_, file)| file).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("dc", "src/diff_core.rs").is_some());
+        assert!(fuzzy_score("xyz", "src/diff_core.rs").is_none());
+        assert!(fuzzy_score("lib", "src/lib.rs") > fuzzy_score("lib", "src/inline_builder.rs"));
+        assert!(fuzzy_score("DIFF", "src/diff_core.rs") > fuzzy_score("diff", "src/modified_file.rs"));
+    }
+
+    #[test]
+    fn test_pick_files() {
+        let root = TempDir::new("picker");
+        for path in ["src/main.rs", "src/mainframe.txt", ".git/config", "node_modules/x/main.js", "README.md"] {
+            fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            fs::write(root.join(path), "x").unwrap();
+        }
+        let mut listed: Vec<String> = list_files(&root).into_iter().map(|file| file.path).collect();
+        listed.sort();
+        assert_eq!(listed, vec!["README.md", "src/main.rs", "src/mainframe.txt"]);
+
+        let picked: Vec<String> = pick_files(&root, "main", Some("src/main.rs"), 5).into_iter().map(|file| file.path).collect();
+        assert_eq!(picked, vec!["src/mainframe.txt"]);
+
+        let at = |seconds| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+        let files = vec![
+            FileCandidate { path: "old.rs".to_string(), modified: at(1) },
+            FileCandidate { path: "new.rs".to_string(), modified: at(2) },
+        ];
+        let recent: Vec<String> = rank_candidates(files, "", None, 5).into_iter().map(|file| file.path).collect();
+        assert_eq!(recent, vec!["new.rs", "old.rs"]);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::diff_core::LineChange;
+
+pub const GENERATED_BEGIN_MARKER: &str = "@generated begin";
+pub const GENERATED_END_MARKER: &str = "@generated end";
+
+/// Line ranges enclosed by `@generated begin` / `@generated end` comments, markers included
+pub fn detect_generated_regions(lines: &[String]) -> Vec<(usize, usize)> {
+    let mut regions = Vec::new();
+    let mut begin = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.contains(GENERATED_BEGIN_MARKER) {
+            begin.get_or_insert(index);
+        } else if line.contains(GENERATED_END_MARKER) {
+            if let Some(start) = begin.take() {
+                regions.push((start, index + 1));
+            }
+        }
+    }
+
+    // An unterminated region runs to the end of the file
+    if let Some(start) = begin {
+        regions.push((start, lines.len()));
+    }
+    regions
+}
+
+/// Flag changes that fall entirely inside generated regions on both sides
+pub fn mark_generated_changes(
+    changes: &mut [LineChange],
+    original_lines: &[String],
+    modified_lines: &[String],
+) {
+    let original_regions = detect_generated_regions(original_lines);
+    let modified_regions = detect_generated_regions(modified_lines);
+    if original_regions.is_empty() && modified_regions.is_empty() {
+        return;
+    }
+
+    for change in changes {
+        change.generated = within_regions(&original_regions, change.original_start, change.original_end)
+            && within_regions(&modified_regions, change.modified_start, change.modified_end);
+    }
+}
+
+fn within_regions(regions: &[(usize, usize)], start: usize, end: usize) -> bool {
+    if start == end {
+        // Empty side of an insertion or deletion, check the insertion point
+        return regions.iter().any(|&(lo, hi)| lo < start && start < hi) || regions.is_empty();
+    }
+    regions.iter().any(|&(lo, hi)| lo <= start && end <= hi)
+}
+
+/// Whether `path` matches any of the configured generated-file patterns (`*` wildcards)
+pub fn is_generated_path(path: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| {
+        glob_match(pattern, path)
+            || path
+                .rsplit('/')
+                .next()
+                .is_some_and(|file_name| glob_match(pattern, file_name))
+    })
+}
+
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_changes_inside_markers_are_generated() {
+        let original = lines("fn a() {}\n// @generated begin\nconst X: u32 = 1;\n// @generated end\nfn b() {}");
+        let modified = lines("fn a2() {}\n// @generated begin\nconst X: u32 = 2;\n// @generated end\nfn b() {}");
+
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        assert_eq!(changes.len(), 2);
+        assert!(!changes[0].generated);
+        assert!(changes[1].generated);
+    }
+
+    #[test]
+    fn test_generated_path_patterns() {
+        let patterns = vec!["*.pb.rs".to_string(), "gen/*".to_string()];
+        assert!(is_generated_path("src/proto/api.pb.rs", &patterns));
+        assert!(is_generated_path("gen/bindings.rs", &patterns));
+        assert!(!is_generated_path("src/lib.rs", &patterns));
+    }
+}
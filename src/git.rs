@@ -0,0 +1,460 @@
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use crate::diff_core::{compute_diff, DiffOptions, LineChange};
+use crate::merge::{predict_conflicts, PredictedConflict};
+
+pub struct BranchComparison {
+    pub path: String,
+    pub base_branch: String,
+    pub branch: String,
+    pub merge_base: String,
+    /// Direct comparison of the two branch tips (`base..branch`)
+    pub two_dot: Vec<LineChange>,
+    /// Changes made on `branch` since the merge base (`base...branch`)
+    pub three_dot: Vec<LineChange>,
+}
+
+pub struct HistoryStep {
+    pub commit: String,
+    /// First parent, or `None` for a root commit
+    pub parent: Option<String>,
+    pub subject: String,
+    pub changes: Vec<LineChange>,
+}
+
+pub struct FileConflicts {
+    pub path: String,
+    pub conflicts: Vec<PredictedConflict>,
+}
+
+/// A file whose working copy differs from HEAD
+pub struct WorktreeChange {
+    /// Relative to the repository root
+    pub path: String,
+    /// `M` modified, `A` added to the index, `D` deleted, `?` untracked
+    pub status: char,
+    /// Either side holds a NUL byte; no line diff is computed
+    pub binary: bool,
+    /// A submodule or nested repository; its contents are not diffed
+    pub submodule: bool,
+    pub original: Vec<String>,
+    pub modified: Vec<String>,
+    pub changes: Vec<LineChange>,
+}
+
+/// Run a git command inside `repo` and return its trimmed stdout
+pub fn run_git(repo: &Path, args: &[&str]) -> Result<String, io::Error> {
+    let stdout = run_git_bytes(repo, args)?;
+    Ok(String::from_utf8_lossy(&stdout).trim_end().to_string())
+}
+
+/// `run_git` without decoding, for output that may not be text
+fn run_git_bytes(repo: &Path, args: &[&str]) -> Result<Vec<u8>, io::Error> {
+    let output = Command::new("git").arg("-C").arg(repo).args(args).output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Refuse a user-supplied revision git would parse as an option, such as `--output=<file>`
+fn check_revision(rev: &str) -> Result<(), io::Error> {
+    if rev.starts_with('-') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Not a revision: {}", rev)));
+    }
+    Ok(())
+}
+
+pub fn merge_base(repo: &Path, rev_a: &str, rev_b: &str) -> Result<String, io::Error> {
+    check_revision(rev_a)?;
+    check_revision(rev_b)?;
+    run_git(repo, &["merge-base", rev_a, rev_b])
+}
+
+/// Read `path` as of `rev`; a file missing at that revision reads as empty
+pub fn file_lines_at_revision(repo: &Path, rev: &str, path: &str) -> Result<Vec<String>, io::Error> {
+    check_revision(rev)?;
+    let object = format!("{}:{}", rev, path);
+    if run_git(repo, &["cat-file", "-e", &object]).is_err() {
+        // Still surface unknown revisions as errors
+        run_git(repo, &["rev-parse", "--verify", &format!("{}^{{commit}}", rev)])?;
+        return Ok(Vec::new());
+    }
+
+    // Untrimmed, so trailing blank lines and whitespace survive as they do in the working copy
+    let content = run_git_bytes(repo, &["show", &object])?;
+    Ok(String::from_utf8_lossy(&content).lines().map(String::from).collect())
+}
+
+pub fn compare_across_branches(
+    repo: &Path,
+    path: &str,
+    base_branch: &str,
+    branch: &str,
+    options: DiffOptions,
+) -> Result<BranchComparison, io::Error> {
+    let merge_base = merge_base(repo, base_branch, branch)?;
+
+    let base_lines = file_lines_at_revision(repo, base_branch, path)?;
+    let branch_lines = file_lines_at_revision(repo, branch, path)?;
+    let merge_base_lines = file_lines_at_revision(repo, &merge_base, path)?;
+
+    Ok(BranchComparison {
+        path: path.to_string(),
+        base_branch: base_branch.to_string(),
+        branch: branch.to_string(),
+        merge_base,
+        two_dot: compute_diff(&base_lines, &branch_lines, options.clone()),
+        three_dot: compute_diff(&merge_base_lines, &branch_lines, options),
+    })
+}
+
+/// Diff `path` commit-by-commit over `rev_range` (anything `git log` accepts), oldest first
+pub fn file_history(
+    repo: &Path,
+    path: &str,
+    rev_range: &str,
+    options: DiffOptions,
+) -> Result<Vec<HistoryStep>, io::Error> {
+    check_revision(rev_range)?;
+    let log = run_git(
+        repo,
+        &["log", "--reverse", "--format=%H%x09%P%x09%s", rev_range, "--", path],
+    )?;
+
+    let mut steps = Vec::new();
+    for entry in log.lines() {
+        let mut fields = entry.splitn(3, '\t');
+        let commit = fields.next().unwrap_or_default().to_string();
+        let parent = fields
+            .next()
+            .and_then(|parents| parents.split_whitespace().next())
+            .map(String::from);
+        let subject = fields.next().unwrap_or_default().to_string();
+
+        let before = match &parent {
+            Some(parent) => file_lines_at_revision(repo, parent, path)?,
+            None => Vec::new(),
+        };
+        let after = file_lines_at_revision(repo, &commit, path)?;
+
+        steps.push(HistoryStep {
+            changes: compute_diff(&before, &after, options.clone()),
+            commit,
+            parent,
+            subject,
+        });
+    }
+
+    Ok(steps)
+}
+
+/// Files changed between `from` and `to`
+pub fn changed_files(repo: &Path, from: &str, to: &str) -> Result<Vec<String>, io::Error> {
+    check_revision(from)?;
+    check_revision(to)?;
+    let names = run_git(repo, &["diff", "--name-only", from, to])?;
+    Ok(names.lines().map(String::from).collect())
+}
+
+/// Every file in the working tree (staged or not, plus untracked files) that differs from HEAD,
+/// sorted by path
+pub fn worktree_changes(repo: &Path, options: DiffOptions) -> Result<Vec<WorktreeChange>, io::Error> {
+    let root = run_git(repo, &["rev-parse", "--show-toplevel"])?;
+    let root = Path::new(&root);
+
+    // `(status, path, submodule)`; `--raw` gives the modes that mark gitlinks
+    let mut entries: Vec<(char, String, bool)> = Vec::new();
+    let tracked = run_git(root, &["diff", "--raw", "--no-renames", "-z", "HEAD"])?;
+    let mut fields = tracked.split('\0').filter(|field| !field.is_empty());
+    while let (Some(raw), Some(path)) = (fields.next(), fields.next()) {
+        let gitlink = raw.trim_start_matches(':').split(' ').take(2).any(|mode| mode == "160000");
+        entries.push((raw.chars().last().unwrap_or('M'), path.to_string(), gitlink));
+    }
+    // An untracked nested repository is listed as its directory, with a trailing `/`
+    let untracked = run_git(root, &["ls-files", "--others", "--exclude-standard", "-z"])?;
+    entries.extend(untracked.split('\0').filter(|path| !path.is_empty()).map(|path| {
+        let nested = path.ends_with('/');
+        ('?', path.trim_end_matches('/').to_string(), nested)
+    }));
+    entries.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let mut result = Vec::new();
+    for (status, path, submodule) in entries {
+        if submodule || root.join(&path).is_dir() {
+            result.push(WorktreeChange {
+                path,
+                status,
+                binary: false,
+                submodule: true,
+                original: Vec::new(),
+                modified: Vec::new(),
+                changes: Vec::new(),
+            });
+            continue;
+        }
+        let head = if status == 'A' || status == '?' {
+            Vec::new()
+        } else {
+            run_git_bytes(root, &["show", &format!("HEAD:{}", path)])?
+        };
+        let work = if status == 'D' { Vec::new() } else { std::fs::read(root.join(&path))? };
+        let binary = head.contains(&0) || work.contains(&0);
+        let to_lines = |bytes: &[u8]| -> Vec<String> {
+            if binary {
+                return Vec::new();
+            }
+            String::from_utf8_lossy(bytes).lines().map(String::from).collect()
+        };
+        let (original, modified) = (to_lines(&head), to_lines(&work));
+        result.push(WorktreeChange {
+            changes: compute_diff(&original, &modified, options.clone()),
+            path,
+            status,
+            binary,
+            submodule: false,
+            original,
+            modified,
+        });
+    }
+
+    Ok(result)
+}
+
+/// Who last touched the original lines of one hunk, most lines first
+#[derive(Clone, Debug, PartialEq)]
+pub struct HunkAuthors {
+    pub original_start: usize,
+    pub original_end: usize,
+    /// Author names with how many of the hunk's lines each last changed
+    pub authors: Vec<(String, usize)>,
+}
+
+/// Run `git blame` at HEAD over the lines each hunk of a working-tree file replaces or deletes;
+/// hunks that only add lines, and files new since HEAD, have no authors to report
+pub fn blame_hunks(repo: &Path, change: &WorktreeChange) -> Result<Vec<HunkAuthors>, io::Error> {
+    if change.binary || change.submodule || matches!(change.status, 'A' | '?') {
+        return Ok(Vec::new());
+    }
+    let root = run_git(repo, &["rev-parse", "--show-toplevel"])?;
+    let mut result = Vec::new();
+    for hunk in change.changes.iter().filter(|hunk| hunk.original_end > hunk.original_start) {
+        let range = format!("{},{}", hunk.original_start + 1, hunk.original_end);
+        let blame = run_git(Path::new(&root), &["blame", "--line-porcelain", "-L", &range, "HEAD", "--", &change.path])?;
+        let mut authors: Vec<(String, usize)> = Vec::new();
+        for author in blame.lines().filter_map(|line| line.strip_prefix("author ")) {
+            match authors.iter_mut().find(|(name, _)| name == author) {
+                Some((_, count)) => *count += 1,
+                None => authors.push((author.to_string(), 1)),
+            }
+        }
+        authors.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        result.push(HunkAuthors {
+            original_start: hunk.original_start,
+            original_end: hunk.original_end,
+            authors,
+        });
+    }
+    Ok(result)
+}
+
+/// Predict the files and hunks that will conflict when merging `branch` into `base_branch`
+pub fn predict_branch_conflicts(
+    repo: &Path,
+    base_branch: &str,
+    branch: &str,
+    options: DiffOptions,
+) -> Result<Vec<FileConflicts>, io::Error> {
+    let merge_base = merge_base(repo, base_branch, branch)?;
+    let theirs_changed = changed_files(repo, &merge_base, branch)?;
+
+    let mut result = Vec::new();
+    for path in changed_files(repo, &merge_base, base_branch)? {
+        if !theirs_changed.contains(&path) {
+            continue;
+        }
+
+        let base = file_lines_at_revision(repo, &merge_base, &path)?;
+        let ours = file_lines_at_revision(repo, base_branch, &path)?;
+        let theirs = file_lines_at_revision(repo, branch, &path)?;
+
+        let conflicts = predict_conflicts(&base, &ours, &theirs, options.clone());
+        if !conflicts.is_empty() {
+            result.push(FileConflicts { path, conflicts });
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::diff_core::ChangeType;
+    use std::fs;
+    use crate::test_helpers::TempDir;
+    use crate::ui::DEFAULT_CONTEXT_LINES;
+
+    pub(crate) fn init_repo(name: &str) -> TempDir {
+        let dir = TempDir::new(name);
+        run_git(&dir, &["init", "-q", "-b", "main"]).unwrap();
+        run_git(&dir, &["config", "user.email", "test@example.com"]).unwrap();
+        run_git(&dir, &["config", "user.name", "Test"]).unwrap();
+        dir
+    }
+
+    pub(crate) fn commit_file(repo: &Path, path: &str, content: &str, message: &str) {
+        fs::write(repo.join(path), content).unwrap();
+        run_git(repo, &["add", path]).unwrap();
+        run_git(repo, &["commit", "-q", "-m", message]).unwrap();
+    }
+
+    #[test]
+    fn test_compare_across_branches() {
+        let repo = init_repo("branches");
+        commit_file(&repo, "a.txt", "one\ntwo\nthree\n", "base");
+        run_git(&repo, &["checkout", "-q", "-b", "feature"]).unwrap();
+        commit_file(&repo, "a.txt", "one\ntwo\nthree\nfour\n", "feature");
+        run_git(&repo, &["checkout", "-q", "main"]).unwrap();
+        commit_file(&repo, "a.txt", "zero\none\ntwo\nthree\n", "main");
+
+        let comparison =
+            compare_across_branches(&repo, "a.txt", "main", "feature", DiffOptions::default())
+                .unwrap();
+
+        // The direct comparison also shows main's own change, the merge-base one does not
+        assert_eq!(comparison.two_dot.len(), 2);
+        assert_eq!(comparison.three_dot.len(), 1);
+        assert_eq!(comparison.three_dot[0].modified_start, 3);
+    }
+
+    #[test]
+    fn test_blame_hunks() {
+        let repo = init_repo("blame");
+        commit_file(&repo, "a.txt", "one\ntwo\nthree\n", "base");
+        run_git(&repo, &["config", "user.name", "Other"]).unwrap();
+        commit_file(&repo, "a.txt", "one\n2\nthree\n", "edit");
+        fs::write(repo.join("a.txt"), "ONE\nTWO\nthree\nfour\n").unwrap();
+
+        let changes = worktree_changes(&repo, DiffOptions::default()).unwrap();
+        let blamed = blame_hunks(&repo, &changes[0]).unwrap();
+        assert_eq!(blamed.len(), 1);
+        assert_eq!(blamed[0].authors, vec![("Test".to_string(), 1), ("Other".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_missing_file_reads_as_empty() {
+        let repo = init_repo("missing");
+        commit_file(&repo, "a.txt", "one\n", "base");
+
+        assert!(file_lines_at_revision(&repo, "main", "b.txt").unwrap().is_empty());
+        assert!(file_lines_at_revision(&repo, "nope", "a.txt").is_err());
+    }
+
+    #[test]
+    fn test_revision_keeps_trailing_blank_lines() {
+        let repo = init_repo("trailing");
+        commit_file(&repo, "a.txt", "one  \n\n\n", "base");
+
+        assert_eq!(file_lines_at_revision(&repo, "main", "a.txt").unwrap(), vec!["one  ", "", ""]);
+    }
+
+    #[test]
+    fn test_file_history() {
+        let repo = init_repo("history");
+        commit_file(&repo, "a.txt", "one\n", "create");
+        commit_file(&repo, "b.txt", "unrelated\n", "other file");
+        commit_file(&repo, "a.txt", "one\ntwo\n", "append");
+        commit_file(&repo, "a.txt", "two\n", "trim");
+
+        let steps = file_history(&repo, "a.txt", "HEAD", DiffOptions::default()).unwrap();
+        let subjects: Vec<&str> = steps.iter().map(|s| s.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["create", "append", "trim"]);
+        assert!(steps[0].parent.is_none());
+        assert_eq!(steps[1].changes[0].change_type, ChangeType::Added);
+        assert_eq!(steps[2].changes[0].change_type, ChangeType::Deleted);
+
+        let range = file_history(&repo, "a.txt", "HEAD~2..HEAD", DiffOptions::default()).unwrap();
+        assert_eq!(range.len(), 2);
+
+        let injected = format!("--output={}", repo.join("out.txt").display());
+        assert!(file_history(&repo, "a.txt", &injected, DiffOptions::default()).is_err());
+        assert!(!repo.join("out.txt").exists());
+        assert!(changed_files(&repo, "HEAD~1", &injected).is_err());
+    }
+
+    #[test]
+    fn test_worktree_changes() {
+        let repo = init_repo("worktree");
+        commit_file(&repo, "a.txt", "one\ntwo\n", "base");
+        commit_file(&repo, "gone.txt", "bye\n", "base");
+        fs::write(repo.join("a.txt"), "one\n2\nthree\n").unwrap();
+        fs::remove_file(repo.join("gone.txt")).unwrap();
+        fs::write(repo.join("new.bin"), b"\0\x01").unwrap();
+
+        let changes = worktree_changes(&repo, DiffOptions::default()).unwrap();
+        let summary: Vec<(char, &str, bool)> = changes.iter().map(|c| (c.status, c.path.as_str(), c.binary)).collect();
+        assert_eq!(summary, vec![('M', "a.txt", false), ('D', "gone.txt", false), ('?', "new.bin", true)]);
+
+        let stat = crate::ui::format_worktree_stat(&changes);
+        assert!(stat.starts_with("M a.txt    |   3 ++-\nD gone.txt |   1 -\n? new.bin  | Bin\n"));
+        assert!(stat.ends_with("3 file(s) changed, 2 insertion(s)(+), 2 deletion(s)(-), churn 4\n"));
+        let diff = crate::ui::format_worktree_file(&changes[1], DEFAULT_CONTEXT_LINES);
+        assert_eq!(diff, "diff --git a/gone.txt b/gone.txt\n--- a/gone.txt\n+++ /dev/null\n@@ -1,1 +0,0 @@\n-bye\n");
+        let diff = crate::ui::format_worktree_file(&changes[0], 0);
+        assert!(diff.ends_with("@@ -2,1 +2,2 @@\n-two\n+2\n+three\n"));
+    }
+
+    #[test]
+    fn test_worktree_changes_with_nested_repos() {
+        let repo = init_repo("worktree-nested");
+        commit_file(&repo, "a.txt", "one\n", "base");
+        let sub = repo.join("sub");
+        fs::create_dir(&sub).unwrap();
+        run_git(&sub, &["init", "-q", "-b", "main"]).unwrap();
+        run_git(&sub, &["config", "user.email", "test@example.com"]).unwrap();
+        run_git(&sub, &["config", "user.name", "Test"]).unwrap();
+        commit_file(&sub, "lib.txt", "v1\n", "sub base");
+        // Recorded as a gitlink, then moved on so the parent sees it modified
+        run_git(&repo, &["add", "sub"]).unwrap();
+        run_git(&repo, &["commit", "-q", "-m", "add sub"]).unwrap();
+        commit_file(&sub, "lib.txt", "v2\n", "sub update");
+        let other = repo.join("other");
+        fs::create_dir(&other).unwrap();
+        run_git(&other, &["init", "-q"]).unwrap();
+        fs::write(other.join("x.txt"), "x\n").unwrap();
+        fs::write(repo.join("a.txt"), "two\n").unwrap();
+
+        let changes = worktree_changes(&repo, DiffOptions::default()).unwrap();
+        let summary: Vec<(char, &str, bool)> = changes.iter().map(|c| (c.status, c.path.as_str(), c.submodule)).collect();
+        assert_eq!(summary, vec![('M', "a.txt", false), ('?', "other", true), ('M', "sub", true)]);
+        assert_eq!(crate::ui::format_worktree_file(&changes[2], DEFAULT_CONTEXT_LINES), "diff --git a/sub b/sub\nSubmodule sub changed\n");
+        assert!(blame_hunks(&repo, &changes[2]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_predict_branch_conflicts() {
+        let repo = init_repo("conflicts");
+        commit_file(&repo, "a.txt", "one\ntwo\nthree\n", "base");
+        commit_file(&repo, "b.txt", "one\ntwo\nthree\n", "base b");
+        run_git(&repo, &["checkout", "-q", "-b", "feature"]).unwrap();
+        commit_file(&repo, "a.txt", "one\nTWO\nthree\n", "feature a");
+        commit_file(&repo, "b.txt", "ONE\ntwo\nthree\n", "feature b");
+        run_git(&repo, &["checkout", "-q", "main"]).unwrap();
+        commit_file(&repo, "a.txt", "one\n2\nthree\n", "main a");
+
+        let predicted =
+            predict_branch_conflicts(&repo, "main", "feature", DiffOptions::default()).unwrap();
+        assert_eq!(predicted.len(), 1);
+        assert_eq!(predicted[0].path, "a.txt");
+        assert_eq!(predicted[0].conflicts.len(), 1);
+    }
+}
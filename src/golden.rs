@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::diff_core::{compute_diff, DiffOptions, Granularity};
+use crate::inline::{format_inline_diff, DEFAULT_MAX_HUNK_LINES};
+use crate::numbering::LineNumbering;
+use crate::patchmail::unified_hunks;
+use crate::structure::{format_function_summary, format_structural_diff, outline, structural_diff, summarize_functions};
+use crate::ui::{format_json, format_numbered_diff, format_stat, format_unified_diff, DEFAULT_CONTEXT_LINES};
+use crate::wdiff::format_wdiff;
+
+/// Set to `1` to rewrite expected outputs instead of comparing against them
+pub const UPDATE_ENV: &str = "UPDATE_GOLDEN";
+
+/// One case of the corpus: `<dir>/old.<ext>` and `<dir>/new.<ext>`, with expected outputs in
+/// `<dir>/expected/<output>.txt`
+pub struct Fixture {
+    pub name: String,
+    pub dir: PathBuf,
+    /// File names as they appear in headers, so outputs don't depend on where the corpus lives
+    pub old_name: String,
+    pub new_name: String,
+    pub original: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+/// Every fixture under `root`, sorted by name
+pub fn load_fixtures(root: &Path) -> Result<Vec<Fixture>, String> {
+    let entries = fs::read_dir(root).map_err(|e| format!("Cannot read fixtures {}: {}", root.display(), e))?;
+    let mut dirs: Vec<PathBuf> = entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.is_dir()).collect();
+    dirs.sort();
+    dirs.iter().map(|dir| load_fixture(dir)).collect()
+}
+
+fn load_fixture(dir: &Path) -> Result<Fixture, String> {
+    let name = dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let (old_name, original) = read_side(dir, "old")?;
+    let (new_name, modified) = read_side(dir, "new")?;
+    Ok(Fixture { name, dir: dir.to_path_buf(), old_name, new_name, original, modified })
+}
+
+/// The file in `dir` named `<stem>` or `<stem>.<ext>`, and its lines
+fn read_side(dir: &Path, stem: &str) -> Result<(String, Vec<String>), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Cannot read fixture {}: {}", dir.display(), e))?;
+    let path = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| path.is_file() && path.file_stem().is_some_and(|s| s == stem))
+        .ok_or_else(|| format!("Fixture {} has no {} file", dir.display(), stem))?;
+    let text = fs::read_to_string(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    Ok((name, text.lines().map(String::from).collect()))
+}
+
+/// Each formatter's and algorithm's output for `fixture`, by output name
+pub fn render_outputs(fixture: &Fixture) -> Vec<(&'static str, String)> {
+    let (old, new) = (fixture.old_name.as_str(), fixture.new_name.as_str());
+    let (original, modified) = (&fixture.original, &fixture.modified);
+    let options = DiffOptions { verify: true, ..DiffOptions::default() };
+    let changes = compute_diff(original, modified, options.clone());
+    let words = compute_diff(original, modified, DiffOptions { granularity: Granularity::Word, ..options.clone() });
+    let loose = compute_diff(original, modified, DiffOptions { ignore_whitespace: true, ignore_case: true, ..options.clone() });
+    let structure = structural_diff(&outline(original), &outline(modified), original, modified, &options);
+
+    vec![
+        ("unified", format_unified_diff(old, new, &changes)),
+        ("stat", format_stat(old, new, &changes)),
+        ("json", format_json(old, new, &changes)),
+        ("json-words", format_json(old, new, &words)),
+        ("unified-loose", format_unified_diff(old, new, &loose)),
+        ("inline", format_inline_diff(old, new, original, modified, &changes, DEFAULT_MAX_HUNK_LINES, None)),
+        ("patch", format!("--- a/{}\n+++ b/{}\n{}", old, new, unified_hunks(original, modified, &changes, DEFAULT_CONTEXT_LINES))),
+        ("numbered", format_numbered_diff(old, new, original, modified, &changes, DEFAULT_CONTEXT_LINES, &LineNumbering::default())),
+        ("wdiff", format_wdiff(original, modified, &options)),
+        ("structure", format_structural_diff(old, new, &structure)),
+        ("functions", format_function_summary(&summarize_functions(original, modified, &changes))),
+    ]
+}
+
+/// Compare every fixture's outputs with its expected files, or rewrite them when `update` is set.
+/// Returns a description of each mismatch; missing expected files count as mismatches.
+pub fn check_fixtures(root: &Path, update: bool) -> Result<Vec<String>, String> {
+    let mut mismatches = Vec::new();
+    for fixture in load_fixtures(root)? {
+        let expected_dir = fixture.dir.join("expected");
+        if update {
+            fs::create_dir_all(&expected_dir).map_err(|e| format!("Cannot create {}: {}", expected_dir.display(), e))?;
+        }
+        for (output, actual) in render_outputs(&fixture) {
+            let path = expected_dir.join(format!("{}.txt", output));
+            if update {
+                fs::write(&path, &actual).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+                continue;
+            }
+            match fs::read_to_string(&path) {
+                Ok(expected) if expected == actual => {}
+                Ok(expected) => mismatches.push(format!("{}/{}: {}", fixture.name, output, first_difference(&expected, &actual))),
+                Err(_) => mismatches.push(format!("{}/{}: no expected output", fixture.name, output)),
+            }
+        }
+    }
+    Ok(mismatches)
+}
+
+/// The first line where `expected` and `actual` disagree
+fn first_difference(expected: &str, actual: &str) -> String {
+    let (mut expected_lines, mut actual_lines) = (expected.lines(), actual.lines());
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => {}
+            (None, None) => break,
+            (e, a) => return format!("line {}: expected {:?}, got {:?}", line, e.unwrap_or("<end>"), a.unwrap_or("<end>")),
+        }
+    }
+    "trailing newline differs".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_golden_fixtures() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let update = std::env::var(UPDATE_ENV).is_ok_and(|value| value == "1");
+        let mismatches = check_fixtures(&root, update).unwrap();
+        assert!(
+            mismatches.is_empty(),
+            "{} golden output(s) differ; review and rerun with {}=1 to accept:\n{}",
+            mismatches.len(),
+            UPDATE_ENV,
+            mismatches.join("\n")
+        );
+    }
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(first_difference("a\nb\n", "a\nc\n"), "line 2: expected \"b\", got \"c\"");
+        assert_eq!(first_difference("a\n", "a\nb\n"), "line 2: expected \"<end>\", got \"b\"");
+        assert_eq!(first_difference("a\n", "a"), "trailing newline differs");
+    }
+}
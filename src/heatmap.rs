@@ -0,0 +1,72 @@
+use serde::Serialize;
+use crate::diff_core::{ChangeType, LineChange};
+
+/// Where the changes sit along the modified file, for a scrollbar or minimap heatmap.
+/// Each array has one value per bucket: the share of the bucket's lines touched by that kind of
+/// change, from 0.0 to 1.0.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Heatmap {
+    /// Lines of the modified file the buckets span
+    pub lines: usize,
+    pub added: Vec<f32>,
+    /// Deleted lines have no extent in the modified file; they count where they were removed
+    pub deleted: Vec<f32>,
+    pub modified: Vec<f32>,
+}
+
+/// Bin `changes` into `buckets` equal slices of a modified file `modified_len` lines long
+pub fn change_heatmap(changes: &[LineChange], modified_len: usize, buckets: usize) -> Heatmap {
+    let buckets = buckets.max(1);
+    let span = modified_len.max(1);
+    let mut counts = [vec![0usize; buckets], vec![0usize; buckets], vec![0usize; buckets]];
+    let bucket = |line: usize| line.min(span - 1) * buckets / span;
+
+    for change in changes {
+        match change.change_type {
+            ChangeType::Deleted => counts[1][bucket(change.modified_start)] += change.original_end - change.original_start,
+            ChangeType::Added | ChangeType::Modified => {
+                let kind = if change.change_type == ChangeType::Added { 0 } else { 2 };
+                for line in change.modified_start..change.modified_end {
+                    counts[kind][bucket(line)] += 1;
+                }
+            }
+        }
+    }
+
+    // Buckets cover `span / buckets` lines each, fewer than one when the file is short
+    let lines_per_bucket = span as f32 / buckets as f32;
+    let density = |counts: &[usize]| -> Vec<f32> {
+        counts.iter().map(|&n| (n as f32 / lines_per_bucket.max(1.0)).min(1.0)).collect()
+    };
+    Heatmap {
+        lines: modified_len,
+        added: density(&counts[0]),
+        deleted: density(&counts[1]),
+        modified: density(&counts[2]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_change_heatmap() {
+        let original = lines("1\n2\n3\n4\n5\n6\n7\n8");
+        let modified = lines("1\nA\nB\n2\n3\n4\n5\n6\n7\nX");
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+
+        let heatmap = change_heatmap(&changes, modified.len(), 5);
+        assert_eq!(heatmap.lines, 10);
+        assert_eq!(heatmap.added, vec![0.5, 0.5, 0.0, 0.0, 0.0]);
+        assert_eq!(heatmap.modified, vec![0.0, 0.0, 0.0, 0.0, 0.5]);
+        assert_eq!(heatmap.deleted, vec![0.0; 5]);
+
+        // More buckets than lines, and a file that was emptied entirely
+        assert_eq!(change_heatmap(&changes, modified.len(), 20).added.iter().filter(|&&d| d > 0.0).count(), 2);
+        let emptied = compute_diff(&original, &[], DiffOptions::default());
+        assert_eq!(change_heatmap(&emptied, 0, 3).deleted, vec![1.0, 0.0, 0.0]);
+    }
+}
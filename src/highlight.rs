@@ -0,0 +1,242 @@
+use std::ops::Range;
+use serde::Serialize;
+use crate::diff_core::LineChange;
+
+/// Token classes a renderer can map onto its syntax theme
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum TokenClass {
+    Keyword,
+    String,
+    Comment,
+    Number,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HighlightSpan {
+    /// Character range within the line
+    pub range: Range<usize>,
+    pub class: TokenClass,
+}
+
+/// One line of a hunk body with its side and syntax spans
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HighlightedLine {
+    /// `-` for original lines, `+` for modified ones
+    pub prefix: char,
+    pub text: String,
+    pub spans: Vec<HighlightSpan>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HighlightedHunk {
+    /// Language of the block, e.g. `rust`; `None` when the file type is not recognised
+    pub language: Option<&'static str>,
+    pub lines: Vec<HighlightedLine>,
+}
+
+struct Syntax {
+    line_comment: &'static str,
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Rust lifetimes make a lone `'` ambiguous, so only some languages quote with it
+    single_quote_strings: bool,
+    keywords: &'static [&'static str],
+}
+
+const C_LIKE_KEYWORDS: &[&str] = &[
+    "break", "case", "class", "const", "continue", "default", "else", "enum", "for", "if", "import", "interface",
+    "new", "package", "private", "public", "return", "static", "struct", "switch", "this", "void", "while",
+];
+
+/// Language name for a file path, from its extension
+pub fn language_for_path(path: &str) -> Option<&'static str> {
+    let extension = path.rsplit('/').next()?.rsplit_once('.')?.1.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "js" | "mjs" | "cjs" | "jsx" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" => "cpp",
+        "java" => "java",
+        "rb" => "ruby",
+        "sh" | "bash" | "zsh" => "shell",
+        "sql" => "sql",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        _ => return None,
+    })
+}
+
+fn syntax_for(language: &str) -> Option<Syntax> {
+    let c_like = |keywords| Syntax {
+        line_comment: "//",
+        block_comment: Some(("/*", "*/")),
+        single_quote_strings: true,
+        keywords,
+    };
+    let hash_comments = |keywords| Syntax {
+        line_comment: "#",
+        block_comment: None,
+        single_quote_strings: true,
+        keywords,
+    };
+    Some(match language {
+        "rust" => Syntax {
+            single_quote_strings: false,
+            ..c_like(&[
+                "as", "async", "await", "break", "const", "continue", "crate", "else", "enum", "fn", "for", "if",
+                "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+                "static", "struct", "super", "trait", "type", "unsafe", "use", "where", "while",
+            ])
+        },
+        "go" => c_like(&[
+            "break", "case", "chan", "const", "continue", "default", "defer", "else", "for", "func", "go", "if",
+            "import", "interface", "map", "package", "range", "return", "select", "struct", "switch", "type", "var",
+        ]),
+        "javascript" | "typescript" => c_like(&[
+            "async", "await", "break", "case", "class", "const", "continue", "default", "else", "export", "extends",
+            "for", "function", "if", "import", "interface", "let", "new", "return", "switch", "this", "type", "var",
+            "while",
+        ]),
+        "c" | "cpp" | "java" => c_like(C_LIKE_KEYWORDS),
+        "python" => hash_comments(&[
+            "and", "as", "async", "await", "class", "def", "elif", "else", "for", "from", "if", "import", "in",
+            "is", "lambda", "not", "or", "pass", "return", "while", "with", "yield",
+        ]),
+        "ruby" => hash_comments(&["class", "def", "do", "else", "elsif", "end", "if", "module", "return", "unless", "while"]),
+        "shell" => hash_comments(&["do", "done", "elif", "else", "esac", "fi", "for", "function", "if", "in", "then", "while"]),
+        "toml" | "yaml" => hash_comments(&["true", "false", "null"]),
+        "sql" => Syntax {
+            line_comment: "--",
+            block_comment: Some(("/*", "*/")),
+            single_quote_strings: true,
+            keywords: &[
+                "alter", "and", "by", "create", "delete", "from", "group", "insert", "into", "join", "not", "null",
+                "on", "or", "order", "select", "set", "table", "update", "values", "where",
+            ],
+        },
+        _ => return None,
+    })
+}
+
+/// Keyword, string, comment and number spans of one line; block comments and strings are only
+/// recognised when they close on the same line
+pub fn classify_line(line: &str, language: &str) -> Vec<HighlightSpan> {
+    let Some(syntax) = syntax_for(language) else {
+        return Vec::new();
+    };
+    let chars: Vec<char> = line.chars().collect();
+    let starts_with = |at: usize, token: &str| token.chars().enumerate().all(|(i, c)| chars.get(at + i) == Some(&c));
+    let mut spans = Vec::new();
+    let mut push = |range: Range<usize>, class| spans.push(HighlightSpan { range, class });
+    let mut at = 0;
+
+    while at < chars.len() {
+        let c = chars[at];
+        if starts_with(at, syntax.line_comment) {
+            push(at..chars.len(), TokenClass::Comment);
+            break;
+        }
+        if let Some((open, close)) = syntax.block_comment.filter(|(open, _)| starts_with(at, open)) {
+            let body = at + open.chars().count();
+            let end = (body..chars.len())
+                .find(|&i| starts_with(i, close))
+                .map_or(chars.len(), |i| i + close.chars().count());
+            push(at..end, TokenClass::Comment);
+            at = end;
+        } else if c == '"' || (c == '\'' && syntax.single_quote_strings) {
+            let mut end = at + 1;
+            while end < chars.len() && chars[end] != c {
+                end += if chars[end] == '\\' { 2 } else { 1 };
+            }
+            let end = (end + 1).min(chars.len());
+            push(at..end, TokenClass::String);
+            at = end;
+        } else if c.is_alphanumeric() || c == '_' {
+            let end = (at..chars.len()).find(|&i| !(chars[i].is_alphanumeric() || chars[i] == '_')).unwrap_or(chars.len());
+            let word: String = chars[at..end].iter().collect();
+            if c.is_ascii_digit() {
+                push(at..end, TokenClass::Number);
+            } else if syntax.keywords.contains(&word.as_str())
+                || (language == "sql" && syntax.keywords.contains(&word.to_ascii_lowercase().as_str()))
+            {
+                push(at..end, TokenClass::Keyword);
+            }
+            at = end;
+        } else {
+            at += 1;
+        }
+    }
+    spans
+}
+
+/// The removed and added lines of each change, classified for the language of `path`
+pub fn highlight_hunks(path: &str, original: &[String], modified: &[String], changes: &[LineChange]) -> Vec<HighlightedHunk> {
+    let language = language_for_path(path);
+    let line = |prefix: char, text: &String| HighlightedLine {
+        prefix,
+        text: text.clone(),
+        spans: language.map(|language| classify_line(text, language)).unwrap_or_default(),
+    };
+    changes
+        .iter()
+        .map(|change| HighlightedHunk {
+            language,
+            lines: original[change.original_start..change.original_end]
+                .iter()
+                .map(|text| line('-', text))
+                .chain(modified[change.modified_start..change.modified_end].iter().map(|text| line('+', text)))
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_classify_line() {
+        let classes = |line: &str, language: &str| -> Vec<(String, TokenClass)> {
+            let chars: Vec<char> = line.chars().collect();
+            classify_line(line, language)
+                .into_iter()
+                .map(|span| (chars[span.range].iter().collect(), span.class))
+                .collect()
+        };
+        assert_eq!(
+            classes("let s = \"a\\\"b\"; // x 1", "rust"),
+            vec![
+                ("let".to_string(), TokenClass::Keyword),
+                ("\"a\\\"b\"".to_string(), TokenClass::String),
+                ("// x 1".to_string(), TokenClass::Comment),
+            ]
+        );
+        assert_eq!(
+            classes("SELECT 'x' /* c */ FROM t", "sql"),
+            vec![
+                ("SELECT".to_string(), TokenClass::Keyword),
+                ("'x'".to_string(), TokenClass::String),
+                ("/* c */".to_string(), TokenClass::Comment),
+                ("FROM".to_string(), TokenClass::Keyword),
+            ]
+        );
+        // A lifetime is not an unterminated string
+        assert_eq!(classes("fn f<'a>() -> u8 { 42 }", "rust")[1], ("42".to_string(), TokenClass::Number));
+    }
+
+    #[test]
+    fn test_highlight_hunks() {
+        let (original, modified) = (lines("x = 1\ndef f(): pass"), lines("x = 2\ndef f(): pass"));
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let hunks = highlight_hunks("pkg/mod.py", &original, &modified, &changes);
+        assert_eq!(hunks[0].language, Some("python"));
+        let prefixes: Vec<char> = hunks[0].lines.iter().map(|l| l.prefix).collect();
+        assert_eq!(prefixes, vec!['-', '+']);
+        assert_eq!(hunks[0].lines[1].spans, vec![HighlightSpan { range: 4..5, class: TokenClass::Number }]);
+        assert!(highlight_hunks("notes.txt", &original, &modified, &changes)[0].lines[0].spans.is_empty());
+    }
+}
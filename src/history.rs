@@ -0,0 +1,179 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::changeset::Changeset;
+use crate::messages::{text, Message};
+
+/// Every this-many versions are stored in full, bounding how many deltas a lookup replays
+pub const CHECKPOINT_INTERVAL: usize = 20;
+
+/// Versions kept per file before the oldest are dropped
+pub const DEFAULT_MAX_VERSIONS: usize = 200;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StoredVersion {
+    Full(Vec<String>),
+    /// The edit from the previous version
+    Delta(Changeset),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Seconds since the Unix epoch
+    pub saved_at: u64,
+    pub version: StoredVersion,
+}
+
+/// Local history of one file, oldest version first. Most versions are deltas from the one
+/// before; a full copy starts every `CHECKPOINT_INTERVAL` versions and the oldest versions are
+/// dropped past `max_versions`, so the history stays bounded however often the file is saved.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LocalHistory {
+    pub path: String,
+    pub max_versions: usize,
+    pub snapshots: Vec<Snapshot>,
+}
+
+impl LocalHistory {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            max_versions: DEFAULT_MAX_VERSIONS,
+            snapshots: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Store `lines` as the newest version; returns false, storing nothing, when they match it
+    pub fn record(&mut self, lines: &[String], saved_at: u64) -> Result<bool, String> {
+        let chain = self.snapshots.iter().rev().take_while(|s| matches!(s.version, StoredVersion::Delta(_))).count();
+        let version = match self.len().checked_sub(1).map(|last| self.materialize(last)).transpose()? {
+            Some(latest) if latest == lines => return Ok(false),
+            Some(latest) if chain + 1 < CHECKPOINT_INTERVAL => {
+                StoredVersion::Delta(Changeset::between(&latest, lines).simplify())
+            }
+            _ => StoredVersion::Full(lines.to_vec()),
+        };
+        self.snapshots.push(Snapshot { saved_at, version });
+        self.prune(self.max_versions)?;
+        Ok(true)
+    }
+
+    /// The content of version `index`, replaying deltas from the nearest full copy before it
+    pub fn materialize(&self, index: usize) -> Result<Vec<String>, String> {
+        if index >= self.len() {
+            return Err(format!("No version {} of {} ({} stored)", index, self.path, self.len()));
+        }
+        let base = (0..=index)
+            .rev()
+            .find(|&i| matches!(self.snapshots[i].version, StoredVersion::Full(_)))
+            .ok_or_else(|| format!("History of {} has no full copy before version {}", self.path, index))?;
+        let StoredVersion::Full(lines) = &self.snapshots[base].version else {
+            unreachable!()
+        };
+        if base == index {
+            return Ok(lines.clone());
+        }
+        self.delta(base, index)?.apply(lines)
+    }
+
+    /// The composed edit taking version `from` to the later version `to`
+    pub fn delta(&self, from: usize, to: usize) -> Result<Changeset, String> {
+        if from > to || to >= self.len() {
+            return Err(format!("No versions {}..{} of {}", from, to, self.path));
+        }
+        if from == to {
+            let lines = self.materialize(from)?;
+            return Ok(Changeset::between(&lines, &lines));
+        }
+        let mut composed: Option<Changeset> = None;
+        for snapshot in &self.snapshots[from + 1..=to] {
+            composed = Some(match (&snapshot.version, composed) {
+                (StoredVersion::Delta(delta), None) => delta.clone(),
+                (StoredVersion::Delta(delta), Some(chain)) => chain.compose(delta)?,
+                // The chain restarts at a full copy, so diff the two ends instead
+                (StoredVersion::Full(_), _) => {
+                    return Ok(Changeset::between(&self.materialize(from)?, &self.materialize(to)?))
+                }
+            });
+        }
+        Ok(composed.unwrap_or_default().simplify())
+    }
+
+    /// Keep only the newest `keep` versions, turning the oldest survivor into a full copy
+    pub fn prune(&mut self, keep: usize) -> Result<(), String> {
+        let Some(excess) = self.len().checked_sub(keep).filter(|&excess| excess > 0) else {
+            return Ok(());
+        };
+        let first = self.materialize(excess)?;
+        self.snapshots.drain(..excess);
+        if let Some(snapshot) = self.snapshots.first_mut() {
+            snapshot.version = StoredVersion::Full(first);
+        }
+        Ok(())
+    }
+
+    /// Write the history through a temporary file, so a crash mid-write keeps the previous one
+    pub fn save(&self, history_file: &Path) -> io::Result<()> {
+        let json = serde_json::to_string(self).map_err(io::Error::other)?;
+        let temp = history_file.with_extension("tmp");
+        fs::write(&temp, json)?;
+        fs::rename(&temp, history_file)
+    }
+
+    pub fn load(history_file: &Path) -> Result<Self, String> {
+        let text =
+            fs::read_to_string(history_file).map_err(|e| text(Message::ReadFailed, [&history_file.display(), &e]))?;
+        serde_json::from_str(&text).map_err(|e| format!("invalid local history {}: {}", history_file.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{lines, TempDir};
+
+    #[test]
+    fn test_history_round_trip() {
+        let versions: Vec<Vec<String>> =
+            (0..45).map(|i| lines(&format!("header\nvalue = {}\n{}footer", i, "x\n".repeat(i % 4)))).collect();
+        let mut history = LocalHistory::new("config.toml");
+        for (i, version) in versions.iter().enumerate() {
+            assert!(history.record(version, i as u64).unwrap());
+        }
+        assert!(!history.record(&versions[44], 45).unwrap());
+
+        let full = history.snapshots.iter().filter(|s| matches!(s.version, StoredVersion::Full(_))).count();
+        assert_eq!(full, 3);
+        for (i, version) in versions.iter().enumerate() {
+            assert_eq!(&history.materialize(i).unwrap(), version);
+        }
+        assert_eq!(history.delta(3, 7).unwrap().apply(&versions[3]).unwrap(), versions[7]);
+        assert_eq!(history.delta(15, 25).unwrap().apply(&versions[15]).unwrap(), versions[25]);
+
+        let dir = TempDir::new("history");
+        let file = dir.join("history.json");
+        history.save(&file).unwrap();
+        assert_eq!(LocalHistory::load(&file).unwrap().materialize(30).unwrap(), versions[30]);
+    }
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let mut history = LocalHistory::new("a.txt");
+        history.max_versions = 3;
+        for i in 0..5 {
+            history.record(&lines(&format!("a\n{}", i)), i).unwrap();
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.snapshots[0].version, StoredVersion::Full(lines("a\n2")));
+        assert_eq!(history.materialize(2).unwrap(), lines("a\n4"));
+    }
+}
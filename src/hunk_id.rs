@@ -0,0 +1,133 @@
+use crate::diff_core::LineChange;
+
+/// Lines of surrounding context mixed into each hunk ID
+pub const HUNK_ID_CONTEXT: usize = 2;
+
+/// Give every change an ID derived from its content and surrounding context, so the same
+/// hunk keeps its ID when unrelated edits shift it up or down
+pub fn assign_hunk_ids(changes: &mut [LineChange], original_lines: &[String], modified_lines: &[String]) {
+    for change in changes.iter_mut() {
+        change.id = hunk_id(change, original_lines, modified_lines);
+    }
+}
+
+pub fn hunk_id(change: &LineChange, original_lines: &[String], modified_lines: &[String]) -> String {
+    let before_start = change.original_start.saturating_sub(HUNK_ID_CONTEXT);
+    let after_end = (change.original_end + HUNK_ID_CONTEXT).min(original_lines.len());
+
+    let mut hasher = Fnv1a::new();
+    for (section, lines) in [
+        ("before", &original_lines[before_start..change.original_start]),
+        ("removed", &original_lines[change.original_start..change.original_end]),
+        ("added", &modified_lines[change.modified_start..change.modified_end]),
+        ("after", &original_lines[change.original_end..after_end]),
+    ] {
+        hasher.write(section.as_bytes());
+        for line in lines {
+            hasher.write(&[0]);
+            hasher.write(line.as_bytes());
+        }
+        hasher.write(&[0xff]);
+    }
+
+    format!("{:08x}", hasher.finish() as u32)
+}
+
+pub fn find_hunk<'a>(changes: &'a [LineChange], id: &str) -> Option<&'a LineChange> {
+    changes.iter().find(|change| change.id == id)
+}
+
+/// Trailing `@@` comment carrying a hunk ID, e.g. `@@ -5,3 +5,4 @@ #h=ab12cd34`
+pub fn hunk_id_comment(id: &str) -> String {
+    format!(" #h={}", id)
+}
+
+/// The hunk ID embedded in an `@@` line by `hunk_id_comment`, if any
+pub fn parse_hunk_id_comment(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("@@ ")?;
+    let after_ranges = &rest[rest.find("@@")? + 2..];
+    let id = &after_ranges[after_ranges.rfind(" #h=")? + 4..];
+    let id = id.split_whitespace().next()?;
+    id.chars().all(|c| c.is_ascii_hexdigit()).then_some(id)
+}
+
+/// Append each change's ID to the `@@` lines of `text`, taking IDs in order from `ids`; the
+/// unified formatter writes one such line per change, so pages of one diff can share `ids`
+pub fn embed_hunk_ids<'a>(text: &str, ids: &mut impl Iterator<Item = &'a str>) -> String {
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let (body, newline) = line.strip_suffix('\n').map_or((line, ""), |body| (body, "\n"));
+        output.push_str(body);
+        if body.starts_with("@@ -") {
+            if let Some(id) = ids.next() {
+                output.push_str(&hunk_id_comment(id));
+            }
+        }
+        output.push_str(newline);
+    }
+    output
+}
+
+/// FNV-1a, chosen over `DefaultHasher` because its output must stay stable across builds
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_ids_survive_unrelated_edits() {
+        let original = lines("a\nb\nc\nd\ne\nf\ng");
+        let modified = lines("a\nb\nc\nD\ne\nf\ng");
+        let shifted_original = lines("x\ny\na\nb\nc\nd\ne\nf\ng");
+        let shifted_modified = lines("x\ny\na\nb\nc\nD\ne\nf\ng");
+
+        let first = compute_diff(&original, &modified, DiffOptions::default());
+        let second = compute_diff(&shifted_original, &shifted_modified, DiffOptions::default());
+        assert_eq!(first[0].id.len(), 8);
+        assert_eq!(first[0].id, second[0].id);
+        assert_eq!(find_hunk(&second, &first[0].id).unwrap().original_start, 5);
+    }
+
+    #[test]
+    fn test_ids_differ_by_content() {
+        let original = lines("a\nb\na\nb");
+        let modified = lines("a\nX\na\nY");
+
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        assert_eq!(changes.len(), 2);
+        assert_ne!(changes[0].id, changes[1].id);
+    }
+
+    #[test]
+    fn test_embedded_ids_round_trip() {
+        let changes = compute_diff(&lines("a\nb\na\nb"), &lines("a\nX\na\nY"), DiffOptions::default());
+        let text = crate::ui::format_unified_diff("x", "y", &changes);
+        let embedded = embed_hunk_ids(&text, &mut changes.iter().map(|c| c.id.as_str()));
+
+        let parsed: Vec<&str> = embedded.lines().filter_map(parse_hunk_id_comment).collect();
+        assert_eq!(parsed, vec![changes[0].id.as_str(), changes[1].id.as_str()]);
+        assert_eq!(parse_hunk_id_comment("@@ -1 +1 @@ fn main() #h=00ff00ff"), Some("00ff00ff"));
+        assert_eq!(parse_hunk_id_comment("@@ -1 +1 @@ #h=not-hex"), None);
+        assert_eq!(parse_hunk_id_comment("+ #h=00ff00ff"), None);
+    }
+}
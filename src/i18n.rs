@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde_json::Value;
+use crate::keyvalue::parse_config;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TranslationFormat {
+    /// Nested JSON objects, keys flattened with `.`
+    Json,
+    /// gettext catalogs (`.po` / `.pot`)
+    Po,
+    /// Java-style `.properties` bundles
+    Properties,
+}
+
+impl TranslationFormat {
+    pub fn for_path(path: &str) -> Option<Self> {
+        match Path::new(path).extension()?.to_str()? {
+            "json" => Some(TranslationFormat::Json),
+            "po" | "pot" => Some(TranslationFormat::Po),
+            "properties" => Some(TranslationFormat::Properties),
+            _ => None,
+        }
+    }
+}
+
+/// Keys of the translation compared with the reference locale
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TranslationReport {
+    /// In the reference but not in the translation
+    pub missing: Vec<String>,
+    /// In the translation but not in the reference
+    pub extra: Vec<String>,
+    /// Present but untranslated (empty, or marked fuzzy in gettext catalogs)
+    pub empty: Vec<String>,
+}
+
+pub fn parse_translations(format: TranslationFormat, lines: &[String]) -> Result<BTreeMap<String, String>, String> {
+    match format {
+        TranslationFormat::Json => {
+            let document: Value =
+                serde_json::from_str(&lines.join("\n")).map_err(|e| format!("invalid translation JSON: {}", e))?;
+            let mut messages = BTreeMap::new();
+            flatten_json(&document, "", &mut messages);
+            Ok(messages)
+        }
+        TranslationFormat::Po => Ok(parse_po(lines)),
+        TranslationFormat::Properties => Ok(parse_config(lines).into_iter().map(|e| (e.key, e.value)).collect()),
+    }
+}
+
+fn flatten_json(value: &Value, prefix: &str, messages: &mut BTreeMap<String, String>) {
+    let join = |key: &str| if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) };
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                flatten_json(child, &join(key), messages);
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                flatten_json(child, &join(&i.to_string()), messages);
+            }
+        }
+        Value::String(text) => {
+            messages.insert(prefix.to_string(), text.clone());
+        }
+        Value::Null => {
+            messages.insert(prefix.to_string(), String::new());
+        }
+        other => {
+            messages.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+/// gettext entries keyed by `msgid`, or `msgctxt|msgid` when a context is given. Plural forms
+/// join their `msgstr[n]` with `|` and read as empty when any form is missing; the header entry
+/// (empty `msgid`) is skipped.
+pub fn parse_po(lines: &[String]) -> BTreeMap<String, String> {
+    #[derive(Default)]
+    struct Entry {
+        context: Option<String>,
+        id: Option<String>,
+        forms: Vec<String>,
+        fuzzy: bool,
+    }
+
+    let mut messages = BTreeMap::new();
+    let mut entry = Entry::default();
+    // Index into the field that continuation lines (`"..."`) append to
+    let mut field: Option<usize> = None;
+
+    let finish = |entry: Entry, messages: &mut BTreeMap<String, String>| {
+        let Some(id) = entry.id.filter(|id| !id.is_empty()) else {
+            return;
+        };
+        let key = match entry.context {
+            Some(context) => format!("{}|{}", context, id),
+            None => id,
+        };
+        let translated = !entry.fuzzy && !entry.forms.is_empty() && entry.forms.iter().all(|form| !form.is_empty());
+        let value = if translated { entry.forms.join("|") } else { String::new() };
+        messages.insert(key, value);
+    };
+
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(flags) = line.strip_prefix("#,") {
+            // Flags start a new entry
+            if entry.id.is_some() {
+                finish(std::mem::take(&mut entry), &mut messages);
+            }
+            entry.fuzzy = flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some(text) = line.strip_prefix('"') {
+            let text = unescape_po(text.strip_suffix('"').unwrap_or(text));
+            match field {
+                Some(0) => entry.context.get_or_insert_with(String::new).push_str(&text),
+                Some(1) => entry.id.get_or_insert_with(String::new).push_str(&text),
+                Some(n) => {
+                    if let Some(form) = entry.forms.get_mut(n - 2) {
+                        form.push_str(&text);
+                    }
+                }
+                None => {}
+            }
+            continue;
+        }
+
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let text = unescape_po(rest.strip_prefix('"').and_then(|r| r.strip_suffix('"')).unwrap_or(rest));
+        match keyword {
+            "msgctxt" => {
+                if entry.id.is_some() {
+                    finish(std::mem::take(&mut entry), &mut messages);
+                }
+                entry.context = Some(text);
+                field = Some(0);
+            }
+            "msgid" => {
+                if entry.id.is_some() {
+                    finish(std::mem::take(&mut entry), &mut messages);
+                }
+                entry.id = Some(text);
+                field = Some(1);
+            }
+            "msgid_plural" => field = None,
+            _ if keyword.starts_with("msgstr") => {
+                entry.forms.push(text);
+                field = Some(entry.forms.len() + 1);
+            }
+            _ => field = None,
+        }
+    }
+    finish(entry, &mut messages);
+    messages
+}
+
+fn unescape_po(text: &str) -> String {
+    text.replace("\\n", "\n").replace("\\t", "\t").replace("\\\"", "\"").replace("\\\\", "\\")
+}
+
+pub fn compare_translations(reference: &BTreeMap<String, String>, translation: &BTreeMap<String, String>) -> TranslationReport {
+    let mut report = TranslationReport::default();
+    // A `.pot` template has no reference text at all, so emptiness is judged on the translation alone
+    for key in reference.keys() {
+        match translation.get(key) {
+            None => report.missing.push(key.clone()),
+            Some(translated) if translated.trim().is_empty() => report.empty.push(key.clone()),
+            Some(_) => {}
+        }
+    }
+    report.extra = translation.keys().filter(|key| !reference.contains_key(*key)).cloned().collect();
+    report
+}
+
+pub fn format_translation_report(file1_path: &str, file2_path: &str, report: &TranslationReport) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", file1_path, file2_path);
+    if report.missing.is_empty() && report.extra.is_empty() && report.empty.is_empty() {
+        output.push_str("\nTranslations are in sync\n");
+        return output;
+    }
+
+    output.push_str(&format!(
+        "\n{} missing, {} extra, {} untranslated\n",
+        report.missing.len(),
+        report.extra.len(),
+        report.empty.len()
+    ));
+    for (title, keys) in [("Missing", &report.missing), ("Extra", &report.extra), ("Untranslated", &report.empty)] {
+        if keys.is_empty() {
+            continue;
+        }
+        output.push_str(&format!("\n## {}\n", title));
+        for key in keys {
+            output.push_str(&format!("{}\n", key));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_json_translations() {
+        let reference = parse_translations(
+            TranslationFormat::Json,
+            &lines(r#"{"menu": {"open": "Open", "save": "Save"}, "title": "App", "items": ["One"]}"#),
+        )
+        .unwrap();
+        let translation = parse_translations(
+            TranslationFormat::Json,
+            &lines(r#"{"menu": {"open": "Ouvrir", "save": ""}, "items": ["Un"], "old": "Vieux"}"#),
+        )
+        .unwrap();
+        assert!(reference.contains_key("items.0"));
+
+        let report = compare_translations(&reference, &translation);
+        assert_eq!(report.missing, vec!["title"]);
+        assert_eq!(report.extra, vec!["old"]);
+        assert_eq!(report.empty, vec!["menu.save"]);
+
+        let text = format_translation_report("en.json", "fr.json", &report);
+        assert!(text.contains("1 missing, 1 extra, 1 untranslated"));
+        assert!(text.contains("\n## Untranslated\nmenu.save\n"));
+    }
+
+    #[test]
+    fn test_po_entries() {
+        let messages = parse_po(&lines(
+            "msgid \"\"\nmsgstr \"\"\n\"Language: fr\\n\"\n\n#: main.c:1\nmsgid \"Hello\"\nmsgstr \"Bonjour\"\n\n#, fuzzy\nmsgid \"Bye\"\nmsgstr \"Salut\"\n\nmsgctxt \"menu\"\nmsgid \"\"\n\"Open\"\nmsgstr \"\"\n\nmsgid \"file\"\nmsgid_plural \"files\"\nmsgstr[0] \"fichier\"\nmsgstr[1] \"fichiers\"",
+        ));
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages["Hello"], "Bonjour");
+        assert_eq!(messages["Bye"], "");
+        assert_eq!(messages["menu|Open"], "");
+        assert_eq!(messages["file"], "fichier|fichiers");
+    }
+}
@@ -0,0 +1,81 @@
+use crate::classify::is_import_line;
+
+/// Sort each contiguous block of single-line import statements so reordering by tooling
+/// does not show up as a change. Blocks keep their position and size.
+pub fn normalize_import_blocks(lines: &[String]) -> Vec<String> {
+    let mut normalized = lines.to_vec();
+    let mut index = 0;
+
+    while index < normalized.len() {
+        if !is_sortable_import(&normalized[index]) {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        while index < normalized.len() && is_sortable_import(&normalized[index]) {
+            index += 1;
+        }
+        normalized[start..index].sort_by_key(|line| import_sort_key(line));
+    }
+
+    normalized
+}
+
+/// Multi-line statements (`use a::{` ...) are left in place
+fn is_sortable_import(line: &str) -> bool {
+    is_import_line(line) && !line.trim_end().ends_with(['{', '(', ','])
+}
+
+/// Sort by the imported path so `import x` and `from x import y` group together
+fn import_sort_key(line: &str) -> String {
+    let trimmed = line.trim();
+    let path = ["pub use ", "use ", "from ", "import ", "#include ", "extern crate "]
+        .iter()
+        .find_map(|prefix| trimmed.strip_prefix(prefix))
+        .unwrap_or(trimmed);
+    path.trim_matches(|c: char| c == '<' || c == '"' || c == '\'')
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::ChangeTag;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_normalize_import_blocks() {
+        let normalized = normalize_import_blocks(&lines("use b;\nuse a::{\n    c,\n};\nuse z;\nuse y;"));
+        assert_eq!(normalized, lines("use b;\nuse a::{\n    c,\n};\nuse y;\nuse z;"));
+    }
+
+    #[test]
+    fn test_reordered_imports_are_not_changes() {
+        let original = lines("use std::io;\nuse crate::a;\nuse std::fs;\n\nfn main() {}");
+        let modified = lines("use crate::a;\nuse std::fs;\nuse std::io;\n\nfn main() {}");
+        let options = DiffOptions {
+            normalize_imports: true,
+            ..DiffOptions::default()
+        };
+
+        assert!(compute_diff(&original, &modified, options).is_empty());
+        assert!(!compute_diff(&original, &modified, DiffOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_added_import_still_reported() {
+        let original = lines("import os\nimport sys\nprint(1)");
+        let modified = lines("import sys\nimport json\nimport os\nprint(1)");
+        let options = DiffOptions {
+            normalize_imports: true,
+            ..DiffOptions::default()
+        };
+
+        let changes = compute_diff(&original, &modified, options);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].tags, vec![ChangeTag::ImportOnly]);
+        assert_eq!(changes[0].modified_end - changes[0].modified_start, 1);
+    }
+}
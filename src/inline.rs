@@ -0,0 +1,202 @@
+use crate::align::align_lines;
+use crate::diff_core::{compute_diff, ChangeType, DiffOptions, LineChange};
+use crate::messages::{text, Message};
+use crate::numbering::{Gutter, LineNumbering};
+
+/// Unchanged lines shown around each change
+const CONTEXT_LINES: usize = 3;
+
+/// Hunk rows shown before an omission marker when the setting is absent
+pub const DEFAULT_MAX_HUNK_LINES: usize = 200;
+
+/// Rows kept at each end of a truncated hunk
+const TRUNCATED_EDGE_LINES: usize = 5;
+
+/// Single-pane view: each change's old lines, struck through, directly above their
+/// replacements. Old and new lines of a modified hunk are paired by similarity; within a pair the
+/// removed text is wrapped in `~~` and the inserted text in `**`, while unpaired deleted lines are
+/// struck through entirely.
+///
+/// Hunks over `max_hunk_lines` rows (0 for no limit) keep their first and last few rows around an
+/// `… N lines omitted` marker naming the hunk and the omitted rows for `inline_hunk_rows`.
+///
+/// With `numbering`, hunk headers count from its base and every row starts with a gutter holding
+/// the line's number: in the original file for deleted rows, in the modified file otherwise.
+pub fn format_inline_diff(
+    file1_path: &str,
+    file2_path: &str,
+    original: &[String],
+    modified: &[String],
+    changes: &[LineChange],
+    max_hunk_lines: usize,
+    numbering: Option<&LineNumbering>,
+) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", file1_path, file2_path);
+    if changes.is_empty() {
+        output.push_str(&format!("\n{}\n", text(Message::FilesIdentical, [])));
+        return output;
+    }
+
+    let default = LineNumbering::default();
+    let gutter = numbering.map(|numbering| Gutter::new(numbering, original, modified));
+    let number = |index: usize| numbering.unwrap_or(&default).number(index);
+    let prefix = |side: usize, index: usize| gutter.as_ref().map(|gutter| gutter.format(side, Some(index))).unwrap_or_default();
+
+    // Original lines already printed as context, so neighbouring changes share it
+    let mut shown_until = 0;
+    for (index, change) in changes.iter().enumerate() {
+        let context_start = change.original_start.saturating_sub(CONTEXT_LINES).max(shown_until);
+        if index == 0 || context_start > shown_until {
+            output.push_str(&format!("\n@@ line {} @@\n", number(change.modified_start)));
+        }
+        // Context lines are numbered where they sit in the modified file
+        let shift = change.modified_start as isize - change.original_start as isize;
+        for (i, line) in original.iter().enumerate().take(change.original_start).skip(context_start) {
+            output.push_str(&format!("{}  {}\n", prefix(1, (i as isize + shift) as usize), line));
+        }
+
+        let mut rows = inline_hunk_rows(original, modified, change);
+        if gutter.is_some() {
+            let mut next = [change.original_start, change.modified_start];
+            for row in &mut rows {
+                let side = if row.starts_with('-') { 0 } else { 1 };
+                row.insert_str(0, &prefix(side, next[side]));
+                next[side] += 1;
+            }
+        }
+        let edge = TRUNCATED_EDGE_LINES.min(max_hunk_lines / 2);
+        if max_hunk_lines > 0 && rows.len() > max_hunk_lines {
+            let (first, last) = (edge + 1, rows.len() - edge);
+            rows[..edge].iter().for_each(|row| output.push_str(&format!("{}\n", row)));
+            output.push_str(&format!(
+                "… {} lines omitted (expand: --expand={}:{}-{})\n",
+                last - first + 1,
+                change.id,
+                first,
+                last
+            ));
+            rows[last..].iter().for_each(|row| output.push_str(&format!("{}\n", row)));
+        } else {
+            rows.iter().for_each(|row| output.push_str(&format!("{}\n", row)));
+        }
+
+        // Trailing context stops where the next change's leading context would begin
+        let next_start = changes.get(index + 1).map_or(original.len(), |next| next.original_start);
+        let context_end = (change.original_end + CONTEXT_LINES).min(next_start).min(original.len());
+        let shift = change.modified_end as isize - change.original_end as isize;
+        for (i, line) in original.iter().enumerate().take(context_end).skip(change.original_end) {
+            output.push_str(&format!("{}  {}\n", prefix(1, (i as isize + shift) as usize), line));
+        }
+        shown_until = context_end;
+    }
+    output
+}
+
+/// The rendered rows of one change, without context
+pub fn inline_hunk_rows(original: &[String], modified: &[String], change: &LineChange) -> Vec<String> {
+    let (old_lines, new_lines) = (
+        &original[change.original_start..change.original_end],
+        &modified[change.modified_start..change.modified_end],
+    );
+    let mut output = String::new();
+    if change.change_type == ChangeType::Modified {
+        // Rows follow similarity, so an edited line sits under its original despite insertions
+        for row in align_lines(old_lines, new_lines) {
+            match row {
+                (Some(i), Some(j)) => push_edited_line(&mut output, &old_lines[i], &new_lines[j]),
+                (Some(i), None) => output.push_str(&format!("- ~~{}~~\n", old_lines[i])),
+                (None, Some(j)) => output.push_str(&format!("+ {}\n", new_lines[j])),
+                (None, None) => {}
+            }
+        }
+    } else {
+        for line in old_lines {
+            output.push_str(&format!("- ~~{}~~\n", line));
+        }
+        for line in new_lines {
+            output.push_str(&format!("+ {}\n", line));
+        }
+    }
+    output.lines().map(String::from).collect()
+}
+
+/// An original line and its aligned replacement, with the changed characters marked
+fn push_edited_line(output: &mut String, old: &str, new: &str) {
+    let (old, new) = (old.to_string(), new.to_string());
+    let changes = compute_diff(std::slice::from_ref(&old), std::slice::from_ref(&new), DiffOptions::default());
+    let mut old_marks = vec![false; old.chars().count()];
+    let mut new_marks = vec![false; new.chars().count()];
+    for c in changes.iter().filter_map(|change| change.char_changes.as_ref()).flatten() {
+        old_marks[c.original_start..c.original_start + c.original_length].fill(true);
+        new_marks[c.modified_start..c.modified_start + c.modified_length].fill(true);
+    }
+    push_marked(output, '-', &old, &old_marks, "~~");
+    push_marked(output, '+', &new, &new_marks, "**");
+}
+
+/// Write `text` line by line after `prefix`, wrapping each run of marked characters in `marker`
+fn push_marked(output: &mut String, prefix: char, text: &str, marks: &[bool], marker: &str) {
+    let mut offset = 0;
+    for line in text.split('\n') {
+        output.push(prefix);
+        output.push(' ');
+        let mut open = false;
+        for c in line.chars() {
+            if marks[offset] != open {
+                output.push_str(marker);
+                open = marks[offset];
+            }
+            output.push(c);
+            offset += 1;
+        }
+        if open {
+            output.push_str(marker);
+        }
+        output.push('\n');
+        // The joining newline
+        offset += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_format_inline_diff() {
+        let original = lines("a\nb\nc\nlet x = 1;\nd\ne\nf\ng\nh\ni\nj\nk\nold");
+        let modified = lines("a\nb\nc\nlet x = 2;\nd\ne\nf\ng\nh\ni\nj\nk");
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let text = format_inline_diff("x", "y", &original, &modified, &changes, DEFAULT_MAX_HUNK_LINES, None);
+        assert!(text.starts_with("--- x\n+++ y\n\n@@ line 4 @@\n  a\n  b\n  c\n- let x = ~~1~~;\n+ let x = **2**;\n  d\n  e\n  f\n"));
+        assert!(text.ends_with("\n@@ line 13 @@\n  i\n  j\n  k\n- ~~old~~\n"));
+    }
+
+    #[test]
+    fn test_inline_aligns_edited_lines() {
+        let original = lines("start\nfoo(a, b);\nend");
+        let modified = lines("start\nlet c = 3;\nfoo(a, b, c);\nend");
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let text = format_inline_diff("x", "y", &original, &modified, &changes, DEFAULT_MAX_HUNK_LINES, None);
+        assert!(text.contains("  start\n+ let c = 3;\n- foo(a, b);\n+ foo(a, b**, c**);\n  end\n"));
+
+        let numbering = LineNumbering { base: 0, ..LineNumbering::default() };
+        let text = format_inline_diff("x", "y", &original, &modified, &changes, DEFAULT_MAX_HUNK_LINES, Some(&numbering));
+        assert!(text.ends_with(
+            "@@ line 1 @@\n0 |   start\n1 | + let c = 3;\n1 | - foo(a, b);\n2 | + foo(a, b**, c**);\n3 |   end\n"
+        ));
+    }
+
+    #[test]
+    fn test_truncated_hunk() {
+        let modified: Vec<String> = (1..=30).map(|i| i.to_string()).collect();
+        let changes = compute_diff(&[], &modified, DiffOptions::default());
+        let text = format_inline_diff("x", "y", &[], &modified, &changes, 12, None);
+        let marker = format!("+ 5\n… 20 lines omitted (expand: --expand={}:6-25)\n+ 26\n", changes[0].id);
+        assert!(text.contains(&marker));
+        assert!(text.ends_with("+ 30\n"));
+        assert_eq!(inline_hunk_rows(&[], &modified, &changes[0])[5..25].len(), 20);
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use crate::messages::{text, Message};
+
+/// One `key=value` pair from a `.env`, INI or properties file
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigEntry {
+    /// Enclosing `[section]`, empty before the first section header
+    pub section: String,
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyChange {
+    Added { section: String, key: String, value: String },
+    Removed { section: String, key: String, value: String },
+    ValueChanged { section: String, key: String, old_value: String, new_value: String },
+    /// The key disappeared from one section and appeared in another
+    Moved { key: String, from_section: String, to_section: String, old_value: String, new_value: String },
+}
+
+pub fn parse_config(lines: &[String]) -> Vec<ConfigEntry> {
+    let mut entries = Vec::new();
+    let mut section = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with(['#', ';', '!']) {
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            section = name.trim().to_string();
+            continue;
+        }
+
+        let trimmed = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+        let Some(separator) = trimmed.find(['=', ':']) else {
+            continue;
+        };
+        let value = trimmed[separator + 1..].trim();
+
+        entries.push(ConfigEntry {
+            section: section.clone(),
+            key: trimmed[..separator].trim().to_string(),
+            value: unquote(value).to_string(),
+            line: index,
+        });
+    }
+
+    entries
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
+
+pub fn compare_config(original_lines: &[String], modified_lines: &[String]) -> Vec<KeyChange> {
+    let index = |lines: &[String]| -> BTreeMap<(String, String), String> {
+        parse_config(lines)
+            .into_iter()
+            .map(|entry| ((entry.section, entry.key), entry.value))
+            .collect()
+    };
+    let original = index(original_lines);
+    let modified = index(modified_lines);
+
+    let mut changes = Vec::new();
+    let mut removed = Vec::new();
+    for ((section, key), old_value) in &original {
+        match modified.get(&(section.clone(), key.clone())) {
+            Some(new_value) if new_value != old_value => changes.push(KeyChange::ValueChanged {
+                section: section.clone(),
+                key: key.clone(),
+                old_value: old_value.clone(),
+                new_value: new_value.clone(),
+            }),
+            Some(_) => {}
+            None => removed.push((section.clone(), key.clone(), old_value.clone())),
+        }
+    }
+
+    for ((section, key), value) in &modified {
+        if original.contains_key(&(section.clone(), key.clone())) {
+            continue;
+        }
+        if let Some(position) = removed.iter().position(|(_, removed_key, _)| removed_key == key) {
+            let (from_section, _, old_value) = removed.remove(position);
+            changes.push(KeyChange::Moved {
+                key: key.clone(),
+                from_section,
+                to_section: section.clone(),
+                old_value,
+                new_value: value.clone(),
+            });
+        } else {
+            changes.push(KeyChange::Added {
+                section: section.clone(),
+                key: key.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+
+    changes.extend(removed.into_iter().map(|(section, key, value)| KeyChange::Removed {
+        section,
+        key,
+        value,
+    }));
+    changes
+}
+
+fn qualified(section: &str, key: &str) -> String {
+    if section.is_empty() {
+        key.to_string()
+    } else {
+        format!("[{}] {}", section, key)
+    }
+}
+
+pub fn format_config_diff(file1_path: &str, file2_path: &str, changes: &[KeyChange]) -> String {
+    let mut output = format!("--- {}\n+++ {}\n(compared as key=value pairs)\n", file1_path, file2_path);
+
+    if changes.is_empty() {
+        output.push_str(&format!("\n{}\n", text(Message::KeysIdentical, [])));
+        return output;
+    }
+
+    output.push('\n');
+    for change in changes {
+        let line = match change {
+            KeyChange::Added { section, key, value } => format!("+ {} = {}", qualified(section, key), value),
+            KeyChange::Removed { section, key, value } => format!("- {} = {}", qualified(section, key), value),
+            KeyChange::ValueChanged { section, key, old_value, new_value } => {
+                format!("~ {}: {} -> {}", qualified(section, key), old_value, new_value)
+            }
+            KeyChange::Moved { key, from_section, to_section, old_value, new_value } => {
+                let mut line = format!("> {} moved from [{}] to [{}]", key, from_section, to_section);
+                if old_value != new_value {
+                    line.push_str(&format!(", value {} -> {}", old_value, new_value));
+                }
+                line
+            }
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_parse_env_and_ini() {
+        let entries = parse_config(&lines("# comment\nexport TOKEN=\"abc\"\n[server]\nport: 80"));
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].key.as_str(), entries[0].value.as_str()), ("TOKEN", "abc"));
+        assert_eq!((entries[1].section.as_str(), entries[1].line), ("server", 3));
+    }
+
+    #[test]
+    fn test_compare_config() {
+        let original = lines("[a]\nhost=x\nport=1\nold=1\n[b]\nmoving=yes");
+        let modified = lines("[b]\nport=1\nnew=2\n[a]\nport=2\nhost=x\nmoving=yes");
+
+        let changes = compare_config(&original, &modified);
+        assert!(changes.contains(&KeyChange::ValueChanged {
+            section: "a".into(),
+            key: "port".into(),
+            old_value: "1".into(),
+            new_value: "2".into(),
+        }));
+        assert!(changes.contains(&KeyChange::Moved {
+            key: "moving".into(),
+            from_section: "b".into(),
+            to_section: "a".into(),
+            old_value: "yes".into(),
+            new_value: "yes".into(),
+        }));
+        assert!(changes.iter().any(|c| matches!(c, KeyChange::Added { key, .. } if key == "new")));
+        assert!(changes.iter().any(|c| matches!(c, KeyChange::Removed { key, .. } if key == "old")));
+        // `port` exists in both sections of the modified file, so it is an addition to [b], not a move
+        assert!(changes.iter().any(|c| matches!(c, KeyChange::Added { section, key, .. } if section == "b" && key == "port")));
+    }
+}
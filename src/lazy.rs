@@ -0,0 +1,190 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use crate::diff_core::{compute_diff, ChangeType, DiffOptions, LineChange};
+
+/// Random access to the lines of one side of a comparison
+pub trait LineSource {
+    fn line_count(&self) -> usize;
+    fn read_lines(&self, range: Range<usize>) -> io::Result<Vec<String>>;
+}
+
+impl LineSource for Vec<String> {
+    fn line_count(&self) -> usize {
+        self.len()
+    }
+
+    fn read_lines(&self, range: Range<usize>) -> io::Result<Vec<String>> {
+        Ok(self[range].to_vec())
+    }
+}
+
+/// A file indexed by line start offsets; line text is read back from disk on demand
+pub struct FileLines {
+    path: String,
+    /// Byte offset of each line start, plus the file length as a final entry
+    offsets: Vec<u64>,
+}
+
+impl FileLines {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut offsets = vec![0];
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            let read = reader.read_until(b'\n', &mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            offsets.push(offsets[offsets.len() - 1] + read as u64);
+        }
+        Ok(Self {
+            path: path.to_string(),
+            offsets,
+        })
+    }
+}
+
+impl LineSource for FileLines {
+    fn line_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    fn read_lines(&self, range: Range<usize>) -> io::Result<Vec<String>> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let start = self.offsets[range.start];
+        let mut bytes = vec![0; (self.offsets[range.end] - start) as usize];
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut bytes)?;
+
+        let text = String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(text.lines().map(String::from).collect())
+    }
+}
+
+/// Hunk descriptors plus the sources to materialize their text from
+pub struct LazyDiff<S> {
+    pub original: S,
+    pub modified: S,
+    pub changes: Vec<LineChange>,
+}
+
+/// A borrowed view of one change that renders its lines only when asked
+pub struct Hunk<'a, S> {
+    diff: &'a LazyDiff<S>,
+    /// Position of `change` in the diff, for finding its neighbours
+    index: usize,
+    pub change: &'a LineChange,
+}
+
+impl<S: LineSource> LazyDiff<S> {
+    pub fn hunks(&self) -> impl Iterator<Item = Hunk<'_, S>> {
+        self.changes.iter().enumerate().map(move |(index, change)| Hunk { diff: self, index, change })
+    }
+}
+
+impl<S: LineSource> Hunk<'_, S> {
+    /// Render the hunk in unified format with up to `context_lines` of surrounding context; the
+    /// context stops at the neighbouring changes, so it only holds lines both files share
+    pub fn render(&self, context_lines: usize) -> io::Result<String> {
+        let change = self.change;
+        let original = &self.diff.original;
+        let changes = &self.diff.changes;
+        let previous_end = self.index.checked_sub(1).map_or(0, |i| changes[i].original_end);
+        let next_start = changes.get(self.index + 1).map_or(original.line_count(), |next| next.original_start);
+        let before = change.original_start.saturating_sub(context_lines).max(previous_end)..change.original_start;
+        let after = change.original_end..(change.original_end + context_lines).min(next_start);
+        let modified_start = change.modified_start - before.len();
+
+        let mut output = format!(
+            "@@ -{},{} +{},{} @@\n",
+            before.start + 1,
+            after.end - before.start,
+            modified_start + 1,
+            before.len() + (change.modified_end - change.modified_start) + after.len()
+        );
+        let mut push = |prefix: char, lines: Vec<String>| {
+            for line in lines {
+                output.push(prefix);
+                output.push_str(&line);
+                output.push('\n');
+            }
+        };
+        push(' ', original.read_lines(before)?);
+        if change.change_type != ChangeType::Added {
+            push('-', original.read_lines(change.original_start..change.original_end)?);
+        }
+        if change.change_type != ChangeType::Deleted {
+            push('+', self.diff.modified.read_lines(change.modified_start..change.modified_end)?);
+        }
+        push(' ', original.read_lines(after)?);
+        Ok(output)
+    }
+}
+
+/// Diff two files, keeping only hunk descriptors and line offsets once the diff is computed
+pub fn diff_files_lazy(
+    file1_path: &str,
+    file2_path: &str,
+    options: DiffOptions,
+) -> Result<LazyDiff<FileLines>, Box<dyn std::error::Error>> {
+    let changes = crate::file_handler::compare_files(file1_path, file2_path, options)?;
+    Ok(LazyDiff {
+        original: FileLines::open(file1_path)?,
+        modified: FileLines::open(file2_path)?,
+        changes,
+    })
+}
+
+/// Wrap in-memory lines, e.g. revisions read from git, in the same hunk API
+pub fn diff_lines_lazy(original: Vec<String>, modified: Vec<String>, options: DiffOptions) -> LazyDiff<Vec<String>> {
+    let changes = compute_diff(&original, &modified, options);
+    LazyDiff {
+        original,
+        modified,
+        changes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::test_helpers::{lines, TempDir};
+
+    #[test]
+    fn test_render_with_context() {
+        let diff = diff_lines_lazy(lines("a\nb\nc\nd\ne"), lines("a\nb\nC\nd\ne"), DiffOptions::default());
+        let hunk = diff.hunks().next().unwrap();
+
+        assert_eq!(hunk.render(1).unwrap(), "@@ -2,3 +2,3 @@\n b\n-c\n+C\n d\n");
+        assert_eq!(hunk.render(0).unwrap(), "@@ -3,1 +3,1 @@\n-c\n+C\n");
+    }
+
+    #[test]
+    fn test_context_stops_at_earlier_deletion() {
+        let diff = diff_lines_lazy(lines("d1\nd2\nd3\na\nc"), lines("a\nC"), DiffOptions::default());
+        let hunks: Vec<_> = diff.hunks().collect();
+        assert_eq!(hunks[0].render(5).unwrap(), "@@ -1,4 +1,1 @@\n-d1\n-d2\n-d3\n a\n");
+        assert_eq!(hunks[1].render(5).unwrap(), "@@ -4,2 +1,2 @@\n a\n-c\n+C\n");
+    }
+
+    #[test]
+    fn test_file_lines_read_on_demand() {
+        let dir = TempDir::new("lazy");
+        let (file1, file2) = (dir.join("1.txt"), dir.join("2.txt"));
+        fs::write(&file1, "one\r\ntwo\nthree").unwrap();
+        fs::write(&file2, "one\r\nTWO\nthree").unwrap();
+
+        let diff = diff_files_lazy(file1.to_str().unwrap(), file2.to_str().unwrap(), DiffOptions::default()).unwrap();
+        assert_eq!(diff.original.line_count(), 3);
+        assert_eq!(diff.original.read_lines(0..3).unwrap(), lines("one\ntwo\nthree"));
+
+        let rendered = diff.hunks().next().unwrap().render(5).unwrap();
+        assert_eq!(rendered, "@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n");
+    }
+}
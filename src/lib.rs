@@ -1,27 +1,183 @@
 use zed_extension_api as zed;
 
-mod diff_core;
-mod file_handler;
-mod ui;
+pub mod align;
+pub mod anchors;
+pub mod backup;
+pub mod band;
+pub mod bundle;
+pub mod changelog;
+pub mod changeset;
+pub mod checkpoint;
+pub mod churn;
+pub mod classify;
+pub mod clock;
+pub mod codeowners;
+pub mod commitmsg;
+pub mod comparator;
+pub mod crossmove;
+pub mod delimited;
+pub mod diff_core;
+pub mod duplicates;
+pub mod difftool;
+pub mod dirdiff;
+pub mod dirjob;
+pub mod doctor;
+pub mod embedded;
+pub mod eol;
+pub mod error;
+pub mod export;
+pub mod external;
+pub mod file_handler;
+pub mod filepicker;
+pub mod generated;
+pub mod golden;
+pub mod i18n;
+pub mod imports;
+pub mod inline;
+pub mod keyvalue;
+pub mod lazy;
+pub mod lockfile;
+pub mod logmask;
+pub mod manifest;
+pub mod git;
+pub mod heatmap;
+pub mod highlight;
+pub mod history;
+pub mod hunk_id;
+pub mod merge;
+pub mod messages;
+pub mod minified;
+pub mod modes;
+pub mod numbering;
+pub mod ordering;
+pub mod openapi;
+pub mod outline;
+pub mod paging;
+pub mod patch;
+pub mod patchmail;
+pub mod patchview;
+pub mod patience;
+pub mod pattern;
+pub mod planner;
+pub mod pretty;
+pub mod profiles;
+pub mod prompt;
+pub mod proto;
+pub mod rebase;
+pub mod redact;
+pub mod risk;
+pub mod rust_api;
+pub mod sarif;
+pub mod selection;
+pub mod sessions;
+pub mod settings;
+pub mod sqldump;
+pub mod state;
+pub mod structure;
+pub mod syncconflict;
+#[cfg(test)]
+mod test_helpers;
+pub mod testimpact;
+pub mod testing;
+pub mod token_diff;
+pub mod todos;
+pub mod tools;
+pub mod trace;
+pub mod ui;
+pub mod unicode_check;
+pub mod unordered;
+pub mod walk;
+pub mod wdiff;
+pub mod wrap;
 
-use diff_core::{DiffOptions, LineChange};
-use file_handler::compare_files;
-use ui::format_unified_diff;
+use backup::find_backups;
+use bundle::ReviewBundle;
+use changelog::{format_whats_new, whats_new};
+use checkpoint::{diff_with_checkpoints, DEFAULT_CHECKPOINT_LINES};
+use churn::{format_churn_report, ChurnStore};
+use classify::{filter_by_tags, ChangeTag};
+use codeowners::{format_owner_report, group_by_owners, load_codeowners};
+use commitmsg::suggest_commit_message;
+use ordering::{arrange, EntryFacts, ReportOrder};
+use crossmove::{detect_cross_file_moves, format_cross_file_moves};
+use duplicates::{collapse_duplicates, duplicate_label, format_duplicate_change, DEFAULT_MIN_DUPLICATE_FILES};
+use dirdiff::{
+    change_counts, compare_directories, compare_relative_batch, format_grouped_report, format_relative_report, DirectoryFilter,
+    DirectoryOptions, FileStatus, NestedRepoMode, RelativeComparison,
+};
+use dirjob::DirectoryJob;
+use difftool::{run_tool, ToolInvocation, ToolOutcome};
+use diff_core::{try_compute_diff, DiffOptions, LineChange};
+use doctor::{run_checks, HealthReport};
+use error::DiffError;
+use export::{export_html, export_markdown};
+use external::{external_diff, ExternalTool};
+use file_handler::{compare_files, normalize_path, read_file_lines};
+use filepicker::pick_files;
+use generated::is_generated_path;
+use heatmap::{change_heatmap, Heatmap};
+use highlight::{highlight_hunks, HighlightedHunk};
+use history::LocalHistory;
+use hunk_id::{embed_hunk_ids, find_hunk, parse_hunk_id_comment};
+use inline::{format_inline_diff, inline_hunk_rows, DEFAULT_MAX_HUNK_LINES};
+use modes::{compare_files_with_mode, CompareMode};
+use minified::{is_minified, split_minified};
+use merge::{merge_three_way, merge_two_way, ConflictStyle, MergeOptions};
+use messages::{activate, text, Catalog, Message};
+use outline::{outline_comparison, outline_patch, OutlineItem};
+use patchmail::{format_patch_email, unified_hunks, PatchEmailOptions, PatchFile};
+use eol::{with_line_endings, OutputLineEndings};
+use patch::{format_unified, parse_unified};
+use patchview::PatchView;
+use paging::{hunk_range, oversized_summary, paginate, OutputLimit, Page, DEFAULT_HUNKS_PER_PAGE};
+use git::{blame_hunks, compare_across_branches, file_history, file_lines_at_revision, predict_branch_conflicts, worktree_changes, WorktreeChange};
+use risk::{format_risk_report, score_files, FileRisk};
+use sarif::{collect_findings, to_sarif};
+use testimpact::{affected_tests, format_affected_tests};
+use todos::{debt_delta, format_debt_delta};
+use rebase::{format_relocations, rebase_patch, RebaseOptions};
+use pretty::{pretty_print, PrettyFormat};
+use prompt::{package_comparison, PromptOptions};
+use profiles::{default_profile, profile_by_name, Profile, PROFILE_NAMES};
+use selection::{compare_selections, Selection, SelectionTarget};
+use sessions::{SessionId, SessionManager, SessionOwner};
+use settings::ExtensionSettings;
+pub use state::{ComparisonState, OptionOverrides};
+use structure::{format_function_summary, summarize_functions};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tools::{call_tool, tool_definitions};
+use wdiff::format_wdiff;
+use wrap::{split_rows, SplitRow, DEFAULT_TAB_WIDTH};
+use trace::{format_trace, last_trace, Category, Level};
+use syncconflict::{find_conflict_copies, pair_conflict_copy, resolve_conflict_copy};
+use unicode_check::{format_unicode_warnings, scan_changes};
+use ui::{
+    format_branch_comparison, format_conflict_prediction, format_diff, format_file_history, format_hunk_authors, format_worktree_file,
+    format_numbered_diff, format_worktree_stat, OutputFormat, DEFAULT_CONTEXT_LINES,
+};
 
-struct DiffExtension {
-    comparison_state: Option<ComparisonState>,
-}
+/// Candidate files offered when completing a path argument
+const PATH_COMPLETIONS: usize = 20;
 
-struct ComparisonState {
-    file1_path: String,
-    file2_path: String,
-    diff_result: Vec<LineChange>,
+pub struct DiffExtension {
+    sessions: SessionManager,
+    patch_view: Option<PatchView>,
+    /// Root of the worktree the last slash command ran in; argument completion isn't given one
+    worktree_root: Mutex<Option<String>>,
+    /// Settings of that worktree, for the exports, which aren't given one either
+    settings: Mutex<ExtensionSettings>,
 }
 
 impl zed::Extension for DiffExtension {
     fn new() -> Self {
         Self {
-            comparison_state: None,
+            sessions: SessionManager::default(),
+            patch_view: None,
+            worktree_root: Mutex::new(None),
+            settings: Mutex::new(ExtensionSettings::default()),
         }
     }
 
@@ -36,29 +192,1517 @@ impl zed::Extension for DiffExtension {
             env: Default::default(),
         })
     }
+
+    fn complete_slash_command_argument(
+        &self,
+        command: zed::SlashCommand,
+        args: Vec<String>,
+    ) -> Result<Vec<zed::SlashCommandArgumentCompletion>, String> {
+        match command.name.as_str() {
+            "diff" | "diff-head" | "diff-backup" | "diff-sync-conflict" if !is_flag(args.last()) => {
+                Ok(self.complete_path(&args))
+            }
+            "diff" => {
+                let last = args.last().map(String::as_str).unwrap_or_default();
+                if !last.starts_with("--profile=") {
+                    return Ok(Vec::new());
+                }
+
+                Ok(PROFILE_NAMES
+                    .iter()
+                    .map(|name| zed::SlashCommandArgumentCompletion {
+                        label: name.to_string(),
+                        new_text: format!("--profile={}", name),
+                        run_command: false,
+                    })
+                    .collect())
+            }
+            "diff-backup" | "diff-sync-conflict" | "diff-worktree" | "diff-head" | "diff-dirs" | "diff-diagnostics" | "diff-doctor" => Ok(Vec::new()),
+            command => Err(text(Message::UnknownCommand, [&command])),
+        }
+    }
+
+    fn run_slash_command(
+        &self,
+        command: zed::SlashCommand,
+        args: Vec<String>,
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        if let Some(worktree) = worktree {
+            *self.worktree_root.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(worktree.root_path());
+        }
+        let settings = worktree.map(ExtensionSettings::load).unwrap_or_default();
+        *self.settings.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = settings.clone();
+        // A broken translation file falls back to English here; commands report it as they validate
+        let _messages = activate(settings.catalog().unwrap_or_else(|_| Catalog::english()));
+        match command.name.as_str() {
+            "diff" => run_diff_command(args, worktree),
+            "diff-backup" => run_backup_command(args, worktree),
+            "diff-sync-conflict" => run_sync_conflict_command(args, worktree),
+            "diff-worktree" => run_worktree_command(args, worktree),
+            "diff-head" => run_head_command(args, worktree),
+            "diff-dirs" => run_directories_command(args, worktree),
+            "diff-diagnostics" => Ok(paged_output(vec![Page {
+                label: text(Message::DiagnosticsLabel, []),
+                text: self.diagnostics(),
+            }])),
+            "diff-doctor" => Ok(paged_output(vec![Page {
+                label: text(Message::HealthLabel, []),
+                text: run_checks(&settings, &work_dir()).format(),
+            }])),
+            command => Err(text(Message::UnknownCommand, [&command])),
+        }
+    }
+}
+
+/// Where the extension may keep files of its own: Zed runs it inside its work directory
+fn work_dir() -> PathBuf {
+    std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+}
+
+/// The options `/diff` takes before its files, as its usage message lists them
+const DIFF_FLAGS: &[&str] = &[
+    "--profile=<name>",
+    "--mode=<name>",
+    "--hide=<tag,...>",
+    "--redact",
+    "--inline",
+    "--numbered",
+    "--pretty",
+    "--hunk-ids",
+    "--expand=<hunk>:<first>-<last>",
+    "--hunks=<first>-<last>",
+];
+
+fn diff_usage() -> String {
+    let flags: Vec<String> = DIFF_FLAGS.iter().map(|flag| format!("[{}]", flag)).collect();
+    text(Message::Usage, [&format!("/diff {} <file1> <file2>", flags.join(" "))])
+}
+
+/// `/diff [<flag>...] <file1> <file2>` with the flags in `DIFF_FLAGS`; relative paths resolve
+/// against the worktree root
+fn run_diff_command(
+    args: Vec<String>,
+    worktree: Option<&zed::Worktree>,
+) -> Result<zed::SlashCommandOutput, String> {
+    let mut settings = worktree.map(ExtensionSettings::load).unwrap_or_default();
+    settings.validate()?;
+    let mut profile = settings.profile();
+    let mut paths = Vec::new();
+    let mut hidden_tags = Vec::new();
+    let mut mode = None;
+    let mut redact = settings.redact;
+    let mut expand = None;
+    let mut hunks = None;
+
+    for arg in args {
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            profile = profile_by_name(name)
+                .map(|profile| settings.apply_overrides(profile))
+                .ok_or_else(|| text(Message::UnknownProfile, [&name]))?;
+        } else if arg == "--redact" {
+            redact = true;
+        } else if arg == "--hunk-ids" {
+            settings.hunk_ids_in_output = true;
+        } else if arg == "--pretty" {
+            settings.pretty_print = true;
+        } else if arg == "--inline" {
+            profile.output_format = OutputFormat::Inline;
+        } else if arg == "--numbered" {
+            profile.output_format = OutputFormat::Numbered;
+        } else if let Some(spec) = arg.strip_prefix("--expand=") {
+            expand = Some(spec.to_string());
+        } else if let Some(spec) = arg.strip_prefix("--hunks=") {
+            hunks = Some(spec.to_string());
+        } else if let Some(name) = arg.strip_prefix("--mode=") {
+            mode = Some(CompareMode::from_name(name).ok_or_else(|| text(Message::UnknownMode, [&name]))?);
+        } else if let Some(labels) = arg.strip_prefix("--hide=") {
+            for label in labels.split(',') {
+                hidden_tags.push(
+                    ChangeTag::from_label(label).ok_or_else(|| text(Message::UnknownChangeTag, [&label]))?,
+                );
+            }
+        } else {
+            paths.push(match worktree {
+                Some(worktree) if Path::new(&arg).is_relative() => {
+                    Path::new(&worktree.root_path()).join(&arg).to_string_lossy().into_owned()
+                }
+                _ => arg,
+            });
+        }
+    }
+
+    if let ([file], Some(worktree)) = (paths.as_slice(), worktree) {
+        return Ok(comparison_targets(worktree, file));
+    }
+    let [file1, file2] = paths.as_slice() else {
+        return Err(diff_usage());
+    };
+
+    let _trace = trace::start(&format!("{} ↔ {}", file1, file2), settings.log_level()?);
+    let mode = mode.unwrap_or_else(|| CompareMode::for_path(file1));
+    trace::log(Level::Debug, Category::Format, || format!("{:?} mode, {:?} output", mode, profile.output_format));
+    let mut pages = if let Some(spec) = expand {
+        vec![Page {
+            label: text(Message::HunkLabel, [&spec, &file1, &file2]),
+            text: expand_hunk_rows(file1, file2, &profile.options, &spec, settings.pretty_print)?,
+        }]
+    } else if mode == CompareMode::Lines {
+        compare_and_format(file1, file2, &profile, &settings, &hidden_tags, hunks.as_deref())?
+    } else {
+        let diff = compare_files_with_mode(mode, file1, file2, &profile.options)
+            .map_err(|e| text(Message::CompareFailed, [&e]))?;
+        vec![Page {
+            label: text(Message::DiffLabel, [&file1, &file2]),
+            text: diff,
+        }]
+    };
+    if redact {
+        let redactor = settings.redactor()?;
+        for page in &mut pages {
+            page.text = redactor.redact(&page.text);
+        }
+    }
+
+    Ok(paged_output(pages))
+}
+
+/// `/diff <file>` with the second file left out: files to compare it with, recently modified first
+fn comparison_targets(worktree: &zed::Worktree, file: &str) -> zed::SlashCommandOutput {
+    let root = worktree.root_path();
+    let relative = Path::new(file).strip_prefix(&root).map(|path| path.to_string_lossy().into_owned()).ok();
+    let mut listing = format!("{}\n", text(Message::CompareWith, [&relative.as_deref().unwrap_or(file)]));
+    for candidate in pick_files(Path::new(&root), "", relative.as_deref(), PATH_COMPLETIONS) {
+        listing.push_str(&format!("  {}\n", candidate.path));
+    }
+    paged_output(vec![Page {
+        label: text(Message::ComparisonTargetsLabel, []),
+        text: listing,
+    }])
+}
+
+fn is_flag(arg: Option<&String>) -> bool {
+    arg.is_some_and(|arg| arg.starts_with("--"))
+}
+
+/// `/diff-backup <file> [n]`: diff the n-th newest backup of a file (default 1) against it,
+/// listing the other backups found
+fn run_backup_command(
+    args: Vec<String>,
+    worktree: Option<&zed::Worktree>,
+) -> Result<zed::SlashCommandOutput, String> {
+    let usage = || text(Message::Usage, [&"/diff-backup <file> [n]"]);
+    let settings = worktree.map(ExtensionSettings::load).unwrap_or_default();
+    settings.validate()?;
+    let file = args.first().ok_or_else(usage)?;
+    let file = match worktree {
+        Some(worktree) if Path::new(file).is_relative() => {
+            Path::new(&worktree.root_path()).join(file).to_string_lossy().into_owned()
+        }
+        _ => file.clone(),
+    };
+    let choice = match args.get(1) {
+        Some(n) => n.parse::<usize>().ok().filter(|n| *n > 0).ok_or_else(usage)?,
+        None => 1,
+    };
+
+    let home = std::env::var("HOME").ok();
+    let backups = find_backups(Path::new(&file), home.as_deref().map(Path::new));
+    let backup = backups
+        .get(choice - 1)
+        .ok_or_else(|| text(Message::NoBackup, [&choice, &file, &backups.len()]))?;
+
+    let mut listing = format!("{}\n", text(Message::BackupsOf, [&file]));
+    for (i, candidate) in backups.iter().enumerate() {
+        let marker = if i + 1 == choice { format!(" {}", text(Message::Shown, [])) } else { String::new() };
+        listing.push_str(&format!("{:>3}. {} [{}]{}\n", i + 1, candidate.path.display(), candidate.kind, marker));
+    }
+    listing.push('\n');
+
+    let backup_path = backup.path.to_string_lossy().into_owned();
+    let mut pages = compare_and_format(&backup_path, &file, &settings.profile(), &settings, &[], None)?;
+    pages[0].text.insert_str(0, &listing);
+    Ok(paged_output(pages))
+}
+
+/// `/diff-sync-conflict <file> [n]`: compare a file with a sync client's conflicted copy of it
+/// (the n-th newest, default 1); the file may also be the conflicted copy itself
+fn run_sync_conflict_command(
+    args: Vec<String>,
+    worktree: Option<&zed::Worktree>,
+) -> Result<zed::SlashCommandOutput, String> {
+    let usage = || text(Message::Usage, [&"/diff-sync-conflict <file> [n]"]);
+    let settings = worktree.map(ExtensionSettings::load).unwrap_or_default();
+    settings.validate()?;
+    let file = args.first().ok_or_else(usage)?;
+    let file = match worktree {
+        Some(worktree) if Path::new(file).is_relative() => Path::new(&worktree.root_path()).join(file),
+        _ => Path::new(file).to_path_buf(),
+    };
+    let choice = match args.get(1) {
+        Some(n) => n.parse::<usize>().ok().filter(|n| *n > 0).ok_or_else(usage)?,
+        None => 1,
+    };
+
+    let (original, copy) = pair_conflict_copy(&file, choice - 1)?;
+    let copies = find_conflict_copies(&original);
+    let mut listing = format!("{}\n", text(Message::ConflictCopiesOf, [&original.display()]));
+    for (i, candidate) in copies.iter().enumerate() {
+        let marker = if candidate.path == copy { format!(" {}", text(Message::Shown, [])) } else { String::new() };
+        listing.push_str(&format!("{:>3}. {} [{}]{}\n", i + 1, candidate.path.display(), candidate.tool, marker));
+    }
+    listing.push('\n');
+
+    let (original, copy) = (original.to_string_lossy().into_owned(), copy.to_string_lossy().into_owned());
+    let mut pages = compare_and_format(&original, &copy, &settings.profile(), &settings, &[], None)?;
+    pages[0].text.insert_str(0, &listing);
+    Ok(paged_output(pages))
+}
+
+/// `/diff-worktree [--blame] [--owners] [--risk] [--tests]`: every file in the worktree that
+/// differs from HEAD, as a diffstat page followed by one foldable page per file; `--blame` names
+/// who last changed each hunk's lines, `--owners` adds a page grouping the files by their
+/// CODEOWNERS, `--risk` scores the files and shows the riskiest first, and `--tests` lists the
+/// Rust tests the changes likely affect. `--sort=` and `--group=` order the files as in
+/// `/diff-dirs`. A hunk made identically in many files gets one page of its own instead of
+/// appearing in each file's.
+fn run_worktree_command(mut args: Vec<String>, worktree: Option<&zed::Worktree>) -> Result<zed::SlashCommandOutput, String> {
+    let worktree = worktree.ok_or_else(|| text(Message::NeedsWorktree, [&"/diff-worktree"]))?;
+    let order = ReportOrder::from_args(&mut args)?;
+    let (mut blame, mut owners, mut risk, mut tests) = (false, false, false, false);
+    for arg in &args {
+        match arg.as_str() {
+            "--blame" => blame = true,
+            "--owners" => owners = true,
+            "--risk" => risk = true,
+            "--tests" => tests = true,
+            _ => {
+                return Err(text(
+                    Message::Usage,
+                    [&"/diff-worktree [--blame] [--owners] [--risk] [--tests] [--sort=<key>] [--group=<key>]"],
+                ))
+            }
+        }
+    }
+    let settings = ExtensionSettings::load(worktree);
+    settings.validate()?;
+    let root = worktree.root_path();
+    let mut changes = worktree_changes(Path::new(&root), settings.profile().options)
+        .map_err(|e| text(Message::WorktreeStatusFailed, [&e]))?;
+
+    let mut pages = Vec::new();
+    if risk {
+        let risks = score_files(&changes);
+        pages.push(Page {
+            label: text(Message::RiskLabel, []),
+            text: format_risk_report(&risks),
+        });
+        changes = sort_by_risk(changes, &risks);
+    }
+    let groups = arrange(changes, &order, |change| worktree_facts(Path::new(&root), change));
+    pages.insert(0, Page {
+        label: text(Message::WorktreeLabel, []),
+        text: grouped_worktree_stat(&groups),
+    });
+    let changes: Vec<WorktreeChange> = groups.into_iter().flat_map(|(_, members)| members).collect();
+    if owners {
+        pages.push(Page {
+            label: text(Message::OwnersLabel, []),
+            text: owners_report(Path::new(&root), &changes)?,
+        });
+    }
+    if tests {
+        pages.push(Page {
+            label: text(Message::TestsLabel, []),
+            text: test_impact_report(Path::new(&root), &changes),
+        });
+    }
+    let min_files = settings.min_duplicate_files.unwrap_or(DEFAULT_MIN_DUPLICATE_FILES);
+    let (duplicates, changes) = collapse_duplicates(changes, min_files);
+    for duplicate in &duplicates {
+        pages.push(Page {
+            label: duplicate_label(duplicate),
+            text: format_duplicate_change(duplicate),
+        });
+    }
+    for change in &changes {
+        pages.push(Page {
+            label: format!("{} {}", change.status, change.path),
+            text: worktree_file_report(Path::new(&root), change, settings.context_lines(), blame)?,
+        });
+    }
+    Ok(paged_output(pages))
+}
+
+/// `/diff-head <file>`: the file as committed at HEAD against its working copy
+fn run_head_command(args: Vec<String>, worktree: Option<&zed::Worktree>) -> Result<zed::SlashCommandOutput, String> {
+    let worktree = worktree.ok_or_else(|| text(Message::NeedsWorktree, [&"/diff-head"]))?;
+    let [file] = args.as_slice() else {
+        return Err(text(Message::Usage, [&"/diff-head <file>"]));
+    };
+    let settings = ExtensionSettings::load(worktree);
+    settings.validate()?;
+    let root = PathBuf::from(worktree.root_path());
+    let path = normalize_path(&root.join(file));
+    let relative = path.strip_prefix(&root).map_err(|_| text(Message::OutsideWorktree, [&file]))?;
+    let relative = relative.to_string_lossy().into_owned();
+
+    let original = file_lines_at_revision(&root, "HEAD", &relative)
+        .map_err(|e| text(Message::ReadAtRevisionFailed, [&relative, &"HEAD", &e]))?;
+    let modified = read_file_lines(&path.to_string_lossy())
+        .map_err(|e| text(Message::ReadFailed, [&path.display(), &e]))?;
+    let profile = settings.profile();
+    let changes = try_compute_diff(&original, &modified, profile.options.clone())
+        .map_err(|e| text(Message::CompareFailed, [&e]))?;
+
+    let head = format!("HEAD:{}", relative);
+    let mut pages = compare_pages(&head, &relative, &original, &modified, &changes, &profile, &settings);
+    let limit = settings.output_limit();
+    if limit.exceeded_by(&pages) {
+        pages = vec![oversized_summary(&head, &relative, &changes, 1, &limit)];
+    }
+    Ok(paged_output(pages))
+}
+
+/// `/diff-dirs <dir1> <dir2> [--ext=…] [--include=…] [--min-size=…] [--max-size=…]
+/// [--newer-than=…] [--older-than=…] [--trivial=…]`: which files differ between two directory trees
+fn run_directories_command(
+    args: Vec<String>,
+    worktree: Option<&zed::Worktree>,
+) -> Result<zed::SlashCommandOutput, String> {
+    let (mut filters, dirs): (Vec<String>, Vec<String>) = args.into_iter().partition(|arg| arg.starts_with("--"));
+    let [dir1, dir2] = dirs.as_slice() else {
+        return Err(text(
+            Message::Usage,
+            [&"/diff-dirs <dir1> <dir2> [--ext=<list>] [--include=<glob>] [--trivial=<ratio>] [--sort=<key>] [--group=<key>]"],
+        ));
+    };
+    let order = ReportOrder::from_args(&mut filters)?;
+    let resolve = |dir: &String| match worktree {
+        Some(worktree) if Path::new(dir).is_relative() => {
+            Path::new(&worktree.root_path()).join(dir).to_string_lossy().into_owned()
+        }
+        _ => dir.clone(),
+    };
+    let (dir1, dir2) = (resolve(dir1), resolve(dir2));
+    let settings = worktree.map(ExtensionSettings::load).unwrap_or_default();
+    settings.validate()?;
+    let options = directory_options("skip", &filters)?;
+    let options = DirectoryOptions {
+        diff: settings.profile().options,
+        max_trivial_ratio: options.max_trivial_ratio.or(settings.max_trivial_ratio),
+        ..options
+    };
+
+    let results = compare_directories(Path::new(&dir1), Path::new(&dir2), &options);
+    let mut pages = vec![Page {
+        label: text(Message::DirectoriesLabel, [&dir1, &dir2]),
+        text: format_grouped_report(&dir1, &dir2, &arrange_results(Path::new(&dir1), Path::new(&dir2), &results, &order)),
+    }];
+    let moves = detect_cross_file_moves(Path::new(&dir1), Path::new(&dir2), &results);
+    if !moves.is_empty() {
+        pages.push(Page {
+            label: text(Message::CrossFileMovesLabel, []),
+            text: format_cross_file_moves(&moves),
+        });
+    }
+    Ok(paged_output(pages))
+}
+
+/// The diffstat of arranged worktree changes, with a heading per labelled group
+fn grouped_worktree_stat(groups: &[(Option<String>, Vec<WorktreeChange>)]) -> String {
+    match groups {
+        [(None, changes)] => format_worktree_stat(changes),
+        [] => format_worktree_stat(&[]),
+        _ => groups
+            .iter()
+            .map(|(label, changes)| {
+                let label = label.as_deref().unwrap_or_default();
+                format!("## {} ({} file(s))\n{}", label, changes.len(), format_worktree_stat(changes))
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+fn worktree_facts(root: &Path, change: &WorktreeChange) -> EntryFacts {
+    let (added, deleted) = change_counts(&change.changes);
+    EntryFacts {
+        path: change.path.clone(),
+        churn: added + deleted,
+        modified: fs::metadata(root.join(&change.path)).and_then(|m| m.modified()).ok(),
+    }
+}
+
+/// `changes` in the order of `risks`, as returned by `score_files` for them
+fn sort_by_risk(mut changes: Vec<WorktreeChange>, risks: &[FileRisk]) -> Vec<WorktreeChange> {
+    changes.sort_by_key(|change| risks.iter().position(|risk| risk.path == change.path));
+    changes
+}
+
+/// The tests likely affected by `changes`, found by the conventions of a Rust crate at `root`
+fn test_impact_report(root: &Path, changes: &[WorktreeChange]) -> String {
+    let paths: Vec<&str> = changes.iter().filter(|change| change.status != 'D').map(|change| change.path.as_str()).collect();
+    format_affected_tests(&affected_tests(root, &paths))
+}
+
+/// The changed files grouped by the owners named in the repository's CODEOWNERS file
+fn owners_report(root: &Path, changes: &[WorktreeChange]) -> Result<String, String> {
+    let rules = load_codeowners(root).ok_or_else(|| text(Message::NoCodeowners, [&root.display()]))?;
+    Ok(format_owner_report(&group_by_owners(&rules, changes)))
+}
+
+/// One file's diff against HEAD, with the authors of the replaced lines when `blame` is set
+fn worktree_file_report(repo: &Path, change: &WorktreeChange, context_lines: usize, blame: bool) -> Result<String, String> {
+    let mut report = format_worktree_file(change, context_lines);
+    let debt = format_debt_delta(&debt_delta(&change.original, &change.modified, &change.changes));
+    if !debt.is_empty() {
+        report.push('\n');
+        report.push_str(debt.trim_end_matches('\n'));
+        report.push('\n');
+    }
+    if blame {
+        let blamed = blame_hunks(repo, change).map_err(|e| text(Message::BlameFailed, [&change.path, &e]))?;
+        report.push_str(&format_hunk_authors(&blamed));
+    }
+    Ok(report)
+}
+
+/// Diff two files into output pages. `hunks` limits the output to a 1-based `<first>-<last>`
+/// range of the visible hunks; output over the settings' size limit is replaced by a summary.
+fn compare_and_format(
+    file1: &str,
+    file2: &str,
+    profile: &Profile,
+    settings: &ExtensionSettings,
+    hidden_tags: &[ChangeTag],
+    hunks: Option<&str>,
+) -> Result<Vec<Page>, String> {
+    let read = |path: &str| read_file_lines(path).map_err(|e| text(Message::ReadFailed, [&path, &e]));
+    let (original, modified) = trace::phase(Category::Io, "read", || Ok::<_, String>((read(file1)?, read(file2)?)))?;
+    let (original, modified, note) = diffed_lines(file1, original, modified, settings.pretty_print);
+    if let Some(note) = &note {
+        trace::log(Level::Info, Category::Format, || note.clone());
+    }
+    // The external tool works on the files as they are on disk
+    let tool = settings.external_tool.as_ref().filter(|_| note.is_none());
+    let external = |tool: &ExternalTool| {
+        trace::log(Level::Info, Category::Io, || format!("diffed by external tool {}", tool.command));
+        external_diff(tool, file1, file2, &original, &modified, &profile.options)
+    };
+    let mut changes = trace::phase(Category::Algorithm, "diff", || match tool {
+        Some(tool) if tool.applies_to(file1, file2) => external(tool),
+        _ => match (try_compute_diff(&original, &modified, profile.options.clone()), tool) {
+            (Ok(changes), _) => Ok(changes),
+            // Over the built-in engine's memory budget: hand the files to the external tool
+            (Err(DiffError::MemoryLimit { .. }), Some(tool)) => external(tool),
+            (Err(e), _) => Err(text(Message::CompareFailed, [&e])),
+        },
+    })?;
+    if is_generated_path(file1, &settings.generated_files)
+        || is_generated_path(file2, &settings.generated_files)
+    {
+        changes.iter_mut().for_each(|change| change.generated = true);
+    }
+    // Warn about every suspicious character and debt comment, including on hunks hidden by tag
+    let mut warnings = format_unicode_warnings(&scan_changes(&modified, &changes));
+    warnings.push_str(&format_debt_delta(&debt_delta(&original, &modified, &changes)));
+    if let Some(churn_file) = &settings.churn_file {
+        // Losing a churn record is no reason to withhold the diff
+        if let Err(e) = record_churn(Path::new(churn_file), file2, &changes) {
+            warnings.push_str(&format!("⚠ {}\n\n", text(Message::ChurnRecordFailed, [churn_file, &e])));
+        }
+    }
+    let mut changes = filter_by_tags(&changes, hidden_tags);
+    let range = match hunks {
+        Some(spec) => hunk_range(spec, changes.len())?,
+        None => 0..changes.len(),
+    };
+    let first_hunk = range.start + 1;
+    if range.len() < changes.len() {
+        trace::log(Level::Info, Category::Format, || format!("showing hunks {}-{} of {}", first_hunk, range.end, changes.len()));
+    }
+    changes = changes.drain(range).collect();
+    let mut pages = trace::phase(Category::Format, "format", || {
+        compare_pages(file1, file2, &original, &modified, &changes, profile, settings)
+    });
+    let limit = settings.output_limit();
+    if limit.exceeded_by(&pages) {
+        trace::log(Level::Warn, Category::Format, || {
+            format!("output over {} bytes or {} lines, replaced by a summary", limit.max_bytes, limit.max_lines)
+        });
+        pages = vec![oversized_summary(file1, file2, &changes, first_hunk, &limit)];
+    }
+    pages[0].text.insert_str(0, &warnings);
+    if let Some(note) = note {
+        pages[0].text.insert_str(0, &format!("{}\n\n", note));
+    }
+    Ok(pages)
+}
+
+fn record_churn(churn_file: &Path, path: &str, changes: &[LineChange]) -> Result<(), String> {
+    let mut store = if churn_file.exists() { ChurnStore::load(churn_file)? } else { ChurnStore::default() };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    store.record(path, now, changes);
+    store.save(churn_file).map_err(|e| e.to_string())
+}
+
+/// The lines actually diffed, and a note when they are not the files' own lines: with `pretty`
+/// both sides are re-indented if the format is known, and one enormous line would be a single
+/// hunk, so minified files are diffed over synthetic lines
+fn diffed_lines(
+    file1: &str,
+    original: Vec<String>,
+    modified: Vec<String>,
+    pretty: bool,
+) -> (Vec<String>, Vec<String>, Option<String>) {
+    if let Some(format) = PrettyFormat::detect(file1, &original).filter(|_| pretty) {
+        let note = text(Message::FormattedView, [&format.name()]);
+        return (pretty_print(&original, format), pretty_print(&modified, format), Some(note));
+    }
+    if is_minified(&original) || is_minified(&modified) {
+        let (original, modified) = (split_minified(&original), split_minified(&modified));
+        let note = text(Message::MinifiedInput, [&original.len(), &modified.len()]);
+        return (original, modified, Some(note));
+    }
+    (original, modified, None)
+}
+
+/// The output pages of an already computed comparison
+fn compare_pages(
+    file1: &str,
+    file2: &str,
+    original: &[String],
+    modified: &[String],
+    changes: &[LineChange],
+    profile: &Profile,
+    settings: &ExtensionSettings,
+) -> Vec<Page> {
+    if profile.output_format == OutputFormat::Inline {
+        return vec![Page {
+            label: text(Message::DiffLabel, [&file1, &file2]),
+            text: format_inline_diff(
+                file1,
+                file2,
+                original,
+                modified,
+                changes,
+                settings.max_hunk_lines.unwrap_or(DEFAULT_MAX_HUNK_LINES),
+                settings.line_numbers.as_ref(),
+            ),
+        }];
+    }
+    if profile.output_format == OutputFormat::Wdiff {
+        return vec![Page {
+            label: text(Message::WordsLabel, [&file1, &file2]),
+            text: format_wdiff(original, modified, &profile.options),
+        }];
+    }
+    if profile.output_format == OutputFormat::Numbered {
+        let numbering = settings.line_numbers.clone().unwrap_or_default();
+        return vec![Page {
+            label: text(Message::DiffLabel, [&file1, &file2]),
+            text: format_numbered_diff(file1, file2, original, modified, changes, settings.context_lines(), &numbering),
+        }];
+    }
+    let hunks_per_page = settings.hunks_per_page.unwrap_or(DEFAULT_HUNKS_PER_PAGE);
+    let mut pages = paginate(profile.output_format, file1, file2, changes, hunks_per_page);
+    if profile.output_format == OutputFormat::Unified {
+        if settings.hunk_ids_in_output {
+            let mut ids = changes.iter().map(|change| change.id.as_str());
+            for page in &mut pages {
+                page.text = embed_hunk_ids(&page.text, &mut ids);
+            }
+        }
+        let summary = format_function_summary(&summarize_functions(original, modified, changes));
+        pages[0].text.insert_str(0, &summary);
+    }
+    pages
+}
+
+/// A session's cached diff in its current view; the files are re-read for context but not re-diffed.
+/// Inline and numbered views number lines as `settings` say.
+fn render_session(state: &ComparisonState, original: &[String], modified: &[String], settings: &ExtensionSettings) -> String {
+    let (file1, file2) = (state.file1_path.as_str(), state.file2_path.as_str());
+    let rendered = match state.view.format {
+        OutputFormat::Unified if !state.diff_result.is_empty() => {
+            let hunks = unified_hunks(original, modified, &state.diff_result, state.view.context_lines);
+            format!("--- {}\n+++ {}\n{}", file1, file2, hunks)
+        }
+        OutputFormat::Inline => {
+            let max_hunk_lines = settings.max_hunk_lines.unwrap_or(DEFAULT_MAX_HUNK_LINES);
+            let numbering = settings.line_numbers.as_ref();
+            format_inline_diff(file1, file2, original, modified, &state.diff_result, max_hunk_lines, numbering)
+        }
+        OutputFormat::Wdiff => format_wdiff(original, modified, &state.options),
+        OutputFormat::Numbered => {
+            let numbering = settings.line_numbers.clone().unwrap_or_default();
+            format_numbered_diff(file1, file2, original, modified, &state.diff_result, state.view.context_lines, &numbering)
+        }
+        format => format_diff(format, file1, file2, &state.diff_result),
+    };
+    with_line_endings(&rendered, state.view.line_endings.resolve(file1, file2))
+}
+
+/// Rows `first..=last` (1-based) of the inline rendering of hunk `id`, as named by an
+/// `--expand=<id>:<first>-<last>` omission marker
+fn expand_hunk_rows(file1: &str, file2: &str, options: &DiffOptions, spec: &str, pretty: bool) -> Result<String, String> {
+    let invalid = || text(Message::InvalidHunkRows, [&spec]);
+    let (id, range) = spec.split_once(':').ok_or_else(invalid)?;
+    let (first, last) = range.split_once('-').ok_or_else(invalid)?;
+    let (first, last): (usize, usize) = (first.parse().map_err(|_| invalid())?, last.parse().map_err(|_| invalid())?);
+
+    let read = |path: &str| read_file_lines(path).map_err(|e| text(Message::ReadFailed, [&path, &e]));
+    // Hunk IDs refer to the lines `compare_and_format` diffed
+    let (original, modified, _) = diffed_lines(file1, read(file1)?, read(file2)?, pretty);
+    let changes = try_compute_diff(&original, &modified, options.clone())
+        .map_err(|e| text(Message::CompareFailed, [&e]))?;
+    let hunk = find_hunk(&changes, id).ok_or_else(|| text(Message::NoHunkWithId, [&id]))?;
+    let rows = inline_hunk_rows(&original, &modified, hunk);
+    if first == 0 || first > last || last > rows.len() {
+        return Err(text(Message::HunkRows, [&id, &rows.len()]));
+    }
+    Ok(rows[first - 1..last].iter().map(|row| format!("{}\n", row)).collect())
+}
+
+/// `filters` may also hold `--trivial=<ratio>`, setting `DirectoryOptions::max_trivial_ratio`
+fn directory_options(nested_repos: &str, filters: &[String]) -> Result<DirectoryOptions, String> {
+    let mut max_trivial_ratio = None;
+    let mut rest = Vec::new();
+    for arg in filters {
+        match arg.strip_prefix("--trivial=") {
+            Some(ratio) => {
+                max_trivial_ratio = Some(ratio.parse::<f64>().map_err(|_| text(Message::InvalidRatio, [&ratio]))?);
+            }
+            None => rest.push(arg.clone()),
+        }
+    }
+    Ok(DirectoryOptions {
+        nested_repos: NestedRepoMode::from_name(nested_repos)
+            .ok_or_else(|| text(Message::UnknownNestedRepoMode, [&nested_repos]))?,
+        filter: DirectoryFilter::from_args(&rest)?,
+        max_trivial_ratio,
+        ..DirectoryOptions::default()
+    })
+}
+
+fn finish_directory_job(mut job: DirectoryJob, job_file: &str) -> Result<String, String> {
+    job.run(Path::new(job_file)).map_err(|e| text(Message::CheckpointFailed, [&job_file, &e]))?;
+    Ok(directory_report(&job.root_a, &job.root_b, &job.results, &ReportOrder::default()))
+}
+
+/// The report of a directory comparison in `order`, followed by the blocks moved from one file
+/// to another
+fn directory_report(root_a: &str, root_b: &str, results: &[RelativeComparison], order: &ReportOrder) -> String {
+    let groups = arrange_results(Path::new(root_a), Path::new(root_b), results, order);
+    let mut report = format_grouped_report(root_a, root_b, &groups);
+    let moves = detect_cross_file_moves(Path::new(root_a), Path::new(root_b), results);
+    if !moves.is_empty() {
+        report.push('\n');
+        report.push_str(&format_cross_file_moves(&moves));
+    }
+    report
+}
+
+fn arrange_results<'a>(
+    root_a: &Path,
+    root_b: &Path,
+    results: &'a [RelativeComparison],
+    order: &ReportOrder,
+) -> Vec<(Option<String>, Vec<&'a RelativeComparison>)> {
+    arrange(results.iter().collect(), order, |result| {
+        let churn = match &result.status {
+            FileStatus::Modified(changes) => {
+                let (added, deleted) = change_counts(changes);
+                added + deleted
+            }
+            _ => 0,
+        };
+        let modified = |root: &Path| fs::metadata(root.join(&result.path)).and_then(|m| m.modified()).ok();
+        EntryFacts {
+            path: result.path.clone(),
+            churn,
+            modified: modified(root_b).or_else(|| modified(root_a)),
+        }
+    })
+}
+
+/// One output section per page, so the editor can fold pages of a large diff independently
+fn paged_output(pages: Vec<Page>) -> zed::SlashCommandOutput {
+    let mut text = String::new();
+    let mut sections = Vec::new();
+    for page in pages {
+        let start = text.len();
+        text.push_str(&page.text);
+        sections.push(zed::SlashCommandOutputSection {
+            range: (start..text.len()).into(),
+            label: page.label,
+        });
+    }
+    zed::SlashCommandOutput { text, sections }
 }
 
 impl DiffExtension {
-    pub fn compare_two_files(&mut self, file1: String, file2: String) -> Result<String, String> {
-        let options = DiffOptions {
-            ignore_whitespace: false,
-            ignore_case: false,
-            max_computation_time_ms: 5000,
-            compute_char_changes: true,
+    /// Files under `root` matching `query` fuzzily, best first and then most recently modified,
+    /// leaving out `exclude` (the file already chosen)
+    pub fn file_candidates(&self, root: String, query: String, exclude: Option<String>, limit: usize) -> Vec<String> {
+        pick_files(Path::new(&root), &query, exclude.as_deref(), limit)
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect()
+    }
+
+    /// Completions for the path being typed as the last of `args`
+    fn complete_path(&self, args: &[String]) -> Vec<zed::SlashCommandArgumentCompletion> {
+        let root = self.worktree_root.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        let Some(root) = root else {
+            return Vec::new();
         };
+        let query = args.last().cloned().unwrap_or_default();
+        let exclude = args.iter().rev().skip(1).find(|arg| !arg.starts_with("--")).cloned();
+        self.file_candidates(root, query, exclude, PATH_COMPLETIONS)
+            .into_iter()
+            .map(|path| zed::SlashCommandArgumentCompletion {
+                label: path.clone(),
+                new_text: path,
+                run_command: false,
+            })
+            .collect()
+    }
+
+    pub fn compare_two_files(&mut self, file1: String, file2: String) -> Result<String, String> {
+        self.compare_with_overrides(file1, file2, &default_profile(), OptionOverrides::default())
+            .map(|(_, output)| output)
+    }
 
-        match compare_files(&file1, &file2, options) {
+    pub fn compare_with_profile(
+        &mut self,
+        file1: String,
+        file2: String,
+        profile: &Profile,
+    ) -> Result<String, String> {
+        self.compare_with_overrides(file1, file2, profile, OptionOverrides::default())
+            .map(|(_, output)| output)
+    }
+
+    /// Compare with `profile`'s options adjusted for this session only, e.g. whitespace ignored.
+    /// The comparison replaces the active one; returns its session and the formatted diff.
+    pub fn compare_with_overrides(
+        &mut self,
+        file1: String,
+        file2: String,
+        profile: &Profile,
+        overrides: OptionOverrides,
+    ) -> Result<(SessionId, String), String> {
+        let options = overrides.apply(&profile.options);
+        let _trace = trace::start(&format!("{} ↔ {}", file1, file2), Level::Info);
+        match trace::phase(Category::Algorithm, "diff", || compare_files(&file1, &file2, options.clone())) {
             Ok(changes) => {
-                self.comparison_state = Some(ComparisonState {
-                    file1_path: file1.clone(),
-                    file2_path: file2.clone(),
-                    diff_result: changes.clone(),
-                });
+                let mut state = ComparisonState::new(file1.clone(), file2.clone(), changes.clone());
+                state.options = options;
+                state.overrides = overrides;
+                let id = self.sessions.replace_active(state);
+
+                let page = Page {
+                    label: String::new(),
+                    text: format_diff(profile.output_format, &file1, &file2, &changes),
+                };
+                let limit = OutputLimit::default();
+                if limit.exceeded_by(std::slice::from_ref(&page)) {
+                    return Ok((id, oversized_summary(&file1, &file2, &changes, 1, &limit).text));
+                }
+                Ok((id, page.text))
+            }
+            Err(e) => Err(text(Message::CompareFailed, [&e])),
+        }
+    }
+
+    /// Compare two files a window at a time, passing each finished stretch of the diff to
+    /// `on_partial` as unified hunks along with the share compared so far, so a huge diff can
+    /// be shown from the top while the rest is computed; returns the whole diff at the end
+    pub fn compare_files_progressively(
+        &self,
+        file1: String,
+        file2: String,
+        options: DiffOptions,
+        on_partial: &mut dyn FnMut(&str, f32),
+    ) -> Result<String, String> {
+        let read = |path: &str| read_file_lines(path).map_err(|e| text(Message::ReadFailed, [&path, &e]));
+        let (original, modified) = (read(&file1)?, read(&file2)?);
+        let changes = diff_with_checkpoints(&original, &modified, options, DEFAULT_CHECKPOINT_LINES, &mut |checkpoint| {
+            on_partial(&unified_hunks(&original, &modified, checkpoint.changes, DEFAULT_CONTEXT_LINES), checkpoint.progress());
+        });
+        Ok(format!("--- {}\n+++ {}\n{}", file1, file2, unified_hunks(&original, &modified, &changes, DEFAULT_CONTEXT_LINES)))
+    }
+
+    /// Compare a buffer selection with another selection, a file or supplied text such as the
+    /// clipboard; line numbers in the output refer to the buffers the selections came from
+    pub fn compare_selection(&self, selection: Selection, target: SelectionTarget) -> Result<String, String> {
+        let target = match target {
+            SelectionTarget::Selection(other) => other,
+            SelectionTarget::File(path) => {
+                let text = std::fs::read_to_string(&path).map_err(|e| text(Message::ReadFailed, [&path, &e]))?;
+                Selection::whole(&path, &text)
+            }
+            SelectionTarget::Text(text) => Selection::whole("<text>", &text),
+        };
+        let changes = compare_selections(&selection, &target, default_profile().options);
+        Ok(format_diff(OutputFormat::Unified, &selection.describe(), &target.describe(), &changes))
+    }
+
+    /// Hunks `first..=last` (1-based) of the active comparison, a range at a time when the whole
+    /// diff was too large to show
+    pub fn comparison_hunks(&self, first: usize, last: usize) -> Result<String, String> {
+        let state = self.session_state(self.active_session()?)?;
+        let range = hunk_range(&format!("{}-{}", first, last), state.diff_result.len())?;
+        Ok(format_diff(state.view.format, &state.file1_path, &state.file2_path, &state.diff_result[range]))
+    }
+
+    /// Check git, the default settings, the working directory and the diff engine, for when
+    /// the extension seems to do nothing; `/diff-doctor` checks a worktree's settings
+    pub fn doctor(&self) -> HealthReport {
+        run_checks(&ExtensionSettings::default(), &work_dir())
+    }
+
+    /// Phase timings, the algorithm chosen and any truncation in the last comparison, for
+    /// attaching to performance reports
+    pub fn diagnostics(&self) -> String {
+        match last_trace() {
+            Some(trace) => format_trace(&trace),
+            None => format!("{}\n", text(Message::NoTrace, [])),
+        }
+    }
+
+    /// The active comparison, i.e. the user's latest
+    pub fn comparison_state(&self) -> Option<ComparisonState> {
+        self.sessions.get(self.sessions.active()?)
+    }
+
+    /// Compare two files in a new session owned by `owner`, leaving the active comparison alone
+    /// unless the owner is the user
+    pub fn open_session(
+        &self,
+        file1: String,
+        file2: String,
+        owner: SessionOwner,
+        overrides: OptionOverrides,
+    ) -> Result<SessionId, String> {
+        let options = overrides.apply(&default_profile().options);
+        let changes =
+            compare_files(&file1, &file2, options.clone()).map_err(|e| text(Message::CompareFailed, [&e]))?;
+        let mut state = ComparisonState::new(file1, file2, changes);
+        state.options = options;
+        state.overrides = overrides;
+        Ok(self.sessions.open(owner, state))
+    }
+
+    /// Flip one option (see `TOGGLE_NAMES`) for the active comparison and show the re-computed diff
+    pub fn toggle_option(&mut self, name: &str) -> Result<String, String> {
+        self.toggle_session_option(self.active_session()?, &SessionOwner::User, name)
+    }
+
+    pub fn toggle_session_option(&self, id: SessionId, owner: &SessionOwner, name: &str) -> Result<String, String> {
+        let (state, original, modified) = self.load_session_files(id, owner)?;
+        let mut overrides = state.overrides.clone();
+        overrides.toggle(name, &state.options)?;
+        let options = overrides.apply(&state.options);
+        // Diff outside the session lock, then apply only what the toggle changed
+        let changes = if state.options.changes_result(&options) {
+            let changes = try_compute_diff(&original, &modified, options.clone())
+                .map_err(|e| text(Message::CompareFailed, [&e]))?;
+            Some(changes)
+        } else {
+            None
+        };
+        self.sessions.update(id, owner, |current| {
+            if current.overrides != state.overrides {
+                return Err(text(Message::SessionChanged, [&id, &name]));
+            }
+            if let Some(changes) = changes {
+                current.replace_diff_result(changes);
+            }
+            current.options = options;
+            current.overrides = overrides;
+            Ok(())
+        })?;
+        let (state, original, modified) = self.load_session_files(id, owner)?;
+        Ok(render_session(&state, &original, &modified, &self.settings()))
+    }
+
+    /// Switch the active comparison's output format (`unified`, `stat`, `json`, `inline`),
+    /// context lines or line endings (`original`, `modified`, `lf`, `crlf`); the cached diff is
+    /// re-rendered, not recomputed
+    pub fn set_view(
+        &mut self,
+        format: Option<String>,
+        context_lines: Option<usize>,
+        line_endings: Option<String>,
+    ) -> Result<String, String> {
+        self.set_session_view(self.active_session()?, &SessionOwner::User, format, context_lines, line_endings)
+    }
+
+    pub fn set_session_view(
+        &self,
+        id: SessionId,
+        owner: &SessionOwner,
+        format: Option<String>,
+        context_lines: Option<usize>,
+        line_endings: Option<String>,
+    ) -> Result<String, String> {
+        let format = format
+            .map(|name| OutputFormat::from_name(&name).ok_or_else(|| text(Message::UnknownOutputFormat, [&name])))
+            .transpose()?;
+        let line_endings = line_endings
+            .map(|name| OutputLineEndings::from_name(&name).ok_or_else(|| text(Message::UnknownLineEndings, [&name])))
+            .transpose()?;
+        self.sessions.update(id, owner, |state| {
+            state.view.format = format.unwrap_or(state.view.format);
+            state.view.context_lines = context_lines.unwrap_or(state.view.context_lines);
+            state.view.line_endings = line_endings.unwrap_or(state.view.line_endings);
+            Ok(())
+        })?;
+        let (state, original, modified) = self.load_session_files(id, owner)?;
+        Ok(render_session(&state, &original, &modified, &self.settings()))
+    }
+
+    pub fn session_state(&self, id: SessionId) -> Result<ComparisonState, String> {
+        self.sessions.get(id).ok_or_else(|| text(Message::NoSession, [&id]))
+    }
+
+    pub fn close_session(&self, id: SessionId, owner: &SessionOwner) -> Result<(), String> {
+        self.sessions.close(id, owner).map(|_| ())
+    }
+
+    pub fn sessions(&self) -> Vec<(SessionId, SessionOwner)> {
+        self.sessions.list()
+    }
+
+    fn active_session(&self) -> Result<SessionId, String> {
+        self.sessions.active().ok_or_else(|| text(Message::NoActiveComparison, []))
+    }
+
+    /// Change the active comparison, as the user
+    fn update_current<T>(&self, update: impl FnOnce(&mut ComparisonState) -> Result<T, String>) -> Result<T, String> {
+        self.sessions.update(self.active_session()?, &SessionOwner::User, update)
+    }
+
+    pub fn add_annotation(&mut self, hunk_index: usize, text: String) -> Result<u64, String> {
+        self.update_current(|state| state.add_annotation(hunk_index, text))
+    }
+
+    pub fn edit_annotation(&mut self, id: u64, text: String) -> Result<(), String> {
+        self.update_current(|state| state.edit_annotation(id, text))
+    }
+
+    pub fn delete_annotation(&mut self, id: u64) -> Result<(), String> {
+        self.update_current(|state| state.delete_annotation(id))
+    }
+
+    /// `goto_hunk` for an `@@` line carrying a `#h=<id>` comment, as copied from the output
+    pub fn goto_hunk_line(&mut self, line: &str) -> Result<LineChange, String> {
+        let id = parse_hunk_id_comment(line).ok_or_else(|| text(Message::NoHunkId, [&line]))?;
+        self.goto_hunk(id)
+    }
+
+    /// Re-diff the active comparison and locate the hunk with the given ID
+    pub fn goto_hunk(&mut self, id: &str) -> Result<LineChange, String> {
+        let session = self.active_session()?;
+        let state = self.session_state(session)?;
+        // Diff outside the session lock so other sessions are not held up meanwhile
+        let changes = compare_files(&state.file1_path, &state.file2_path, state.options.clone())
+            .map_err(|e| text(Message::CompareFailed, [&e]))?;
+        let hunk = find_hunk(&changes, id)
+            .cloned()
+            .ok_or_else(|| text(Message::NoHunkWithId, [&id]))?;
+
+        self.sessions.update(session, &SessionOwner::User, |state| {
+            state.replace_diff_result(changes);
+            Ok(())
+        })?;
+        Ok(hunk)
+    }
+
+    /// Change density of the active comparison in `buckets` slices of the modified file, for
+    /// drawing a scrollbar or minimap heatmap
+    pub fn change_heatmap(&self, buckets: usize) -> Result<Heatmap, String> {
+        let (state, _, modified) = self.load_current_files()?;
+        Ok(change_heatmap(&state.diff_result, modified.len(), buckets))
+    }
+
+    /// The active comparison's hunk lines with syntax spans for the file's language, so a renderer
+    /// can highlight code on top of the add/remove colouring
+    pub fn highlighted_hunks(&self) -> Result<Vec<HighlightedHunk>, String> {
+        let (state, original, modified) = self.load_current_files()?;
+        Ok(highlight_hunks(&state.file2_path, &original, &modified, &state.diff_result))
+    }
+
+    /// The active comparison as split-view rows, each side wrapped at `width` columns, so a UI
+    /// that soft-wraps long lines can pad the shorter side and keep both sides level
+    pub fn split_view_rows(&self, width: usize, tab_width: Option<usize>) -> Result<Vec<SplitRow>, String> {
+        let (state, original, modified) = self.load_current_files()?;
+        let tab_width = tab_width.unwrap_or(DEFAULT_TAB_WIDTH);
+        Ok(split_rows(&original, &modified, &state.diff_result, width, tab_width))
+    }
+
+    /// The rows an inline view's `… N lines omitted (expand: --expand=<spec>)` marker left out
+    pub fn expand_hunk(&self, file1: String, file2: String, spec: String) -> Result<String, String> {
+        expand_hunk_rows(&file1, &file2, &default_profile().options, &spec, false)
+    }
+
+    /// Files → hunks → intra-line edits of the active comparison, for an outline panel
+    pub fn diff_outline(&self) -> Result<OutlineItem, String> {
+        let (state, original, modified) = self.load_current_files()?;
+        Ok(outline_comparison(&state.file2_path, &original, &modified, &state.diff_result))
+    }
+
+    /// Outline of a `.diff`/`.patch` buffer's text
+    pub fn patch_outline(&self, text: String) -> Vec<OutlineItem> {
+        outline_patch(&text)
+    }
+
+    /// Open a pasted unified diff as a comparison. Files are looked up under `root` (the current
+    /// directory by default); where the patch applies to the local copy the view shows the whole
+    /// file, otherwise only the lines the patch itself carries.
+    pub fn open_patch(&mut self, text: &str, root: Option<String>) -> Result<String, String> {
+        let root = root.map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+        let view = PatchView::open(text, &root, &DiffOptions::default())?;
+        let formatted = view.format();
+        self.patch_view = Some(view);
+        Ok(formatted)
+    }
+
+    pub fn patch_view(&self) -> Option<&PatchView> {
+        self.patch_view.as_ref()
+    }
+
+    /// The `hunk`-th change of the `file`-th file in the open patch
+    pub fn patch_hunk(&self, file: usize, hunk: usize) -> Result<LineChange, String> {
+        let view = self.patch_view.as_ref().ok_or_else(|| text(Message::NoPatchOpen, []))?;
+        view.hunk(file, hunk)
+            .cloned()
+            .ok_or_else(|| text(Message::NoPatchHunk, [&hunk, &file]))
+    }
+
+    /// Write the open patch to disk, returning the files written
+    pub fn apply_patch(&mut self) -> Result<Vec<String>, String> {
+        let view = self.patch_view.as_ref().ok_or_else(|| text(Message::NoPatchOpen, []))?;
+        let written = view.apply()?;
+        self.patch_view = None;
+        Ok(written)
+    }
+
+    /// Re-anchor a patch that no longer applies onto the files under `root`, searching `radius`
+    /// lines around each hunk. Returns the relocation report followed by the rebased patch.
+    pub fn rebase_patch(&self, patch: &str, root: Option<String>, radius: Option<usize>) -> Result<String, String> {
+        let files = parse_unified(patch);
+        if files.is_empty() {
+            return Err(text(Message::NoFileDiffs, []));
+        }
+        let mut options = RebaseOptions::default();
+        if let Some(radius) = radius {
+            options.radius = radius;
+        }
+        let root = root.map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+        let (rebased, relocations) = rebase_patch(&files, &root, &options)?;
+        Ok(format!("{}\n{}", format_relocations(&relocations), format_unified(&rebased)))
+    }
+
+    /// The active comparison packaged for an assistant prompt, within `token_budget` tokens
+    pub fn summarization_prompt(&self, token_budget: Option<usize>) -> Result<String, String> {
+        let (state, original, modified) = self.load_shared_files()?;
+        let defaults = PromptOptions::default();
+        let options = PromptOptions {
+            token_budget: token_budget.unwrap_or(defaults.token_budget),
+            ..defaults
+        };
+        Ok(package_comparison(&state.file1_path, &state.file2_path, &original, &modified, &state.diff_result, &options))
+    }
+
+    /// Export the active comparison and its annotations as Markdown
+    pub fn export_markdown(&self) -> Result<String, String> {
+        let (state, original, modified) = self.load_shared_files()?;
+        Ok(export_markdown(&state, &original, &modified))
+    }
+
+    /// Export the active comparison and its annotations as HTML
+    pub fn export_html(&self) -> Result<String, String> {
+        let (state, original, modified) = self.load_shared_files()?;
+        Ok(export_html(&state, &original, &modified))
+    }
+
+    /// Pack the active comparison, both file snapshots and its annotations into a bundle file
+    pub fn export_review_bundle(&self, bundle_path: String) -> Result<(), String> {
+        let (state, original, modified) = self.load_shared_files()?;
+        let bundle = ReviewBundle::new(state, original, modified);
+        std::fs::write(&bundle_path, bundle.to_json())
+            .map_err(|e| text(Message::WriteFailed, [&bundle_path, &e]))
+    }
+
+    /// Unpack a review bundle into `dir` and make it the active comparison, keeping the
+    /// author's recorded diff even if this build would compute a different one
+    pub fn import_review_bundle(&mut self, bundle_path: String, dir: String) -> Result<String, String> {
+        let json = std::fs::read_to_string(&bundle_path)
+            .map_err(|e| text(Message::ReadFailed, [&bundle_path, &e]))?;
+        let bundle = ReviewBundle::from_json(&json)?;
+        let state = bundle
+            .extract(Path::new(&dir))
+            .map_err(|e| text(Message::ExtractFailed, [&bundle_path, &e]))?;
+
+        let mut output = format_diff(OutputFormat::Unified, &state.file1_path, &state.file2_path, &state.diff_result);
+        if !bundle.reproduces() {
+            output.insert_str(0, &format!("{}\n\n", text(Message::BundleDiffers, [])));
+        }
+        self.sessions.replace_active(state);
+        Ok(output)
+    }
+
+    /// Render the active comparison as a ready-to-send patch email
+    pub fn export_patch_email(&self, options: &PatchEmailOptions) -> Result<String, String> {
+        let (state, original, modified) = self.load_shared_files()?;
+        let file = PatchFile {
+            old_path: state.file1_path.clone(),
+            new_path: state.file2_path.clone(),
+            original,
+            modified,
+            changes: state.diff_result.clone(),
+        };
+        Ok(format_patch_email(&[file], options))
+    }
 
-                Ok(format_unified_diff(&file1, &file2, &changes))
+    /// Settings of the worktree the last slash command ran in
+    fn settings(&self) -> ExtensionSettings {
+        self.settings.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// `load_current_files` for output leaving the editor: exports, bundles, patch emails and
+    /// prompts. With redaction on, secrets are masked in both files' lines before formatting.
+    fn load_shared_files(&self) -> Result<(ComparisonState, Vec<String>, Vec<String>), String> {
+        let (state, original, modified) = self.load_current_files()?;
+        let settings = self.settings();
+        if !settings.redact {
+            return Ok((state, original, modified));
+        }
+        let redactor = settings.redactor()?;
+        Ok((state, redactor.redact_lines(&original), redactor.redact_lines(&modified)))
+    }
+
+    fn load_current_files(&self) -> Result<(ComparisonState, Vec<String>, Vec<String>), String> {
+        self.load_session_files(self.active_session()?, &SessionOwner::User)
+    }
+
+    /// A session's state with its files as they are now. Files edited since the diff no longer
+    /// match its hunks, so where a hunk falls outside them the diff is recomputed and saved.
+    fn load_session_files(
+        &self,
+        id: SessionId,
+        owner: &SessionOwner,
+    ) -> Result<(ComparisonState, Vec<String>, Vec<String>), String> {
+        let mut state = self.session_state(id)?;
+        let read = |path: &str| {
+            read_file_lines(path).map_err(|e| text(Message::ReadFailed, [&path, &e]))
+        };
+        let (original, modified) = (read(&state.file1_path)?, read(&state.file2_path)?);
+        if !state.diff_fits(original.len(), modified.len()) {
+            let changes = try_compute_diff(&original, &modified, state.options.clone())
+                .map_err(|e| text(Message::CompareFailed, [&e]))?;
+            state.replace_diff_result(changes.clone());
+            self.sessions.update(id, owner, |current| {
+                current.replace_diff_result(changes);
+                Ok(())
+            })?;
+        }
+        Ok((state, original, modified))
+    }
+
+    /// Compare the copies of `path` under two roots, e.g. staging and production checkouts
+    pub fn compare_relative(&self, path: String, root_a: String, root_b: String) -> Result<String, String> {
+        self.compare_relative_batch(vec![path], root_a, root_b)
+    }
+
+    /// `compare_relative` over several paths, reported together
+    pub fn compare_relative_batch(&self, paths: Vec<String>, root_a: String, root_b: String) -> Result<String, String> {
+        if paths.is_empty() {
+            return Err(text(Message::NoPaths, []));
+        }
+        let results = compare_relative_batch(&paths, Path::new(&root_a), Path::new(&root_b), &DiffOptions::default());
+        Ok(format_relative_report(&root_a, &root_b, &results))
+    }
+
+    /// Compare every file under two directory trees; `nested_repos` is `skip`, `revision` or
+    /// `recurse` and decides how nested git repositories and submodules are treated, `filters`
+    /// are `--ext=`, `--include=`, `--min-size=`, `--max-size=`, `--newer-than=` and
+    /// `--older-than=` arguments limiting which files are compared, `--trivial=<ratio>` reports
+    /// files changed in that fraction of their lines or less, in whitespace only, as effectively
+    /// identical, and `--sort=path|size|mtime` and `--group=dir|ext|size` order the report
+    pub fn compare_directories(
+        &self,
+        root_a: String,
+        root_b: String,
+        nested_repos: String,
+        mut filters: Vec<String>,
+    ) -> Result<String, String> {
+        let order = ReportOrder::from_args(&mut filters)?;
+        let options = directory_options(&nested_repos, &filters)?;
+        let results = compare_directories(Path::new(&root_a), Path::new(&root_b), &options);
+        Ok(directory_report(&root_a, &root_b, &results, &order))
+    }
+
+    /// `compare_directories` for trees too large to finish in one go: progress is checkpointed
+    /// to `job_file`, and `resume_directory_job` picks up an interrupted run from there
+    pub fn start_directory_job(
+        &self,
+        root_a: String,
+        root_b: String,
+        nested_repos: String,
+        filters: Vec<String>,
+        job_file: String,
+    ) -> Result<String, String> {
+        let job = DirectoryJob::start(&root_a, &root_b, directory_options(&nested_repos, &filters)?);
+        job.save(Path::new(&job_file)).map_err(|e| text(Message::WriteFailed, [&job_file, &e]))?;
+        finish_directory_job(job, &job_file)
+    }
+
+    pub fn resume_directory_job(&self, job_file: String) -> Result<String, String> {
+        finish_directory_job(DirectoryJob::load(Path::new(&job_file))?, &job_file)
+    }
+
+    /// Add the current content of `file_path` to its local history in `history_file`, creating
+    /// the history on first use; returns how many versions are stored
+    pub fn record_snapshot(&self, file_path: String, history_file: String) -> Result<usize, String> {
+        let history_path = Path::new(&history_file);
+        let mut history = if history_path.exists() {
+            LocalHistory::load(history_path)?
+        } else {
+            LocalHistory::new(&file_path)
+        };
+        let lines = read_file_lines(&file_path).map_err(|e| text(Message::ReadFailed, [&file_path, &e]))?;
+        let saved_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        if history.record(&lines, saved_at)? {
+            history.save(history_path).map_err(|e| text(Message::WriteFailed, [&history_file, &e]))?;
+        }
+        Ok(history.len())
+    }
+
+    /// Hotspots and the biggest changes in a churn store over the last `days` days (all time when
+    /// unset), `limit` entries each
+    pub fn churn_report(&self, churn_file: String, days: Option<u64>, limit: usize) -> Result<String, String> {
+        let store = ChurnStore::load(Path::new(&churn_file))?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let since = match days {
+            Some(days) => {
+                let window = days.checked_mul(24 * 60 * 60).ok_or_else(|| text(Message::InvalidDays, [&days]))?;
+                now.saturating_sub(window)
             }
-            Err(e) => Err(format!("Failed to compare files: {}", e)),
+            None => 0,
+        };
+        Ok(format_churn_report(&store.hotspots(since, limit), &store.biggest_changes(since, limit)))
+    }
+
+    /// Version `version` (0 is the oldest kept) from a local history file
+    pub fn snapshot_version(&self, history_file: String, version: usize) -> Result<Vec<String>, String> {
+        LocalHistory::load(Path::new(&history_file))?.materialize(version)
+    }
+
+    /// Diffstat of every file in `repo`'s worktree that differs from HEAD, followed by each diff,
+    /// with hunks made identically in many files shown once; with `blame`, each diff also names
+    /// who last changed the lines its hunks replace
+    pub fn worktree_report(&self, repo: String, blame: bool) -> Result<String, String> {
+        let changes = worktree_changes(Path::new(&repo), DiffOptions::default())
+            .map_err(|e| text(Message::WorktreeStatusFailed, [&e]))?;
+        let context_lines = self.settings().context_lines();
+        let mut report = format_worktree_stat(&changes);
+        let (duplicates, changes) = collapse_duplicates(changes, DEFAULT_MIN_DUPLICATE_FILES);
+        for duplicate in &duplicates {
+            report.push('\n');
+            report.push_str(&format_duplicate_change(duplicate));
+        }
+        for change in &changes {
+            report.push('\n');
+            report.push_str(&worktree_file_report(Path::new(&repo), change, context_lines, blame)?);
+        }
+        Ok(report)
+    }
+
+    /// The files changed in `repo`'s worktree scored by risk, followed by their diffs riskiest
+    /// first, so reviewers know where to spend their attention
+    pub fn risk_report(&self, repo: String) -> Result<String, String> {
+        let changes = worktree_changes(Path::new(&repo), DiffOptions::default())
+            .map_err(|e| text(Message::WorktreeStatusFailed, [&e]))?;
+        let risks = score_files(&changes);
+        let context_lines = self.settings().context_lines();
+        let mut report = format_risk_report(&risks);
+        for change in sort_by_risk(changes, &risks) {
+            report.push('\n');
+            report.push_str(&format_worktree_file(&change, context_lines));
         }
+        Ok(report)
+    }
+
+    /// Risky hunks, suspicious characters and generated-code edits among the uncommitted changes
+    /// in `repo`'s worktree, as a SARIF log for code-scanning dashboards; files matching
+    /// `generated_files` patterns count as generated
+    pub fn findings_sarif(&self, repo: String, generated_files: Vec<String>) -> Result<String, String> {
+        let changes = worktree_changes(Path::new(&repo), DiffOptions::default())
+            .map_err(|e| text(Message::WorktreeStatusFailed, [&e]))?;
+        serde_json::to_string_pretty(&to_sarif(&collect_findings(&changes, &generated_files)))
+            .map_err(|e| text(Message::SarifFailed, [&e]))
+    }
+
+    /// The files changed in `repo`'s worktree, grouped by their owners in CODEOWNERS, so it is
+    /// clear whom to ask for review of each part; `repo` must be the repository root
+    pub fn owners_report(&self, repo: String) -> Result<String, String> {
+        let changes = worktree_changes(Path::new(&repo), DiffOptions::default())
+            .map_err(|e| text(Message::WorktreeStatusFailed, [&e]))?;
+        owners_report(Path::new(&repo), &changes)
+    }
+
+    /// The Rust tests likely affected by the uncommitted changes in `repo`'s worktree, each with
+    /// the `cargo test` command that runs it; `repo` must be the crate root
+    pub fn test_impact(&self, repo: String) -> Result<String, String> {
+        let changes = worktree_changes(Path::new(&repo), DiffOptions::default())
+            .map_err(|e| text(Message::WorktreeStatusFailed, [&e]))?;
+        Ok(test_impact_report(Path::new(&repo), &changes))
+    }
+
+    /// A conventional-commit message skeleton for the uncommitted changes in `repo`'s worktree
+    pub fn suggest_commit_message(&self, repo: String) -> Result<String, String> {
+        let changes = worktree_changes(Path::new(&repo), DiffOptions::default())
+            .map_err(|e| text(Message::WorktreeStatusFailed, [&e]))?;
+        if changes.is_empty() {
+            return Err(text(Message::NothingToCommit, []));
+        }
+        Ok(suggest_commit_message(&changes).format())
+    }
+
+    /// Compare `path` between two branches, both directly and since their merge base
+    pub fn compare_branches(
+        &self,
+        repo: String,
+        path: String,
+        base_branch: String,
+        branch: String,
+    ) -> Result<String, String> {
+        let options = DiffOptions::default();
+
+        match compare_across_branches(Path::new(&repo), &path, &base_branch, &branch, options) {
+            Ok(comparison) => Ok(format_branch_comparison(&comparison)),
+            Err(e) => Err(text(Message::CompareBranchesFailed, [&e])),
+        }
+    }
+
+    /// Step through the history of `path` over a revision range, one diff per commit
+    pub fn file_history(&self, repo: String, path: String, rev_range: String) -> Result<String, String> {
+        let options = DiffOptions::default();
+
+        match file_history(Path::new(&repo), &path, &rev_range, options) {
+            Ok(steps) => Ok(format_file_history(&path, &steps)),
+            Err(e) => Err(text(Message::HistoryFailed, [&e])),
+        }
+    }
+
+    /// Report the files and hunks expected to conflict when merging `branch` into `base_branch`
+    pub fn predict_conflicts(
+        &self,
+        repo: String,
+        base_branch: String,
+        branch: String,
+    ) -> Result<String, String> {
+        let options = DiffOptions::default();
+
+        match predict_branch_conflicts(Path::new(&repo), &base_branch, &branch, options) {
+            Ok(files) => Ok(format_conflict_prediction(&files)),
+            Err(e) => Err(text(Message::PredictConflictsFailed, [&e])),
+        }
+    }
+
+    /// New changelog entries between two revisions of `path`, grouped by release; removals are left out
+    pub fn whats_new(&self, path: String, rev_a: String, rev_b: String) -> Result<String, String> {
+        let file = Path::new(&path);
+        let repo = file.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        // `rev:./name` resolves against the directory git runs in, wherever the repository root is
+        let name = format!("./{}", file.file_name().and_then(|n| n.to_str()).unwrap_or_default());
+        let read = |rev: &str| {
+            file_lines_at_revision(repo, rev, &name).map_err(|e| text(Message::ReadAtRevisionFailed, [&path, &rev, &e]))
+        };
+
+        let groups = whats_new(&read(&rev_a)?, &read(&rev_b)?, DiffOptions::default());
+        Ok(format_whats_new(&path, &rev_a, &rev_b, &groups))
+    }
+
+    /// The diff tools offered to assistants and agents, as a JSON array of name, description and
+    /// input schema
+    pub fn tool_definitions(&self) -> String {
+        let tools: Vec<_> = tool_definitions().iter().map(|tool| tool.to_json()).collect();
+        serde_json::to_string(&tools).unwrap_or_default()
+    }
+
+    /// Run a tool from `tool_definitions` with JSON `arguments`, returning its JSON result
+    pub fn call_tool(&self, name: String, arguments: String) -> Result<String, String> {
+        let arguments = serde_json::from_str(&arguments).map_err(|e| text(Message::InvalidToolArguments, [&name, &e]))?;
+        call_tool(&name, &arguments).map(|result| result.to_string())
+    }
+
+    /// Entry point for `git difftool` / `git mergetool`: `LOCAL REMOTE [BASE MERGED]` as git passes
+    /// them, with the exit code the wrapper should return to git
+    pub fn git_tool(&self, args: Vec<String>) -> ToolOutcome {
+        let invocation = match ToolInvocation::from_args(&args) {
+            Ok(invocation) => invocation,
+            Err(e) => {
+                return ToolOutcome {
+                    exit_code: 1,
+                    report: format!("{}\n", e),
+                }
+            }
+        };
+        run_tool(&invocation, &DiffOptions::default(), &MergeOptions::default())
+    }
+
+    /// Merge a file with a sync client's conflicted copy; every differing region is left as a
+    /// conflict block since the two share no recorded ancestor
+    pub fn merge_conflict_copy(&self, path: String, copy: String) -> Result<String, String> {
+        let read = |path: &str| {
+            read_file_lines(path).map_err(|e| text(Message::ReadFailed, [&path, &e]))
+        };
+        let merge_options = MergeOptions {
+            theirs_label: Path::new(&copy)
+                .file_name()
+                .map_or(copy.clone(), |name| name.to_string_lossy().into_owned()),
+            ..MergeOptions::default()
+        };
+        let result = merge_two_way(&read(&path)?, &read(&copy)?, DiffOptions::default(), &merge_options);
+        Ok(result.lines.join("\n") + "\n")
+    }
+
+    /// Keep the resolved merge as the file and delete the conflicted copy
+    pub fn resolve_conflict_copy(&self, path: String, copy: String, merged: String) -> Result<(), String> {
+        let lines: Vec<String> = merged.lines().map(String::from).collect();
+        resolve_conflict_copy(Path::new(&path), Path::new(&copy), &lines)
+    }
+
+    /// Three-way merge of two files against their common base, returning the merged text
+    pub fn merge_files(
+        &self,
+        base: String,
+        ours: String,
+        theirs: String,
+        conflict_style: ConflictStyle,
+    ) -> Result<String, String> {
+        let read = |path: &str| {
+            read_file_lines(path).map_err(|e| text(Message::ReadFailed, [&path, &e]))
+        };
+        let merge_options = MergeOptions {
+            conflict_style,
+            ..MergeOptions::default()
+        };
+
+        let result = merge_three_way(
+            &read(&base)?,
+            &read(&ours)?,
+            &read(&theirs)?,
+            DiffOptions::default(),
+            &merge_options,
+        );
+        Ok(result.lines.join("\n") + "\n")
     }
 }
 
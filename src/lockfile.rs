@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use serde_json::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LockfileKind {
+    Cargo,
+    NpmLock,
+    Yarn,
+}
+
+impl LockfileKind {
+    pub fn for_path(path: &str) -> Option<Self> {
+        match Path::new(path).file_name()?.to_str()? {
+            "Cargo.lock" => Some(LockfileKind::Cargo),
+            "package-lock.json" | "npm-shrinkwrap.json" => Some(LockfileKind::NpmLock),
+            "yarn.lock" => Some(LockfileKind::Yarn),
+            _ => None,
+        }
+    }
+}
+
+/// Every locked version of each package; a package may be locked at several versions
+pub type LockedVersions = BTreeMap<String, BTreeSet<String>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LockChange {
+    Added { name: String, versions: Vec<String> },
+    Removed { name: String, versions: Vec<String> },
+    Updated { name: String, from: Vec<String>, to: Vec<String> },
+}
+
+pub fn parse_lockfile(kind: LockfileKind, lines: &[String]) -> Result<LockedVersions, String> {
+    match kind {
+        LockfileKind::Cargo => Ok(parse_cargo_lock(lines)),
+        LockfileKind::NpmLock => parse_package_lock(&lines.join("\n")),
+        LockfileKind::Yarn => Ok(parse_yarn_lock(lines)),
+    }
+}
+
+pub fn parse_cargo_lock(lines: &[String]) -> LockedVersions {
+    let mut locked = LockedVersions::new();
+    let mut name: Option<String> = None;
+
+    for line in lines {
+        let line = line.trim();
+        if line == "[[package]]" {
+            name = None;
+        } else if let Some(value) = line.strip_prefix("name = ") {
+            name = Some(value.trim_matches('"').to_string());
+        } else if let (Some(value), Some(name)) = (line.strip_prefix("version = "), &name) {
+            locked.entry(name.clone()).or_default().insert(value.trim_matches('"').to_string());
+        }
+    }
+    locked
+}
+
+/// Reads `packages` (lockfile v2/v3) or, for v1, the nested `dependencies` tree
+pub fn parse_package_lock(text: &str) -> Result<LockedVersions, String> {
+    let document: Value = serde_json::from_str(text).map_err(|e| format!("invalid package-lock.json: {}", e))?;
+    let mut locked = LockedVersions::new();
+
+    if let Some(packages) = document.get("packages").and_then(Value::as_object) {
+        for (path, package) in packages {
+            // The root project is keyed by the empty path
+            let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                continue;
+            };
+            if let Some(version) = package.get("version").and_then(Value::as_str) {
+                locked.entry(name.to_string()).or_default().insert(version.to_string());
+            }
+        }
+    } else if let Some(dependencies) = document.get("dependencies") {
+        collect_npm_v1(dependencies, &mut locked);
+    }
+    Ok(locked)
+}
+
+fn collect_npm_v1(dependencies: &Value, locked: &mut LockedVersions) {
+    for (name, package) in dependencies.as_object().into_iter().flatten() {
+        if let Some(version) = package.get("version").and_then(Value::as_str) {
+            locked.entry(name.clone()).or_default().insert(version.to_string());
+        }
+        if let Some(nested) = package.get("dependencies") {
+            collect_npm_v1(nested, locked);
+        }
+    }
+}
+
+/// Unindented `"name@range", name@range:` headers followed by an indented version line
+pub fn parse_yarn_lock(lines: &[String]) -> LockedVersions {
+    let mut locked = LockedVersions::new();
+    let mut name: Option<String> = None;
+
+    for line in lines {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            let spec = line.split(',').next().unwrap_or_default().trim().trim_matches('"');
+            // Scoped packages start with `@`; the version range follows the last `@`
+            name = spec.get(1..).and_then(|rest| rest.rfind('@')).map(|at| spec[..at + 1].to_string());
+        } else if let (Some(value), Some(name)) = (line.trim().strip_prefix("version"), &name) {
+            // Yarn 1 writes `version "x"`, Yarn 2+ `version: x`
+            let value = value.trim_start_matches(':').trim().trim_matches('"');
+            locked.entry(name.clone()).or_default().insert(value.to_string());
+        }
+    }
+    locked
+}
+
+pub fn compare_lockfiles(original: &LockedVersions, modified: &LockedVersions) -> Vec<LockChange> {
+    let names: BTreeSet<&String> = original.keys().chain(modified.keys()).collect();
+    let list = |versions: &BTreeSet<String>| versions.iter().cloned().collect::<Vec<_>>();
+
+    names
+        .into_iter()
+        .filter_map(|name| match (original.get(name), modified.get(name)) {
+            (Some(from), Some(to)) if from == to => None,
+            (Some(from), Some(to)) => Some(LockChange::Updated {
+                name: name.clone(),
+                from: list(from),
+                to: list(to),
+            }),
+            (Some(from), None) => Some(LockChange::Removed {
+                name: name.clone(),
+                versions: list(from),
+            }),
+            (None, Some(to)) => Some(LockChange::Added {
+                name: name.clone(),
+                versions: list(to),
+            }),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+pub fn format_lockfile_diff(file1_path: &str, file2_path: &str, changes: &[LockChange]) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", file1_path, file2_path);
+    if changes.is_empty() {
+        output.push_str("\nNo locked version changes\n");
+        return output;
+    }
+
+    let count = |f: fn(&LockChange) -> bool| changes.iter().filter(|c| f(c)).count();
+    output.push_str(&format!(
+        "\n{} package(s) updated, {} added, {} removed\n\n",
+        count(|c| matches!(c, LockChange::Updated { .. })),
+        count(|c| matches!(c, LockChange::Added { .. })),
+        count(|c| matches!(c, LockChange::Removed { .. }))
+    ));
+    for change in changes {
+        output.push_str(&match change {
+            LockChange::Updated { name, from, to } => format!("~ {} {} -> {}\n", name, from.join(", "), to.join(", ")),
+            LockChange::Added { name, versions } => format!("+ {} {}\n", name, versions.join(", ")),
+            LockChange::Removed { name, versions } => format!("- {} {}\n", name, versions.join(", ")),
+        });
+    }
+    output.push_str("\n(use --mode=lines for the raw diff)\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_cargo_lock_diff() {
+        let original = parse_cargo_lock(&lines(
+            "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.1\"\n\n[[package]]\nname = \"old\"\nversion = \"0.1.0\"\ndependencies = [\n \"serde\",\n]",
+        ));
+        let modified = parse_cargo_lock(&lines(
+            "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.2\"\n\n[[package]]\nname = \"new\"\nversion = \"2.0.0\"",
+        ));
+
+        let text = format_lockfile_diff("a", "b", &compare_lockfiles(&original, &modified));
+        assert!(text.contains("1 package(s) updated, 1 added, 1 removed"));
+        assert!(text.contains("+ new 2.0.0\n- old 0.1.0\n~ serde 1.0.1 -> 1.0.2\n"));
+    }
+
+    #[test]
+    fn test_npm_and_yarn_locks() {
+        let npm = parse_package_lock(
+            r#"{"packages": {"": {"version": "1.0.0"}, "node_modules/a": {"version": "1.2.3"}, "node_modules/a/node_modules/@s/b": {"version": "0.1.0"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(npm.keys().collect::<Vec<_>>(), vec!["@s/b", "a"]);
+
+        let yarn = parse_yarn_lock(&lines(
+            "# yarn lockfile v1\n\n\"@s/b@^0.1.0\", \"@s/b@^0.1.1\":\n  version \"0.1.2\"\n  resolved \"x\"\n\na@^1.0.0:\n  version \"1.2.3\"",
+        ));
+        assert_eq!(yarn["@s/b"].iter().collect::<Vec<_>>(), vec!["0.1.2"]);
+        assert_eq!(yarn["a"].iter().collect::<Vec<_>>(), vec!["1.2.3"]);
+    }
+}
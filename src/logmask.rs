@@ -0,0 +1,77 @@
+use crate::pattern::Pattern;
+
+/// Leading timestamps, PIDs and thread IDs commonly found in application logs
+pub const DEFAULT_LOG_MASK_PATTERNS: &[&str] = &[
+    // ISO 8601 / RFC 3339 style, optional fraction and offset
+    r"\d\d\d\d-\d\d-\d\d[T ]\d\d:\d\d:\d\d[.,]?\d*Z?[+-]?\d*:?\d*",
+    // Bracketed ISO timestamps
+    r"\[\d\d\d\d-\d\d-\d\d[T ]\d\d:\d\d:\d\d[^\]]*\]",
+    // Syslog: `Jan  2 15:04:05`
+    r"[A-Z][a-z][a-z] +\d+ \d\d:\d\d:\d\d",
+    // Bare time of day
+    r"\d\d:\d\d:\d\d[.,]?\d*",
+    // Epoch seconds or milliseconds
+    r"\d\d\d\d\d\d\d\d\d\d\d*",
+    // `[1234]`, `pid=1234`, `[pid 1234]`
+    r"\[\d+\]",
+    r"\[?pid[:= ]?\d+\]?",
+    // `[tid 0x7f..]`, `thread-12`, `[Thread-3]`
+    r"\[?tid[:= ]?[\w]+\]?",
+    r"\[?[Tt]hread[-_:= ]?[\w]+\]?",
+    r"0x[0-9a-fA-F]+",
+];
+
+pub fn compile_patterns(sources: &[String]) -> Result<Vec<Pattern>, String> {
+    sources.iter().map(|source| Pattern::parse(source)).collect()
+}
+
+pub fn default_log_mask_patterns() -> Vec<String> {
+    DEFAULT_LOG_MASK_PATTERNS.iter().map(|p| p.to_string()).collect()
+}
+
+/// Strip any sequence of masked tokens from the start of `line`, returning the message part
+pub fn mask_log_prefix(line: &str, patterns: &[Pattern]) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut position = 0;
+
+    loop {
+        while position < chars.len() && (chars[position].is_whitespace() || chars[position] == '|') {
+            position += 1;
+        }
+
+        let matched = patterns
+            .iter()
+            .filter_map(|pattern| pattern.match_prefix(&chars[position..]))
+            .max()
+            .filter(|&len| len > 0);
+
+        match matched {
+            Some(len) => position += len,
+            None => break,
+        }
+    }
+
+    chars[position..].iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(line: &str) -> String {
+        let patterns = compile_patterns(&default_log_mask_patterns()).unwrap();
+        mask_log_prefix(line, &patterns)
+    }
+
+    #[test]
+    fn test_masks_common_prefixes() {
+        assert_eq!(mask("2024-03-01T12:00:01.123Z [1234] [thread-7] INFO started"), "INFO started");
+        assert_eq!(mask("Mar  1 12:00:01 pid=42 ERROR boom"), "ERROR boom");
+        assert_eq!(mask("12:00:01,500 | WARN slow"), "WARN slow");
+    }
+
+    #[test]
+    fn test_message_content_is_kept() {
+        assert_eq!(mask("INFO user 12 logged in at 12:00:01"), "INFO user 12 logged in at 12:00:01");
+    }
+}
@@ -0,0 +1,249 @@
+use std::cmp::Ordering;
+use std::path::Path;
+use serde_json::Value;
+
+/// A dependency declared in a manifest section such as `dependencies` or `devDependencies`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dependency {
+    pub section: String,
+    pub name: String,
+    /// Version requirement as written, or the source (`git`, `path`) when there is none
+    pub version: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DependencyChange {
+    Added(Dependency),
+    Removed(Dependency),
+    Upgraded { from: Dependency, to: Dependency },
+    Downgraded { from: Dependency, to: Dependency },
+    /// Requirement changed in a way that is not an ordered version change
+    Changed { from: Dependency, to: Dependency },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ManifestKind {
+    Cargo,
+    Npm,
+}
+
+impl ManifestKind {
+    pub fn for_path(path: &str) -> Option<Self> {
+        match Path::new(path).file_name()?.to_str()? {
+            "Cargo.toml" => Some(ManifestKind::Cargo),
+            "package.json" => Some(ManifestKind::Npm),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_manifest(kind: ManifestKind, lines: &[String]) -> Result<Vec<Dependency>, String> {
+    match kind {
+        ManifestKind::Cargo => Ok(parse_cargo_toml(lines)),
+        ManifestKind::Npm => parse_package_json(&lines.join("\n")),
+    }
+}
+
+/// Line-based reading of `[*dependencies]` tables, inline `{ version = .. }` tables and
+/// `[dependencies.name]` sub-tables
+pub fn parse_cargo_toml(lines: &[String]) -> Vec<Dependency> {
+    let mut dependencies: Vec<Dependency> = Vec::new();
+    let mut section: Option<String> = None;
+    // `[dependencies.name]` tables collect their keys onto a single entry
+    let mut table_entry: Option<usize> = None;
+
+    for line in lines {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            table_entry = None;
+            section = None;
+            let header = header.trim();
+            if let Some((table, name)) = header.rsplit_once('.').filter(|(table, _)| table.ends_with("dependencies")) {
+                dependencies.push(Dependency {
+                    section: table.to_string(),
+                    name: name.trim_matches('"').to_string(),
+                    version: String::new(),
+                });
+                table_entry = Some(dependencies.len() - 1);
+            } else if header.ends_with("dependencies") {
+                section = Some(header.to_string());
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim().trim_matches('"'), value.trim());
+
+        if let Some(index) = table_entry {
+            if key == "version" || (key != "features" && dependencies[index].version.is_empty()) {
+                dependencies[index].version = describe_cargo_source(key, value);
+            }
+        } else if let Some(section) = &section {
+            let version = if value.starts_with('{') {
+                inline_table_version(value)
+            } else {
+                value.trim_matches('"').to_string()
+            };
+            dependencies.push(Dependency {
+                section: section.clone(),
+                name: key.to_string(),
+                version,
+            });
+        }
+    }
+    dependencies
+}
+
+/// `version = "1"` reads as `1`, other sources as e.g. `git https://...`
+fn describe_cargo_source(key: &str, value: &str) -> String {
+    let value = value.trim_matches('"');
+    if key == "version" {
+        value.to_string()
+    } else {
+        format!("{} {}", key, value)
+    }
+}
+
+fn inline_table_version(table: &str) -> String {
+    let body = table.trim_start_matches('{').trim_end_matches('}');
+    let pairs: Vec<(&str, &str)> = body
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect();
+    pairs
+        .iter()
+        .find(|(key, _)| *key == "version")
+        .or_else(|| pairs.iter().find(|(key, _)| matches!(*key, "git" | "path" | "workspace")))
+        .map(|(key, value)| describe_cargo_source(key, value))
+        .unwrap_or_default()
+}
+
+pub fn parse_package_json(text: &str) -> Result<Vec<Dependency>, String> {
+    let document: Value = serde_json::from_str(text).map_err(|e| format!("invalid package.json: {}", e))?;
+    let mut dependencies = Vec::new();
+
+    for section in ["dependencies", "devDependencies", "peerDependencies", "optionalDependencies"] {
+        if let Some(entries) = document.get(section).and_then(Value::as_object) {
+            for (name, version) in entries {
+                dependencies.push(Dependency {
+                    section: section.to_string(),
+                    name: name.clone(),
+                    version: version.as_str().unwrap_or_default().to_string(),
+                });
+            }
+        }
+    }
+    Ok(dependencies)
+}
+
+pub fn compare_dependencies(original: &[Dependency], modified: &[Dependency]) -> Vec<DependencyChange> {
+    let same = |a: &Dependency, b: &Dependency| a.section == b.section && a.name == b.name;
+    let mut changes = Vec::new();
+
+    for from in original {
+        match modified.iter().find(|to| same(from, to)) {
+            None => changes.push(DependencyChange::Removed(from.clone())),
+            Some(to) if to.version == from.version => {}
+            Some(to) => {
+                let (from, to) = (from.clone(), to.clone());
+                changes.push(match compare_versions(&from.version, &to.version) {
+                    Some(Ordering::Less) => DependencyChange::Upgraded { from, to },
+                    Some(Ordering::Greater) => DependencyChange::Downgraded { from, to },
+                    _ => DependencyChange::Changed { from, to },
+                });
+            }
+        }
+    }
+    for to in modified {
+        if !original.iter().any(|from| same(from, to)) {
+            changes.push(DependencyChange::Added(to.clone()));
+        }
+    }
+    changes
+}
+
+/// Order two requirements by their numeric components, ignoring operators like `^` and `>=`;
+/// `None` when either has no leading version number
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let components = |version: &str| -> Option<Vec<u64>> {
+        let version = version.trim_start_matches(|c: char| "^~=<>v ".contains(c));
+        let numeric: Vec<u64> = version
+            .split(['.', '-', '+'])
+            .map_while(|part| part.parse().ok())
+            .collect();
+        (!numeric.is_empty()).then_some(numeric)
+    };
+    let (a, b) = (components(a)?, components(b)?);
+    let length = a.len().max(b.len());
+    let pad = |mut v: Vec<u64>| {
+        v.resize(length, 0);
+        v
+    };
+    Some(pad(a).cmp(&pad(b)))
+}
+
+pub fn format_dependency_diff(file1_path: &str, file2_path: &str, changes: &[DependencyChange]) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", file1_path, file2_path);
+    if changes.is_empty() {
+        output.push_str("\nNo dependency changes\n");
+        return output;
+    }
+
+    output.push('\n');
+    for change in changes {
+        output.push_str(&match change {
+            DependencyChange::Added(dep) => format!("+ {} {} [{}]\n", dep.name, dep.version, dep.section),
+            DependencyChange::Removed(dep) => format!("- {} {} [{}]\n", dep.name, dep.version, dep.section),
+            DependencyChange::Upgraded { from, to } => {
+                format!("↑ {} {} -> {} [{}]\n", from.name, from.version, to.version, from.section)
+            }
+            DependencyChange::Downgraded { from, to } => {
+                format!("↓ {} {} -> {} [{}]\n", from.name, from.version, to.version, from.section)
+            }
+            DependencyChange::Changed { from, to } => {
+                format!("~ {} {} -> {} [{}]\n", from.name, from.version, to.version, from.section)
+            }
+        });
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_cargo_manifest_diff() {
+        let original = parse_cargo_toml(&lines(
+            "[package]\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1.0.100\"\nregex = { version = \"1.5\", features = [\"std\"] }\nold = \"2\"\n\n[dependencies.tokio]\nversion = \"1.20\"\nfeatures = [\"full\"]",
+        ));
+        let modified = parse_cargo_toml(&lines(
+            "[package]\nversion = \"0.2.0\"\n\n[dependencies]\nserde = \"1.0.99\"\nregex = { version = \"1.10\" }\nlocal = { path = \"../local\" }\n\n[dependencies.tokio]\nversion = \"1.20\"",
+        ));
+        assert_eq!(original.len(), 4);
+
+        let text = format_dependency_diff("a", "b", &compare_dependencies(&original, &modified));
+        assert!(text.contains("↓ serde 1.0.100 -> 1.0.99 [dependencies]"));
+        assert!(text.contains("↑ regex 1.5 -> 1.10 [dependencies]"));
+        assert!(text.contains("- old 2 [dependencies]"));
+        assert!(text.contains("+ local path ../local [dependencies]"));
+        assert!(!text.contains("tokio"));
+        assert!(!text.contains("0.2.0"));
+    }
+
+    #[test]
+    fn test_package_json_diff() {
+        let original = parse_package_json(r#"{"dependencies": {"react": "^17.0.2"}, "devDependencies": {"jest": "29"}}"#).unwrap();
+        let modified = parse_package_json(r#"{"dependencies": {"react": "^18.2.0"}}"#).unwrap();
+
+        let changes = compare_dependencies(&original, &modified);
+        assert!(matches!(&changes[0], DependencyChange::Upgraded { to, .. } if to.version == "^18.2.0"));
+        assert!(matches!(&changes[1], DependencyChange::Removed(dep) if dep.name == "jest"));
+        assert_eq!(ManifestKind::for_path("/x/package.json"), Some(ManifestKind::Npm));
+        assert!(parse_package_json("{").is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use crate::diff_core::{compute_diff, DiffOptions, LineChange};
+
+#[derive(Clone, Debug)]
+pub struct PredictedConflict {
+    /// Hunk from base to "ours"
+    pub ours: LineChange,
+    /// Overlapping hunk from base to "theirs"
+    pub theirs: LineChange,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictStyle {
+    /// `<<<<<<<` / `=======` / `>>>>>>>` with both sides only
+    Merge,
+    /// Also include the base section after `|||||||`
+    Diff3,
+}
+
+#[derive(Clone, Debug)]
+pub struct MergeOptions {
+    pub conflict_style: ConflictStyle,
+    pub ours_label: String,
+    pub base_label: String,
+    pub theirs_label: String,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            conflict_style: ConflictStyle::Merge,
+            ours_label: "ours".to_string(),
+            base_label: "base".to_string(),
+            theirs_label: "theirs".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MergeResult {
+    pub lines: Vec<String>,
+    pub conflict_count: usize,
+}
+
+/// Predict which hunks will conflict when merging two descendants of `base`
+pub fn predict_conflicts(
+    base: &[String],
+    ours: &[String],
+    theirs: &[String],
+    options: DiffOptions,
+) -> Vec<PredictedConflict> {
+    let our_changes = compute_diff(base, ours, options.clone());
+    let their_changes = compute_diff(base, theirs, options);
+
+    let mut conflicts = Vec::new();
+    for our_change in &our_changes {
+        for their_change in &their_changes {
+            if hunks_overlap(our_change, their_change)
+                && !same_edit(our_change, their_change, ours, theirs)
+            {
+                conflicts.push(PredictedConflict {
+                    ours: our_change.clone(),
+                    theirs: their_change.clone(),
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+/// Three-way merge of `ours` and `theirs` against their common `base`
+pub fn merge_three_way(
+    base: &[String],
+    ours: &[String],
+    theirs: &[String],
+    options: DiffOptions,
+    merge_options: &MergeOptions,
+) -> MergeResult {
+    let our_changes = compute_diff(base, ours, options.clone());
+    let their_changes = compute_diff(base, theirs, options);
+
+    let mut lines = Vec::new();
+    let mut conflict_count = 0;
+    let mut base_pos = 0;
+    let (mut oi, mut ti) = (0, 0);
+    // Running line-count difference between each side and the base
+    let (mut our_offset, mut their_offset) = (0isize, 0isize);
+
+    loop {
+        let next_start = match (our_changes.get(oi), their_changes.get(ti)) {
+            (Some(a), Some(b)) => a.original_start.min(b.original_start),
+            (Some(a), None) => a.original_start,
+            (None, Some(b)) => b.original_start,
+            (None, None) => break,
+        };
+
+        // Grow the region until no hunk from either side touches it
+        let region_start = next_start;
+        let mut region_end = next_start;
+        let (oi_start, ti_start) = (oi, ti);
+        loop {
+            if let Some(c) = our_changes.get(oi).filter(|c| c.original_start <= region_end) {
+                region_end = region_end.max(c.original_end);
+                oi += 1;
+            } else if let Some(c) = their_changes.get(ti).filter(|c| c.original_start <= region_end) {
+                region_end = region_end.max(c.original_end);
+                ti += 1;
+            } else {
+                break;
+            }
+        }
+
+        lines.extend_from_slice(&base[base_pos..region_start]);
+
+        let our_delta = size_delta(&our_changes[oi_start..oi]);
+        let their_delta = size_delta(&their_changes[ti_start..ti]);
+        let our_slice = side_slice(ours, region_start, region_end, our_offset, our_delta);
+        let their_slice = side_slice(theirs, region_start, region_end, their_offset, their_delta);
+
+        if ti == ti_start || our_slice == their_slice {
+            lines.extend_from_slice(our_slice);
+        } else if oi == oi_start {
+            lines.extend_from_slice(their_slice);
+        } else {
+            conflict_count += 1;
+            push_conflict(
+                &mut lines,
+                merge_options,
+                our_slice,
+                &base[region_start..region_end],
+                their_slice,
+            );
+        }
+
+        our_offset += our_delta;
+        their_offset += their_delta;
+        base_pos = region_end;
+    }
+
+    lines.extend_from_slice(&base[base_pos..]);
+    MergeResult { lines, conflict_count }
+}
+
+/// Merge two versions without a common ancestor: shared lines are kept and every differing
+/// region becomes a conflict, since neither side can be preferred
+pub fn merge_two_way(
+    ours: &[String],
+    theirs: &[String],
+    options: DiffOptions,
+    merge_options: &MergeOptions,
+) -> MergeResult {
+    let changes = compute_diff(ours, theirs, options);
+    let mut lines = Vec::new();
+    let mut position = 0;
+    for change in &changes {
+        lines.extend_from_slice(&ours[position..change.original_start]);
+        push_conflict(
+            &mut lines,
+            merge_options,
+            &ours[change.original_start..change.original_end],
+            &[],
+            &theirs[change.modified_start..change.modified_end],
+        );
+        position = change.original_end;
+    }
+    lines.extend_from_slice(&ours[position..]);
+    MergeResult {
+        lines,
+        conflict_count: changes.len(),
+    }
+}
+
+fn size_delta(changes: &[LineChange]) -> isize {
+    changes
+        .iter()
+        .map(|c| (c.modified_end - c.modified_start) as isize - (c.original_end - c.original_start) as isize)
+        .sum()
+}
+
+fn side_slice(side: &[String], start: usize, end: usize, offset: isize, delta: isize) -> &[String] {
+    let lo = (start as isize + offset) as usize;
+    let hi = (end as isize + offset + delta) as usize;
+    &side[lo..hi]
+}
+
+fn push_conflict(
+    lines: &mut Vec<String>,
+    merge_options: &MergeOptions,
+    ours: &[String],
+    base: &[String],
+    theirs: &[String],
+) {
+    lines.push(format!("<<<<<<< {}", merge_options.ours_label));
+    lines.extend_from_slice(ours);
+    if merge_options.conflict_style == ConflictStyle::Diff3 {
+        lines.push(format!("||||||| {}", merge_options.base_label));
+        lines.extend_from_slice(base);
+    }
+    lines.push("=======".to_string());
+    lines.extend_from_slice(theirs);
+    lines.push(format!(">>>>>>> {}", merge_options.theirs_label));
+}
+
+fn hunks_overlap(a: &LineChange, b: &LineChange) -> bool {
+    // Touching hunks count too, matching git's refusal to merge adjacent edits
+    a.original_start <= b.original_end && b.original_start <= a.original_end
+}
+
+fn same_edit(a: &LineChange, b: &LineChange, ours: &[String], theirs: &[String]) -> bool {
+    a.original_start == b.original_start
+        && a.original_end == b.original_end
+        && ours[a.modified_start..a.modified_end] == theirs[b.modified_start..b.modified_end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_disjoint_edits_do_not_conflict() {
+        let base = lines("a\nb\nc\nd\ne");
+        let ours = lines("A\nb\nc\nd\ne");
+        let theirs = lines("a\nb\nc\nd\nE");
+
+        assert!(predict_conflicts(&base, &ours, &theirs, DiffOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_overlapping_edits_conflict() {
+        let base = lines("a\nb\nc");
+        let ours = lines("a\nB1\nc");
+        let theirs = lines("a\nB2\nc");
+
+        let conflicts = predict_conflicts(&base, &ours, &theirs, DiffOptions::default());
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].ours.original_start, 1);
+    }
+
+    #[test]
+    fn test_identical_edits_do_not_conflict() {
+        let base = lines("a\nb\nc");
+        let ours = lines("a\nB\nc");
+
+        assert!(predict_conflicts(&base, &ours, &ours, DiffOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_merge_applies_both_sides() {
+        let base = lines("a\nb\nc\nd\ne");
+        let ours = lines("A\nb\nc\nd\ne");
+        let theirs = lines("a\nb\nc\nd\ne\nf");
+
+        let result = merge_three_way(&base, &ours, &theirs, DiffOptions::default(), &MergeOptions::default());
+        assert_eq!(result.conflict_count, 0);
+        assert_eq!(result.lines, lines("A\nb\nc\nd\ne\nf"));
+    }
+
+    #[test]
+    fn test_two_way_merge_marks_every_difference() {
+        let ours = lines("a\nb\nc\nd");
+        let theirs = lines("a\nB\nc\nd\ne");
+        let result = merge_two_way(&ours, &theirs, DiffOptions::default(), &MergeOptions::default());
+        assert_eq!(result.conflict_count, 2);
+        assert_eq!(
+            result.lines.join("\n"),
+            "a\n<<<<<<< ours\nb\n=======\nB\n>>>>>>> theirs\nc\nd\n<<<<<<< ours\n=======\ne\n>>>>>>> theirs"
+        );
+    }
+
+    #[test]
+    fn test_diff3_conflict_includes_base() {
+        let base = lines("a\nb\nc");
+        let ours = lines("a\nB1\nc");
+        let theirs = lines("a\nB2\nc");
+        let merge_options = MergeOptions {
+            conflict_style: ConflictStyle::Diff3,
+            ..MergeOptions::default()
+        };
+
+        let result = merge_three_way(&base, &ours, &theirs, DiffOptions::default(), &merge_options);
+        assert_eq!(result.conflict_count, 1);
+        assert_eq!(
+            result.lines,
+            lines("a\n<<<<<<< ours\nB1\n||||||| base\nb\n=======\nB2\n>>>>>>> theirs\nc")
+        );
+
+        let plain = merge_three_way(&base, &ours, &theirs, DiffOptions::default(), &MergeOptions::default());
+        assert!(!plain.lines.iter().any(|line| line.starts_with("|||||||")));
+    }
+}
@@ -0,0 +1,502 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::Path;
+use crate::file_handler::read_file_lines;
+use crate::i18n::{parse_translations, TranslationFormat};
+
+/// A user-facing message shared across formatters and commands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message {
+    FilesIdentical,
+    /// A directory entry whose few changes are all whitespace
+    EffectivelyIdentical,
+    KeysIdentical,
+    /// `{0} -> {1}: {2} hunk(s), ...` for one file pair
+    Stat,
+    /// Totals under a multi-file diffstat
+    StatTotals,
+    LinesAdded,
+    LinesDeleted,
+    LinesModified,
+    CharacterChanges,
+    GeneratedCollapsed,
+    ReadFailed,
+    ReadAtRevisionFailed,
+    CompareFailed,
+    UnknownCommand,
+    /// `Usage: {0}` with a command's syntax
+    Usage,
+    NeedsWorktree,
+    UnknownProfile,
+    UnknownMode,
+    UnknownChangeTag,
+    UnknownNestedRepoMode,
+    UnknownOutputFormat,
+    UnknownLineEndings,
+    InvalidRatio,
+    InvalidDays,
+    InvalidLineNumberBase,
+    NoBackup,
+    NoCodeowners,
+    NoHunkWithId,
+    NoHunkId,
+    HunkRows,
+    NoSession,
+    NoActiveComparison,
+    SessionChanged,
+    NoTrace,
+    NoPatchOpen,
+    NoPatchHunk,
+    NoFileDiffs,
+    NoPaths,
+    NothingToCommit,
+    /// An imported review bundle whose hunks this build would not reproduce
+    BundleDiffers,
+    WriteFailed,
+    ExtractFailed,
+    CheckpointFailed,
+    WorktreeStatusFailed,
+    BlameFailed,
+    SarifFailed,
+    CompareBranchesFailed,
+    HistoryFailed,
+    PredictConflictsFailed,
+    /// Page labels
+    DiagnosticsLabel,
+    HealthLabel,
+    ComparisonTargetsLabel,
+    DiffLabel,
+    WordsLabel,
+    HunkLabel,
+    DirectoriesLabel,
+    WorktreeLabel,
+    RiskLabel,
+    OwnersLabel,
+    TestsLabel,
+    CrossFileMovesLabel,
+    CompareWith,
+    BackupsOf,
+    ConflictCopiesOf,
+    /// Marks the listed candidate being compared
+    Shown,
+    OutsideWorktree,
+    ChurnRecordFailed,
+    InvalidHunkRows,
+    FormattedView,
+    MinifiedInput,
+    NoCommitsInRange,
+    NoConflictsExpected,
+    WorktreeClean,
+    BinaryFilesDiffer,
+    SubmoduleChanged,
+    LastChangedBy,
+    BranchChangesHeading,
+    BranchTipsHeading,
+    SessionOwned,
+    UnknownToggle,
+    NoHunkAtIndex,
+    NoAnnotation,
+    InvalidToolArguments,
+    ToolNeedsString,
+    UnknownTool,
+}
+
+impl Message {
+    pub const ALL: [Message; 85] = [
+        Message::FilesIdentical,
+        Message::EffectivelyIdentical,
+        Message::KeysIdentical,
+        Message::Stat,
+        Message::StatTotals,
+        Message::LinesAdded,
+        Message::LinesDeleted,
+        Message::LinesModified,
+        Message::CharacterChanges,
+        Message::GeneratedCollapsed,
+        Message::ReadFailed,
+        Message::ReadAtRevisionFailed,
+        Message::CompareFailed,
+        Message::UnknownCommand,
+        Message::Usage,
+        Message::NeedsWorktree,
+        Message::UnknownProfile,
+        Message::UnknownMode,
+        Message::UnknownChangeTag,
+        Message::UnknownNestedRepoMode,
+        Message::UnknownOutputFormat,
+        Message::UnknownLineEndings,
+        Message::InvalidRatio,
+        Message::InvalidDays,
+        Message::InvalidLineNumberBase,
+        Message::NoBackup,
+        Message::NoCodeowners,
+        Message::NoHunkWithId,
+        Message::NoHunkId,
+        Message::HunkRows,
+        Message::NoSession,
+        Message::NoActiveComparison,
+        Message::SessionChanged,
+        Message::NoTrace,
+        Message::NoPatchOpen,
+        Message::NoPatchHunk,
+        Message::NoFileDiffs,
+        Message::NoPaths,
+        Message::NothingToCommit,
+        Message::BundleDiffers,
+        Message::WriteFailed,
+        Message::ExtractFailed,
+        Message::CheckpointFailed,
+        Message::WorktreeStatusFailed,
+        Message::BlameFailed,
+        Message::SarifFailed,
+        Message::CompareBranchesFailed,
+        Message::HistoryFailed,
+        Message::PredictConflictsFailed,
+        Message::DiagnosticsLabel,
+        Message::HealthLabel,
+        Message::ComparisonTargetsLabel,
+        Message::DiffLabel,
+        Message::WordsLabel,
+        Message::HunkLabel,
+        Message::DirectoriesLabel,
+        Message::WorktreeLabel,
+        Message::RiskLabel,
+        Message::OwnersLabel,
+        Message::TestsLabel,
+        Message::CrossFileMovesLabel,
+        Message::CompareWith,
+        Message::BackupsOf,
+        Message::ConflictCopiesOf,
+        Message::Shown,
+        Message::OutsideWorktree,
+        Message::ChurnRecordFailed,
+        Message::InvalidHunkRows,
+        Message::FormattedView,
+        Message::MinifiedInput,
+        Message::NoCommitsInRange,
+        Message::NoConflictsExpected,
+        Message::WorktreeClean,
+        Message::BinaryFilesDiffer,
+        Message::SubmoduleChanged,
+        Message::LastChangedBy,
+        Message::BranchChangesHeading,
+        Message::BranchTipsHeading,
+        Message::SessionOwned,
+        Message::UnknownToggle,
+        Message::NoHunkAtIndex,
+        Message::NoAnnotation,
+        Message::InvalidToolArguments,
+        Message::ToolNeedsString,
+        Message::UnknownTool,
+    ];
+
+    /// The key translation files use for this message
+    pub fn key(self) -> &'static str {
+        match self {
+            Message::FilesIdentical => "files_identical",
+            Message::EffectivelyIdentical => "effectively_identical",
+            Message::KeysIdentical => "keys_identical",
+            Message::Stat => "stat",
+            Message::StatTotals => "stat_totals",
+            Message::LinesAdded => "lines_added",
+            Message::LinesDeleted => "lines_deleted",
+            Message::LinesModified => "lines_modified",
+            Message::CharacterChanges => "character_changes",
+            Message::GeneratedCollapsed => "generated_collapsed",
+            Message::ReadFailed => "read_failed",
+            Message::ReadAtRevisionFailed => "read_at_revision_failed",
+            Message::CompareFailed => "compare_failed",
+            Message::UnknownCommand => "unknown_command",
+            Message::Usage => "usage",
+            Message::NeedsWorktree => "needs_worktree",
+            Message::UnknownProfile => "unknown_profile",
+            Message::UnknownMode => "unknown_mode",
+            Message::UnknownChangeTag => "unknown_change_tag",
+            Message::UnknownNestedRepoMode => "unknown_nested_repo_mode",
+            Message::UnknownOutputFormat => "unknown_output_format",
+            Message::UnknownLineEndings => "unknown_line_endings",
+            Message::InvalidRatio => "invalid_ratio",
+            Message::InvalidDays => "invalid_days",
+            Message::InvalidLineNumberBase => "invalid_line_number_base",
+            Message::NoBackup => "no_backup",
+            Message::NoCodeowners => "no_codeowners",
+            Message::NoHunkWithId => "no_hunk_with_id",
+            Message::NoHunkId => "no_hunk_id",
+            Message::HunkRows => "hunk_rows",
+            Message::NoSession => "no_session",
+            Message::NoActiveComparison => "no_active_comparison",
+            Message::SessionChanged => "session_changed",
+            Message::NoTrace => "no_trace",
+            Message::NoPatchOpen => "no_patch_open",
+            Message::NoPatchHunk => "no_patch_hunk",
+            Message::NoFileDiffs => "no_file_diffs",
+            Message::NoPaths => "no_paths",
+            Message::NothingToCommit => "nothing_to_commit",
+            Message::BundleDiffers => "bundle_differs",
+            Message::WriteFailed => "write_failed",
+            Message::ExtractFailed => "extract_failed",
+            Message::CheckpointFailed => "checkpoint_failed",
+            Message::WorktreeStatusFailed => "worktree_status_failed",
+            Message::BlameFailed => "blame_failed",
+            Message::SarifFailed => "sarif_failed",
+            Message::CompareBranchesFailed => "compare_branches_failed",
+            Message::HistoryFailed => "history_failed",
+            Message::PredictConflictsFailed => "predict_conflicts_failed",
+            Message::DiagnosticsLabel => "diagnostics_label",
+            Message::HealthLabel => "health_label",
+            Message::ComparisonTargetsLabel => "comparison_targets_label",
+            Message::DiffLabel => "diff_label",
+            Message::WordsLabel => "words_label",
+            Message::HunkLabel => "hunk_label",
+            Message::DirectoriesLabel => "directories_label",
+            Message::WorktreeLabel => "worktree_label",
+            Message::RiskLabel => "risk_label",
+            Message::OwnersLabel => "owners_label",
+            Message::TestsLabel => "tests_label",
+            Message::CrossFileMovesLabel => "cross_file_moves_label",
+            Message::CompareWith => "compare_with",
+            Message::BackupsOf => "backups_of",
+            Message::ConflictCopiesOf => "conflict_copies_of",
+            Message::Shown => "shown",
+            Message::OutsideWorktree => "outside_worktree",
+            Message::ChurnRecordFailed => "churn_record_failed",
+            Message::InvalidHunkRows => "invalid_hunk_rows",
+            Message::FormattedView => "formatted_view",
+            Message::MinifiedInput => "minified_input",
+            Message::NoCommitsInRange => "no_commits_in_range",
+            Message::NoConflictsExpected => "no_conflicts_expected",
+            Message::WorktreeClean => "worktree_clean",
+            Message::BinaryFilesDiffer => "binary_files_differ",
+            Message::SubmoduleChanged => "submodule_changed",
+            Message::LastChangedBy => "last_changed_by",
+            Message::BranchChangesHeading => "branch_changes_heading",
+            Message::BranchTipsHeading => "branch_tips_heading",
+            Message::SessionOwned => "session_owned",
+            Message::UnknownToggle => "unknown_toggle",
+            Message::NoHunkAtIndex => "no_hunk_at_index",
+            Message::NoAnnotation => "no_annotation",
+            Message::InvalidToolArguments => "invalid_tool_arguments",
+            Message::ToolNeedsString => "tool_needs_string",
+            Message::UnknownTool => "unknown_tool",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|message| message.key() == key)
+    }
+
+    /// The built-in English text; `{0}`, `{1}`, ... stand for the arguments
+    pub fn english(self) -> &'static str {
+        match self {
+            Message::FilesIdentical => "Files are identical",
+            Message::EffectivelyIdentical => "effectively identical ({0} trivial difference(s))",
+            Message::KeysIdentical => "All keys and values are identical",
+            Message::Stat => "{0} -> {1}: {2} hunk(s), {3} insertion(s)(+), {4} deletion(s)(-)",
+            Message::StatTotals => "{0} file(s) changed, {1} insertion(s)(+), {2} deletion(s)(-), churn {3}",
+            Message::LinesAdded => "+{0} line(s) added",
+            Message::LinesDeleted => "-{0} line(s) deleted",
+            Message::LinesModified => "~{0} line(s) modified",
+            Message::CharacterChanges => "({0} character-level changes)",
+            Message::GeneratedCollapsed => "(generated code, collapsed)",
+            Message::ReadFailed => "Failed to read {0}: {1}",
+            Message::ReadAtRevisionFailed => "Failed to read {0} at {1}: {2}",
+            Message::CompareFailed => "Failed to compare files: {0}",
+            Message::UnknownCommand => "unknown slash command: \"{0}\"",
+            Message::Usage => "Usage: {0}",
+            Message::NeedsWorktree => "{0} needs an open worktree",
+            Message::UnknownProfile => "Unknown profile: {0}",
+            Message::UnknownMode => "Unknown mode: {0}",
+            Message::UnknownChangeTag => "Unknown change tag: {0}",
+            Message::UnknownNestedRepoMode => "Unknown nested repository mode: {0}",
+            Message::UnknownOutputFormat => "Unknown output format {0}",
+            Message::UnknownLineEndings => "Unknown line endings {0}",
+            Message::InvalidRatio => "Invalid ratio: {0}",
+            Message::InvalidDays => "Invalid number of days: {0}",
+            Message::InvalidLineNumberBase => "Line numbers start at 0 or 1, not {0}",
+            Message::NoBackup => "No backup #{0} found for {1} ({2} found)",
+            Message::NoCodeowners => "No CODEOWNERS file in {0}",
+            Message::NoHunkWithId => "No hunk with id {0}",
+            Message::NoHunkId => "No hunk ID in {0}",
+            Message::HunkRows => "Hunk {0} has rows 1-{1}",
+            Message::NoSession => "No session {0}",
+            Message::NoActiveComparison => "No active comparison",
+            Message::SessionChanged => "Session {0} changed options meanwhile; toggle {1} again",
+            Message::NoTrace => "No comparison has been traced yet",
+            Message::NoPatchOpen => "No patch is open",
+            Message::NoPatchHunk => "No hunk {0} in file {1}",
+            Message::NoFileDiffs => "No file diffs found in the patch",
+            Message::NoPaths => "No paths to compare",
+            Message::NothingToCommit => "Nothing to commit",
+            Message::BundleDiffers => "Note: this build computes a different diff; showing the bundled one",
+            Message::WriteFailed => "Failed to write {0}: {1}",
+            Message::ExtractFailed => "Failed to extract {0}: {1}",
+            Message::CheckpointFailed => "Failed to checkpoint {0}: {1}",
+            Message::WorktreeStatusFailed => "Failed to read the worktree status: {0}",
+            Message::BlameFailed => "Failed to blame {0}: {1}",
+            Message::SarifFailed => "Failed to serialize SARIF: {0}",
+            Message::CompareBranchesFailed => "Failed to compare branches: {0}",
+            Message::HistoryFailed => "Failed to read file history: {0}",
+            Message::PredictConflictsFailed => "Failed to predict conflicts: {0}",
+            Message::DiagnosticsLabel => "Diagnostics",
+            Message::HealthLabel => "Extension health",
+            Message::ComparisonTargetsLabel => "Comparison targets",
+            Message::DiffLabel => "Diff {0} ↔ {1}",
+            Message::WordsLabel => "Words {0} ↔ {1}",
+            Message::HunkLabel => "Hunk {0} of {1} ↔ {2}",
+            Message::DirectoriesLabel => "Directories {0} ↔ {1}",
+            Message::WorktreeLabel => "Worktree vs HEAD",
+            Message::RiskLabel => "Risk",
+            Message::OwnersLabel => "Owners",
+            Message::TestsLabel => "Tests",
+            Message::CrossFileMovesLabel => "Moved between files",
+            Message::CompareWith => "Compare {0} with one of:",
+            Message::BackupsOf => "Backups of {0}, newest first:",
+            Message::ConflictCopiesOf => "Conflicted copies of {0}, newest first:",
+            Message::Shown => "(shown)",
+            Message::OutsideWorktree => "{0} is outside the worktree",
+            Message::ChurnRecordFailed => "Failed to record churn in {0}: {1}",
+            Message::InvalidHunkRows => "Expected <hunk>:<first>-<last>, got {0}",
+            Message::FormattedView => "Formatted view ({0}): both inputs were re-indented before diffing",
+            Message::MinifiedInput => "Minified input, compared as {0} and {1} synthetic lines",
+            Message::NoCommitsInRange => "No commits touch {0} in this range",
+            Message::NoConflictsExpected => "No conflicts expected",
+            Message::WorktreeClean => "Working tree clean",
+            Message::BinaryFilesDiffer => "Binary files {0} and {1} differ",
+            Message::SubmoduleChanged => "Submodule {0} changed",
+            Message::LastChangedBy => "Last changed at HEAD by:",
+            Message::BranchChangesHeading => "Changes on branch since merge base",
+            Message::BranchTipsHeading => "Direct comparison of branch tips",
+            Message::SessionOwned => "Session {0} belongs to {1}",
+            Message::UnknownToggle => "Unknown option {0}; expected one of {1}",
+            Message::NoHunkAtIndex => "No hunk at index {0}",
+            Message::NoAnnotation => "No annotation with id {0}",
+            Message::InvalidToolArguments => "Invalid arguments for {0}: {1}",
+            Message::ToolNeedsString => "{0} needs a string argument \"{1}\"",
+            Message::UnknownTool => "Unknown tool: {0}",
+        }
+    }
+
+    fn arguments(self) -> usize {
+        (0..).take_while(|i| self.english().contains(&format!("{{{}}}", i))).count()
+    }
+}
+
+/// Message texts for one locale; messages it lacks are shown in English
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Catalog {
+    pub locale: String,
+    pub messages: BTreeMap<String, String>,
+}
+
+impl Catalog {
+    pub fn english() -> Self {
+        Self { locale: "en".to_string(), messages: BTreeMap::new() }
+    }
+
+    /// Read a translation file in any format `i18n` understands, keyed by `Message::key`; the
+    /// locale is the file name without its extension. Unknown keys, and texts dropping an
+    /// argument the English one uses, are rejected.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let format =
+            TranslationFormat::for_path(path).ok_or_else(|| format!("Unsupported translation file: {}", path))?;
+        let lines = read_file_lines(path).map_err(|e| text(Message::ReadFailed, [&path, &e]))?;
+        let messages = parse_translations(format, &lines)?;
+        for (key, translated) in &messages {
+            let message = Message::from_key(key).ok_or_else(|| format!("Unknown message in {}: {}", path, key))?;
+            if let Some(i) = (0..message.arguments()).find(|i| !translated.contains(&format!("{{{}}}", i))) {
+                return Err(format!("Translation of {} in {} lacks {{{}}}", key, path, i));
+            }
+        }
+        let locale = Path::new(path).file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        Ok(Self { locale, messages: messages.into_iter().filter(|(_, translated)| !translated.is_empty()).collect() })
+    }
+
+    pub fn template(&self, message: Message) -> &str {
+        self.messages.get(message.key()).map(String::as_str).unwrap_or(message.english())
+    }
+}
+
+thread_local! {
+    /// The catalog of the command running; English when none is
+    static CURRENT: RefCell<Option<Catalog>> = const { RefCell::new(None) };
+}
+
+/// Restores English when dropped, whichever way the command ends
+pub struct CatalogGuard(());
+
+impl Drop for CatalogGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.borrow_mut().take());
+    }
+}
+
+/// Show messages from `catalog` until the guard is dropped
+pub fn activate(catalog: Catalog) -> CatalogGuard {
+    CURRENT.with(|current| *current.borrow_mut() = Some(catalog));
+    CatalogGuard(())
+}
+
+/// `message` in the active locale, with `{0}`, `{1}`, ... replaced by `args`
+pub fn text<const N: usize>(message: Message, args: [&dyn Display; N]) -> String {
+    CURRENT.with(|current| {
+        let current = current.borrow();
+        let template = match current.as_ref() {
+            Some(catalog) => catalog.template(message),
+            None => message.english(),
+        };
+        // One pass over the template, so an argument that itself contains `{1}` stays as it is
+        let mut output = String::new();
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            output.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            let placeholder = after.find('}').and_then(|close| Some((close, after[..close].parse::<usize>().ok()?)));
+            match placeholder {
+                Some((close, i)) if i < N => {
+                    output.push_str(&args[i].to_string());
+                    rest = &after[close + 1..];
+                }
+                _ => {
+                    output.push('{');
+                    rest = after;
+                }
+            }
+        }
+        output.push_str(rest);
+        output
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_catalog() {
+        assert_eq!(text(Message::CompareFailed, [&"timed out"]), "Failed to compare files: timed out");
+        assert_eq!(text(Message::ReadFailed, [&"{1}.txt", &"gone"]), "Failed to read {1}.txt: gone");
+        assert!(Message::ALL.iter().all(|message| Message::from_key(message.key()) == Some(*message)));
+
+        let dir = TempDir::new("messages");
+        let french = dir.join("fr.json");
+        fs::write(&french, r#"{"files_identical": "Fichiers identiques", "lines_added": "+{0} ligne(s) ajoutée(s)"}"#)
+            .unwrap();
+        let catalog = Catalog::load(&french.to_string_lossy()).unwrap();
+        assert_eq!(catalog.locale, "fr");
+        {
+            let _messages = activate(catalog);
+            assert_eq!(text(Message::LinesAdded, [&3]), "+3 ligne(s) ajoutée(s)");
+            assert_eq!(text(Message::LinesDeleted, [&2]), "-2 line(s) deleted");
+        }
+        assert_eq!(text(Message::FilesIdentical, []), "Files are identical");
+
+        let broken = dir.join("de.properties");
+        fs::write(&broken, "lines_added=Zeilen hinzugefügt\n").unwrap();
+        assert!(Catalog::load(&broken.to_string_lossy()).unwrap_err().contains("lacks {0}"));
+        fs::write(&broken, "hello=Hallo\n").unwrap();
+        assert!(Catalog::load(&broken.to_string_lossy()).unwrap_err().contains("Unknown message"));
+    }
+}
@@ -0,0 +1,89 @@
+/// A line at least this long that holds nearly all of a file's text marks it as minified
+pub const MINIFIED_LINE_LENGTH: usize = 2000;
+
+/// Synthetic lines longer than this are cut into chunks of this many characters
+const MAX_CHUNK_CHARS: usize = 200;
+
+/// Whether `lines` is effectively one enormous line: minified JS/CSS, single-line JSON and the like
+pub fn is_minified(lines: &[String]) -> bool {
+    let total: usize = lines.iter().map(|line| line.chars().count()).sum();
+    let longest = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    longest >= MINIFIED_LINE_LENGTH && longest * 10 >= total * 9
+}
+
+/// Re-break minified text into synthetic lines: after `;`, `,`, `{` and `[`, and before `}` and
+/// `]`, never inside a string literal, with any remaining overlong run cut into fixed chunks.
+/// Lines are trimmed, so a re-indented copy of the same code splits the same way.
+pub fn split_minified(lines: &[String]) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+
+    for line in lines {
+        let mut quote: Option<char> = None;
+        let mut escaped = false;
+        for c in line.chars() {
+            if let Some(open) = quote {
+                current.push(c);
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == open {
+                    quote = None;
+                }
+                continue;
+            }
+            match c {
+                '"' | '\'' | '`' => {
+                    quote = Some(c);
+                    current.push(c);
+                }
+                ';' | ',' | '{' | '[' => {
+                    current.push(c);
+                    flush(&mut result, &mut current);
+                }
+                '}' | ']' => {
+                    flush(&mut result, &mut current);
+                    current.push(c);
+                }
+                _ => current.push(c),
+            }
+        }
+        flush(&mut result, &mut current);
+    }
+    result
+}
+
+/// Move `current`, trimmed and chunked, into `result`
+fn flush(result: &mut Vec<String>, current: &mut String) {
+    let chars: Vec<char> = current.trim().chars().collect();
+    result.extend(chars.chunks(MAX_CHUNK_CHARS).map(|chunk| chunk.iter().collect::<String>()));
+    current.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_split_minified() {
+        let split = split_minified(&lines(r#"var a={b:"x;y",c:[1,2]};f(a);"#));
+        assert_eq!(split, vec!["var a={", "b:\"x;y\",", "c:[", "1,", "2", "]", "};", "f(a);"]);
+        assert_eq!(split_minified(&["x".repeat(450)]).iter().map(String::len).collect::<Vec<_>>(), vec![200, 200, 50]);
+    }
+
+    #[test]
+    fn test_minified_files_diff_locally() {
+        let original: String = (0..500).map(|i| format!("function f{}(){{return {};}}", i, i)).collect();
+        let modified = original.replacen("return 250;", "return 251;", 1);
+        let (original, modified) = (vec![original], vec![modified]);
+        assert!(is_minified(&original) && !is_minified(&lines("short\nfile")));
+
+        let (original, modified) = (split_minified(&original), split_minified(&modified));
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(modified[changes[0].modified_start], "return 251;");
+    }
+}
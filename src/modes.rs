@@ -0,0 +1,184 @@
+use crate::delimited::{compare_delimited, detect_delimiter, format_delimited_diff, is_delimited_path};
+use crate::diff_core::{compute_diff, DiffOptions};
+use crate::file_handler::read_file_lines;
+use crate::i18n::{compare_translations, format_translation_report, parse_translations, TranslationFormat};
+use crate::keyvalue::{compare_config, format_config_diff};
+use crate::lockfile::{compare_lockfiles, format_lockfile_diff, parse_lockfile, LockfileKind};
+use crate::manifest::{compare_dependencies, format_dependency_diff, parse_manifest, ManifestKind};
+use crate::openapi::{compare_openapi, format_openapi_diff, is_openapi_path, parse_spec};
+use crate::proto::{compare_proto, format_proto_diff, parse_proto};
+use crate::rust_api::{compare_api, format_api_diff, parse_api_items};
+use crate::sqldump::{compare_sql_dumps, format_sql_dump_diff, parse_sql_dump};
+use crate::structure::{format_structural_diff, outline, structural_diff};
+use crate::ui::format_unified_diff;
+use crate::unordered::{
+    compare_unordered, format_frequency_report, format_unordered_diff, frequency_report,
+};
+
+/// How two files are compared
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompareMode {
+    /// Positional line diff
+    Lines,
+    /// Lines as a multiset, order ignored
+    Sorted,
+    /// `.env` / INI / properties files as key=value pairs
+    KeyValue,
+    /// Per-line occurrence counts
+    Frequency,
+    /// Functions and classes matched by name
+    Structure,
+    /// Public items of Rust sources: signatures, visibility, additions and removals
+    RustApi,
+    /// Dependencies of Cargo.toml / package.json: added, removed, upgraded, downgraded
+    Manifest,
+    /// Locked package versions of Cargo.lock, package-lock.json or yarn.lock
+    Lockfile,
+    /// TSV/CSV with column insertions and removals detected from the header row
+    Columns,
+    /// SQL dumps grouped by table, INSERT rows compared ignoring order
+    SqlDump,
+    /// Protocol Buffers schemas: field additions, removals and renumbering, with
+    /// wire-incompatible changes flagged as warnings
+    Proto,
+    /// OpenAPI/Swagger specs: endpoints, parameters and response schemas, with breaking changes flagged
+    OpenApi,
+    /// Localization resources keyed by message: missing, extra and untranslated keys
+    Translations,
+}
+
+impl CompareMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lines" => Some(CompareMode::Lines),
+            "sorted" => Some(CompareMode::Sorted),
+            "keyvalue" => Some(CompareMode::KeyValue),
+            "frequency" => Some(CompareMode::Frequency),
+            "structure" => Some(CompareMode::Structure),
+            "api" => Some(CompareMode::RustApi),
+            "manifest" => Some(CompareMode::Manifest),
+            "lockfile" => Some(CompareMode::Lockfile),
+            "columns" => Some(CompareMode::Columns),
+            "sql" => Some(CompareMode::SqlDump),
+            "proto" => Some(CompareMode::Proto),
+            "openapi" => Some(CompareMode::OpenApi),
+            "i18n" => Some(CompareMode::Translations),
+            _ => None,
+        }
+    }
+
+    /// The mode used when none is requested, chosen by file name
+    pub fn for_path(path: &str) -> Self {
+        if ManifestKind::for_path(path).is_some() {
+            CompareMode::Manifest
+        } else if LockfileKind::for_path(path).is_some() {
+            CompareMode::Lockfile
+        } else if is_delimited_path(path) {
+            CompareMode::Columns
+        } else if path.ends_with(".proto") {
+            CompareMode::Proto
+        } else if is_openapi_path(path) {
+            CompareMode::OpenApi
+        } else if TranslationFormat::for_path(path) == Some(TranslationFormat::Po) {
+            // JSON and properties bundles are only compared as translations on request
+            CompareMode::Translations
+        } else {
+            CompareMode::Lines
+        }
+    }
+}
+
+/// Compare two files with the given mode and return the formatted report
+pub fn compare_files_with_mode(
+    mode: CompareMode,
+    file1_path: &str,
+    file2_path: &str,
+    options: &DiffOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let lines1 = read_file_lines(file1_path)?;
+    let lines2 = read_file_lines(file2_path)?;
+
+    Ok(match mode {
+        CompareMode::Lines => {
+            let changes = compute_diff(&lines1, &lines2, options.clone());
+            format_unified_diff(file1_path, file2_path, &changes)
+        }
+        CompareMode::Sorted => {
+            let diff = compare_unordered(&lines1, &lines2, options);
+            format_unordered_diff(file1_path, file2_path, &diff)
+        }
+        CompareMode::KeyValue => {
+            let changes = compare_config(&lines1, &lines2);
+            format_config_diff(file1_path, file2_path, &changes)
+        }
+        CompareMode::Frequency => {
+            let report = frequency_report(&lines1, &lines2, options);
+            format_frequency_report(file1_path, file2_path, &report)
+        }
+        CompareMode::Structure => {
+            let changes = structural_diff(&outline(&lines1), &outline(&lines2), &lines1, &lines2, options);
+            format_structural_diff(file1_path, file2_path, &changes)
+        }
+        CompareMode::RustApi => {
+            let changes = compare_api(&parse_api_items(&lines1), &parse_api_items(&lines2));
+            format_api_diff(file1_path, file2_path, &changes)
+        }
+        CompareMode::Manifest => {
+            let kind = ManifestKind::for_path(file1_path)
+                .or_else(|| ManifestKind::for_path(file2_path))
+                .ok_or("manifest mode needs a Cargo.toml or package.json")?;
+            let changes = compare_dependencies(&parse_manifest(kind, &lines1)?, &parse_manifest(kind, &lines2)?);
+            format_dependency_diff(file1_path, file2_path, &changes)
+        }
+        CompareMode::Lockfile => {
+            let kind = LockfileKind::for_path(file1_path)
+                .or_else(|| LockfileKind::for_path(file2_path))
+                .ok_or("lockfile mode needs a Cargo.lock, package-lock.json or yarn.lock")?;
+            let changes = compare_lockfiles(&parse_lockfile(kind, &lines1)?, &parse_lockfile(kind, &lines2)?);
+            format_lockfile_diff(file1_path, file2_path, &changes)
+        }
+        CompareMode::Columns => {
+            let header = lines1.first().map(String::as_str).unwrap_or_default();
+            let delimiter = detect_delimiter(file1_path, header);
+            let diff = compare_delimited(&lines1, &lines2, delimiter, options);
+            format_delimited_diff(file1_path, file2_path, &diff)
+        }
+        CompareMode::SqlDump => {
+            let changes = compare_sql_dumps(&parse_sql_dump(&lines1), &parse_sql_dump(&lines2), options);
+            format_sql_dump_diff(file1_path, file2_path, &changes)
+        }
+        CompareMode::Proto => {
+            let changes = compare_proto(&parse_proto(&lines1), &parse_proto(&lines2));
+            format_proto_diff(file1_path, file2_path, &changes)
+        }
+        CompareMode::OpenApi => {
+            let changes = compare_openapi(&parse_spec(&lines1)?, &parse_spec(&lines2)?);
+            format_openapi_diff(file1_path, file2_path, &changes)
+        }
+        CompareMode::Translations => {
+            let format = TranslationFormat::for_path(file1_path)
+                .or_else(|| TranslationFormat::for_path(file2_path))
+                .ok_or("i18n mode needs .json, .po or .properties files")?;
+            let report = compare_translations(&parse_translations(format, &lines1)?, &parse_translations(format, &lines2)?);
+            format_translation_report(file1_path, file2_path, &report)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_names() {
+        assert_eq!(CompareMode::from_name("sorted"), Some(CompareMode::Sorted));
+        assert_eq!(CompareMode::from_name("lines"), Some(CompareMode::Lines));
+        assert!(CompareMode::from_name("bogus").is_none());
+        assert_eq!(CompareMode::for_path("crates/a/Cargo.toml"), CompareMode::Manifest);
+        assert_eq!(CompareMode::for_path("src/main.rs"), CompareMode::Lines);
+        assert_eq!(CompareMode::for_path("yarn.lock"), CompareMode::Lockfile);
+        assert_eq!(CompareMode::for_path("api/v1/order.proto"), CompareMode::Proto);
+        assert_eq!(CompareMode::for_path("docs/openapi.yaml"), CompareMode::OpenApi);
+        assert_eq!(CompareMode::for_path("locale/fr.po"), CompareMode::Translations);
+    }
+}
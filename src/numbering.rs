@@ -0,0 +1,112 @@
+use zed_extension_api::serde_json::Value;
+use crate::messages::{text, Message};
+
+/// How formatters number lines: the number of the first line, the width of the gutter, and
+/// whether each line also shows the byte offset it starts at
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineNumbering {
+    /// 0 or 1
+    pub base: usize,
+    /// Gutter width in characters; 0 fits the largest number shown
+    pub width: usize,
+    pub byte_offsets: bool,
+}
+
+impl Default for LineNumbering {
+    fn default() -> Self {
+        Self { base: 1, width: 0, byte_offsets: false }
+    }
+}
+
+impl LineNumbering {
+    pub fn from_json(value: &Value) -> Self {
+        let default = Self::default();
+        Self {
+            base: value.get("base").and_then(Value::as_u64).map_or(default.base, |n| n as usize),
+            width: value.get("width").and_then(Value::as_u64).map_or(default.width, |n| n as usize),
+            byte_offsets: value.get("byte_offsets").and_then(Value::as_bool).unwrap_or(default.byte_offsets),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self.base {
+            0 | 1 => Ok(()),
+            base => Err(text(Message::InvalidLineNumberBase, [&base])),
+        }
+    }
+
+    /// The number shown for the line at `index`
+    pub fn number(&self, index: usize) -> usize {
+        index + self.base
+    }
+}
+
+/// Gutters for the lines of two files, all of one width so the text after them lines up
+pub struct Gutter<'a> {
+    numbering: &'a LineNumbering,
+    offsets: [Vec<usize>; 2],
+    number_width: usize,
+    offset_width: usize,
+}
+
+impl<'a> Gutter<'a> {
+    pub fn new(numbering: &'a LineNumbering, original: &[String], modified: &[String]) -> Self {
+        let offsets = [line_offsets(original), line_offsets(modified)];
+        let largest = numbering.number(original.len().max(modified.len()));
+        let number_width = if numbering.width > 0 { numbering.width } else { largest.to_string().len() };
+        let offset_width = offsets.iter().flat_map(|o| o.last()).max().map_or(1, |n| n.to_string().len());
+        Self { numbering, offsets, number_width, offset_width }
+    }
+
+    /// The gutter of line `index` of the original (`side` 0) or modified (`side` 1) file, or a
+    /// blank one for `None`
+    pub fn format(&self, side: usize, index: Option<usize>) -> String {
+        let (number, offset) = match index {
+            Some(index) => (
+                self.numbering.number(index).to_string(),
+                self.offsets[side].get(index).map(|offset| format!("@{}", offset)).unwrap_or_default(),
+            ),
+            None => (String::new(), String::new()),
+        };
+        if self.numbering.byte_offsets {
+            format!("{:>nw$} {:<ow$} | ", number, offset, nw = self.number_width, ow = self.offset_width + 1)
+        } else {
+            format!("{:>nw$} | ", number, nw = self.number_width)
+        }
+    }
+}
+
+/// The byte each line starts at, counting one `\n` after every line, followed by the total length
+pub fn line_offsets(lines: &[String]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(lines.len() + 1);
+    let mut offset = 0;
+    for line in lines {
+        offsets.push(offset);
+        offset += line.len() + 1;
+    }
+    offsets.push(offset);
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::json;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_gutter() {
+        let original = lines("héllo\nworld");
+        assert_eq!(line_offsets(&original), vec![0, 7, 13]);
+
+        let default = LineNumbering::default();
+        let gutter = Gutter::new(&default, &original, &lines("a"));
+        assert_eq!(gutter.format(0, Some(1)), "2 | ");
+
+        let numbering = LineNumbering::from_json(&json!({ "base": 0, "width": 4, "byte_offsets": true }));
+        let gutter = Gutter::new(&numbering, &original, &lines("a"));
+        assert_eq!(gutter.format(0, Some(1)), "   1 @7  | ");
+        assert_eq!(gutter.format(1, None), "         | ");
+        assert!(LineNumbering { base: 2, ..numbering }.validate().is_err());
+    }
+}
@@ -0,0 +1,517 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde_json::{Map, Value};
+
+const METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Deepest YAML nesting accepted, far beyond what specs use; the parser recurses per level and
+/// the resulting value is dropped recursively, so unbounded depth could overflow a WASM stack
+const MAX_YAML_DEPTH: usize = 64;
+
+/// Arrays of arrays unwrapped before a schema type is reported as `any`; `$ref` cycles through
+/// `items` would otherwise never end
+const MAX_ARRAY_NESTING: usize = 32;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecChange {
+    /// Existing clients may fail against the new spec
+    pub breaking: bool,
+    /// Endpoint the change belongs to, e.g. `GET /users/{id}`
+    pub endpoint: String,
+    pub message: String,
+}
+
+/// `openapi.yaml`, `swagger.json` and similar names
+pub fn is_openapi_path(path: &str) -> bool {
+    let path = Path::new(path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    matches!(stem, "openapi" | "swagger") && matches!(extension, "json" | "yaml" | "yml")
+}
+
+/// JSON documents are parsed with serde_json, anything else as block-style YAML
+pub fn parse_spec(lines: &[String]) -> Result<Value, String> {
+    let text = lines.join("\n");
+    if text.trim_start().starts_with('{') {
+        serde_json::from_str(&text).map_err(|e| format!("invalid OpenAPI JSON: {}", e))
+    } else {
+        parse_yaml(lines)
+    }
+}
+
+/// The block-style YAML subset specs are written in: nested mappings, `-` sequences,
+/// plain and quoted scalars, `|`/`>` block scalars and single-line flow collections.
+/// Anchors, tags and multi-line flow collections are not supported.
+pub fn parse_yaml(lines: &[String]) -> Result<Value, String> {
+    let mut content: Vec<(usize, String)> = Vec::new();
+    for line in lines {
+        let code = strip_yaml_comment(line);
+        if code.trim().is_empty() || code.trim() == "---" {
+            // Blank lines are kept as markers so block scalars can preserve them
+            content.push((usize::MAX, String::new()));
+            continue;
+        }
+        if code.starts_with('\t') {
+            return Err("tabs are not allowed for YAML indentation".to_string());
+        }
+        let indent = code.len() - code.trim_start().len();
+        content.push((indent, code.trim().to_string()));
+    }
+
+    let mut pos = skip_blank(&content, 0);
+    if pos >= content.len() {
+        return Ok(Value::Null);
+    }
+    let indent = content[pos].0;
+    let value = parse_yaml_block(&mut content, &mut pos, indent, 0)?;
+    match content.get(skip_blank(&content, pos)) {
+        Some((_, text)) => Err(format!("unexpected YAML content: {}", text)),
+        None => Ok(value),
+    }
+}
+
+fn skip_blank(content: &[(usize, String)], mut pos: usize) -> usize {
+    while content.get(pos).is_some_and(|(indent, _)| *indent == usize::MAX) {
+        pos += 1;
+    }
+    pos
+}
+
+fn strip_yaml_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '#') if i == 0 || line[..i].ends_with(' ') => return line[..i].trim_end(),
+            _ => {}
+        }
+    }
+    line.trim_end()
+}
+
+fn parse_yaml_block(content: &mut [(usize, String)], pos: &mut usize, indent: usize, depth: usize) -> Result<Value, String> {
+    if depth > MAX_YAML_DEPTH {
+        return Err(format!("YAML nested deeper than {} levels", MAX_YAML_DEPTH));
+    }
+    let is_item = |text: &str| text == "-" || text.starts_with("- ");
+
+    if is_item(&content[*pos].1) {
+        let mut items = Vec::new();
+        loop {
+            *pos = skip_blank(content, *pos);
+            match content.get(*pos) {
+                Some((i, text)) if *i == indent && is_item(text) => {}
+                _ => break,
+            }
+            let rest = content[*pos].1[1..].trim_start().to_string();
+            if rest.is_empty() {
+                *pos += 1;
+                let next = skip_blank(content, *pos);
+                match content.get(next) {
+                    Some((i, _)) if *i > indent => {
+                        *pos = next;
+                        items.push(parse_yaml_block(content, pos, *i, depth + 1)?);
+                    }
+                    _ => items.push(Value::Null),
+                }
+            } else {
+                // Re-read `- key: value` as the first line of a block nested at the item's content column
+                let column = indent + content[*pos].1.len() - rest.len();
+                content[*pos] = (column, rest);
+                items.push(parse_yaml_block(content, pos, column, depth + 1)?);
+            }
+        }
+        return Ok(Value::Array(items));
+    }
+
+    let mut map = Map::new();
+    loop {
+        *pos = skip_blank(content, *pos);
+        match content.get(*pos) {
+            Some((i, _)) if *i == indent => {}
+            Some((i, text)) if *i > indent => return Err(format!("unexpected indentation: {}", text)),
+            _ => break,
+        }
+        let text = content[*pos].1.clone();
+        if is_item(&text) {
+            break;
+        }
+        let (key, value) = split_yaml_key(&text).ok_or_else(|| format!("expected `key: value`, found: {}", text))?;
+        *pos += 1;
+
+        let value = if value.is_empty() {
+            let next = skip_blank(content, *pos);
+            match content.get(next) {
+                // Sequences may sit at the same indentation as their key
+                Some((i, text)) if *i > indent || (*i == indent && is_item(text)) => {
+                    *pos = next;
+                    let i = *i;
+                    parse_yaml_block(content, pos, i, depth + 1)?
+                }
+                _ => Value::Null,
+            }
+        } else if value == "|" || value == ">" || value.starts_with("|-") || value.starts_with(">-") {
+            let mut block = Vec::new();
+            while content.get(*pos).is_some_and(|(i, _)| *i > indent) {
+                let (_, line) = &content[*pos];
+                block.push(line.clone());
+                *pos += 1;
+            }
+            while block.last().is_some_and(|l| l.is_empty()) {
+                block.pop();
+            }
+            let separator = if value.starts_with('>') { " " } else { "\n" };
+            Value::String(block.join(separator))
+        } else {
+            parse_yaml_scalar(value)
+        };
+        map.insert(key, value);
+    }
+    Ok(Value::Object(map))
+}
+
+fn split_yaml_key(text: &str) -> Option<(String, &str)> {
+    let (key, value) = if let Some(key) = text.strip_suffix(':') {
+        (key, "")
+    } else {
+        let (key, value) = text.split_once(": ")?;
+        (key, value.trim())
+    };
+    Some((unquote(key.trim()).to_string(), value))
+}
+
+fn unquote(text: &str) -> &str {
+    let quoted = text.len() >= 2
+        && ((text.starts_with('"') && text.ends_with('"')) || (text.starts_with('\'') && text.ends_with('\'')));
+    if quoted {
+        &text[1..text.len() - 1]
+    } else {
+        text
+    }
+}
+
+fn parse_yaml_scalar(text: &str) -> Value {
+    let text = text.trim();
+    if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        return Value::Array(
+            inner
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(parse_yaml_scalar)
+                .collect(),
+        );
+    }
+    if let Some(inner) = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+        return Value::Object(
+            inner
+                .split(',')
+                .filter_map(|pair| split_yaml_key(pair.trim()))
+                .map(|(key, value)| (key, parse_yaml_scalar(value)))
+                .collect(),
+        );
+    }
+    if text.starts_with('"') || text.starts_with('\'') {
+        return Value::String(unquote(text).to_string());
+    }
+    match text {
+        "" | "~" | "null" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => serde_json::from_str::<serde_json::Number>(text)
+            .map(Value::Number)
+            .unwrap_or_else(|_| Value::String(text.to_string())),
+    }
+}
+
+/// Endpoints, parameters and response schemas of two specs, each change classified as
+/// breaking or not from the point of view of an existing client
+pub fn compare_openapi(original: &Value, modified: &Value) -> Vec<SpecChange> {
+    let mut changes = Vec::new();
+    let (before, after) = (operations(original), operations(modified));
+
+    for (endpoint, old) in &before {
+        let mut push = |breaking, message: String| {
+            changes.push(SpecChange {
+                breaking,
+                endpoint: endpoint.clone(),
+                message,
+            })
+        };
+        let Some(new) = after.get(endpoint) else {
+            push(true, "endpoint removed".to_string());
+            continue;
+        };
+
+        let (old_params, new_params) = (parameters(original, old), parameters(modified, new));
+        for (key, param) in &old_params {
+            match new_params.get(key) {
+                None => push(true, format!("parameter {} removed", key)),
+                Some(new_param) => {
+                    if !is_required(param) && is_required(new_param) {
+                        push(true, format!("parameter {} is now required", key));
+                    }
+                    let (old_type, new_type) = (parameter_type(original, param), parameter_type(modified, new_param));
+                    if old_type != new_type {
+                        push(true, format!("parameter {} type changed {} -> {}", key, old_type, new_type));
+                    }
+                }
+            }
+        }
+        for (key, param) in &new_params {
+            if !old_params.contains_key(key) {
+                let required = is_required(param);
+                let kind = if required { "required" } else { "optional" };
+                push(required, format!("{} parameter {} added", kind, key));
+            }
+        }
+
+        let (old_responses, new_responses) = (responses(original, old), responses(modified, new));
+        for (status, old_fields) in &old_responses {
+            match new_responses.get(status) {
+                None => push(true, format!("response {} removed", status)),
+                Some(new_fields) => {
+                    for (field, old_type) in old_fields {
+                        match new_fields.get(field) {
+                            None => push(true, format!("response {} field {} removed", status, field)),
+                            Some(new_type) if new_type != old_type => push(
+                                true,
+                                format!("response {} field {} type changed {} -> {}", status, field, old_type, new_type),
+                            ),
+                            _ => {}
+                        }
+                    }
+                    for field in new_fields.keys().filter(|f| !old_fields.contains_key(*f)) {
+                        push(false, format!("response {} field {} added", status, field));
+                    }
+                }
+            }
+        }
+        for status in new_responses.keys().filter(|s| !old_responses.contains_key(*s)) {
+            push(false, format!("response {} added", status));
+        }
+    }
+
+    for endpoint in after.keys().filter(|e| !before.contains_key(*e)) {
+        changes.push(SpecChange {
+            breaking: false,
+            endpoint: endpoint.clone(),
+            message: "endpoint added".to_string(),
+        });
+    }
+    changes
+}
+
+/// Operation objects keyed by `METHOD /path`
+fn operations(spec: &Value) -> BTreeMap<String, Value> {
+    let mut operations = BTreeMap::new();
+    for (path, item) in spec.get("paths").and_then(Value::as_object).into_iter().flatten() {
+        for method in METHODS {
+            if let Some(operation) = item.get(method) {
+                // Path-level parameters apply to every operation under the path
+                let mut operation = operation.clone();
+                if let (Some(shared), Some(object)) = (item.get("parameters"), operation.as_object_mut()) {
+                    let own = object.get("parameters").and_then(Value::as_array).cloned().unwrap_or_default();
+                    let mut merged = shared.as_array().cloned().unwrap_or_default();
+                    merged.extend(own);
+                    object.insert("parameters".to_string(), Value::Array(merged));
+                }
+                operations.insert(format!("{} {}", method.to_uppercase(), path), operation);
+            }
+        }
+    }
+    operations
+}
+
+/// Parameters keyed by `name (in)`, with `$ref`s resolved; a v3 request body counts as the `body` parameter
+fn parameters(spec: &Value, operation: &Value) -> BTreeMap<String, Value> {
+    let mut parameters = BTreeMap::new();
+    for param in operation.get("parameters").and_then(Value::as_array).into_iter().flatten() {
+        let param = resolve(spec, param);
+        let name = param.get("name").and_then(Value::as_str).unwrap_or_default();
+        let location = param.get("in").and_then(Value::as_str).unwrap_or_default();
+        parameters.insert(format!("{} ({})", name, location), param.clone());
+    }
+    if let Some(body) = operation.get("requestBody") {
+        parameters.insert("body".to_string(), resolve(spec, body).clone());
+    }
+    parameters
+}
+
+fn is_required(param: &Value) -> bool {
+    param.get("required").and_then(Value::as_bool).unwrap_or(false)
+}
+
+fn parameter_type(spec: &Value, param: &Value) -> String {
+    let schema = param.get("schema").map(|s| resolve(spec, s)).unwrap_or(param);
+    schema_type(spec, schema)
+}
+
+fn schema_type(spec: &Value, schema: &Value) -> String {
+    let mut schema = resolve(spec, schema);
+    let mut arrays = 0;
+    let inner = loop {
+        match schema.get("type").and_then(Value::as_str) {
+            Some("array") if arrays < MAX_ARRAY_NESTING => {
+                arrays += 1;
+                match schema.get("items") {
+                    Some(items) => schema = resolve(spec, items),
+                    None => break "any",
+                }
+            }
+            Some("array") => break "any",
+            Some(kind) => break kind,
+            None if schema.get("properties").is_some() => break "object",
+            None => break "any",
+        }
+    };
+    format!("{}{}{}", "array<".repeat(arrays), inner, ">".repeat(arrays))
+}
+
+/// Response schemas per status code, flattened to `field.path -> type`
+fn responses(spec: &Value, operation: &Value) -> BTreeMap<String, BTreeMap<String, String>> {
+    let mut responses = BTreeMap::new();
+    for (status, response) in operation.get("responses").and_then(Value::as_object).into_iter().flatten() {
+        let response = resolve(spec, response);
+        // v2 puts the schema on the response, v3 under each media type; the first media type is used
+        let schema = response.get("schema").or_else(|| {
+            response
+                .get("content")
+                .and_then(Value::as_object)
+                .and_then(|content| content.values().next())
+                .and_then(|media| media.get("schema"))
+        });
+        let mut fields = BTreeMap::new();
+        if let Some(schema) = schema {
+            flatten_schema(spec, schema, "", &mut fields, 0);
+        }
+        responses.insert(status.clone(), fields);
+    }
+    responses
+}
+
+fn flatten_schema(spec: &Value, schema: &Value, prefix: &str, fields: &mut BTreeMap<String, String>, depth: usize) {
+    // Recursive schemas are cut off rather than followed forever
+    if depth > 8 {
+        return;
+    }
+    let schema = resolve(spec, schema);
+    let root = if prefix.is_empty() { "(body)" } else { prefix };
+    fields.insert(root.to_string(), schema_type(spec, schema));
+
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (name, property) in properties {
+            let path = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+            flatten_schema(spec, property, &path, fields, depth + 1);
+        }
+    } else if let Some(items) = schema.get("items") {
+        flatten_schema(spec, items, &format!("{}[]", prefix), fields, depth + 1);
+    }
+}
+
+/// Follow local `$ref`s such as `#/components/schemas/User`
+fn resolve<'a>(spec: &'a Value, value: &'a Value) -> &'a Value {
+    let mut value = value;
+    for _ in 0..16 {
+        match value.get("$ref").and_then(Value::as_str).and_then(|r| r.strip_prefix('#')) {
+            Some(pointer) => match spec.pointer(pointer) {
+                Some(target) => value = target,
+                None => break,
+            },
+            None => break,
+        }
+    }
+    value
+}
+
+pub fn format_openapi_diff(file1_path: &str, file2_path: &str, changes: &[SpecChange]) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", file1_path, file2_path);
+    if changes.is_empty() {
+        output.push_str("\nNo API changes\n");
+        return output;
+    }
+
+    let breaking = changes.iter().filter(|c| c.breaking).count();
+    output.push_str(&format!("\n{} change(s), {} breaking\n", changes.len(), breaking));
+    let mut endpoint = "";
+    for change in changes {
+        if change.endpoint != endpoint {
+            endpoint = &change.endpoint;
+            output.push_str(&format!("\n## {}\n", endpoint));
+        }
+        let prefix = if change.breaking { "BREAKING: " } else { "" };
+        output.push_str(&format!("{}{}\n", prefix, change.message));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_parse_yaml() {
+        let value = parse_yaml(&lines(
+            "# spec\nopenapi: \"3.0.0\"\ninfo:\n  title: Demo # name\n  description: |\n    line one\n    line two\ntags: [a, 'b']\nitems:\n- name: x\n  required: true\n-\n  name: y\ncount: 3",
+        ))
+        .unwrap();
+        assert_eq!(value["openapi"], "3.0.0");
+        assert_eq!(value["info"]["title"], "Demo");
+        assert_eq!(value["info"]["description"], "line one\nline two");
+        assert_eq!(value["tags"], serde_json::json!(["a", "b"]));
+        assert_eq!(value["items"][0]["required"], true);
+        assert_eq!(value["items"][1]["name"], "y");
+        assert_eq!(value["count"], 3);
+        assert!(parse_yaml(&lines("a: 1\n    b: 2")).is_err());
+    }
+
+    #[test]
+    fn test_breaking_changes() {
+        let original = parse_spec(&lines(
+            "paths:\n  /users/{id}:\n    parameters:\n      - name: id\n        in: path\n        required: true\n        schema:\n          type: string\n    get:\n      parameters:\n        - name: verbose\n          in: query\n          schema: {type: boolean}\n      responses:\n        '200':\n          content:\n            application/json:\n              schema:\n                $ref: '#/components/schemas/User'\n    delete:\n      responses:\n        '204':\n          description: gone\ncomponents:\n  schemas:\n    User:\n      properties:\n        id: {type: string}\n        email: {type: string}",
+        ))
+        .unwrap();
+        let modified = parse_spec(&lines(
+            r##"{"paths": {"/users/{id}": {"parameters": [{"name": "id", "in": "path", "required": true, "schema": {"type": "integer"}}],
+              "get": {"parameters": [{"name": "verbose", "in": "query", "required": true, "schema": {"type": "boolean"}}],
+                      "responses": {"200": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/User"}}}}, "404": {}}}},
+              "/users": {"post": {"responses": {}}}},
+             "components": {"schemas": {"User": {"properties": {"id": {"type": "string"}, "name": {"type": "string"}}}}}}"##,
+        ))
+        .unwrap();
+
+        let text = format_openapi_diff("a.yaml", "b.json", &compare_openapi(&original, &modified));
+        assert!(text.contains("\n## DELETE /users/{id}\nBREAKING: endpoint removed\n"));
+        assert!(text.contains("BREAKING: parameter id (path) type changed string -> integer\n"));
+        assert!(text.contains("BREAKING: parameter verbose (query) is now required\n"));
+        assert!(text.contains("BREAKING: response 200 field email removed\n"));
+        assert!(text.contains("\nresponse 200 field name added\n"));
+        assert!(text.contains("\nresponse 404 added\n"));
+        assert!(text.contains("\n## POST /users\nendpoint added\n"));
+        assert!(text.contains("7 change(s), 4 breaking"));
+    }
+
+    #[test]
+    fn test_adversarial_nesting() {
+        // Well below the 1 MiB stack of a WASM module; unbounded recursion would abort here
+        let small_stack = std::thread::Builder::new().stack_size(256 * 1024);
+        small_stack
+            .spawn(|| {
+                let deep = vec![format!("{}x", "- ".repeat(100_000))];
+                assert_eq!(parse_yaml(&deep), Err("YAML nested deeper than 64 levels".to_string()));
+                assert!(parse_yaml(&[format!("{}a: 1", "- ".repeat(60))]).is_ok());
+
+                // A list whose items are the list itself
+                let spec = serde_json::json!({ "components": { "schemas": {
+                    "List": { "type": "array", "items": { "$ref": "#/components/schemas/List" } }
+                } } });
+                let list = &spec["components"]["schemas"]["List"];
+                assert_eq!(schema_type(&spec, list), format!("{}any{}", "array<".repeat(32), ">".repeat(32)));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
@@ -0,0 +1,164 @@
+use std::time::SystemTime;
+
+/// Changed lines below which a file counts as a small change, and below which a medium one
+const SMALL_CHANGE: usize = 10;
+const MEDIUM_CHANGE: usize = 100;
+
+/// What multi-file reports are sorted by; ties are always broken by path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    Path,
+    /// Lines added plus lines deleted, largest first
+    Size,
+    /// Last modification of the newer copy, most recent first
+    Modified,
+}
+
+impl SortKey {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "path" => Some(SortKey::Path),
+            "size" => Some(SortKey::Size),
+            "mtime" | "modified" => Some(SortKey::Modified),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    Directory,
+    Extension,
+    /// Small, medium or large, by lines changed
+    Size,
+}
+
+impl GroupBy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dir" | "directory" => Some(GroupBy::Directory),
+            "ext" | "extension" => Some(GroupBy::Extension),
+            "size" => Some(GroupBy::Size),
+            _ => None,
+        }
+    }
+}
+
+/// How to order a multi-file report; unset fields keep the order the files were found in
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReportOrder {
+    pub sort: Option<SortKey>,
+    pub group: Option<GroupBy>,
+}
+
+impl ReportOrder {
+    /// Take `--sort=path|size|mtime` and `--group=dir|ext|size` out of `args`, leaving the rest
+    pub fn from_args(args: &mut Vec<String>) -> Result<Self, String> {
+        let mut order = Self::default();
+        let mut rest = Vec::new();
+        for arg in args.drain(..) {
+            if let Some(name) = arg.strip_prefix("--sort=") {
+                order.sort = Some(SortKey::from_name(name).ok_or_else(|| format!("Unknown sort order: {}", name))?);
+            } else if let Some(name) = arg.strip_prefix("--group=") {
+                order.group = Some(GroupBy::from_name(name).ok_or_else(|| format!("Unknown grouping: {}", name))?);
+            } else {
+                rest.push(arg);
+            }
+        }
+        *args = rest;
+        Ok(order)
+    }
+}
+
+/// What ordering needs to know about one file of a report
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntryFacts {
+    pub path: String,
+    /// Lines added plus lines deleted
+    pub churn: usize,
+    pub modified: Option<SystemTime>,
+}
+
+/// `items` sorted and grouped by `order`. Groups appear in the order of their first file, so a
+/// size sort also puts the group with the largest change first; without grouping there is one
+/// group, labelled `None`.
+pub fn arrange<T>(items: Vec<T>, order: &ReportOrder, facts: impl Fn(&T) -> EntryFacts) -> Vec<(Option<String>, Vec<T>)> {
+    let mut entries: Vec<(EntryFacts, T)> = items.into_iter().map(|item| (facts(&item), item)).collect();
+    match order.sort {
+        Some(SortKey::Path) => entries.sort_by(|(a, _), (b, _)| a.path.cmp(&b.path)),
+        Some(SortKey::Size) => entries.sort_by(|(a, _), (b, _)| b.churn.cmp(&a.churn).then_with(|| a.path.cmp(&b.path))),
+        // Files without a modification time, such as deleted ones, go last
+        Some(SortKey::Modified) => entries.sort_by(|(a, _), (b, _)| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path))),
+        None => {}
+    }
+
+    let mut groups: Vec<(Option<String>, Vec<T>)> = Vec::new();
+    for (facts, item) in entries {
+        let label = order.group.map(|group| group_label(group, &facts));
+        match groups.iter_mut().find(|(existing, _)| *existing == label) {
+            Some((_, members)) => members.push(item),
+            None => groups.push((label, vec![item])),
+        }
+    }
+    groups
+}
+
+fn group_label(group: GroupBy, facts: &EntryFacts) -> String {
+    match group {
+        GroupBy::Directory => match facts.path.rsplit_once('/') {
+            Some((directory, _)) => format!("{}/", directory),
+            None => "./".to_string(),
+        },
+        GroupBy::Extension => {
+            let name = facts.path.rsplit('/').next().unwrap_or(&facts.path);
+            match name.rsplit_once('.') {
+                Some((stem, extension)) if !stem.is_empty() => format!(".{}", extension),
+                _ => "(no extension)".to_string(),
+            }
+        }
+        GroupBy::Size if facts.churn < SMALL_CHANGE => format!("Small changes (under {} lines)", SMALL_CHANGE),
+        GroupBy::Size if facts.churn < MEDIUM_CHANGE => format!("Medium changes (under {} lines)", MEDIUM_CHANGE),
+        GroupBy::Size => format!("Large changes ({} lines or more)", MEDIUM_CHANGE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_arrange() {
+        let at = |seconds: u64| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+        let files = vec![
+            EntryFacts { path: "src/b.rs".to_string(), churn: 5, modified: at(30) },
+            EntryFacts { path: "README".to_string(), churn: 250, modified: None },
+            EntryFacts { path: "src/a.rs".to_string(), churn: 5, modified: at(10) },
+            EntryFacts { path: "docs/guide.md".to_string(), churn: 40, modified: at(20) },
+        ];
+        let arranged = |order: ReportOrder| -> Vec<(Option<String>, Vec<String>)> {
+            arrange(files.clone(), &order, |facts| facts.clone())
+                .into_iter()
+                .map(|(label, members)| (label, members.into_iter().map(|facts| facts.path).collect()))
+                .collect()
+        };
+
+        let mut args = vec!["--sort=size".to_string(), "--ext=rs".to_string(), "--group=dir".to_string()];
+        let order = ReportOrder::from_args(&mut args).unwrap();
+        assert_eq!(args, ["--ext=rs"]);
+        assert_eq!(
+            arranged(order),
+            vec![
+                (Some("./".to_string()), vec!["README".to_string()]),
+                (Some("docs/".to_string()), vec!["docs/guide.md".to_string()]),
+                (Some("src/".to_string()), vec!["src/a.rs".to_string(), "src/b.rs".to_string()]),
+            ]
+        );
+        let by_time = arranged(ReportOrder { sort: Some(SortKey::Modified), group: None });
+        assert_eq!(by_time, vec![(None, vec!["src/b.rs", "docs/guide.md", "src/a.rs", "README"].into_iter().map(String::from).collect())]);
+        let by_extension = arranged(ReportOrder { sort: Some(SortKey::Path), group: Some(GroupBy::Extension) });
+        let labels: Vec<Option<String>> = by_extension.into_iter().map(|(label, _)| label).collect();
+        assert_eq!(labels, [Some("(no extension)".to_string()), Some(".md".to_string()), Some(".rs".to_string())]);
+        assert!(ReportOrder::from_args(&mut vec!["--sort=random".to_string()]).is_err());
+    }
+}
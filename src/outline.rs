@@ -0,0 +1,253 @@
+use serde::Serialize;
+use crate::diff_core::{compute_diff, ChangeType, CharChange, DiffOptions, LineChange};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum OutlineKind {
+    File,
+    Hunk,
+    Edit,
+}
+
+/// A node of a diff outline: files contain hunks, hunks contain intra-line edits
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OutlineItem {
+    pub kind: OutlineKind,
+    pub label: String,
+    /// 0-based, end-exclusive line range: in the patch text for `outline_patch`, in the modified
+    /// file for `outline_comparison`
+    pub range: (usize, usize),
+    pub children: Vec<OutlineItem>,
+}
+
+/// Edits longer than this are cut short in labels
+const SNIPPET_CHARS: usize = 30;
+
+/// Outline of a `.diff`/`.patch` buffer: one item per file, per `@@` hunk, and per intra-line
+/// edit inside each run of `-` lines followed by `+` lines
+pub fn outline_patch(text: &str) -> Vec<OutlineItem> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut files: Vec<OutlineItem> = Vec::new();
+    // Old and new lines still expected in the current hunk, so `--- ` deletions are not headers
+    let mut remaining = (0usize, 0usize);
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        if remaining != (0, 0) {
+            let start = index;
+            let run = |index: &mut usize, prefix: char, limit: usize| {
+                let begin = *index;
+                while *index < lines.len() && *index - begin < limit && lines[*index].starts_with(prefix) {
+                    *index += 1;
+                }
+                lines[begin..*index].iter().map(|line| line[1..].to_string()).collect::<Vec<_>>()
+            };
+            let removed = run(&mut index, '-', remaining.0);
+            let added = run(&mut index, '+', remaining.1);
+            if removed.is_empty() && added.is_empty() {
+                // Context, or a `\ No newline` marker that counts towards neither side
+                if !line.starts_with('\\') {
+                    remaining = (remaining.0.saturating_sub(1), remaining.1.saturating_sub(1));
+                }
+                index += 1;
+            } else {
+                remaining = (remaining.0.saturating_sub(removed.len()), remaining.1.saturating_sub(added.len()));
+                let edits = block_edits(&removed, &added, start + removed.len());
+                if let Some(hunk) = files.last_mut().and_then(|file| file.children.last_mut()) {
+                    hunk.children.extend(edits);
+                }
+            }
+            extend_last(&mut files, index);
+            continue;
+        }
+
+        if line.starts_with("diff ") {
+            files.push(file_item(diff_line_path(line), index));
+        } else if let (Some(old), Some(new)) = (line.strip_prefix("--- "), lines.get(index + 1).and_then(|l| l.strip_prefix("+++ "))) {
+            let path = header_path(new).or_else(|| header_path(old)).unwrap_or_default();
+            // Headers right after a `diff` line belong to that file
+            match files.last_mut() {
+                Some(file) if file.children.is_empty() && file.range.1 == index => file.label = path,
+                _ => files.push(file_item(path, index)),
+            }
+            index += 1;
+        } else if let Some((old, new)) = parse_hunk_header(line) {
+            if files.is_empty() {
+                files.push(file_item(String::new(), index));
+            }
+            remaining = (old, new);
+            let hunk = OutlineItem {
+                kind: OutlineKind::Hunk,
+                label: line.to_string(),
+                range: (index, index + 1),
+                children: Vec::new(),
+            };
+            files.last_mut().unwrap().children.push(hunk);
+        }
+        index += 1;
+        extend_last(&mut files, index);
+    }
+    files
+}
+
+/// Outline of a comparison: the file, one item per change with its modified-file range, and the
+/// intra-line edits of modified changes
+pub fn outline_comparison(path: &str, original: &[String], modified: &[String], changes: &[LineChange]) -> OutlineItem {
+    let hunks = changes
+        .iter()
+        .map(|change| {
+            let (start, end) = (change.modified_start, change.modified_end);
+            let label = match change.change_type {
+                ChangeType::Added => format!("+{} line(s) at {}", end - start, start + 1),
+                ChangeType::Deleted => format!("-{} line(s) at {}", change.original_end - change.original_start, start + 1),
+                ChangeType::Modified => format!("~ lines {}-{}", start + 1, end),
+            };
+            let children = match &change.char_changes {
+                Some(char_changes) => char_edits(
+                    &original[change.original_start..change.original_end].join("\n"),
+                    &modified[start..end].join("\n"),
+                    char_changes,
+                    start,
+                ),
+                None => Vec::new(),
+            };
+            OutlineItem {
+                kind: OutlineKind::Hunk,
+                label,
+                range: (start, end.max(start + 1).min(modified.len().max(1))),
+                children,
+            }
+        })
+        .collect();
+    OutlineItem {
+        kind: OutlineKind::File,
+        label: path.to_string(),
+        range: (0, modified.len()),
+        children: hunks,
+    }
+}
+
+fn file_item(label: String, line: usize) -> OutlineItem {
+    OutlineItem {
+        kind: OutlineKind::File,
+        label,
+        range: (line, line + 1),
+        children: Vec::new(),
+    }
+}
+
+/// Stretch the open file and hunk to end at `line`
+fn extend_last(files: &mut [OutlineItem], line: usize) {
+    if let Some(file) = files.last_mut() {
+        file.range.1 = line;
+        if let Some(hunk) = file.children.last_mut() {
+            hunk.range.1 = line;
+        }
+    }
+}
+
+/// `b/src/main.rs` from `diff --git a/src/main.rs b/src/main.rs`
+fn diff_line_path(line: &str) -> String {
+    let last = line.rsplit(' ').next().unwrap_or_default();
+    last.strip_prefix("b/").unwrap_or(last).to_string()
+}
+
+fn header_path(header: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    (path != "/dev/null").then(|| path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path).to_string())
+}
+
+/// Line counts from `@@ -a,b +c,d @@`
+fn parse_hunk_header(line: &str) -> Option<(usize, usize)> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, _) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let count = |range: &str| range.split_once(',').map_or(Some(1), |(_, n)| n.parse().ok());
+    Some((count(old)?, count(new)?))
+}
+
+/// Edits for a run of removed lines followed by added lines; `added_line` is where the added
+/// lines start in the patch
+fn block_edits(removed: &[String], added: &[String], added_line: usize) -> Vec<OutlineItem> {
+    if removed.is_empty() || added.is_empty() {
+        let (count, sign, line) = if added.is_empty() {
+            (removed.len(), '-', added_line - removed.len())
+        } else {
+            (added.len(), '+', added_line)
+        };
+        return vec![OutlineItem {
+            kind: OutlineKind::Edit,
+            label: format!("{}{} line(s)", sign, count),
+            range: (line, line + count),
+            children: Vec::new(),
+        }];
+    }
+
+    let (old, new) = (removed.join("\n"), added.join("\n"));
+    compute_diff(std::slice::from_ref(&old), std::slice::from_ref(&new), DiffOptions::default())
+        .iter()
+        .filter_map(|change| change.char_changes.as_ref())
+        .flat_map(|char_changes| char_edits(&old, &new, char_changes, added_line))
+        .collect()
+}
+
+/// One item per intra-line change; `first_line` is the line `new` starts on
+fn char_edits(old: &str, new: &str, char_changes: &[CharChange], first_line: usize) -> Vec<OutlineItem> {
+    let slice = |text: &str, start: usize, length: usize| text.chars().skip(start).take(length).collect::<String>();
+    let snippet = |text: String| {
+        let short: String = text.chars().take(SNIPPET_CHARS).collect();
+        format!("{:?}{}", short, if text.chars().count() > SNIPPET_CHARS { "…" } else { "" })
+    };
+
+    char_changes
+        .iter()
+        .map(|change| {
+            let removed = slice(old, change.original_start, change.original_length);
+            let added = slice(new, change.modified_start, change.modified_length);
+            let label = match (removed.is_empty(), added.is_empty()) {
+                (true, _) => format!("+{}", snippet(added)),
+                (_, true) => format!("-{}", snippet(removed)),
+                _ => format!("{} → {}", snippet(removed), snippet(added)),
+            };
+            let line = first_line + new.chars().take(change.modified_start).filter(|&c| c == '\n').count();
+            OutlineItem {
+                kind: OutlineKind::Edit,
+                label,
+                range: (line, line + 1),
+                children: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_outline_patch() {
+        let patch = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1,3 +1,3 @@ fn main\n ctx\n--- x\n++++ y\n ctx\n@@ -10,1 +10,2 @@\n keep\n+new\n--- /dev/null\n+++ b/b.txt\n@@ -0,0 +1 @@\n+hello\n";
+        let outline = outline_patch(patch);
+        assert_eq!(outline.len(), 2);
+        assert_eq!((outline[0].label.as_str(), outline[0].range), ("src/a.rs", (0, 11)));
+        let hunks = &outline[0].children;
+        assert_eq!(hunks[0].label, "@@ -1,3 +1,3 @@ fn main");
+        assert_eq!(hunks[0].range, (3, 8));
+        // `--- x` / `++++ y` are a removed and an added line, not file headers
+        assert!(!hunks[0].children.is_empty());
+        assert!(hunks[0].children.iter().all(|edit| edit.range == (6, 7)));
+        assert_eq!(hunks[1].children[0].label, "+1 line(s)");
+        assert_eq!((outline[1].label.as_str(), outline[1].range), ("b.txt", (11, 15)));
+    }
+
+    #[test]
+    fn test_outline_comparison() {
+        let (original, modified) = (lines("let a = 1;\nkeep\ngone"), lines("let b = 1;\nkeep"));
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let outline = outline_comparison("x.rs", &original, &modified, &changes);
+        let labels: Vec<&str> = outline.children.iter().map(|h| h.label.as_str()).collect();
+        assert_eq!(labels, vec!["~ lines 1-1", "-1 line(s) at 3"]);
+        assert_eq!(outline.children[0].children[0].label, "\"a\" → \"b\"");
+    }
+}
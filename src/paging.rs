@@ -0,0 +1,204 @@
+use crate::diff_core::LineChange;
+use crate::ui::{format_diff, format_stat, OutputFormat};
+
+/// Hunks per page when the setting is absent
+pub const DEFAULT_HUNKS_PER_PAGE: usize = 200;
+
+/// Output size limits when the settings are absent; well past any diff worth reading in full
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 16 << 20;
+pub const DEFAULT_MAX_OUTPUT_LINES: usize = 200_000;
+
+/// Hunks listed in the summary that replaces an oversized diff
+const LARGEST_HUNKS_SHOWN: usize = 10;
+
+/// Caps on the total formatted output of one comparison; 0 disables a cap
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutputLimit {
+    pub max_bytes: usize,
+    pub max_lines: usize,
+}
+
+impl Default for OutputLimit {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            max_lines: DEFAULT_MAX_OUTPUT_LINES,
+        }
+    }
+}
+
+impl OutputLimit {
+    /// Whether `pages` together go over either cap
+    pub fn exceeded_by(&self, pages: &[Page]) -> bool {
+        let bytes: usize = pages.iter().map(|page| page.text.len()).sum();
+        let lines: usize = pages.iter().map(|page| page.text.lines().count()).sum();
+        (self.max_bytes > 0 && bytes > self.max_bytes) || (self.max_lines > 0 && lines > self.max_lines)
+    }
+}
+
+/// One labelled chunk of formatted output
+#[derive(Clone, Debug, PartialEq)]
+pub struct Page {
+    pub label: String,
+    pub text: String,
+}
+
+/// Split a diff into pages of at most `hunks_per_page` hunks, preceded by an index page.
+/// Diffs that fit on one page, `stat` output, and `hunks_per_page == 0` produce a single page.
+pub fn paginate(
+    format: OutputFormat,
+    file1_path: &str,
+    file2_path: &str,
+    changes: &[LineChange],
+    hunks_per_page: usize,
+) -> Vec<Page> {
+    let label = format!("Diff {} ↔ {}", file1_path, file2_path);
+    if hunks_per_page == 0 || changes.len() <= hunks_per_page || format == OutputFormat::Stat {
+        return vec![Page {
+            label,
+            text: format_diff(format, file1_path, file2_path, changes),
+        }];
+    }
+
+    let chunks: Vec<&[LineChange]> = changes.chunks(hunks_per_page).collect();
+    let mut pages = vec![Page {
+        label: format!("{} (index)", label),
+        text: format_page_index(&chunks, changes.len()),
+    }];
+    for (index, chunk) in chunks.iter().enumerate() {
+        pages.push(Page {
+            label: format!("{} (page {}/{})", label, index + 1, chunks.len()),
+            text: format_diff(format, file1_path, file2_path, chunk),
+        });
+    }
+    pages
+}
+
+/// The page shown instead of a diff too large for the editor: a diffstat, the largest hunks by
+/// line count, and how to fetch the omitted hunks a range at a time. Hunks are numbered from
+/// `first_hunk`, so a summary of a `--hunks` range keeps the numbering of the whole diff.
+pub fn oversized_summary(
+    file1_path: &str,
+    file2_path: &str,
+    changes: &[LineChange],
+    first_hunk: usize,
+    limit: &OutputLimit,
+) -> Page {
+    let size = |change: &LineChange| {
+        (change.original_end - change.original_start) + (change.modified_end - change.modified_start)
+    };
+    let mut largest: Vec<usize> = (0..changes.len()).collect();
+    largest.sort_by_key(|&index| std::cmp::Reverse(size(&changes[index])));
+    largest.truncate(LARGEST_HUNKS_SHOWN);
+
+    let mut text = format!(
+        "⚠ Diff too large (limit {} bytes, {} lines), {} hunk(s) omitted\n\n",
+        limit.max_bytes,
+        limit.max_lines,
+        changes.len()
+    );
+    text.push_str(&format_stat(file1_path, file2_path, changes));
+    if !largest.is_empty() {
+        text.push_str("\nLargest hunks:\n");
+    }
+    for index in largest {
+        let change = &changes[index];
+        text.push_str(&format!(
+            "  hunk {}: original lines {}-{}, modified lines {}-{} (+{} -{})\n",
+            first_hunk + index,
+            change.original_start + 1,
+            change.original_end.max(change.original_start + 1),
+            change.modified_start + 1,
+            change.modified_end.max(change.modified_start + 1),
+            change.modified_end - change.modified_start,
+            change.original_end - change.original_start
+        ));
+    }
+    text.push_str("\nShow a range of hunks with `/diff --hunks=<first>-<last>`\n");
+    Page {
+        label: format!("Diff {} ↔ {} (too large)", file1_path, file2_path),
+        text,
+    }
+}
+
+/// `first..=last` of a `--hunks=<first>-<last>` range, 1-based, as a slice range into `changes`
+pub fn hunk_range(spec: &str, total: usize) -> Result<std::ops::Range<usize>, String> {
+    let invalid = || format!("Expected <first>-<last>, got {}", spec);
+    let (first, last) = spec.split_once('-').ok_or_else(invalid)?;
+    let (first, last): (usize, usize) = (first.parse().map_err(|_| invalid())?, last.parse().map_err(|_| invalid())?);
+    if first == 0 || first > last || last > total {
+        return Err(format!("The diff has hunks 1-{}", total));
+    }
+    Ok(first - 1..last)
+}
+
+fn format_page_index(chunks: &[&[LineChange]], total_hunks: usize) -> String {
+    let mut output = format!("{} hunk(s) in {} page(s)\n", total_hunks, chunks.len());
+    let mut first_hunk = 1;
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let (first, last) = (&chunk[0], &chunk[chunk.len() - 1]);
+        output.push_str(&format!(
+            "  page {}: hunks {}-{}, original lines {}-{}, modified lines {}-{}\n",
+            index + 1,
+            first_hunk,
+            first_hunk + chunk.len() - 1,
+            first.original_start + 1,
+            last.original_end.max(first.original_start + 1),
+            first.modified_start + 1,
+            last.modified_end.max(first.modified_start + 1)
+        ));
+        first_hunk += chunk.len();
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_small_diff_is_one_page() {
+        let changes = compute_diff(&lines("a\nb"), &lines("a\nc"), DiffOptions::default());
+        let pages = paginate(OutputFormat::Unified, "x", "y", &changes, 10);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].label, "Diff x ↔ y");
+    }
+
+    #[test]
+    fn test_large_diff_is_paged_with_index() {
+        let changes = compute_diff(
+            &lines("a\n1\nb\n2\nc\n3\nd"),
+            &lines("a\nX\nb\nY\nc\nZ\nd"),
+            DiffOptions::default(),
+        );
+        assert_eq!(changes.len(), 3);
+
+        let pages = paginate(OutputFormat::Unified, "x", "y", &changes, 2);
+        assert_eq!(pages.len(), 3);
+        assert!(pages[0].text.starts_with("3 hunk(s) in 2 page(s)\n"));
+        assert!(pages[0].text.contains("page 2: hunks 3-3, original lines 6-6"));
+        assert_eq!(pages[2].label, "Diff x ↔ y (page 2/2)");
+        assert_eq!(pages[2].text.matches("@@").count(), 2);
+    }
+
+    #[test]
+    fn test_oversized_output_is_summarized() {
+        let original = lines("a\n1\nb\n2\n3\n4\nc");
+        let modified = lines("a\nX\nb\nc");
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let pages = paginate(OutputFormat::Unified, "x", "y", &changes, 0);
+        assert!(!OutputLimit::default().exceeded_by(&pages));
+        let limit = OutputLimit { max_bytes: 0, max_lines: 3 };
+        assert!(limit.exceeded_by(&pages));
+
+        let summary = oversized_summary("x", "y", &changes, 1, &limit).text;
+        assert!(summary.starts_with("⚠ Diff too large (limit 0 bytes, 3 lines), 2 hunk(s) omitted\n"));
+        let hunks: Vec<&str> = summary.lines().filter(|line| line.starts_with("  hunk")).collect();
+        assert_eq!(hunks, vec!["  hunk 2: original lines 4-6, modified lines 4-4 (+0 -3)", "  hunk 1: original lines 2-2, modified lines 2-2 (+1 -1)"]);
+        assert_eq!(hunk_range("2-2", changes.len()), Ok(1..2));
+        assert!(hunk_range("1-3", changes.len()).is_err());
+    }
+}
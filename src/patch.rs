@@ -0,0 +1,401 @@
+/// One line of a hunk body, without its prefix
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatchLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hunk {
+    /// 1-based, as written in the `@@` header; 0 when that side is empty
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    /// Text after the closing `@@`, usually the enclosing function
+    pub section: String,
+    pub lines: Vec<PatchLine>,
+}
+
+impl Hunk {
+    /// The lines the hunk expects before it applies: context and removed lines
+    pub fn old_lines(&self) -> Vec<String> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                PatchLine::Context(text) | PatchLine::Removed(text) => Some(text.clone()),
+                PatchLine::Added(_) => None,
+            })
+            .collect()
+    }
+
+    /// The lines the hunk leaves behind: context and added lines
+    pub fn new_lines(&self) -> Vec<String> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                PatchLine::Context(text) | PatchLine::Added(text) => Some(text.clone()),
+                PatchLine::Removed(_) => None,
+            })
+            .collect()
+    }
+
+    /// 0-based line where the hunk's old lines start according to its header
+    pub fn expected_start(&self) -> usize {
+        // An empty old side names the line before the insertion
+        if self.old_count == 0 {
+            self.old_start
+        } else {
+            self.old_start.saturating_sub(1)
+        }
+    }
+}
+
+/// Everything a patch says about one file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileDiff {
+    /// `None` for a created file (`/dev/null`)
+    pub old_path: Option<String>,
+    /// `None` for a deleted file
+    pub new_path: Option<String>,
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    /// `similarity index` of a rename or copy, in percent
+    pub similarity: Option<u32>,
+    pub is_rename: bool,
+    pub is_copy: bool,
+    /// `Binary files … differ` or a `GIT binary patch`; there are no hunks
+    pub binary: bool,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    /// The path the file has after the patch, or before it for deletions
+    pub fn path(&self) -> &str {
+        self.new_path.as_deref().or(self.old_path.as_deref()).unwrap_or_default()
+    }
+}
+
+/// Parse a unified diff as produced by `git diff`, `git format-patch`, GitHub/GitLab or plain
+/// `diff -u`. Text outside file sections (email headers, commit messages, signatures) is skipped,
+/// as are `\ No newline at end of file` markers.
+pub fn parse_unified(text: &str) -> Vec<FileDiff> {
+    let lines: Vec<&str> = text.lines().map(|line| line.strip_suffix('\r').unwrap_or(line)).collect();
+    let mut files: Vec<FileDiff> = Vec::new();
+    // Whether the open file came from a `diff --git` header whose `---`/`+++` lines are still due
+    let mut git_header_open = false;
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            let (old, new) = split_git_paths(rest);
+            files.push(FileDiff {
+                old_path: Some(old),
+                new_path: Some(new),
+                ..FileDiff::default()
+            });
+            git_header_open = true;
+            continue;
+        }
+
+        if let (Some(old), Some(new)) = (
+            line.strip_prefix("--- "),
+            lines.get(index).and_then(|next| next.strip_prefix("+++ ")),
+        ) {
+            index += 1;
+            if !git_header_open {
+                files.push(FileDiff::default());
+            }
+            git_header_open = false;
+            let file = files.last_mut().unwrap();
+            file.old_path = header_path(old);
+            file.new_path = header_path(new);
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix("@@ -") {
+            let Some(mut hunk) = parse_hunk_header(header) else {
+                continue;
+            };
+            if files.is_empty() {
+                files.push(FileDiff::default());
+            }
+            git_header_open = false;
+            let (mut old_left, mut new_left) = (hunk.old_count, hunk.new_count);
+            while (old_left > 0 || new_left > 0) && index < lines.len() {
+                let body = lines[index];
+                let text = body.get(1..).unwrap_or_default().to_string();
+                match body.chars().next() {
+                    Some('-') => {
+                        old_left = old_left.saturating_sub(1);
+                        hunk.lines.push(PatchLine::Removed(text));
+                    }
+                    Some('+') => {
+                        new_left = new_left.saturating_sub(1);
+                        hunk.lines.push(PatchLine::Added(text));
+                    }
+                    Some('\\') => {}
+                    // Some mail clients strip the space from empty context lines
+                    Some(' ') | None => {
+                        old_left = old_left.saturating_sub(1);
+                        new_left = new_left.saturating_sub(1);
+                        hunk.lines.push(PatchLine::Context(text));
+                    }
+                    Some(_) => break,
+                }
+                index += 1;
+            }
+            // A marker right after the last line of the hunk
+            if lines.get(index).is_some_and(|next| next.starts_with('\\')) {
+                index += 1;
+            }
+            files.last_mut().unwrap().hunks.push(hunk);
+            continue;
+        }
+
+        // Extended git headers only mean something directly after `diff --git`
+        let Some(file) = files.last_mut().filter(|_| git_header_open) else {
+            continue;
+        };
+        if let Some(mode) = line.strip_prefix("old mode ") {
+            file.old_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("new mode ") {
+            file.new_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("new file mode ") {
+            file.old_path = None;
+            file.new_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+            file.new_path = None;
+            file.old_mode = Some(mode.to_string());
+        } else if let Some(percent) = line.strip_prefix("similarity index ") {
+            file.similarity = percent.trim_end_matches('%').parse().ok();
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            file.is_rename = true;
+            file.old_path = Some(unquote(path));
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            file.new_path = Some(unquote(path));
+        } else if let Some(path) = line.strip_prefix("copy from ") {
+            file.is_copy = true;
+            file.old_path = Some(unquote(path));
+        } else if let Some(path) = line.strip_prefix("copy to ") {
+            file.new_path = Some(unquote(path));
+        } else if line.starts_with("Binary files ") {
+            file.binary = true;
+        } else if line == "GIT binary patch" {
+            file.binary = true;
+            // Skip the base85 payload: `literal`/`delta` blocks up to the next file
+            while index < lines.len() && !lines[index].starts_with("diff --git ") {
+                index += 1;
+            }
+        }
+    }
+    files
+}
+
+/// Apply `hunks` to `original`. Like `patch`, a hunk whose lines moved is still applied at the
+/// nearest place its old lines match exactly; a hunk whose old lines appear nowhere is an error.
+pub fn apply_hunks(original: &[String], hunks: &[Hunk]) -> Result<Vec<String>, String> {
+    let mut result = Vec::with_capacity(original.len());
+    let mut position = 0;
+    for (index, hunk) in hunks.iter().enumerate() {
+        let old_lines = hunk.old_lines();
+        let at = find_block(original, &old_lines, hunk.expected_start(), position)
+            .ok_or_else(|| format!("Hunk {} ({}) does not apply", index + 1, hunk_range(hunk)))?;
+        result.extend_from_slice(&original[position..at]);
+        result.extend(hunk.new_lines());
+        position = at + old_lines.len();
+    }
+    result.extend_from_slice(&original[position..]);
+    Ok(result)
+}
+
+/// Write `files` back out as a unified diff that `parse_unified` reads
+pub fn format_unified(files: &[FileDiff]) -> String {
+    let side = |prefix: &str, path: &Option<String>| {
+        path.as_ref().map_or_else(|| "/dev/null".to_string(), |path| format!("{}{}", prefix, path))
+    };
+    let mut output = String::new();
+    for file in files {
+        output.push_str(&format!("--- {}\n+++ {}\n", side("a/", &file.old_path), side("b/", &file.new_path)));
+        for hunk in &file.hunks {
+            output.push_str(&format!("@@ {} @@", hunk_range(hunk)));
+            if !hunk.section.is_empty() {
+                output.push_str(&format!(" {}", hunk.section));
+            }
+            output.push('\n');
+            for line in &hunk.lines {
+                let (prefix, text) = match line {
+                    PatchLine::Context(text) => (' ', text),
+                    PatchLine::Removed(text) => ('-', text),
+                    PatchLine::Added(text) => ('+', text),
+                };
+                output.push_str(&format!("{}{}\n", prefix, text));
+            }
+        }
+    }
+    output
+}
+
+pub(crate) fn hunk_range(hunk: &Hunk) -> String {
+    format!("-{},{} +{},{}", hunk.old_start, hunk.old_count, hunk.new_start, hunk.new_count)
+}
+
+/// Start of the exact match of `block` closest to `expected`, not before `from`
+fn find_block(lines: &[String], block: &[String], expected: usize, from: usize) -> Option<usize> {
+    let last = lines.len().checked_sub(block.len())?;
+    if from > last {
+        return None;
+    }
+    let matches = |at: usize| lines[at..at + block.len()] == *block;
+    let expected = expected.clamp(from, last);
+    (0..=last - from).find_map(|distance| {
+        let after = expected + distance;
+        let before = expected.checked_sub(distance).filter(|&at| at >= from);
+        [Some(after).filter(|&at| at <= last), before].into_iter().flatten().find(|&at| matches(at))
+    })
+}
+
+/// `a/x b/y` from a `diff --git` line; when unquoted paths contain spaces, prefer the split where
+/// both sides name the same file
+fn split_git_paths(rest: &str) -> (String, String) {
+    if rest.starts_with('"') {
+        let (old, tail) = take_quoted(rest);
+        let new = tail.trim_start();
+        return (strip_side(&old), strip_side(&unquote(new)));
+    }
+    let splits: Vec<usize> = rest.match_indices(" b/").map(|(at, _)| at).collect();
+    let at = splits
+        .iter()
+        .copied()
+        .find(|&at| rest[..at].strip_prefix("a/") == Some(&rest[at + 3..]))
+        .or_else(|| splits.first().copied())
+        .or_else(|| rest.find(' '))
+        .unwrap_or(rest.len());
+    (strip_side(&rest[..at]), strip_side(rest.get(at + 1..).unwrap_or_default()))
+}
+
+fn header_path(header: &str) -> Option<String> {
+    // `diff -u` appends a tab and a timestamp
+    let path = header.split('\t').next().unwrap_or(header).trim_end();
+    let path = unquote(path);
+    (path != "/dev/null").then(|| strip_side(&path))
+}
+
+fn strip_side(path: &str) -> String {
+    path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path).to_string()
+}
+
+fn unquote(text: &str) -> String {
+    if text.starts_with('"') {
+        take_quoted(text).0
+    } else {
+        text.to_string()
+    }
+}
+
+/// A C-style quoted string at the start of `text`, and the text after it
+fn take_quoted(text: &str) -> (String, &str) {
+    let mut value = String::new();
+    let mut chars = text.char_indices().skip(1);
+    while let Some((at, c)) = chars.next() {
+        match c {
+            '"' => return (value, &text[at + 1..]),
+            '\\' => match chars.next().map(|(_, escaped)| escaped) {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some(other) => value.push(other),
+                None => {}
+            },
+            _ => value.push(c),
+        }
+    }
+    (value, "")
+}
+
+/// `a,b +c,d @@ section` after the leading `@@ -`
+fn parse_hunk_header(header: &str) -> Option<Hunk> {
+    let (ranges, section) = header.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let ((old_start, old_count), (new_start, new_count)) = (range(old)?, range(new)?);
+    Some(Hunk {
+        old_start,
+        old_count,
+        new_start,
+        new_count,
+        section: section.trim().to_string(),
+        lines: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_git_patch() {
+        let text = "From abc Mon Sep 17 00:00:00 2001\r\nSubject: [PATCH] x\r\n\r\n---\r\n\
+diff --git a/src/old name.rs b/src/new name.rs\r\n\
+similarity index 90%\r\nrename from src/old name.rs\r\nrename to src/new name.rs\r\n\
+--- a/src/old name.rs\r\n+++ b/src/new name.rs\r\n\
+@@ -1,3 +1,3 @@ fn main()\r\n a\r\n--- b\r\n+++ c\r\n\r\n\\ No newline at end of file\r\n\
+diff --git a/run.sh b/run.sh\nold mode 100644\nnew mode 100755\n\
+diff --git a/logo.png b/logo.png\nnew file mode 100644\nindex 0000000..e69de29\nBinary files /dev/null and b/logo.png differ\n\
+diff --git a/gone.txt b/gone.txt\ndeleted file mode 100644\n--- a/gone.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n-- \n2.40.0\n";
+        let files = parse_unified(text);
+        assert_eq!(files.len(), 4);
+
+        let renamed = &files[0];
+        assert!(renamed.is_rename);
+        assert_eq!(renamed.similarity, Some(90));
+        assert_eq!(renamed.old_path.as_deref(), Some("src/old name.rs"));
+        assert_eq!(renamed.path(), "src/new name.rs");
+        let hunk = &renamed.hunks[0];
+        assert_eq!((hunk.old_start, hunk.old_count, hunk.section.as_str()), (1, 3, "fn main()"));
+        // `--- b` / `+++ c` are body lines, and the blank line is stripped context
+        assert_eq!(
+            hunk.lines,
+            vec![
+                PatchLine::Context("a".to_string()),
+                PatchLine::Removed("-- b".to_string()),
+                PatchLine::Added("++ c".to_string()),
+                PatchLine::Context(String::new()),
+            ]
+        );
+
+        assert_eq!((files[1].old_mode.as_deref(), files[1].new_mode.as_deref()), (Some("100644"), Some("100755")));
+        assert!(files[1].hunks.is_empty());
+        assert!(files[2].binary && files[2].old_path.is_none());
+        assert_eq!((files[3].new_path.as_deref(), files[3].path()), (None, "gone.txt"));
+        assert_eq!(files[3].hunks[0].lines, vec![PatchLine::Removed("bye".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_plain_and_quoted() {
+        let plain = parse_unified("--- old.txt\t2024-01-01 00:00:00\n+++ new.txt\t2024-01-02 00:00:00\n@@ -2 +2,2 @@\n-x\n+y\n+z\n");
+        assert_eq!(plain[0].old_path.as_deref(), Some("old.txt"));
+        assert_eq!((plain[0].hunks[0].old_count, plain[0].hunks[0].new_count), (1, 2));
+
+        let quoted = parse_unified("diff --git \"a/t\\tab\" \"b/t\\tab\"\nnew file mode 100644\n");
+        assert_eq!(quoted[0].new_path.as_deref(), Some("t\tab"));
+    }
+
+    #[test]
+    fn test_apply_hunks_with_offset() {
+        let files = parse_unified("--- a/f\n+++ b/f\n@@ -2,2 +2,2 @@\n b\n-c\n+C\n@@ -6,0 +7 @@\n+end\n");
+        let original: Vec<String> = "new\na\nb\nc\nd\ne\nf".lines().map(String::from).collect();
+        // The first hunk has moved down a line; the insertion lands after line 6 as written
+        let applied = apply_hunks(&original, &files[0].hunks).unwrap();
+        assert_eq!(applied.join(","), "new,a,b,C,d,e,end,f");
+        assert!(apply_hunks(&original[..2], &files[0].hunks).unwrap_err().starts_with("Hunk 1"));
+    }
+}
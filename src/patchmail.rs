@@ -0,0 +1,217 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::diff_core::LineChange;
+use crate::eol::{with_line_endings, OutputLineEndings};
+use crate::ui::write_hunks;
+
+/// One file of a patch: both versions and the changes between them
+#[derive(Clone, Debug)]
+pub struct PatchFile {
+    pub old_path: String,
+    pub new_path: String,
+    pub original: Vec<String>,
+    pub modified: Vec<String>,
+    pub changes: Vec<LineChange>,
+}
+
+/// Header templates accept `{summary}`, `{file}` (first file name) and `{files}` (file count)
+#[derive(Clone, Debug)]
+pub struct PatchEmailOptions {
+    pub from_template: String,
+    pub subject_template: String,
+    /// First line of the commit-style message
+    pub summary: String,
+    pub body: String,
+    /// RFC 2822 date; the current time when unset
+    pub date: Option<String>,
+    pub context_lines: usize,
+    /// Line endings of each file's hunk lines, so the patch applies to a CRLF checkout; headers
+    /// stay LF, as git writes them
+    pub line_endings: OutputLineEndings,
+}
+
+impl Default for PatchEmailOptions {
+    fn default() -> Self {
+        Self {
+            from_template: "Unknown <unknown@localhost>".to_string(),
+            subject_template: "[PATCH] {summary}".to_string(),
+            summary: "Update {file}".to_string(),
+            body: String::new(),
+            date: None,
+            context_lines: 3,
+            line_endings: OutputLineEndings::default(),
+        }
+    }
+}
+
+/// A `git format-patch`-style email: headers, message, diffstat and the unified diff of every file
+pub fn format_patch_email(files: &[PatchFile], options: &PatchEmailOptions) -> String {
+    let first_file = files
+        .first()
+        .map(|f| f.new_path.rsplit('/').next().unwrap_or(&f.new_path).to_string())
+        .unwrap_or_default();
+    let fill = |template: &str, summary: &str| {
+        template
+            .replace("{summary}", summary)
+            .replace("{file}", &first_file)
+            .replace("{files}", &files.len().to_string())
+    };
+    let summary = fill(&options.summary, "");
+    let date = options.date.clone().unwrap_or_else(|| {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        rfc2822_date(seconds)
+    });
+
+    let mut output = String::from("From 0000000000000000000000000000000000000000 Mon Sep 17 00:00:00 2001\n");
+    output.push_str(&format!("From: {}\n", fill(&options.from_template, &summary)));
+    output.push_str(&format!("Date: {}\n", date));
+    output.push_str(&format!("Subject: {}\n\n", fill(&options.subject_template, &summary)));
+    if !options.body.trim().is_empty() {
+        output.push_str(options.body.trim_end());
+        output.push_str("\n\n");
+    }
+    output.push_str("---\n");
+    output.push_str(&format_diffstat(files));
+    output.push('\n');
+
+    for file in files.iter().filter(|f| !f.changes.is_empty()) {
+        let (old, new) = (git_path("a", &file.old_path), git_path("b", &file.new_path));
+        output.push_str(&format!("diff --git {} {}\n--- {}\n+++ {}\n", old, new, old, new));
+        let hunks = unified_hunks(&file.original, &file.modified, &file.changes, options.context_lines);
+        output.push_str(&with_line_endings(&hunks, options.line_endings.resolve(&file.old_path, &file.new_path)));
+    }
+    output.push_str("-- \n");
+    output
+}
+
+fn git_path(prefix: &str, path: &str) -> String {
+    format!("{}/{}", prefix, path.trim_start_matches('/'))
+}
+
+/// ` path | 3 ++-` lines and the `N files changed` total, bars scaled to fit 50 columns
+pub fn format_diffstat(files: &[PatchFile]) -> String {
+    let counts: Vec<(&str, usize, usize)> = files
+        .iter()
+        .filter(|f| !f.changes.is_empty())
+        .map(|f| {
+            let added = f.changes.iter().map(|c| c.modified_end - c.modified_start).sum();
+            let deleted = f.changes.iter().map(|c| c.original_end - c.original_start).sum();
+            (f.new_path.as_str(), added, deleted)
+        })
+        .collect();
+    let name_width = counts.iter().map(|(path, ..)| path.len()).max().unwrap_or(0);
+    let largest = counts.iter().map(|(_, a, d)| a + d).max().unwrap_or(0);
+    let number_width = largest.to_string().len();
+
+    let mut output = String::new();
+    for (path, added, deleted) in &counts {
+        let total = added + deleted;
+        let (plus, minus) = if largest <= 50 {
+            (*added, *deleted)
+        } else {
+            let scale = |n: usize| if n == 0 { 0 } else { (n * 50 / largest).max(1) };
+            (scale(*added), scale(*deleted))
+        };
+        output.push_str(&format!(
+            " {:<name_width$} | {:>number_width$} {}{}\n",
+            path,
+            total,
+            "+".repeat(plus),
+            "-".repeat(minus)
+        ));
+    }
+
+    let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+    let (added, deleted): (usize, usize) = counts.iter().fold((0, 0), |(a, d), (_, x, y)| (a + x, d + y));
+    output.push_str(&format!(" {} changed", plural(counts.len(), "file")));
+    if added > 0 {
+        output.push_str(&format!(", {}(+)", plural(added, "insertion")));
+    }
+    if deleted > 0 {
+        output.push_str(&format!(", {}(-)", plural(deleted, "deletion")));
+    }
+    output.push('\n');
+    output
+}
+
+/// Applicable unified hunks; changes whose context would overlap share one hunk
+pub fn unified_hunks(original: &[String], modified: &[String], changes: &[LineChange], context_lines: usize) -> String {
+    write_hunks(original, modified, changes, context_lines, None)
+}
+
+/// `Tue, 03 Sep 2024 10:00:00 +0000` for seconds since the Unix epoch
+pub fn rfc2822_date(seconds: u64) -> String {
+    const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let days = seconds / 86_400;
+    let time = seconds % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} +0000",
+        DAYS[(days % 7) as usize],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    fn patch_file(path: &str, original: &str, modified: &str) -> PatchFile {
+        let (original, modified) = (lines(original), lines(modified));
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        PatchFile {
+            old_path: path.to_string(),
+            new_path: path.to_string(),
+            original,
+            modified,
+            changes,
+        }
+    }
+
+    #[test]
+    fn test_unified_hunks_merge_nearby_changes() {
+        let file = patch_file("f", "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14", "1\nX\n3\n4\n5\n6\nY\n8\n9\n10\n11\n12\n13\n14\n15");
+        let text = unified_hunks(&file.original, &file.modified, &file.changes, 3);
+        assert!(text.starts_with("@@ -1,10 +1,10 @@\n 1\n-2\n+X\n"));
+        assert!(text.contains("\n-7\n+Y\n 8\n 9\n 10\n@@ -12,3 +12,4 @@\n 12\n 13\n 14\n+15\n"));
+        assert_eq!(unified_hunks(&[], &lines("a"), &compute_diff(&[], &lines("a"), DiffOptions::default()), 3), "@@ -0,0 +1,1 @@\n+a\n");
+    }
+
+    #[test]
+    fn test_patch_email() {
+        let options = PatchEmailOptions {
+            from_template: "Dev <dev@example.com>".to_string(),
+            subject_template: "[PATCH v2] {summary}".to_string(),
+            body: "Longer explanation.".to_string(),
+            date: Some("Mon, 01 Jan 2024 00:00:00 +0000".to_string()),
+            ..PatchEmailOptions::default()
+        };
+        let text = format_patch_email(&[patch_file("/src/main.rs", "a\nb", "a\nc\nd")], &options);
+        assert!(text.contains("From: Dev <dev@example.com>\nDate: Mon, 01 Jan 2024 00:00:00 +0000\nSubject: [PATCH v2] Update main.rs\n\nLonger explanation.\n\n---\n"));
+        assert!(text.contains(" /src/main.rs | 3 ++-\n 1 file changed, 2 insertions(+), 1 deletion(-)\n"));
+        assert!(text.contains("diff --git a/src/main.rs b/src/main.rs\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,2 +1,3 @@\n a\n-b\n+c\n+d\n-- \n"));
+        assert_eq!(rfc2822_date(1_704_067_200), "Mon, 01 Jan 2024 00:00:00 +0000");
+
+        let options = PatchEmailOptions { line_endings: OutputLineEndings::Crlf, ..options };
+        let text = format_patch_email(&[patch_file("/src/main.rs", "a\nb", "a\nc\nd")], &options);
+        assert!(text.contains("+++ b/src/main.rs\n@@ -1,2 +1,3 @@\r\n a\r\n-b\r\n+c\r\n+d\r\n-- \n"));
+    }
+}
@@ -0,0 +1,170 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::diff_core::{compute_diff, DiffOptions, LineChange};
+use crate::file_handler::read_file_lines;
+use crate::patch::{apply_hunks, parse_unified, FileDiff};
+use crate::ui::format_unified_diff;
+
+/// Stands in for the lines between two hunks when only the patch's own context is known
+pub const GAP_LINE: &str = "⋯";
+
+/// One file of a pasted patch, rebuilt into two comparable snapshots
+#[derive(Clone, Debug)]
+pub struct PatchViewFile {
+    pub diff: FileDiff,
+    pub original: Vec<String>,
+    pub modified: Vec<String>,
+    pub changes: Vec<LineChange>,
+    /// Both snapshots are whole files, built by applying the patch to the local copy; otherwise
+    /// they hold only the hunks' lines, with a `GAP_LINE` between hunks
+    pub complete: bool,
+}
+
+/// A patch opened as a comparison, with paths resolved against `root`
+#[derive(Clone, Debug)]
+pub struct PatchView {
+    pub root: PathBuf,
+    pub files: Vec<PatchViewFile>,
+}
+
+impl PatchView {
+    pub fn open(text: &str, root: &Path, options: &DiffOptions) -> Result<Self, String> {
+        let diffs = parse_unified(text);
+        if diffs.is_empty() {
+            return Err("No file diffs found in the patch".to_string());
+        }
+        let files = diffs
+            .into_iter()
+            .map(|diff| {
+                let (original, modified, complete) = reconstruct(&diff, root);
+                PatchViewFile {
+                    changes: compute_diff(&original, &modified, options.clone()),
+                    diff,
+                    original,
+                    modified,
+                    complete,
+                }
+            })
+            .collect();
+        Ok(Self {
+            root: root.to_path_buf(),
+            files,
+        })
+    }
+
+    /// Every file's header and hunks, noting files shown from patch context alone
+    pub fn format(&self) -> String {
+        let mut output = String::new();
+        for (index, file) in self.files.iter().enumerate() {
+            let side = |path: &Option<String>| path.clone().unwrap_or_else(|| "/dev/null".to_string());
+            output.push_str(&format!("# [{}/{}] {}\n", index + 1, self.files.len(), file.diff.path()));
+            if file.diff.binary {
+                output.push_str("Binary file, not shown\n\n");
+                continue;
+            }
+            if !file.complete {
+                output.push_str("(local file missing or different; showing patch context only)\n");
+            }
+            output.push_str(&format_unified_diff(&side(&file.diff.old_path), &side(&file.diff.new_path), &file.changes));
+            output.push('\n');
+        }
+        output
+    }
+
+    /// The `hunk`-th change of the `file`-th file
+    pub fn hunk(&self, file: usize, hunk: usize) -> Option<&LineChange> {
+        self.files.get(file)?.changes.get(hunk)
+    }
+
+    /// Write the patched files under `root`: created and modified files get their new content,
+    /// deleted and renamed-away files are removed. Refuses, writing nothing, unless every
+    /// non-binary file was rebuilt from its local copy.
+    pub fn apply(&self) -> Result<Vec<String>, String> {
+        if let Some(file) = self.files.iter().find(|file| !file.complete && !file.diff.binary) {
+            return Err(format!("{} does not match the patch; nothing was applied", file.diff.path()));
+        }
+
+        let mut written = Vec::new();
+        for file in self.files.iter().filter(|file| !file.diff.binary) {
+            let target = |path: &str| self.root.join(path);
+            if let Some(new_path) = &file.diff.new_path {
+                let mut text = file.modified.join("\n");
+                if !file.modified.is_empty() {
+                    text.push('\n');
+                }
+                if let Some(parent) = target(new_path).parent() {
+                    fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+                }
+                fs::write(target(new_path), text).map_err(|e| format!("Failed to write {}: {}", new_path, e))?;
+                written.push(new_path.clone());
+            }
+            if let Some(old_path) = &file.diff.old_path {
+                let moved_away = file.diff.new_path.as_ref().is_none_or(|new| new != old_path) && !file.diff.is_copy;
+                if moved_away {
+                    fs::remove_file(target(old_path)).map_err(|e| format!("Failed to remove {}: {}", old_path, e))?;
+                }
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// Whole-file snapshots when the local copy takes the patch, else the hunks' own lines
+fn reconstruct(diff: &FileDiff, root: &Path) -> (Vec<String>, Vec<String>, bool) {
+    if diff.binary {
+        return (Vec::new(), Vec::new(), false);
+    }
+    let local = match &diff.old_path {
+        None => Some(Vec::new()),
+        Some(path) => read_file_lines(&root.join(path).to_string_lossy()).ok(),
+    };
+    if let Some(Ok(modified)) = local.as_ref().map(|original| apply_hunks(original, &diff.hunks)) {
+        return (local.unwrap_or_default(), modified, true);
+    }
+
+    let (mut original, mut modified) = (Vec::new(), Vec::new());
+    for (index, hunk) in diff.hunks.iter().enumerate() {
+        if index > 0 || hunk.old_start > 1 {
+            original.push(GAP_LINE.to_string());
+            modified.push(GAP_LINE.to_string());
+        }
+        original.extend(hunk.old_lines());
+        modified.extend(hunk.new_lines());
+    }
+    (original, modified, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+
+    const PATCH: &str = "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n@@ -3,2 +3,2 @@\n c\n-d\n+D\n\
+diff --git a/new.txt b/new.txt\nnew file mode 100644\n--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+fresh\n";
+
+    #[test]
+    fn test_open_and_apply() {
+        let root = TempDir::new("patchview");
+        fs::write(root.join("a.txt"), "a\nb\nc\nd\ne\n").unwrap();
+
+        let view = PatchView::open(PATCH, &root, &DiffOptions::default()).unwrap();
+        assert!(view.files.iter().all(|file| file.complete));
+        assert_eq!(view.hunk(0, 0).unwrap().original_start, 3);
+        assert!(view.format().starts_with("# [1/2] a.txt\n--- a.txt\n+++ a.txt\n"));
+
+        assert_eq!(view.apply().unwrap(), vec!["a.txt", "new.txt"]);
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "a\nb\nc\nD\ne\n");
+        assert_eq!(fs::read_to_string(root.join("new.txt")).unwrap(), "fresh\n");
+    }
+
+    #[test]
+    fn test_context_only_view() {
+        let view = PatchView::open(PATCH, Path::new("/nonexistent"), &DiffOptions::default()).unwrap();
+        let file = &view.files[0];
+        assert!(!file.complete);
+        assert_eq!(file.original, vec![GAP_LINE, "c", "d"]);
+        assert_eq!(file.changes.len(), 1);
+        assert!(view.format().contains("showing patch context only"));
+        assert!(view.apply().unwrap_err().contains("nothing was applied"));
+    }
+}
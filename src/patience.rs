@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use crate::clock::Clock;
+use crate::diff_core::{backtrack_changes, compute_lcs_matrix, merge_adjacent_changes, ChangeType, LineChange};
+
+/// Largest gap between anchors diffed with the full LCS table; bigger ones become one change
+pub const PATIENCE_LEAF_CELLS: usize = 1_000_000;
+
+/// Patience diff: lines occurring exactly once on each side anchor the alignment, taken in the
+/// longest order-preserving run, and the gaps between anchors are diffed the same way until
+/// none are left, then with the full table when small enough. Ranges are kept on an explicit
+/// stack rather than recursed into. Once `clock` runs out, remaining gaps become single changes.
+pub(crate) fn patience_changes(
+    original_hashes: &[u64],
+    modified_hashes: &[u64],
+    same: &impl Fn(usize, usize) -> bool,
+    clock: &dyn Clock,
+) -> Vec<LineChange> {
+    let mut changes = Vec::new();
+    let mut pending = vec![(0, original_hashes.len(), 0, modified_hashes.len())];
+    while let Some((mut a0, mut a1, mut b0, mut b1)) = pending.pop() {
+        while a0 < a1 && b0 < b1 && same(a0, b0) {
+            (a0, b0) = (a0 + 1, b0 + 1);
+        }
+        while a0 < a1 && b0 < b1 && same(a1 - 1, b1 - 1) {
+            (a1, b1) = (a1 - 1, b1 - 1);
+        }
+        if a0 == a1 && b0 == b1 {
+            continue;
+        }
+        if a0 == a1 || b0 == b1 || clock.expired((a1 - a0 + b1 - b0) as u64) {
+            changes.push(range_change(a0, a1, b0, b1));
+            continue;
+        }
+
+        let anchors = unique_anchors(&original_hashes[a0..a1], &modified_hashes[b0..b1])
+            .into_iter()
+            .map(|(i, j)| (a0 + i, b0 + j))
+            .filter(|&(i, j)| same(i, j))
+            .collect::<Vec<_>>();
+        let anchors = longest_increasing_run(&anchors);
+        if anchors.is_empty() {
+            changes.extend(leaf_changes(a0, a1, b0, b1, same, clock));
+            continue;
+        }
+        let (mut i, mut j) = (a0, b0);
+        for &(anchor_i, anchor_j) in &anchors {
+            pending.push((i, anchor_i, j, anchor_j));
+            (i, j) = (anchor_i + 1, anchor_j + 1);
+        }
+        pending.push((i, a1, j, b1));
+    }
+    changes.sort_by_key(|change| (change.original_start, change.modified_start));
+    merge_adjacent_changes(changes)
+}
+
+/// Positions of the lines occurring exactly once in each of `a` and `b` with the same hash,
+/// in the order of `a`
+pub(crate) fn unique_anchors(a: &[u64], b: &[u64]) -> Vec<(usize, usize)> {
+    // Count and last position on each side, per hash
+    let mut counts: HashMap<u64, (usize, usize, usize, usize)> = HashMap::new();
+    for (i, hash) in a.iter().enumerate() {
+        let entry = counts.entry(*hash).or_default();
+        (entry.0, entry.1) = (entry.0 + 1, i);
+    }
+    for (j, hash) in b.iter().enumerate() {
+        if let Some(entry) = counts.get_mut(hash) {
+            (entry.2, entry.3) = (entry.2 + 1, j);
+        }
+    }
+    a.iter()
+        .filter_map(|hash| match counts[hash] {
+            (1, i, 1, j) => Some((i, j)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The longest subsequence of `pairs` (sorted by first element) increasing in the second
+pub(crate) fn longest_increasing_run(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // Patience sorting: the top of each pile, and each pair's predecessor in its run
+    let mut piles: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; pairs.len()];
+    for (index, &(_, j)) in pairs.iter().enumerate() {
+        let pile = piles.partition_point(|&top| pairs[top].1 < j);
+        previous[index] = pile.checked_sub(1).map(|below| piles[below]);
+        if pile == piles.len() {
+            piles.push(index);
+        } else {
+            piles[pile] = index;
+        }
+    }
+    let mut run = Vec::new();
+    let mut next = piles.last().copied();
+    while let Some(index) = next {
+        run.push(pairs[index]);
+        next = previous[index];
+    }
+    run.reverse();
+    run
+}
+
+/// A gap without anchors: the full table when it is small enough and finishes in time, one
+/// change otherwise
+fn leaf_changes(
+    a0: usize,
+    a1: usize,
+    b0: usize,
+    b1: usize,
+    same: &impl Fn(usize, usize) -> bool,
+    clock: &dyn Clock,
+) -> Vec<LineChange> {
+    let (m, n) = (a1 - a0, b1 - b0);
+    let local = |i: usize, j: usize| same(a0 + i, b0 + j);
+    let table = (m.saturating_mul(n) <= PATIENCE_LEAF_CELLS)
+        .then(|| compute_lcs_matrix(m, n, &local, clock))
+        .flatten();
+    let Some(table) = table else {
+        return vec![range_change(a0, a1, b0, b1)];
+    };
+    let mut changes = backtrack_changes(&table, m, n, &local);
+    for change in &mut changes {
+        change.original_start += a0;
+        change.original_end += a0;
+        change.modified_start += b0;
+        change.modified_end += b0;
+    }
+    changes
+}
+
+fn range_change(a0: usize, a1: usize, b0: usize, b1: usize) -> LineChange {
+    let change_type = match (a0 == a1, b0 == b1) {
+        (true, _) => ChangeType::Added,
+        (_, true) => ChangeType::Deleted,
+        _ => ChangeType::Modified,
+    };
+    LineChange {
+        original_start: a0,
+        original_end: a1,
+        modified_start: b0,
+        modified_end: b1,
+        change_type,
+        char_changes: None,
+        generated: false,
+        tags: Vec::new(),
+        id: String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::HostClock;
+    use crate::diff_core::verify;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_patience_changes() {
+        // Moving a function: the full table may match stray braces, anchors keep it readable
+        let original = lines("fn a() {\n    one();\n}\n\nfn b() {\n    two();\n}\n\nfn c() {\n    three();\n}");
+        let modified = lines("fn b() {\n    two();\n}\n\nfn a() {\n    one();\n}\n\nfn c() {\n    three();\n    four();\n}");
+        let hash = |lines: &[String]| -> Vec<u64> {
+            lines.iter().map(|line| line.bytes().fold(5381u64, |h, b| h.wrapping_mul(33) ^ b as u64)).collect()
+        };
+        let same = |i: usize, j: usize| original[i] == modified[j];
+        let changes = patience_changes(&hash(&original), &hash(&modified), &same, &HostClock::new(60_000));
+        assert!(verify(&original, &modified, &changes).is_ok());
+        let ranges: Vec<(usize, usize, usize, usize)> =
+            changes.iter().map(|c| (c.original_start, c.original_end, c.modified_start, c.modified_end)).collect();
+        assert_eq!(ranges, vec![(0, 4, 0, 0), (8, 8, 4, 8), (10, 10, 10, 11)]);
+
+        assert_eq!(longest_increasing_run(&[(0, 3), (1, 1), (2, 2), (3, 0), (4, 4)]), vec![(1, 1), (2, 2), (4, 4)]);
+    }
+}
@@ -0,0 +1,251 @@
+//! A small regular-expression subset for user-configurable patterns.
+//!
+//! Supported: literals, `.`, `\d`, `\w`, `\s`, escaped metacharacters, character classes
+//! (`[a-z_]`, `[^\]]`), the quantifiers `?`, `*`, `+`, `{n}`, `{n,}` and `{n,m}`, and a
+//! leading `(?i)` for case-insensitive matching (character classes should then be written
+//! in lowercase). Groups and alternation are not.
+
+#[derive(Clone, Debug)]
+enum Atom {
+    Any,
+    Literal(char),
+    Digit,
+    Word,
+    Space,
+    Class { negated: bool, items: Vec<(char, char)> },
+}
+
+/// Minimum and maximum number of repetitions of an atom
+type Repeat = (usize, usize);
+
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    source: String,
+    items: Vec<(Atom, Repeat)>,
+    case_insensitive: bool,
+}
+
+impl Pattern {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let (case_insensitive, body) = match source.strip_prefix("(?i)") {
+            Some(body) => (true, body),
+            None => (false, source),
+        };
+        let chars: Vec<char> = body.chars().collect();
+        let mut items = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let atom = match chars[i] {
+                '.' => Atom::Any,
+                '\\' => {
+                    i += 1;
+                    match chars.get(i) {
+                        Some('d') => Atom::Digit,
+                        Some('w') => Atom::Word,
+                        Some('s') => Atom::Space,
+                        Some(&c) => Atom::Literal(c),
+                        None => return Err(format!("trailing backslash in pattern {:?}", source)),
+                    }
+                }
+                '[' => {
+                    let (atom, end) = parse_class(&chars, i + 1)
+                        .ok_or_else(|| format!("unterminated character class in pattern {:?}", source))?;
+                    i = end;
+                    atom
+                }
+                '?' | '*' | '+' => return Err(format!("dangling quantifier in pattern {:?}", source)),
+                c if case_insensitive => Atom::Literal(c.to_ascii_lowercase()),
+                c => Atom::Literal(c),
+            };
+            i += 1;
+
+            let repeat = match chars.get(i) {
+                Some('?') => (0, 1),
+                Some('*') => (0, usize::MAX),
+                Some('+') => (1, usize::MAX),
+                Some('{') => {
+                    let (repeat, end) = parse_count(&chars, i + 1)
+                        .ok_or_else(|| format!("malformed repetition count in pattern {:?}", source))?;
+                    i = end;
+                    repeat
+                }
+                _ => {
+                    items.push((atom, (1, 1)));
+                    continue;
+                }
+            };
+            i += 1;
+            items.push((atom, repeat));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            items,
+            case_insensitive,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Length in chars of the longest match anchored at the start of `text`
+    pub fn match_prefix(&self, text: &[char]) -> Option<usize> {
+        if self.case_insensitive {
+            let lowered: Vec<char> = text.iter().map(|c| c.to_ascii_lowercase()).collect();
+            return match_items(&self.items, &lowered, 0);
+        }
+        match_items(&self.items, text, 0)
+    }
+
+    /// Char ranges of non-overlapping, non-empty matches, scanning left to right
+    pub fn find_all(&self, text: &str) -> Vec<(usize, usize)> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut matches = Vec::new();
+        let mut start = 0;
+
+        while start < chars.len() {
+            match self.match_prefix(&chars[start..]) {
+                Some(len) if len > 0 => {
+                    matches.push((start, start + len));
+                    start += len;
+                }
+                _ => start += 1,
+            }
+        }
+        matches
+    }
+}
+
+/// Parse `n}`, `n,}` or `n,m}` starting after the opening brace
+fn parse_count(chars: &[char], start: usize) -> Option<(Repeat, usize)> {
+    let close = start + chars[start..].iter().position(|&c| c == '}')?;
+    let body: String = chars[start..close].iter().collect();
+    let repeat = match body.split_once(',') {
+        None => {
+            let n = body.parse().ok()?;
+            (n, n)
+        }
+        Some((min, "")) => (min.parse().ok()?, usize::MAX),
+        Some((min, max)) => (min.parse().ok()?, max.parse().ok()?),
+    };
+    Some((repeat, close))
+}
+
+fn parse_class(chars: &[char], mut i: usize) -> Option<(Atom, usize)> {
+    let negated = chars.get(i) == Some(&'^');
+    if negated {
+        i += 1;
+    }
+
+    let mut items = Vec::new();
+    while i < chars.len() && chars[i] != ']' {
+        let mut c = chars[i];
+        if c == '\\' {
+            i += 1;
+            c = *chars.get(i)?;
+            match c {
+                'd' => {
+                    items.push(('0', '9'));
+                    i += 1;
+                    continue;
+                }
+                's' => {
+                    items.extend([(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')]);
+                    i += 1;
+                    continue;
+                }
+                'w' => {
+                    items.extend([('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')]);
+                    i += 1;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&end| end != ']') {
+            items.push((c, chars[i + 2]));
+            i += 3;
+        } else {
+            items.push((c, c));
+            i += 1;
+        }
+    }
+
+    (i < chars.len()).then_some((Atom::Class { negated, items }, i))
+}
+
+fn atom_matches(atom: &Atom, c: char) -> bool {
+    match atom {
+        Atom::Any => true,
+        Atom::Literal(l) => *l == c,
+        Atom::Digit => c.is_ascii_digit(),
+        Atom::Word => c.is_alphanumeric() || c == '_',
+        Atom::Space => c.is_whitespace(),
+        Atom::Class { negated, items } => {
+            items.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+        }
+    }
+}
+
+/// Greedy matching with backtracking; iteration over the repeat count keeps recursion
+/// depth bounded by the number of pattern items rather than the text length
+fn match_items(items: &[(Atom, Repeat)], text: &[char], position: usize) -> Option<usize> {
+    let Some(((atom, (min, max)), rest)) = items.split_first() else {
+        return Some(position);
+    };
+
+    let (min, max) = (*min, *max);
+    let mut count = 0;
+    while count < max
+        && position + count < text.len()
+        && atom_matches(atom, text[position + count])
+    {
+        count += 1;
+    }
+
+    while count >= min {
+        if let Some(end) = match_items(rest, text, position + count) {
+            return Some(end);
+        }
+        if count == 0 {
+            break;
+        }
+        count -= 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefix(pattern: &str, text: &str) -> Option<usize> {
+        let chars: Vec<char> = text.chars().collect();
+        Pattern::parse(pattern).unwrap().match_prefix(&chars)
+    }
+
+    #[test]
+    fn test_match_prefix() {
+        assert_eq!(prefix(r"\d+-\d+", "2024-01 rest"), Some(7));
+        assert_eq!(prefix(r"\[[^\]]*\]", "[main] x"), Some(6));
+        assert_eq!(prefix(r"a?b", "b"), Some(1));
+        assert_eq!(prefix(r"\w+:", "no colon"), None);
+        assert_eq!(prefix(r"\d{2,3}x", "1234x"), None);
+        assert_eq!(prefix(r"\d{4}", "12345"), Some(4));
+        assert_eq!(prefix(r"a{2,}", "aaab"), Some(3));
+    }
+
+    #[test]
+    fn test_find_all_and_errors() {
+        let pattern = Pattern::parse(r"[A-Z]+_KEY").unwrap();
+        assert_eq!(pattern.find_all("x API_KEY y SECRET_KEY"), vec![(2, 9), (12, 22)]);
+        assert!(Pattern::parse("[abc").is_err());
+        assert!(Pattern::parse("+a").is_err());
+
+        let insensitive = Pattern::parse("(?i)password").unwrap();
+        assert_eq!(insensitive.find_all("PassWord=1"), vec![(0, 8)]);
+    }
+}
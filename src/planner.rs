@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use crate::band::DEFAULT_BAND_WIDTH;
+use crate::diff_core::DiffOptions;
+
+/// LCS cells the full algorithm is expected to fill per millisecond of `max_computation_time_ms`
+pub const CELLS_PER_MS: u64 = 20_000;
+
+/// Inputs this small always get the full algorithm, however short the time budget
+pub const MIN_FULL_CELLS: u64 = 1_000_000;
+
+/// Share of sampled lines that must appear on the other side for anchoring to pay off
+pub const MIN_PATIENCE_SIMILARITY: f32 = 0.5;
+
+/// Share of sampled lines that must appear on the other side to search a band first
+pub const MIN_BAND_SIMILARITY: f32 = 0.9;
+
+/// Lines sampled from each side to estimate similarity
+const SIMILARITY_SAMPLES: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffAlgorithm {
+    /// The full LCS table: minimal diffs, quadratic time and memory
+    Full,
+    /// The full table limited to diagonals near the straight path, widened when that is not enough
+    Banded,
+    /// Anchor on lines unique to both sides and diff the gaps between them separately
+    Patience,
+    /// One change between the common prefix and suffix
+    Coarse,
+}
+
+impl DiffAlgorithm {
+    pub fn name(self) -> &'static str {
+        match self {
+            DiffAlgorithm::Full => "full",
+            DiffAlgorithm::Banded => "banded",
+            DiffAlgorithm::Patience => "patience",
+            DiffAlgorithm::Coarse => "coarse",
+        }
+    }
+}
+
+/// Peak bytes each algorithm is estimated to need
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryEstimate {
+    pub full: u64,
+    pub patience: u64,
+    /// The initial band, before any widening
+    pub band: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffPlan {
+    pub algorithm: DiffAlgorithm,
+    pub reason: String,
+    /// Estimated share of lines found on both sides; `None` when the decision did not need it
+    pub similarity: Option<f32>,
+}
+
+/// How a diff was computed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiffStats {
+    pub algorithm: DiffAlgorithm,
+    pub reason: String,
+    pub similarity: Option<f32>,
+    pub original_lines: usize,
+    pub modified_lines: usize,
+    /// The planned algorithm ran out of time or memory, or failed verification, and a cheaper
+    /// one was used
+    pub fell_back: bool,
+    /// Runs of unique common lines the inputs were split at before diffing the gaps between them
+    pub anchor_runs: usize,
+    pub elapsed_ms: u64,
+}
+
+/// Pick an algorithm from the input sizes, the memory and time budgets in `options`, and,
+/// for inputs past the size the full algorithm always gets, how similar sampled lines say they
+/// are: near-identical ones search a band first, which stays near-linear for small edits
+pub fn plan_diff(original_hashes: &[u64], modified_hashes: &[u64], memory: MemoryEstimate, options: &DiffOptions) -> DiffPlan {
+    let fits = |required: u64| options.max_memory_bytes.is_none_or(|limit| required <= limit);
+    let cells = original_hashes.len() as u64 * modified_hashes.len() as u64;
+    let time_cells = options.max_computation_time_ms.saturating_mul(CELLS_PER_MS);
+    let cell_budget = time_cells.min(options.max_operations.unwrap_or(u64::MAX)).max(MIN_FULL_CELLS);
+    let within_budget = fits(memory.full) && cells <= cell_budget;
+    let full = |similarity| DiffPlan {
+        algorithm: DiffAlgorithm::Full,
+        reason: format!("{} cells within the budget of {}", cells, cell_budget),
+        similarity,
+    };
+    if within_budget && cells <= MIN_FULL_CELLS {
+        return full(None);
+    }
+
+    let similarity = estimate_similarity(original_hashes, modified_hashes);
+    let band_width = options.band_width.unwrap_or(DEFAULT_BAND_WIDTH);
+    if band_width > 0 && similarity >= MIN_BAND_SIMILARITY && fits(memory.band) {
+        return DiffPlan {
+            algorithm: DiffAlgorithm::Banded,
+            reason: format!("{:.0}% of sampled lines shared, searching {} diagonals either side first", similarity * 100.0, band_width),
+            similarity: Some(similarity),
+        };
+    }
+    if within_budget {
+        return full(Some(similarity));
+    }
+
+    let constraint = if fits(memory.full) { "time" } else { "memory" };
+    let (algorithm, reason) = if similarity < MIN_PATIENCE_SIMILARITY {
+        (DiffAlgorithm::Coarse, format!("over the {} budget and too dissimilar to anchor", constraint))
+    } else if !fits(memory.patience) {
+        (DiffAlgorithm::Coarse, "over the memory budget even for anchoring".to_string())
+    } else {
+        (DiffAlgorithm::Patience, format!("over the {} budget, similar enough to anchor", constraint))
+    };
+    DiffPlan { algorithm, reason, similarity: Some(similarity) }
+}
+
+/// Share of evenly sampled lines from each side that also occur on the other side
+pub fn estimate_similarity(original_hashes: &[u64], modified_hashes: &[u64]) -> f32 {
+    let sampled_share = |from: &[u64], to: &[u64]| -> (usize, usize) {
+        if from.is_empty() {
+            return (0, 0);
+        }
+        let present: HashSet<u64> = to.iter().copied().collect();
+        let step = from.len().div_ceil(SIMILARITY_SAMPLES);
+        let samples: Vec<u64> = from.iter().step_by(step).copied().collect();
+        (samples.iter().filter(|hash| present.contains(hash)).count(), samples.len())
+    };
+    let (found_a, sampled_a) = sampled_share(original_hashes, modified_hashes);
+    let (found_b, sampled_b) = sampled_share(modified_hashes, original_hashes);
+    if sampled_a + sampled_b == 0 {
+        return 1.0;
+    }
+    (found_a + found_b) as f32 / (sampled_a + sampled_b) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_diff() {
+        let memory = MemoryEstimate { full: 1 << 20, patience: 1 << 10, band: 1 << 12 };
+        let options = DiffOptions { max_computation_time_ms: 0, ..DiffOptions::default() };
+        let small: Vec<u64> = (0..100).collect();
+        assert_eq!(plan_diff(&small, &small, memory, &options).algorithm, DiffAlgorithm::Full);
+
+        let large: Vec<u64> = (0..2000).collect();
+        let mut edited = large.clone();
+        edited[1000] = 99_999;
+        let plan = plan_diff(&large, &edited, memory, &options);
+        assert_eq!(plan.algorithm, DiffAlgorithm::Banded);
+        assert!(plan.similarity.unwrap() > 0.95);
+        assert_eq!(plan.reason, "100% of sampled lines shared, searching 64 diagonals either side first");
+        let unbanded = DiffOptions { band_width: Some(0), ..options.clone() };
+        let plan = plan_diff(&large, &edited, memory, &unbanded);
+        assert_eq!((plan.algorithm, plan.reason.as_str()), (DiffAlgorithm::Patience, "over the time budget, similar enough to anchor"));
+
+        let unrelated: Vec<u64> = (5000..7000).collect();
+        assert_eq!(plan_diff(&large, &unrelated, memory, &options).algorithm, DiffAlgorithm::Coarse);
+        let tight = DiffOptions { max_memory_bytes: Some(1 << 15), band_width: Some(0), ..DiffOptions::default() };
+        let plan = plan_diff(&small, &small, memory, &tight);
+        assert_eq!((plan.algorithm, plan.reason.as_str()), (DiffAlgorithm::Patience, "over the memory budget, similar enough to anchor"));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::minified::split_minified;
+
+const INDENT: &str = "  ";
+
+/// Formats `pretty_print` can re-indent
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrettyFormat {
+    Json,
+    Xml,
+    /// JavaScript, CSS and other brace-delimited text
+    Script,
+}
+
+impl PrettyFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            PrettyFormat::Json => "json",
+            PrettyFormat::Xml => "xml",
+            PrettyFormat::Script => "script",
+        }
+    }
+
+    /// The format of `path` by extension, or for unknown extensions by its first character
+    pub fn detect(path: &str, lines: &[String]) -> Option<Self> {
+        let extension = path.rsplit('/').next()?.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("json" | "jsonc" | "geojson" | "webmanifest") => Some(PrettyFormat::Json),
+            Some("xml" | "svg" | "xhtml" | "plist" | "csproj" | "xsd" | "xsl") => Some(PrettyFormat::Xml),
+            Some("js" | "mjs" | "cjs" | "css" | "scss" | "less") => Some(PrettyFormat::Script),
+            _ => match lines.iter().flat_map(|line| line.chars()).find(|c| !c.is_whitespace()) {
+                Some('{' | '[') => Some(PrettyFormat::Json),
+                Some('<') => Some(PrettyFormat::Xml),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Re-indent `lines` one element per line, so compact or inconsistently formatted files diff by
+/// structure. Purely lexical: nothing is parsed, so invalid input is re-indented as best it can be.
+pub fn pretty_print(lines: &[String], format: PrettyFormat) -> Vec<String> {
+    match format {
+        PrettyFormat::Json | PrettyFormat::Script => indent_braces(split_minified(lines)),
+        PrettyFormat::Xml => indent_xml(xml_nodes(lines)),
+    }
+}
+
+fn indent_braces(lines: Vec<String>) -> Vec<String> {
+    let mut depth = 0usize;
+    lines
+        .into_iter()
+        .map(|line| {
+            if line.starts_with(['}', ']']) {
+                depth = depth.saturating_sub(1);
+            }
+            let indented = format!("{}{}", INDENT.repeat(depth), line);
+            if line.ends_with(['{', '[']) {
+                depth += 1;
+            }
+            indented
+        })
+        .collect()
+}
+
+/// Tags, comments and trimmed text runs, in order
+fn xml_nodes(lines: &[String]) -> Vec<String> {
+    let text = lines.join("\n");
+    let mut nodes = Vec::new();
+    let mut rest = text.as_str();
+    while !rest.is_empty() {
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map_or(rest.len(), |at| at + 3)
+        } else if rest.starts_with('<') {
+            tag_end(rest)
+        } else {
+            rest.find('<').unwrap_or(rest.len())
+        };
+        let node = rest[..end].trim();
+        if !node.is_empty() {
+            nodes.push(node.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        rest = &rest[end..];
+    }
+    nodes
+}
+
+/// Length of the tag at the start of `text`, up to its closing `>` outside attribute quotes
+fn tag_end(text: &str) -> usize {
+    let mut quote = None;
+    for (at, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, '>') => return at + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+fn indent_xml(nodes: Vec<String>) -> Vec<String> {
+    let mut depth = 0usize;
+    nodes
+        .into_iter()
+        .map(|node| {
+            if node.starts_with("</") {
+                depth = depth.saturating_sub(1);
+            }
+            let indented = format!("{}{}", INDENT.repeat(depth), node);
+            let opens = node.starts_with('<') && !node[1..].starts_with(['/', '?', '!']) && !node.ends_with("/>");
+            if opens {
+                depth += 1;
+            }
+            indented
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_pretty_print_json_and_script() {
+        let json = lines(r#"{"a":[1,2],"b":{"c":"x,y"}}"#);
+        assert_eq!(PrettyFormat::detect("data", &json), Some(PrettyFormat::Json));
+        assert_eq!(
+            pretty_print(&json, PrettyFormat::Json),
+            lines("{\n  \"a\":[\n    1,\n    2\n  ],\n  \"b\":{\n    \"c\":\"x,y\"\n  }\n}")
+        );
+        assert_eq!(
+            pretty_print(&lines("a{color:red;margin:0}"), PrettyFormat::Script),
+            lines("a{\n  color:red;\n  margin:0\n}")
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_xml() {
+        let xml = lines(r#"<?xml version="1.0"?><root><item id="a>b">text</item><!-- c > d --><br/></root>"#);
+        assert_eq!(PrettyFormat::detect("feed.xml", &xml), Some(PrettyFormat::Xml));
+        assert_eq!(
+            pretty_print(&xml, PrettyFormat::Xml),
+            vec![
+                "<?xml version=\"1.0\"?>",
+                "<root>",
+                "  <item id=\"a>b\">",
+                "    text",
+                "  </item>",
+                "  <!-- c > d -->",
+                "  <br/>",
+                "</root>",
+            ]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crate::diff_core::{DiffOptions, Granularity};
+use crate::logmask::default_log_mask_patterns;
+use crate::ui::OutputFormat;
+
+pub const DEFAULT_PROFILE: &str = "strict";
+
+pub const PROFILE_NAMES: &[&str] = &["strict", "code-review", "whitespace-insensitive", "prose", "log"];
+
+/// A named bundle of diff options and output format
+#[derive(Clone, Debug)]
+pub struct Profile {
+    pub name: &'static str,
+    pub options: DiffOptions,
+    pub output_format: OutputFormat,
+}
+
+pub fn profile_by_name(name: &str) -> Option<Profile> {
+    let defaults = DiffOptions::default();
+
+    // Each profile's format suits what it is for: reviews read replacements inline, prose
+    // reads best as marked-up words and logs are referred to by line number
+    let (name, options, output_format) = match name {
+        "strict" => (
+            "strict",
+            DiffOptions {
+                granularity: Granularity::Char,
+                ..defaults
+            },
+            OutputFormat::Unified,
+        ),
+        "code-review" => (
+            "code-review",
+            DiffOptions {
+                granularity: Granularity::Word,
+                detect_embedded_languages: true,
+                normalize_imports: true,
+                ..defaults
+            },
+            OutputFormat::Inline,
+        ),
+        "whitespace-insensitive" => (
+            "whitespace-insensitive",
+            DiffOptions {
+                ignore_whitespace: true,
+                granularity: Granularity::Word,
+                ..defaults
+            },
+            OutputFormat::Unified,
+        ),
+        "prose" => (
+            "prose",
+            DiffOptions {
+                ignore_whitespace: true,
+                ignore_case: true,
+                granularity: Granularity::Word,
+                ..defaults
+            },
+            OutputFormat::Wdiff,
+        ),
+        "log" => (
+            "log",
+            DiffOptions {
+                ignore_whitespace: true,
+                granularity: Granularity::Word,
+                mask_patterns: default_log_mask_patterns(),
+                ..defaults
+            },
+            OutputFormat::Numbered,
+        ),
+        _ => return None,
+    };
+
+    Some(Profile {
+        name,
+        options,
+        output_format,
+    })
+}
+
+pub fn default_profile() -> Profile {
+    profile_by_name(DEFAULT_PROFILE).expect("default profile exists")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_profiles_resolve() {
+        for name in PROFILE_NAMES {
+            assert_eq!(profile_by_name(name).unwrap().name, *name);
+        }
+        assert!(profile_by_name("nonexistent").is_none());
+        assert!(profile_by_name("whitespace-insensitive").unwrap().options.ignore_whitespace);
+        assert_eq!(profile_by_name("prose").unwrap().output_format, OutputFormat::Wdiff);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::diff_core::LineChange;
+use crate::patchmail::unified_hunks;
+use crate::structure::{format_function_summary, summarize_functions};
+
+/// Rough size of a token in characters, close enough for English text and code
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Kept free for the omission trailer
+const TRAILER_TOKENS: usize = 20;
+
+/// How much of a comparison to put in a prompt
+#[derive(Clone, Copy, Debug)]
+pub struct PromptOptions {
+    pub token_budget: usize,
+    /// Unchanged lines around each hunk, dropped first when a hunk doesn't fit
+    pub context_lines: usize,
+}
+
+impl Default for PromptOptions {
+    fn default() -> Self {
+        Self {
+            token_budget: 4000,
+            context_lines: 2,
+        }
+    }
+}
+
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// A comparison packaged for an assistant to summarize or review: diffstat, changed definitions,
+/// then as many hunks as fit in the token budget. A hunk that doesn't fit is retried without
+/// context, then cut short; the hunks left out are counted at the end.
+pub fn package_comparison(
+    file1_path: &str,
+    file2_path: &str,
+    original: &[String],
+    modified: &[String],
+    changes: &[LineChange],
+    options: &PromptOptions,
+) -> String {
+    let added: usize = changes.iter().map(|c| c.modified_end - c.modified_start).sum();
+    let deleted: usize = changes.iter().map(|c| c.original_end - c.original_start).sum();
+    let mut output = format!(
+        "Diff of {} -> {}: {} hunk(s), +{} -{} line(s)\n",
+        file1_path,
+        file2_path,
+        changes.len(),
+        added,
+        deleted
+    );
+    let budget = options.token_budget.saturating_sub(TRAILER_TOKENS);
+    let summary = format_function_summary(&summarize_functions(original, modified, changes));
+    if estimate_tokens(&output) + estimate_tokens(&summary) <= budget / 4 {
+        output.push_str(&summary);
+    }
+    output.push_str(&format!("--- a/{}\n+++ b/{}\n", file1_path, file2_path));
+
+    let mut used = estimate_tokens(&output);
+    for (index, change) in changes.iter().enumerate() {
+        let change = std::slice::from_ref(change);
+        let with_context = unified_hunks(original, modified, change, options.context_lines);
+        let hunk = if used + estimate_tokens(&with_context) <= budget {
+            with_context
+        } else {
+            unified_hunks(original, modified, change, 0)
+        };
+        let cost = estimate_tokens(&hunk);
+        if used + cost <= budget {
+            output.push_str(&hunk);
+            used += cost;
+            continue;
+        }
+        // Cut the hunk short when at least its header and one row still fit
+        let mut trimmed = String::new();
+        let rows = hunk.lines().count();
+        for (shown, row) in hunk.lines().enumerate() {
+            if used + estimate_tokens(&trimmed) + estimate_tokens(row) + 1 > budget {
+                if shown > 1 {
+                    output.push_str(&trimmed);
+                    output.push_str(&format!("… {} line(s) of this hunk trimmed\n", rows - shown));
+                    return omitted(output, changes.len() - index - 1);
+                }
+                break;
+            }
+            trimmed.push_str(row);
+            trimmed.push('\n');
+        }
+        return omitted(output, changes.len() - index);
+    }
+    output
+}
+
+fn omitted(mut output: String, hunks: usize) -> String {
+    if hunks > 0 {
+        output.push_str(&format!("… {} more hunk(s) omitted to fit the token budget\n", hunks));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_package_comparison_fits_budget() {
+        let original: Vec<String> = (0..200).map(|i| format!("line number {}", i)).collect();
+        let mut modified = original.clone();
+        for i in (0..200).step_by(20) {
+            modified[i] = format!("changed line number {}", i);
+        }
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+
+        let full = package_comparison("a", "b", &original, &modified, &changes, &PromptOptions::default());
+        assert!(full.starts_with("Diff of a -> b: 10 hunk(s), +10 -10 line(s)\n"));
+        assert!(full.contains("@@ -1,3 +1,3 @@\n-line number 0\n+changed line number 0\n line number 1\n line number 2\n"));
+        assert_eq!(full.matches("@@ -").count(), 10);
+
+        let options = PromptOptions { token_budget: 120, ..PromptOptions::default() };
+        let small = package_comparison("a", "b", &original, &modified, &changes, &options);
+        assert!(estimate_tokens(&small) <= 120);
+        assert!(small.ends_with("more hunk(s) omitted to fit the token budget\n"));
+    }
+
+    #[test]
+    fn test_large_hunk_is_trimmed() {
+        let original = lines("keep");
+        let modified: Vec<String> = (0..100).map(|i| format!("added {}", i)).collect();
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let options = PromptOptions { token_budget: 150, ..PromptOptions::default() };
+        let packaged = package_comparison("a", "b", &original, &modified, &changes, &options);
+        assert!(estimate_tokens(&packaged) <= 150);
+        assert!(packaged.contains("+added 0\n"));
+        assert!(packaged.contains("line(s) of this hunk trimmed\n"));
+    }
+}
@@ -0,0 +1,211 @@
+//! Protocol Buffers schema comparison with wire-compatibility warnings.
+
+/// A numbered member of a message or enum: a field, or an enum value (with an empty type)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProtoField {
+    /// Dotted path of the enclosing message or enum, e.g. `Order.Item`
+    pub scope: String,
+    pub name: String,
+    pub number: u32,
+    /// Label and type, e.g. `repeated string`; empty for enum values
+    pub field_type: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProtoSchema {
+    pub fields: Vec<ProtoField>,
+    /// `(scope, number)` pairs declared `reserved`
+    pub reserved: Vec<(String, u32)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaChange {
+    /// Breaks reading old data with the new schema or vice versa
+    pub incompatible: bool,
+    pub message: String,
+}
+
+/// Line-based parse of messages, enums and `oneof`s; services and options are skipped
+pub fn parse_proto(lines: &[String]) -> ProtoSchema {
+    let mut schema = ProtoSchema::default();
+    // Enclosing blocks: (name, contributes to scope path, is enum)
+    let mut blocks: Vec<(String, bool, bool)> = Vec::new();
+
+    for line in lines {
+        let code = line.split("//").next().unwrap_or_default().trim();
+        let words: Vec<&str> = code.split_whitespace().collect();
+        let scope = || {
+            blocks
+                .iter()
+                .filter(|(_, named, _)| *named)
+                .map(|(name, _, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(".")
+        };
+
+        if code.ends_with('{') && words.len() >= 2 {
+            let name = words[1].trim_end_matches('{').to_string();
+            match words[0] {
+                "message" => blocks.push((name, true, false)),
+                "enum" => blocks.push((name, true, true)),
+                // oneof members belong to the message; other blocks (service, options) are opaque
+                _ => blocks.push((name, false, false)),
+            }
+            continue;
+        }
+        if code.starts_with('}') {
+            blocks.pop();
+            continue;
+        }
+
+        // Members live directly in a message or enum, or in a `oneof` inside a message
+        let in_member_scope = matches!(blocks.as_slice(), [.., (_, true, _)] | [.., (_, true, false), (_, false, _)]);
+        if !in_member_scope || words.first() == Some(&"option") {
+            continue;
+        }
+
+        if words.first() == Some(&"reserved") {
+            for part in code["reserved".len()..].trim_end_matches(';').split(',') {
+                let part = part.trim();
+                if let Some((start, end)) = part.split_once(" to ") {
+                    if let (Ok(start), Ok(end)) = (start.trim().parse::<u32>(), end.trim().parse::<u32>()) {
+                        schema.reserved.extend((start..=end.min(start + 10_000)).map(|n| (scope(), n)));
+                    }
+                } else if let Ok(number) = part.parse() {
+                    schema.reserved.push((scope(), number));
+                }
+            }
+            continue;
+        }
+
+        let Some((declaration, number)) = code.split_once('=') else {
+            continue;
+        };
+        let number: String = number.trim().chars().take_while(char::is_ascii_digit).collect();
+        let Ok(number) = number.parse() else {
+            continue;
+        };
+        let mut parts: Vec<&str> = declaration.split_whitespace().collect();
+        let Some(name) = parts.pop() else {
+            continue;
+        };
+        let is_enum = blocks.iter().rev().find(|(_, named, _)| *named).is_some_and(|(_, _, is_enum)| *is_enum);
+        if parts.is_empty() && !is_enum {
+            continue;
+        }
+        schema.fields.push(ProtoField {
+            scope: scope(),
+            name: name.to_string(),
+            number,
+            field_type: parts.join(" "),
+        });
+    }
+    schema
+}
+
+pub fn compare_proto(original: &ProtoSchema, modified: &ProtoSchema) -> Vec<SchemaChange> {
+    let mut changes = Vec::new();
+    let mut push = |incompatible, message: String| changes.push(SchemaChange { incompatible, message });
+
+    for old in &original.fields {
+        let qualified = qualified_name(old);
+        let by_number = modified.fields.iter().find(|f| f.scope == old.scope && f.number == old.number);
+        let by_name = modified.fields.iter().find(|f| f.scope == old.scope && f.name == old.name);
+
+        match (by_name, by_number) {
+            (Some(new), _) if new.number != old.number => push(
+                true,
+                format!("{} renumbered {} -> {}", qualified, old.number, new.number),
+            ),
+            (_, Some(new)) if new.field_type != old.field_type => push(
+                true,
+                format!("{} = {} type changed `{}` -> `{}`", qualified, old.number, old.field_type, new.field_type),
+            ),
+            (_, Some(new)) if new.name != old.name => push(
+                false,
+                format!("{} = {} renamed to {} (breaks JSON/text format)", qualified, old.number, new.name),
+            ),
+            (None, None) if modified.reserved.contains(&(old.scope.clone(), old.number)) => {
+                push(false, format!("{} = {} removed and reserved", qualified, old.number))
+            }
+            (None, None) => push(
+                true,
+                format!("{} = {} removed without reserving its number", qualified, old.number),
+            ),
+            _ => {}
+        }
+    }
+
+    for new in &modified.fields {
+        let known = original
+            .fields
+            .iter()
+            .any(|f| f.scope == new.scope && (f.name == new.name || f.number == new.number));
+        if known {
+            continue;
+        }
+        if original.reserved.contains(&(new.scope.clone(), new.number)) {
+            push(true, format!("{} = {} reuses a reserved number", qualified_name(new), new.number));
+        } else {
+            push(false, format!("{} = {} added", qualified_name(new), new.number));
+        }
+    }
+    changes
+}
+
+fn qualified_name(field: &ProtoField) -> String {
+    if field.scope.is_empty() {
+        field.name.clone()
+    } else {
+        format!("{}.{}", field.scope, field.name)
+    }
+}
+
+pub fn format_proto_diff(file1_path: &str, file2_path: &str, changes: &[SchemaChange]) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", file1_path, file2_path);
+    if changes.is_empty() {
+        output.push_str("\nNo schema changes\n");
+        return output;
+    }
+
+    let warnings = changes.iter().filter(|c| c.incompatible).count();
+    output.push_str(&format!("\n{} change(s), {} incompatible\n\n", changes.len(), warnings));
+    for change in changes {
+        let prefix = if change.incompatible { "WARNING: " } else { "" };
+        output.push_str(&format!("{}{}\n", prefix, change.message));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_parse_proto() {
+        let schema = parse_proto(&lines(
+            "syntax = \"proto3\";\nmessage Order {\n  option deprecated = true;\n  string id = 1; // key\n  repeated Item items = 2;\n  message Item {\n    int32 qty = 1;\n  }\n  oneof payment {\n    string card = 5;\n  }\n  reserved 3, 8 to 9;\n}\nenum Status {\n  UNKNOWN = 0;\n}\nservice S {\n  rpc Get(Order) returns (Order);\n}",
+        ));
+        let names: Vec<_> = schema.fields.iter().map(qualified_name).collect();
+        assert_eq!(names, vec!["Order.id", "Order.items", "Order.Item.qty", "Order.card", "Status.UNKNOWN"]);
+        assert_eq!(schema.fields[1].field_type, "repeated Item");
+        assert_eq!(schema.reserved.len(), 3);
+    }
+
+    #[test]
+    fn test_compatibility_warnings() {
+        let original = parse_proto(&lines("message M {\n  string a = 1;\n  int32 b = 2;\n  string c = 3;\n  string d = 4;\n  string e = 5;\n}"));
+        let modified = parse_proto(&lines(
+            "message M {\n  string a = 1;\n  int64 b = 2;\n  string c = 6;\n  string renamed = 4;\n  reserved 5;\n  string f = 7;\n}",
+        ));
+
+        let text = format_proto_diff("a.proto", "b.proto", &compare_proto(&original, &modified));
+        assert!(text.contains("WARNING: M.b = 2 type changed `int32` -> `int64`"));
+        assert!(text.contains("WARNING: M.c renumbered 3 -> 6"));
+        assert!(text.contains("\nM.d = 4 renamed to renamed"));
+        assert!(text.contains("\nM.e = 5 removed and reserved"));
+        assert!(text.contains("\nM.f = 7 added"));
+        assert!(text.contains("5 change(s), 2 incompatible"));
+    }
+}
@@ -0,0 +1,219 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::align::line_similarity;
+use crate::file_handler::read_file_lines;
+use crate::messages::{text, Message};
+use crate::patch::{hunk_range, FileDiff, Hunk, PatchLine};
+
+/// Lines either side of a hunk's recorded position searched for its new home
+pub const DEFAULT_REBASE_RADIUS: usize = 100;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RebaseOptions {
+    pub radius: usize,
+    /// Mean line similarity a window needs to take the hunk
+    pub min_similarity: f32,
+}
+
+impl Default for RebaseOptions {
+    fn default() -> Self {
+        Self {
+            radius: DEFAULT_REBASE_RADIUS,
+            min_similarity: 0.6,
+        }
+    }
+}
+
+/// A hunk that no longer sat where its header said, or whose context had changed
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Relocation {
+    pub path: String,
+    /// 1-based position within the file's hunks
+    pub hunk: usize,
+    /// 1-based line the hunk's header named, and the line it was anchored to
+    pub expected_line: usize,
+    pub line: usize,
+    pub offset: isize,
+    pub similarity: f32,
+}
+
+/// Re-anchor every hunk of `diff` in `target`. Each hunk slides over a window of `radius` lines
+/// around its recorded position; windows whose lines are not the hunk's removed lines verbatim are
+/// rejected, and among the rest the one whose context is most similar (nearest the recorded
+/// position on ties) wins. Context is rewritten from the target, so the rebased hunks apply
+/// exactly.
+pub fn rebase_hunks(
+    path: &str,
+    target: &[String],
+    hunks: &[Hunk],
+    options: &RebaseOptions,
+) -> Result<(Vec<Hunk>, Vec<Relocation>), String> {
+    let mut rebased = Vec::with_capacity(hunks.len());
+    let mut relocations = Vec::new();
+    // Lines before `position` belong to earlier hunks; `shift` is their net line count change
+    let (mut position, mut shift) = (0usize, 0isize);
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let old_lines = hunk.old_lines();
+        let expected = hunk.expected_start();
+        let (at, similarity) = best_window(target, hunk, &old_lines, expected, position, options).ok_or_else(|| {
+            format!("Hunk {} ({}) not found within {} lines", index + 1, hunk_range(hunk), options.radius)
+        })?;
+
+        let mut context = target[at..at + old_lines.len()].iter();
+        let lines = hunk
+            .lines
+            .iter()
+            .map(|line| match line {
+                PatchLine::Context(_) => PatchLine::Context(context.next().cloned().unwrap_or_default()),
+                PatchLine::Removed(text) => {
+                    context.next();
+                    PatchLine::Removed(text.clone())
+                }
+                added => added.clone(),
+            })
+            .collect();
+        let new_at = (at as isize + shift) as usize;
+        rebased.push(Hunk {
+            old_start: at + usize::from(hunk.old_count > 0),
+            new_start: new_at + usize::from(hunk.new_count > 0),
+            lines,
+            ..hunk.clone()
+        });
+
+        if at != expected || similarity < 1.0 {
+            relocations.push(Relocation {
+                path: path.to_string(),
+                hunk: index + 1,
+                expected_line: expected + 1,
+                line: at + 1,
+                offset: at as isize - expected as isize,
+                similarity,
+            });
+        }
+        position = at + old_lines.len();
+        shift += hunk.new_count as isize - hunk.old_count as isize;
+    }
+    Ok((rebased, relocations))
+}
+
+/// Rebase each file of a parsed patch onto its current copy under `root`
+pub fn rebase_patch(files: &[FileDiff], root: &Path, options: &RebaseOptions) -> Result<(Vec<FileDiff>, Vec<Relocation>), String> {
+    let mut rebased = Vec::with_capacity(files.len());
+    let mut relocations = Vec::new();
+    for file in files {
+        let Some(old_path) = file.old_path.as_ref().filter(|_| !file.hunks.is_empty()) else {
+            rebased.push(file.clone());
+            continue;
+        };
+        let target = read_file_lines(&root.join(old_path).to_string_lossy())
+            .map_err(|e| text(Message::ReadFailed, [&old_path, &e]))?;
+        let (hunks, moved) = rebase_hunks(file.path(), &target, &file.hunks, options)
+            .map_err(|e| format!("{}: {}", file.path(), e))?;
+        rebased.push(FileDiff { hunks, ..file.clone() });
+        relocations.extend(moved);
+    }
+    Ok((rebased, relocations))
+}
+
+/// One line per relocated hunk, e.g. `src/a.rs: hunk 2 moved +14 lines to line 58 (context 83% similar)`
+pub fn format_relocations(relocations: &[Relocation]) -> String {
+    if relocations.is_empty() {
+        return "All hunks apply where recorded\n".to_string();
+    }
+    relocations
+        .iter()
+        .map(|r| {
+            format!(
+                "{}: hunk {} moved {:+} lines to line {} (context {:.0}% similar)\n",
+                r.path,
+                r.hunk,
+                r.offset,
+                r.line,
+                r.similarity * 100.0
+            )
+        })
+        .collect()
+}
+
+/// Start and similarity of the best window for `hunk`, searched outwards from `expected`
+fn best_window(
+    target: &[String],
+    hunk: &Hunk,
+    old_lines: &[String],
+    expected: usize,
+    from: usize,
+    options: &RebaseOptions,
+) -> Option<(usize, f32)> {
+    let last = target.len().checked_sub(old_lines.len())?;
+    let low = expected.saturating_sub(options.radius).max(from);
+    let high = (expected + options.radius).min(last);
+    if low > high {
+        return None;
+    }
+
+    let score = |at: usize| -> Option<f32> {
+        if old_lines.is_empty() {
+            return Some(1.0);
+        }
+        let mut window = target[at..at + old_lines.len()].iter();
+        let mut total = 0.0;
+        for line in &hunk.lines {
+            total += match line {
+                PatchLine::Context(text) => line_similarity(text, window.next()?),
+                PatchLine::Removed(text) => (window.next()? == text).then_some(1.0)?,
+                PatchLine::Added(_) => continue,
+            };
+        }
+        Some(total / old_lines.len() as f32)
+    };
+
+    let mut candidates: Vec<usize> = (low..=high).collect();
+    candidates.sort_by_key(|&at| at.abs_diff(expected));
+    let mut best: Option<(usize, f32)> = None;
+    for at in candidates {
+        if let Some(similarity) = score(at).filter(|&s| s >= options.min_similarity) {
+            if best.is_none_or(|(_, top)| similarity > top) {
+                best = Some((at, similarity));
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patch::{apply_hunks, format_unified, parse_unified};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_rebase_relocates_hunks() {
+        let patch = parse_unified("--- a/f\n+++ b/f\n@@ -2,3 +2,3 @@\n fn main() {\n-    run();\n+    run(true);\n }\n");
+        // Ten lines were added above the hunk and its context was reformatted
+        let mut target: Vec<String> = (0..10).map(|i| format!("// note {}", i)).collect();
+        target.extend(lines("use x;\nfn main()  {\n    run();\n}"));
+        assert!(apply_hunks(&target, &patch[0].hunks).is_err());
+
+        let (hunks, relocations) = rebase_hunks("f", &target, &patch[0].hunks, &RebaseOptions::default()).unwrap();
+        assert_eq!((relocations[0].expected_line, relocations[0].line, relocations[0].offset), (2, 12, 10));
+        assert!(relocations[0].similarity < 1.0);
+        assert_eq!((hunks[0].old_start, hunks[0].new_start), (12, 12));
+        assert_eq!(apply_hunks(&target, &hunks).unwrap()[12], "    run(true);");
+        assert!(format_unified(&[FileDiff { hunks, ..patch[0].clone() }]).contains("@@ -12,3 +12,3 @@\n fn main()  {\n"));
+        assert!(format_relocations(&relocations).starts_with("f: hunk 1 moved +10 lines to line 12"));
+    }
+
+    #[test]
+    fn test_rebase_respects_radius_and_removed_lines() {
+        let patch = parse_unified("--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n-b\n+B\n");
+        let mut target: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        target.extend(lines("a\nb"));
+        let narrow = RebaseOptions { radius: 5, ..RebaseOptions::default() };
+        assert!(rebase_hunks("f", &target, &patch[0].hunks, &narrow).unwrap_err().contains("within 5 lines"));
+        assert_eq!(rebase_hunks("f", &target, &patch[0].hunks, &RebaseOptions::default()).unwrap().1[0].offset, 20);
+
+        // The line the hunk removes was itself edited upstream, so there is nothing safe to anchor to
+        assert!(rebase_hunks("f", &lines("a\nc"), &patch[0].hunks, &RebaseOptions::default()).is_err());
+    }
+}
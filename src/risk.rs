@@ -0,0 +1,161 @@
+use crate::commitmsg::is_test;
+use crate::diff_core::LineChange;
+use crate::git::WorktreeChange;
+
+/// Changed lines per point of size risk, and the most points size alone can add
+const LINES_PER_POINT: usize = 10;
+const MAX_SIZE_POINTS: u32 = 10;
+
+/// Why a change deserves a closer look
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RiskFactor {
+    /// Many lines changed at once
+    Size,
+    /// Touches error propagation, panics or exception handling
+    ErrorHandling,
+    /// Touches `unsafe` code or similar escape hatches
+    Unsafe,
+    /// Removes tests
+    DeletedTests,
+}
+
+impl RiskFactor {
+    pub fn label(self) -> &'static str {
+        match self {
+            RiskFactor::Size => "size",
+            RiskFactor::ErrorHandling => "error handling",
+            RiskFactor::Unsafe => "unsafe",
+            RiskFactor::DeletedTests => "deleted tests",
+        }
+    }
+}
+
+const ERROR_MARKERS: &[&str] = &[
+    "unwrap(", "expect(", "panic!", "unreachable!", "Err(", "?;", "catch", "except", "raise ", "throw ", "rescue",
+];
+const UNSAFE_MARKERS: &[&str] = &["unsafe ", "unsafe{", "transmute", "eval(", "innerHTML", "dangerouslySetInnerHTML"];
+const TEST_MARKERS: &[&str] = &["#[test]", "fn test_", "def test_", "@Test", "it(\"", "it('", "test(\"", "test('"];
+
+/// Risk of one hunk
+#[derive(Clone, Debug, PartialEq)]
+pub struct HunkRisk {
+    pub hunk: usize,
+    pub score: u32,
+    pub factors: Vec<RiskFactor>,
+}
+
+/// Risk of one file: its hunks' scores summed, halved for test files
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileRisk {
+    pub path: String,
+    pub score: u32,
+    pub hunks: Vec<HunkRisk>,
+}
+
+impl FileRisk {
+    /// Every factor any hunk has, most serious first
+    pub fn factors(&self) -> Vec<RiskFactor> {
+        let all = [RiskFactor::Unsafe, RiskFactor::DeletedTests, RiskFactor::ErrorHandling, RiskFactor::Size];
+        all.into_iter().filter(|factor| self.hunks.iter().any(|hunk| hunk.factors.contains(factor))).collect()
+    }
+}
+
+pub fn score_hunk(original: &[String], modified: &[String], hunk: usize, change: &LineChange) -> HunkRisk {
+    let removed = &original[change.original_start..change.original_end];
+    let added = &modified[change.modified_start..change.modified_end];
+    let mentions = |markers: &[&str]| {
+        removed.iter().chain(added).any(|line| markers.iter().any(|marker| line.contains(marker)))
+    };
+
+    let mut score = 0;
+    let mut factors = Vec::new();
+    let size_points = ((removed.len() + added.len()) / LINES_PER_POINT).min(MAX_SIZE_POINTS as usize) as u32;
+    if size_points > 0 {
+        score += size_points;
+        factors.push(RiskFactor::Size);
+    }
+    if mentions(ERROR_MARKERS) {
+        score += 5;
+        factors.push(RiskFactor::ErrorHandling);
+    }
+    if mentions(UNSAFE_MARKERS) {
+        score += 10;
+        factors.push(RiskFactor::Unsafe);
+    }
+    let tests_in = |lines: &[String]| lines.iter().filter(|line| TEST_MARKERS.iter().any(|m| line.contains(m))).count();
+    if tests_in(removed) > tests_in(added) {
+        score += 8;
+        factors.push(RiskFactor::DeletedTests);
+    }
+    HunkRisk { hunk, score, factors }
+}
+
+/// Score every changed file, riskiest first. Test files count half, except for deleted tests,
+/// which matter wherever they are.
+pub fn score_files(files: &[WorktreeChange]) -> Vec<FileRisk> {
+    let mut risks: Vec<FileRisk> = files
+        .iter()
+        .map(|file| {
+            let hunks: Vec<HunkRisk> = file
+                .changes
+                .iter()
+                .enumerate()
+                .map(|(index, change)| score_hunk(&file.original, &file.modified, index, change))
+                .filter(|hunk| hunk.score > 0)
+                .collect();
+            let score = hunks
+                .iter()
+                .map(|hunk| {
+                    let deleted_tests = if hunk.factors.contains(&RiskFactor::DeletedTests) { 8 } else { 0 };
+                    if is_test(&file.path) { (hunk.score - deleted_tests) / 2 + deleted_tests } else { hunk.score }
+                })
+                .sum();
+            FileRisk { path: file.path.clone(), score, hunks }
+        })
+        .collect();
+    risks.sort_by_key(|risk| std::cmp::Reverse(risk.score));
+    risks
+}
+
+pub fn format_risk_report(risks: &[FileRisk]) -> String {
+    let mut output = "Review order, riskiest first:\n".to_string();
+    for risk in risks {
+        let factors: Vec<&str> = risk.factors().into_iter().map(RiskFactor::label).collect();
+        let factors = if factors.is_empty() { String::new() } else { format!(" [{}]", factors.join(", ")) };
+        output.push_str(&format!("  {:>3} {}{}\n", risk.score, risk.path, factors));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    fn file(path: &str, original: &str, modified: &str) -> WorktreeChange {
+        let (original, modified) = (lines(original), lines(modified));
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        WorktreeChange { path: path.to_string(), status: 'M', binary: false, submodule: false, original, modified, changes }
+    }
+
+    #[test]
+    fn test_score_files() {
+        let files = vec![
+            file("src/docs.rs", "// old comment", "// new comment"),
+            file("src/ffi.rs", "let x = 1;", "let x = unsafe { read(p) };"),
+            file("src/io.rs", "let f = open(p);", "let f = open(p).unwrap();"),
+            file("tests/io_test.rs", "#[test]\nfn test_open() {}\nfn keep() {}", "fn keep() {}"),
+            file("tests/util_test.rs", "let f = open(p);", "let f = open(p).unwrap();"),
+        ];
+        let risks = score_files(&files);
+        let order: Vec<(&str, u32)> = risks.iter().map(|risk| (risk.path.as_str(), risk.score)).collect();
+        assert_eq!(
+            order,
+            vec![("src/ffi.rs", 10), ("tests/io_test.rs", 8), ("src/io.rs", 5), ("tests/util_test.rs", 2), ("src/docs.rs", 0)]
+        );
+        let report = format_risk_report(&risks);
+        assert!(report.contains("   10 src/ffi.rs [unsafe]\n"));
+        assert!(report.ends_with("    0 src/docs.rs\n"));
+    }
+}
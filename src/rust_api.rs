@@ -0,0 +1,337 @@
+//! Public-API comparison of Rust sources using a lightweight, line-based item parser.
+//!
+//! Items are found by keyword after an optional visibility; braces are counted to track
+//! `impl`, `trait` and `mod` scopes and to skip function bodies. String literals containing
+//! braces can confuse it, which is acceptable for a review aid.
+
+const ITEM_KEYWORDS: &[&str] = &["fn", "struct", "enum", "trait", "type", "const", "static", "mod", "union"];
+const QUALIFIERS: &[&str] = &["async", "unsafe", "extern", "default"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiItem {
+    pub kind: String,
+    /// Name qualified by enclosing `mod`/`impl`/`trait`, e.g. `Parser::new`
+    pub path: String,
+    /// `pub`, `pub(crate)`, ..., or empty for private
+    pub visibility: String,
+    /// Header without visibility or body, whitespace collapsed
+    pub signature: String,
+    /// Full item text, used to detect body-only changes
+    pub text: String,
+    /// 0-based line of the item header
+    pub line: usize,
+}
+
+impl ApiItem {
+    /// Visible outside the crate, counting items of public traits and trait impls
+    pub fn is_public(&self) -> bool {
+        self.visibility == "pub"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ApiChange {
+    Added(ApiItem),
+    Removed(ApiItem),
+    SignatureChanged { original: ApiItem, modified: ApiItem },
+    VisibilityChanged { original: ApiItem, modified: ApiItem },
+    /// Same signature and visibility, different implementation
+    BodyChanged { original: ApiItem, modified: ApiItem },
+}
+
+enum Scope {
+    /// `impl`, `trait` or `mod` whose items are collected, with the visibility its items inherit
+    Items { prefix: String, inherited: Option<String> },
+    /// Function bodies and other blocks whose contents are not API
+    Opaque,
+}
+
+pub fn parse_api_items(lines: &[String]) -> Vec<ApiItem> {
+    let mut items = Vec::new();
+    let mut scopes: Vec<(i64, Scope)> = Vec::new();
+    let mut depth = 0i64;
+    let mut index = 0;
+
+    while index < lines.len() {
+        let code = strip_comment(&lines[index]);
+        let in_items = match scopes.last() {
+            None => true,
+            Some((_, Scope::Items { .. })) => true,
+            Some((_, Scope::Opaque)) => false,
+        };
+        let header = if in_items { parse_header(code) } else { None };
+
+        let Some((visibility, kind, name)) = header else {
+            depth = track_braces(code, depth, &mut scopes);
+            index += 1;
+            continue;
+        };
+
+        // Collect the header up to its opening brace or terminating semicolon
+        let mut end = index;
+        while end + 1 < lines.len() && !strip_comment(&lines[end]).contains(['{', ';']) {
+            end += 1;
+        }
+        let header_text: String = (index..=end).map(|i| strip_comment(&lines[i])).collect::<Vec<_>>().join(" ");
+        let signature = header_text.split(['{', ';']).next().unwrap_or_default();
+        let signature = collapse_whitespace(signature.trim_start().strip_prefix(visibility.as_str()).unwrap_or(signature));
+
+        let (prefix, inherited) = scopes
+            .iter()
+            .rev()
+            .find_map(|(_, scope)| match scope {
+                Scope::Items { prefix, inherited } => Some((prefix.clone(), inherited.clone())),
+                Scope::Opaque => None,
+            })
+            .unwrap_or_default();
+        let visibility = inherited.clone().unwrap_or(visibility);
+        let path = if prefix.is_empty() { name.clone() } else { format!("{}::{}", prefix, name) };
+
+        // Walk the item's braces to find its end and what its block contains
+        let opens_block = header_text.split(';').next().unwrap_or_default().contains('{');
+        let block_scope = match kind.as_str() {
+            "impl" => Scope::Items {
+                prefix: name.clone(),
+                // Trait impl items are fixed by the trait; treat them as public
+                inherited: name.contains(" for ").then(|| "pub".to_string()),
+            },
+            "trait" => Scope::Items {
+                prefix: path.clone(),
+                inherited: Some(visibility.clone()),
+            },
+            "mod" => Scope::Items {
+                prefix: path.clone(),
+                inherited: None,
+            },
+            _ => Scope::Opaque,
+        };
+        let collects_items = matches!(block_scope, Scope::Items { .. });
+
+        let item_start_depth = depth;
+        let mut text_end = end;
+        for (i, line) in lines.iter().enumerate().take(end + 1).skip(index) {
+            depth = track_braces(strip_comment(line), depth, &mut scopes);
+            text_end = i;
+        }
+        if opens_block && depth > item_start_depth {
+            if collects_items {
+                scopes.push((item_start_depth, block_scope));
+            } else {
+                // Skip to the end of the body
+                while depth > item_start_depth && text_end + 1 < lines.len() {
+                    text_end += 1;
+                    depth = track_braces(strip_comment(&lines[text_end]), depth, &mut scopes);
+                }
+            }
+        }
+
+        items.push(ApiItem {
+            kind,
+            path,
+            visibility,
+            signature,
+            text: lines[index..=text_end].join("\n"),
+            line: index,
+        });
+        index = text_end + 1;
+    }
+    items
+}
+
+/// Visibility, kind and name of an item header, or `None` for other lines
+fn parse_header(line: &str) -> Option<(String, String, String)> {
+    let trimmed = line.trim_start();
+    let (visibility, rest) = if let Some(rest) = trimmed.strip_prefix("pub(") {
+        let close = rest.find(')')?;
+        (format!("pub({})", &rest[..close]), &rest[close + 1..])
+    } else if let Some(rest) = trimmed.strip_prefix("pub ") {
+        ("pub".to_string(), rest)
+    } else {
+        (String::new(), trimmed)
+    };
+
+    let mut words = rest.split_whitespace().skip_while(|word| QUALIFIERS.contains(word) || word.starts_with('"'));
+    let keyword = words.next()?;
+    if keyword == "impl" || keyword.starts_with("impl<") {
+        let target = rest.trim_start().strip_prefix("impl")?;
+        let target = skip_generics(target).split('{').next()?.split(" where ").next()?;
+        return Some((visibility, "impl".to_string(), collapse_whitespace(target)));
+    }
+    if !ITEM_KEYWORDS.contains(&keyword) {
+        return None;
+    }
+    let name: String = words.next()?.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+    (!name.is_empty()).then(|| (visibility, keyword.to_string(), name))
+}
+
+fn skip_generics(text: &str) -> &str {
+    if !text.starts_with('<') {
+        return text;
+    }
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &text[i + 1..];
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Update brace depth for one line, closing scopes that end on it
+fn track_braces(line: &str, mut depth: i64, scopes: &mut Vec<(i64, Scope)>) -> i64 {
+    for c in line.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                while scopes.last().is_some_and(|(open_depth, _)| *open_depth >= depth) {
+                    scopes.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+    depth
+}
+
+fn strip_comment(line: &str) -> &str {
+    line.split("//").next().unwrap_or_default()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Compare items by kind and path; only public items on either side produce API changes,
+/// other modified items are reported as body changes
+pub fn compare_api(original: &[ApiItem], modified: &[ApiItem]) -> Vec<ApiChange> {
+    let same_item = |a: &ApiItem, b: &ApiItem| a.kind == b.kind && a.path == b.path;
+    let mut changes = Vec::new();
+
+    for item in original {
+        match modified.iter().find(|other| same_item(item, other)) {
+            Some(other) => {
+                let pair = (item.clone(), other.clone());
+                if item.visibility != other.visibility && (item.is_public() || other.is_public()) {
+                    changes.push(ApiChange::VisibilityChanged {
+                        original: pair.0,
+                        modified: pair.1,
+                    });
+                } else if item.signature != other.signature && item.is_public() {
+                    changes.push(ApiChange::SignatureChanged {
+                        original: pair.0,
+                        modified: pair.1,
+                    });
+                } else if item.text != other.text && item.kind != "impl" && item.kind != "mod" && item.kind != "trait" {
+                    changes.push(ApiChange::BodyChanged {
+                        original: pair.0,
+                        modified: pair.1,
+                    });
+                }
+            }
+            None if item.is_public() => changes.push(ApiChange::Removed(item.clone())),
+            None => {}
+        }
+    }
+    for item in modified {
+        if item.is_public() && !original.iter().any(|other| same_item(item, other)) {
+            changes.push(ApiChange::Added(item.clone()));
+        }
+    }
+    changes
+}
+
+pub fn format_api_diff(file1_path: &str, file2_path: &str, changes: &[ApiChange]) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", file1_path, file2_path);
+    let (api, bodies): (Vec<_>, Vec<_>) = changes
+        .iter()
+        .partition(|change| !matches!(change, ApiChange::BodyChanged { .. }));
+
+    output.push_str("\n## Public API\n");
+    if api.is_empty() {
+        output.push_str("No public API changes\n");
+    }
+    for change in api {
+        output.push_str(&match change {
+            ApiChange::Added(item) => format!("+ {} {}: `{}`\n", item.kind, item.path, item.signature),
+            ApiChange::Removed(item) => format!("- {} {}: `{}`\n", item.kind, item.path, item.signature),
+            ApiChange::SignatureChanged { original, modified } => format!(
+                "~ {} {}: `{}` -> `{}`\n",
+                original.kind, original.path, original.signature, modified.signature
+            ),
+            ApiChange::VisibilityChanged { original, modified } => format!(
+                "~ {} {}: visibility `{}` -> `{}`\n",
+                original.kind,
+                original.path,
+                visibility_label(&original.visibility),
+                visibility_label(&modified.visibility)
+            ),
+            ApiChange::BodyChanged { .. } => unreachable!(),
+        });
+    }
+
+    if !bodies.is_empty() {
+        output.push_str("\n## Implementation changes\n");
+        for change in bodies {
+            if let ApiChange::BodyChanged { modified, .. } = change {
+                output.push_str(&format!("  {} {} (line {})\n", modified.kind, modified.path, modified.line + 1));
+            }
+        }
+    }
+    output
+}
+
+fn visibility_label(visibility: &str) -> &str {
+    if visibility.is_empty() {
+        "private"
+    } else {
+        visibility
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_parse_items() {
+        let source = lines(
+            "pub struct Parser {\n    pos: usize,\n}\n\nimpl Parser {\n    pub fn new(\n        text: &str,\n    ) -> Self {\n        let x = { 1 };\n        Self { pos: 0 }\n    }\n    fn helper(&self) {}\n}\n\nimpl Default for Parser {\n    fn default() -> Self { Self::new(\"\") }\n}",
+        );
+        let items = parse_api_items(&source);
+        let summary: Vec<_> = items.iter().map(|i| (i.kind.as_str(), i.path.as_str(), i.visibility.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("struct", "Parser", "pub"),
+                ("impl", "Parser", ""),
+                ("fn", "Parser::new", "pub"),
+                ("fn", "Parser::helper", ""),
+                ("impl", "Default for Parser", ""),
+                ("fn", "Default for Parser::default", "pub"),
+            ]
+        );
+        assert_eq!(items[2].signature, "fn new( text: &str, ) -> Self");
+    }
+
+    #[test]
+    fn test_compare_api() {
+        let original = parse_api_items(&lines("pub fn a(x: u8) {\n    x;\n}\npub fn b() {}\nfn c() {}\npub fn d() {\n    1;\n}"));
+        let modified = parse_api_items(&lines("pub fn a(x: u16) {\n    x;\n}\nfn b() {}\npub fn e() {}\npub fn d() {\n    2;\n}"));
+
+        let text = format_api_diff("x.rs", "y.rs", &compare_api(&original, &modified));
+        assert!(text.contains("~ fn a: `fn a(x: u8)` -> `fn a(x: u16)`"));
+        assert!(text.contains("~ fn b: visibility `pub` -> `private`"));
+        assert!(text.contains("+ fn e: `fn e()`"));
+        assert!(!text.contains("fn c"));
+        assert!(text.contains("## Implementation changes\n  fn d (line 6)"));
+    }
+}
@@ -0,0 +1,156 @@
+use serde_json::{json, Value};
+use crate::diff_core::LineChange;
+use crate::generated::is_generated_path;
+use crate::git::WorktreeChange;
+use crate::risk::{score_files, RiskFactor};
+use crate::unicode_check::{scan_changes, SuspiciousKind};
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Rule IDs, with the short description each is published under
+const RULES: &[(&str, &str)] = &[
+    ("risky-hunk", "Hunk touches code that deserves a closer review"),
+    ("suspicious-character", "Changed line holds a bidi control, invisible or confusable character"),
+    ("generated-edit", "Change inside generated code, which is usually regenerated rather than edited"),
+];
+
+/// One result of an analysis pass, located in the modified file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finding {
+    pub rule: &'static str,
+    /// `error`, `warning` or `note`
+    pub level: &'static str,
+    pub message: String,
+    pub path: String,
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    pub start_column: Option<usize>,
+}
+
+/// Findings of the risk, Unicode and generated-code passes over `files`, file by file. Hunks
+/// in files matching `generated_patterns` count as generated edits too.
+pub fn collect_findings(files: &[WorktreeChange], generated_patterns: &[String]) -> Vec<Finding> {
+    let risks = score_files(files);
+    let mut findings = Vec::new();
+    for file in files.iter().filter(|file| !file.binary) {
+        let finding = |rule, level, message: String, change: &LineChange| {
+            let (start_line, end_line) = modified_lines(change);
+            Finding { rule, level, message, path: file.path.clone(), start_line, end_line, start_column: None }
+        };
+        let hunks = risks.iter().find(|risk| risk.path == file.path).map(|risk| risk.hunks.as_slice()).unwrap_or_default();
+        for hunk in hunks {
+            let factors: Vec<&str> = hunk.factors.iter().map(|factor| factor.label()).collect();
+            let serious = hunk.factors.iter().any(|f| matches!(f, RiskFactor::Unsafe | RiskFactor::DeletedTests));
+            let message = format!("Risk score {}: {}", hunk.score, factors.join(", "));
+            findings.push(finding("risky-hunk", if serious { "warning" } else { "note" }, message, &file.changes[hunk.hunk]));
+        }
+        for found in scan_changes(&file.modified, &file.changes) {
+            findings.push(Finding {
+                rule: "suspicious-character",
+                level: if found.kind == SuspiciousKind::Bidi { "error" } else { "warning" },
+                message: found.description,
+                path: file.path.clone(),
+                start_line: found.line,
+                end_line: found.line,
+                start_column: Some(found.column),
+            });
+        }
+        let generated_file = is_generated_path(&file.path, generated_patterns);
+        for change in file.changes.iter().filter(|change| change.generated || generated_file) {
+            findings.push(finding("generated-edit", "note", "Edit inside generated code".to_string(), change));
+        }
+    }
+    findings
+}
+
+/// The lines a change covers in the modified file; a deletion points at the line after it
+fn modified_lines(change: &LineChange) -> (usize, usize) {
+    let start = change.modified_start + 1;
+    (start, change.modified_end.max(start))
+}
+
+/// A SARIF log with a single run holding `findings`
+pub fn to_sarif(findings: &[Finding]) -> Value {
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|(id, description)| json!({ "id": id, "shortDescription": { "text": description } }))
+        .collect();
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let mut region = json!({ "startLine": finding.start_line, "endLine": finding.end_line });
+            if let Some(column) = finding.start_column {
+                region["startColumn"] = json!(column);
+            }
+            json!({
+                "ruleId": finding.rule,
+                "ruleIndex": RULES.iter().position(|(id, _)| *id == finding.rule),
+                "level": finding.level,
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": finding.path },
+                        "region": region,
+                    }
+                }],
+            })
+        })
+        .collect();
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_sarif_findings() {
+        let file = |path: &str, original: &str, modified: &str| {
+            let (original, modified) = (lines(original), lines(modified));
+            let changes = compute_diff(&original, &modified, DiffOptions::default());
+            WorktreeChange { path: path.to_string(), status: 'M', binary: false, submodule: false, original, modified, changes }
+        };
+        let files = vec![
+            file("src/ffi.rs", "fn f() {}\nlet x = 1;", "fn f() {}\nlet x = unsafe { read(p) };"),
+            file("src/auth.rs", "let ok = false;", "if аdmin { ok = true; }"),
+            file("src/api.pb.rs", "a", "b"),
+        ];
+        let findings = collect_findings(&files, &["*.pb.rs".to_string()]);
+        let summary: Vec<(&str, &str, &str, usize)> =
+            findings.iter().map(|f| (f.rule, f.level, f.path.as_str(), f.start_line)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("risky-hunk", "warning", "src/ffi.rs", 2),
+                ("suspicious-character", "warning", "src/auth.rs", 1),
+                ("generated-edit", "note", "src/api.pb.rs", 1),
+            ]
+        );
+
+        let log = to_sarif(&findings);
+        assert_eq!(log["version"], "2.1.0");
+        let result = &log["runs"][0]["results"][1];
+        assert_eq!(result["ruleId"], "suspicious-character");
+        assert_eq!(result["ruleIndex"], 1);
+        assert_eq!(result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "src/auth.rs");
+        assert_eq!(result["locations"][0]["physicalLocation"]["region"]["startColumn"], 4);
+        assert_eq!(log["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,120 @@
+use std::ops::Range;
+use crate::diff_core::{compute_diff, DiffOptions, LineChange};
+
+/// A span of an editor buffer, diffed on its own but reported in the buffer's coordinates
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection {
+    /// The buffer's path or another name shown in output
+    pub label: String,
+    pub text: String,
+    /// 0-based buffer line the selection starts on
+    pub start_line: usize,
+    /// Characters before the selection on its first line
+    pub start_column: usize,
+}
+
+/// What a selection is compared with
+#[derive(Clone, Debug)]
+pub enum SelectionTarget {
+    Selection(Selection),
+    /// A whole file on disk
+    File(String),
+    /// Text from outside any buffer, e.g. clipboard contents supplied by the caller
+    Text(String),
+}
+
+impl Selection {
+    /// The text at byte `range` of `buffer`
+    pub fn from_range(label: &str, buffer: &str, range: Range<usize>) -> Result<Self, String> {
+        let text = buffer
+            .get(range.clone())
+            .ok_or_else(|| format!("{}..{} is not a range of characters in {}", range.start, range.end, label))?;
+        let before = &buffer[..range.start];
+        let line_start = before.rfind('\n').map_or(0, |at| at + 1);
+        Ok(Selection {
+            label: label.to_string(),
+            text: text.to_string(),
+            start_line: before.matches('\n').count(),
+            start_column: before[line_start..].chars().count(),
+        })
+    }
+
+    /// All of `text`, as when comparing with a file or pasted text
+    pub fn whole(label: &str, text: &str) -> Self {
+        Selection {
+            label: label.to_string(),
+            text: text.to_string(),
+            start_line: 0,
+            start_column: 0,
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.text.lines().map(String::from).collect()
+    }
+
+    /// `label:first-last` with 1-based buffer lines, or just the label for whole texts
+    pub fn describe(&self) -> String {
+        if self.start_line == 0 && self.start_column == 0 {
+            return self.label.clone();
+        }
+        let last = self.start_line + self.text.lines().count().max(1);
+        format!("{}:{}-{}", self.label, self.start_line + 1, last)
+    }
+}
+
+/// Diff two selections, with line and character positions relative to their buffers
+pub fn compare_selections(original: &Selection, modified: &Selection, options: DiffOptions) -> Vec<LineChange> {
+    let changes = compute_diff(&original.lines(), &modified.lines(), options);
+    map_to_buffers(changes, original, modified)
+}
+
+/// Shift changes computed over the selections' own lines to buffer positions. Character
+/// offsets are relative to each hunk's text, so only those on a selection's first line move.
+pub fn map_to_buffers(mut changes: Vec<LineChange>, original: &Selection, modified: &Selection) -> Vec<LineChange> {
+    let first_line_length = |selection: &Selection| selection.text.lines().next().map_or(0, |line| line.chars().count());
+    let (original_first, modified_first) = (first_line_length(original), first_line_length(modified));
+    for change in &mut changes {
+        let (original_shift, modified_shift) = (
+            if change.original_start == 0 { original.start_column } else { 0 },
+            if change.modified_start == 0 { modified.start_column } else { 0 },
+        );
+        for char_change in change.char_changes.iter_mut().flatten() {
+            if char_change.original_start <= original_first {
+                char_change.original_start += original_shift;
+            }
+            if char_change.modified_start <= modified_first {
+                char_change.modified_start += modified_shift;
+            }
+        }
+        change.original_start += original.start_line;
+        change.original_end += original.start_line;
+        change.modified_start += modified.start_line;
+        change.modified_end += modified.start_line;
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection_positions_map_to_buffer() {
+        let buffer = "fn a() {}\nlet x = compute(1);\nlet y = 2;\n";
+        let start = buffer.find("compute").unwrap();
+        let selection = Selection::from_range("main.rs", buffer, start..buffer.len()).unwrap();
+        assert_eq!((selection.start_line, selection.start_column), (1, 8));
+        assert_eq!(selection.describe(), "main.rs:2-3");
+
+        let pasted = Selection::whole("clipboard", "compute(10);\nlet y = 2;");
+        let changes = compare_selections(&selection, &pasted, DiffOptions::default());
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].original_start, changes[0].original_end), (1, 2));
+        assert_eq!((changes[0].modified_start, changes[0].modified_end), (0, 1));
+        let chars = changes[0].char_changes.as_ref().unwrap();
+        assert_eq!((chars[0].original_start, chars[0].modified_start), (17, 9));
+
+        assert!(Selection::from_range("x", "é", 1..2).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use crate::messages::{text, Message};
+use crate::state::ComparisonState;
+
+pub type SessionId = u64;
+
+/// Who opened a comparison session
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SessionOwner {
+    /// Slash commands and editor actions; the user's latest session is the active one
+    User,
+    /// A watcher or background job, identified by name
+    Task(String),
+}
+
+struct Session {
+    owner: SessionOwner,
+    state: ComparisonState,
+}
+
+#[derive(Default)]
+struct Sessions {
+    next_id: SessionId,
+    active: Option<SessionId>,
+    sessions: BTreeMap<SessionId, Session>,
+}
+
+impl Sessions {
+    fn insert(&mut self, owner: SessionOwner, state: ComparisonState) -> SessionId {
+        self.next_id += 1;
+        let id = self.next_id;
+        if owner == SessionOwner::User {
+            self.active = Some(id);
+        }
+        self.sessions.insert(id, Session { owner, state });
+        id
+    }
+}
+
+/// Comparison sessions shared by slash commands, watchers and background jobs. Anyone holding
+/// an ID may read its session, but only the owner may change or close it, and only `User`
+/// sessions become active, so background work never replaces what the user is looking at.
+#[derive(Default)]
+pub struct SessionManager {
+    inner: Mutex<Sessions>,
+}
+
+impl SessionManager {
+    fn lock(&self) -> MutexGuard<'_, Sessions> {
+        // A panic elsewhere leaves the map itself consistent, so keep serving it
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn open(&self, owner: SessionOwner, state: ComparisonState) -> SessionId {
+        self.lock().insert(owner, state)
+    }
+
+    /// Open a `User` session in place of the active one, closing it, so repeated comparisons
+    /// from the user do not pile up sessions nobody can reach any more
+    pub fn replace_active(&self, state: ComparisonState) -> SessionId {
+        let mut inner = self.lock();
+        if let Some(previous) = inner.active.take() {
+            inner.sessions.remove(&previous);
+        }
+        inner.insert(SessionOwner::User, state)
+    }
+
+    /// The user's most recent session that is still open
+    pub fn active(&self) -> Option<SessionId> {
+        self.lock().active
+    }
+
+    /// A copy of the session's state, taken without holding the lock while the caller uses it
+    pub fn get(&self, id: SessionId) -> Option<ComparisonState> {
+        self.lock().sessions.get(&id).map(|session| session.state.clone())
+    }
+
+    /// Run `update` on the session's state; fails unless `owner` opened it
+    pub fn update<T>(
+        &self,
+        id: SessionId,
+        owner: &SessionOwner,
+        update: impl FnOnce(&mut ComparisonState) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut inner = self.lock();
+        let session = inner.sessions.get_mut(&id).ok_or_else(|| text(Message::NoSession, [&id]))?;
+        if session.owner != *owner {
+            return Err(text(Message::SessionOwned, [&id, &format!("{:?}", session.owner)]));
+        }
+        update(&mut session.state)
+    }
+
+    pub fn close(&self, id: SessionId, owner: &SessionOwner) -> Result<ComparisonState, String> {
+        let mut inner = self.lock();
+        let session = inner.sessions.remove(&id).ok_or_else(|| text(Message::NoSession, [&id]))?;
+        if session.owner != *owner {
+            let error = text(Message::SessionOwned, [&id, &format!("{:?}", session.owner)]);
+            inner.sessions.insert(id, session);
+            return Err(error);
+        }
+        if inner.active == Some(id) {
+            inner.active = None;
+        }
+        Ok(session.state)
+    }
+
+    /// Open sessions in the order they were opened
+    pub fn list(&self) -> Vec<(SessionId, SessionOwner)> {
+        self.lock().sessions.iter().map(|(id, session)| (*id, session.owner.clone())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(file: &str) -> ComparisonState {
+        ComparisonState::new(format!("{}.old", file), format!("{}.new", file), Vec::new())
+    }
+
+    #[test]
+    fn test_sessions_do_not_clobber() {
+        let manager = SessionManager::default();
+        let watcher = SessionOwner::Task("watcher".to_string());
+        let mine = manager.open(SessionOwner::User, state("a"));
+        let background = manager.open(watcher.clone(), state("b"));
+        assert_eq!(manager.active(), Some(mine));
+        assert_eq!(manager.get(background).unwrap().file1_path, "b.old");
+
+        assert!(manager.update(mine, &watcher, |_| Ok(())).unwrap_err().contains("belongs to User"));
+        assert!(manager.close(background, &SessionOwner::User).is_err());
+        manager
+            .update(background, &watcher, |state| {
+                state.file2_path = "b.newer".to_string();
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(manager.get(mine).unwrap().file2_path, "a.new");
+
+        assert_eq!(manager.close(mine, &SessionOwner::User).unwrap().file1_path, "a.old");
+        assert_eq!((manager.active(), manager.list()), (None, vec![(background, watcher)]));
+    }
+
+    #[test]
+    fn test_replace_active() {
+        let manager = SessionManager::default();
+        let watcher = SessionOwner::Task("watcher".to_string());
+        manager.open(SessionOwner::User, state("a"));
+        let background = manager.open(watcher.clone(), state("b"));
+        let latest = manager.replace_active(state("c"));
+        let latest = manager.replace_active(manager.get(latest).unwrap());
+        assert_eq!(manager.active(), Some(latest));
+        assert_eq!(manager.list(), vec![(background, watcher), (latest, SessionOwner::User)]);
+    }
+
+    #[test]
+    fn test_sessions_across_threads() {
+        let manager = SessionManager::default();
+        std::thread::scope(|scope| {
+            for worker in 0..4 {
+                let manager = &manager;
+                scope.spawn(move || manager.open(SessionOwner::Task(worker.to_string()), state("x")));
+            }
+        });
+        let ids: Vec<SessionId> = manager.list().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+    }
+}
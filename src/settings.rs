@@ -0,0 +1,259 @@
+use zed_extension_api::serde_json::Value;
+use zed_extension_api::settings::LspSettings;
+use zed_extension_api::Worktree;
+use crate::external::ExternalTool;
+use crate::logmask::compile_patterns;
+use crate::messages::Catalog;
+use crate::numbering::LineNumbering;
+use crate::paging::{OutputLimit, DEFAULT_MAX_OUTPUT_BYTES, DEFAULT_MAX_OUTPUT_LINES};
+use crate::redact::{default_redaction_patterns, Redactor};
+use crate::profiles::{default_profile, profile_by_name, Profile};
+use crate::trace::Level;
+use crate::ui::DEFAULT_CONTEXT_LINES;
+
+/// Key under `lsp` in the user's settings where the extension reads its configuration
+pub const SETTINGS_KEY: &str = "file-diff-comparison";
+
+#[derive(Clone, Debug, Default)]
+pub struct ExtensionSettings {
+    pub profile: Option<String>,
+    /// File patterns whose changes are all treated as generated code
+    pub generated_files: Vec<String>,
+    /// Replaces the log profile's built-in timestamp/PID patterns
+    pub log_mask_patterns: Option<Vec<String>>,
+    /// Redact secrets in formatted output
+    pub redact: bool,
+    /// Replaces the built-in redaction patterns
+    pub redaction_patterns: Option<Vec<String>>,
+    /// Split output into pages above this many hunks; 0 disables paging
+    pub hunks_per_page: Option<usize>,
+    /// Append `#h=<id>` to unified `@@` lines so tools can refer back to hunks
+    pub hunk_ids_in_output: bool,
+    /// Re-indent JSON, XML and JS/CSS inputs before diffing, showing a formatted view
+    pub pretty_print: bool,
+    /// Base, gutter width and byte offsets of the line numbers in inline and numbered output;
+    /// absent, the inline view has no gutter and both count from 1
+    pub line_numbers: Option<LineNumbering>,
+    /// Unchanged lines around each hunk of the numbered view
+    pub context_lines: Option<usize>,
+    /// Inline hunks longer than this show only their ends and an expand marker; 0 shows all
+    pub max_hunk_lines: Option<usize>,
+    /// Formatted output above this size becomes a summary with the hunks omitted; 0 disables
+    pub max_output_bytes: Option<usize>,
+    /// As `max_output_bytes`, counted in lines
+    pub max_output_lines: Option<usize>,
+    /// Overrides the profile's memory budget for a single diff
+    pub max_memory_bytes: Option<u64>,
+    /// Diagonals searched either side of the straight path for near-identical files; 0 never
+    /// bands
+    pub band_width: Option<usize>,
+    /// Caps the work of a single diff, in LCS cells and lines compared
+    pub max_operations: Option<u64>,
+    /// Lowest level recorded in comparison traces: `debug`, `info` (default) or `warn`
+    pub log_level: Option<String>,
+    /// Translation file (JSON, `.po` or `.properties`) replacing the English messages it
+    /// covers; see `messages::Message::key`
+    pub translations: Option<String>,
+    /// Check every diff reproduces the modified file, falling back to a coarse diff if not
+    pub verify_diffs: Option<bool>,
+    /// External diff program for matching or oversized files
+    pub external_tool: Option<ExternalTool>,
+    /// Store every comparison's diffstat here for `churn_report`
+    pub churn_file: Option<String>,
+    /// `/diff-dirs` reports files changed in whitespace only, in at most this fraction of their
+    /// lines, as effectively identical
+    pub max_trivial_ratio: Option<f64>,
+    /// Collapse hunks made identically in at least this many files of a multi-file report; 0
+    /// never collapses
+    pub min_duplicate_files: Option<usize>,
+}
+
+impl ExtensionSettings {
+    pub fn load(worktree: &Worktree) -> Self {
+        LspSettings::for_worktree(SETTINGS_KEY, worktree)
+            .ok()
+            .and_then(|settings| settings.settings)
+            .map(|value| Self::from_json(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn from_json(value: &Value) -> Self {
+        Self {
+            profile: value.get("profile").and_then(Value::as_str).map(String::from),
+            generated_files: string_list(value, "generated_files").unwrap_or_default(),
+            log_mask_patterns: string_list(value, "log_mask_patterns"),
+            redact: value.get("redact").and_then(Value::as_bool).unwrap_or(false),
+            redaction_patterns: string_list(value, "redaction_patterns"),
+            hunks_per_page: value
+                .get("hunks_per_page")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize),
+            hunk_ids_in_output: value.get("hunk_ids_in_output").and_then(Value::as_bool).unwrap_or(false),
+            pretty_print: value.get("pretty_print").and_then(Value::as_bool).unwrap_or(false),
+            line_numbers: value.get("line_numbers").map(LineNumbering::from_json),
+            context_lines: value
+                .get("context_lines")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize),
+            max_hunk_lines: value
+                .get("max_hunk_lines")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize),
+            max_output_bytes: value
+                .get("max_output_bytes")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize),
+            max_output_lines: value
+                .get("max_output_lines")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize),
+            max_memory_bytes: value.get("max_memory_bytes").and_then(Value::as_u64),
+            band_width: value
+                .get("band_width")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize),
+            max_operations: value.get("max_operations").and_then(Value::as_u64),
+            log_level: value.get("log_level").and_then(Value::as_str).map(String::from),
+            translations: value.get("translations").and_then(Value::as_str).map(String::from),
+            verify_diffs: value.get("verify_diffs").and_then(Value::as_bool),
+            external_tool: value.get("external_tool").and_then(ExternalTool::from_json),
+            churn_file: value.get("churn_file").and_then(Value::as_str).map(String::from),
+            max_trivial_ratio: value.get("max_trivial_ratio").and_then(Value::as_f64),
+            min_duplicate_files: value
+                .get("min_duplicate_files")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize),
+        }
+    }
+
+    /// Reject malformed patterns up front rather than silently ignoring them during comparison
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(patterns) = &self.log_mask_patterns {
+            compile_patterns(patterns)?;
+        }
+        self.redactor()?;
+        self.log_level()?;
+        self.catalog()?;
+        if let Some(numbering) = &self.line_numbers {
+            numbering.validate()?;
+        }
+        Ok(())
+    }
+
+    pub fn catalog(&self) -> Result<Catalog, String> {
+        match &self.translations {
+            Some(path) => Catalog::load(path),
+            None => Ok(Catalog::english()),
+        }
+    }
+
+    pub fn log_level(&self) -> Result<Level, String> {
+        match &self.log_level {
+            Some(name) => Level::from_name(name).ok_or_else(|| format!("Unknown log level: {}", name)),
+            None => Ok(Level::Info),
+        }
+    }
+
+    pub fn redactor(&self) -> Result<Redactor, String> {
+        match &self.redaction_patterns {
+            Some(patterns) => Redactor::new(patterns),
+            None => Redactor::new(&default_redaction_patterns()),
+        }
+    }
+
+    pub fn context_lines(&self) -> usize {
+        self.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES)
+    }
+
+    pub fn output_limit(&self) -> OutputLimit {
+        OutputLimit {
+            max_bytes: self.max_output_bytes.unwrap_or(DEFAULT_MAX_OUTPUT_BYTES),
+            max_lines: self.max_output_lines.unwrap_or(DEFAULT_MAX_OUTPUT_LINES),
+        }
+    }
+
+    /// The configured profile, or the default one when unset or unknown
+    pub fn profile(&self) -> Profile {
+        let profile = self
+            .profile
+            .as_deref()
+            .and_then(profile_by_name)
+            .unwrap_or_else(default_profile);
+        self.apply_overrides(profile)
+    }
+
+    pub fn apply_overrides(&self, mut profile: Profile) -> Profile {
+        if let Some(patterns) = &self.log_mask_patterns {
+            if !profile.options.mask_patterns.is_empty() {
+                profile.options.mask_patterns = patterns.clone();
+            }
+        }
+        if let Some(limit) = self.max_memory_bytes {
+            profile.options.max_memory_bytes = Some(limit);
+        }
+        if let Some(operations) = self.max_operations {
+            profile.options.max_operations = Some(operations);
+        }
+        if let Some(band_width) = self.band_width {
+            profile.options.band_width = Some(band_width);
+        }
+        if let Some(verify) = self.verify_diffs {
+            profile.options.verify = verify;
+        }
+        profile
+    }
+}
+
+fn string_list(value: &Value, key: &str) -> Option<Vec<String>> {
+    value.get(key).and_then(Value::as_array).map(|items| {
+        items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zed_extension_api::serde_json::json;
+
+    #[test]
+    fn test_from_json() {
+        let settings = ExtensionSettings::from_json(&json!({ "profile": "prose" }));
+        assert_eq!(settings.profile().name, "prose");
+
+        let settings = ExtensionSettings::from_json(&json!({ "profile": "unknown" }));
+        assert_eq!(settings.profile().name, "strict");
+
+        let settings = ExtensionSettings::from_json(&json!({ "generated_files": ["*.pb.rs", 3] }));
+        assert_eq!(settings.generated_files, vec!["*.pb.rs".to_string()]);
+
+        let settings = ExtensionSettings::from_json(&json!({ "hunks_per_page": 50 }));
+        assert_eq!(settings.hunks_per_page, Some(50));
+        assert_eq!(settings.context_lines(), DEFAULT_CONTEXT_LINES);
+
+        let settings = ExtensionSettings::from_json(&json!({ "context_lines": 1 }));
+        assert_eq!(settings.context_lines(), 1);
+
+        let settings = ExtensionSettings::from_json(&json!({ "max_output_lines": 0 }));
+        assert_eq!(settings.output_limit(), OutputLimit { max_bytes: DEFAULT_MAX_OUTPUT_BYTES, max_lines: 0 });
+
+        let settings = ExtensionSettings::from_json(&json!({ "max_memory_bytes": 1024 }));
+        assert_eq!(settings.profile().options.max_memory_bytes, Some(1024));
+    }
+
+    #[test]
+    fn test_log_mask_override() {
+        let settings = ExtensionSettings::from_json(&json!({
+            "profile": "log",
+            "log_mask_patterns": ["\\d+ms"],
+        }));
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.profile().options.mask_patterns, vec!["\\d+ms".to_string()]);
+
+        let settings = ExtensionSettings::from_json(&json!({ "log_mask_patterns": ["[oops"] }));
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use crate::diff_core::{compute_diff, ChangeType, DiffOptions};
+use crate::unordered::compare_unordered;
+
+/// The statements of one table in a dump
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableDump {
+    /// Lines of the `CREATE TABLE` statement
+    pub schema: Vec<String>,
+    /// One entry per inserted row tuple, e.g. `(1, 'ann')`
+    pub rows: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableChange {
+    pub table: String,
+    pub original_rows: usize,
+    pub modified_rows: usize,
+    /// Schema lines prefixed with `-` or `+`
+    pub schema_changes: Vec<String>,
+    pub added_rows: Vec<String>,
+    pub removed_rows: Vec<String>,
+}
+
+/// Group `CREATE TABLE` and `INSERT INTO` statements by table; other statements are ignored
+pub fn parse_sql_dump(lines: &[String]) -> BTreeMap<String, TableDump> {
+    let mut tables: BTreeMap<String, TableDump> = BTreeMap::new();
+
+    for statement in split_statements(lines) {
+        let first = statement[0].trim_start();
+        let upper = first.to_uppercase();
+        if upper.starts_with("CREATE TABLE") {
+            let name = table_name(&first["CREATE TABLE".len()..]);
+            tables.entry(name).or_default().schema = statement.iter().map(|l| l.trim().to_string()).collect();
+        } else if upper.starts_with("INSERT INTO") {
+            let text = statement.join("\n");
+            let name = table_name(&text["INSERT INTO".len()..]);
+            let values_at = text.to_uppercase().find("VALUES").map_or(text.len(), |i| i + "VALUES".len());
+            tables.entry(name).or_default().rows.extend(split_tuples(&text[values_at..]));
+        }
+    }
+    tables
+}
+
+/// Statements as groups of lines, each ending at a line whose code ends with `;`
+fn split_statements(lines: &[String]) -> Vec<Vec<String>> {
+    let mut statements = Vec::new();
+    let mut current = Vec::new();
+
+    for line in lines {
+        let trimmed = line.trim();
+        if current.is_empty() && (trimmed.is_empty() || trimmed.starts_with("--") || trimmed.starts_with("/*")) {
+            continue;
+        }
+        current.push(line.clone());
+        if trimmed.ends_with(';') {
+            statements.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        statements.push(current);
+    }
+    statements
+}
+
+/// Table name after `CREATE TABLE` / `INSERT INTO`, without `IF NOT EXISTS` or quoting
+fn table_name(rest: &str) -> String {
+    let rest = rest.trim_start();
+    let rest = if rest.to_uppercase().starts_with("IF NOT EXISTS") {
+        rest["IF NOT EXISTS".len()..].trim_start()
+    } else {
+        rest
+    };
+    rest.split(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or_default()
+        .trim_matches(|c| c == '`' || c == '"' || c == '[' || c == ']')
+        .to_string()
+}
+
+/// Top-level parenthesised tuples of a `VALUES` list, respecting quoted strings
+fn split_tuples(values: &str) -> Vec<String> {
+    let mut tuples = Vec::new();
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    let mut escaped = false;
+
+    for (i, c) in values.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' => quote = Some(c),
+            '(' => {
+                if depth == 0 {
+                    start = i;
+                }
+                depth += 1;
+            }
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    tuples.push(values[start..=i].split_whitespace().collect::<Vec<_>>().join(" "));
+                }
+            }
+            _ => {}
+        }
+    }
+    tuples
+}
+
+/// Per-table schema changes and row differences, rows compared as sorted multisets
+pub fn compare_sql_dumps(
+    original: &BTreeMap<String, TableDump>,
+    modified: &BTreeMap<String, TableDump>,
+    options: &DiffOptions,
+) -> Vec<TableChange> {
+    let empty = TableDump::default();
+    let mut names: Vec<&String> = original.keys().chain(modified.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let before = original.get(name).unwrap_or(&empty);
+            let after = modified.get(name).unwrap_or(&empty);
+            let rows = compare_unordered(&before.rows, &after.rows, options);
+
+            let mut schema_changes = Vec::new();
+            let schema_options = DiffOptions {
+                compute_char_changes: false,
+                ..options.clone()
+            };
+            for change in compute_diff(&before.schema, &after.schema, schema_options) {
+                if change.change_type != ChangeType::Added {
+                    schema_changes.extend(before.schema[change.original_start..change.original_end].iter().map(|l| format!("-{}", l)));
+                }
+                if change.change_type != ChangeType::Deleted {
+                    schema_changes.extend(after.schema[change.modified_start..change.modified_end].iter().map(|l| format!("+{}", l)));
+                }
+            }
+
+            let unchanged = schema_changes.is_empty() && rows.added.is_empty() && rows.removed.is_empty();
+            (!unchanged).then(|| TableChange {
+                table: name.clone(),
+                original_rows: before.rows.len(),
+                modified_rows: after.rows.len(),
+                schema_changes,
+                added_rows: rows.added,
+                removed_rows: rows.removed,
+            })
+        })
+        .collect()
+}
+
+pub fn format_sql_dump_diff(file1_path: &str, file2_path: &str, changes: &[TableChange]) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", file1_path, file2_path);
+    if changes.is_empty() {
+        output.push_str("\nNo table changes\n");
+        return output;
+    }
+
+    for change in changes {
+        let delta = change.modified_rows as isize - change.original_rows as isize;
+        output.push_str(&format!(
+            "\n## {}: {} -> {} rows ({:+})\n",
+            change.table, change.original_rows, change.modified_rows, delta
+        ));
+        if !change.schema_changes.is_empty() {
+            output.push_str("schema changed:\n");
+            for line in &change.schema_changes {
+                output.push_str(&format!("  {}\n", line));
+            }
+        }
+        for row in &change.removed_rows {
+            output.push_str(&format!("- {}\n", row));
+        }
+        for row in &change.added_rows {
+            output.push_str(&format!("+ {}\n", row));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_parse_sql_dump() {
+        let tables = parse_sql_dump(&lines(
+            "-- dump\nCREATE TABLE IF NOT EXISTS `users` (\n  id INT,\n  name TEXT\n);\nINSERT INTO `users` VALUES (1,'a;(b)'),\n(2, 'it\\'s');\nSET x = 1;",
+        ));
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables["users"].schema.len(), 4);
+        assert_eq!(tables["users"].rows, vec!["(1,'a;(b)')", "(2, 'it\\'s')"]);
+    }
+
+    #[test]
+    fn test_rows_compared_out_of_order() {
+        let original = parse_sql_dump(&lines(
+            "CREATE TABLE t (id INT);\nINSERT INTO t VALUES (1),(2),(3);\nCREATE TABLE same (id INT);",
+        ));
+        let modified = parse_sql_dump(&lines(
+            "CREATE TABLE t (id BIGINT);\nINSERT INTO t VALUES (3),(1),(4),(5);\nCREATE TABLE same (id INT);",
+        ));
+
+        let text = format_sql_dump_diff("a.sql", "b.sql", &compare_sql_dumps(&original, &modified, &DiffOptions::default()));
+        assert!(text.contains("## t: 3 -> 4 rows (+1)\n"));
+        assert!(text.contains("  -CREATE TABLE t (id INT);\n  +CREATE TABLE t (id BIGINT);\n"));
+        assert!(text.contains("- (2)\n+ (4)\n+ (5)\n"));
+        assert!(!text.contains("same"));
+    }
+}
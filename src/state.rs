@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use crate::diff_core::{DiffOptions, LineChange};
+use crate::eol::OutputLineEndings;
+use crate::messages::{self, Message};
+use crate::ui::{OutputFormat, DEFAULT_CONTEXT_LINES};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ComparisonState {
+    pub file1_path: String,
+    pub file2_path: String,
+    pub diff_result: Vec<LineChange>,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    /// Options the diff was computed with, so re-diffs and exports reproduce it
+    #[serde(default)]
+    pub options: DiffOptions,
+    /// What this session changed relative to its profile; already folded into `options`
+    #[serde(default)]
+    pub overrides: OptionOverrides,
+    #[serde(default)]
+    pub view: ViewOptions,
+}
+
+/// How a session's diff is shown. Changing these re-renders `diff_result` without re-diffing.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ViewOptions {
+    pub format: OutputFormat,
+    /// Unchanged lines shown around each hunk of the unified view
+    pub context_lines: usize,
+    #[serde(default)]
+    pub line_endings: OutputLineEndings,
+}
+
+impl Default for ViewOptions {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Unified,
+            context_lines: DEFAULT_CONTEXT_LINES,
+            line_endings: OutputLineEndings::default(),
+        }
+    }
+}
+
+/// Names accepted by `OptionOverrides::toggle`
+pub const TOGGLE_NAMES: &[&str] =
+    &["ignore_whitespace", "ignore_case", "char_changes", "normalize_imports", "embedded_languages"];
+
+/// Per-session changes to the profile's options, e.g. "ignore whitespace for this diff"
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OptionOverrides {
+    pub ignore_whitespace: Option<bool>,
+    pub ignore_case: Option<bool>,
+    pub compute_char_changes: Option<bool>,
+    pub normalize_imports: Option<bool>,
+    pub detect_embedded_languages: Option<bool>,
+}
+
+impl OptionOverrides {
+    /// `options` with every override set
+    pub fn apply(&self, options: &DiffOptions) -> DiffOptions {
+        DiffOptions {
+            ignore_whitespace: self.ignore_whitespace.unwrap_or(options.ignore_whitespace),
+            ignore_case: self.ignore_case.unwrap_or(options.ignore_case),
+            compute_char_changes: self.compute_char_changes.unwrap_or(options.compute_char_changes),
+            normalize_imports: self.normalize_imports.unwrap_or(options.normalize_imports),
+            detect_embedded_languages: self.detect_embedded_languages.unwrap_or(options.detect_embedded_languages),
+            ..options.clone()
+        }
+    }
+
+    /// Flip the named option relative to `current`, returning its new value
+    pub fn toggle(&mut self, name: &str, current: &DiffOptions) -> Result<bool, String> {
+        let (slot, value) = match name {
+            "ignore_whitespace" => (&mut self.ignore_whitespace, current.ignore_whitespace),
+            "ignore_case" => (&mut self.ignore_case, current.ignore_case),
+            "char_changes" => (&mut self.compute_char_changes, current.compute_char_changes),
+            "normalize_imports" => (&mut self.normalize_imports, current.normalize_imports),
+            "embedded_languages" => (&mut self.detect_embedded_languages, current.detect_embedded_languages),
+            _ => return Err(messages::text(Message::UnknownToggle, [&name, &TOGGLE_NAMES.join(", ")])),
+        };
+        *slot = Some(!value);
+        Ok(!value)
+    }
+}
+
+/// A free-text review note attached to one hunk of a comparison
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: u64,
+    /// Index into `ComparisonState::diff_result`
+    pub hunk_index: usize,
+    /// Stable ID of the annotated hunk, used to re-attach the note after a re-diff
+    #[serde(default)]
+    pub hunk_id: String,
+    pub text: String,
+    /// The annotated hunk is gone from the latest diff; the note is kept, attached to no hunk,
+    /// in case a later re-diff brings the hunk back
+    #[serde(default)]
+    pub orphaned: bool,
+}
+
+impl ComparisonState {
+    pub fn new(file1_path: String, file2_path: String, diff_result: Vec<LineChange>) -> Self {
+        Self {
+            file1_path,
+            file2_path,
+            diff_result,
+            annotations: Vec::new(),
+            options: DiffOptions::default(),
+            overrides: OptionOverrides::default(),
+            view: ViewOptions::default(),
+        }
+    }
+
+    /// Whether every hunk lies within files of these lengths; a session's files may have changed
+    /// since it was diffed, and a bundle's hunks come from whoever wrote it
+    pub fn diff_fits(&self, original_len: usize, modified_len: usize) -> bool {
+        self.diff_result.iter().all(|c| {
+            c.original_start <= c.original_end
+                && c.original_end <= original_len
+                && c.modified_start <= c.modified_end
+                && c.modified_end <= modified_len
+        })
+    }
+
+    pub fn add_annotation(&mut self, hunk_index: usize, text: String) -> Result<u64, String> {
+        if hunk_index >= self.diff_result.len() {
+            return Err(messages::text(Message::NoHunkAtIndex, [&hunk_index]));
+        }
+
+        let id = self.annotations.iter().map(|a| a.id + 1).max().unwrap_or(1);
+        let hunk_id = self.diff_result[hunk_index].id.clone();
+        self.annotations.push(Annotation {
+            id,
+            hunk_index,
+            hunk_id,
+            text,
+            orphaned: false,
+        });
+        Ok(id)
+    }
+
+    /// Swap in a freshly computed diff, moving annotations along with their hunks and orphaning
+    /// those whose hunk no longer exists
+    pub fn replace_diff_result(&mut self, diff_result: Vec<LineChange>) {
+        for annotation in &mut self.annotations {
+            match diff_result.iter().position(|c| c.id == annotation.hunk_id) {
+                Some(index) => {
+                    annotation.hunk_index = index;
+                    annotation.orphaned = false;
+                }
+                None => annotation.orphaned = true,
+            }
+        }
+        self.diff_result = diff_result;
+    }
+
+    pub fn edit_annotation(&mut self, id: u64, text: String) -> Result<(), String> {
+        let annotation = self
+            .annotations
+            .iter_mut()
+            .find(|a| a.id == id)
+            .ok_or_else(|| messages::text(Message::NoAnnotation, [&id]))?;
+        annotation.text = text;
+        Ok(())
+    }
+
+    pub fn delete_annotation(&mut self, id: u64) -> Result<(), String> {
+        let before = self.annotations.len();
+        self.annotations.retain(|a| a.id != id);
+        if self.annotations.len() == before {
+            return Err(messages::text(Message::NoAnnotation, [&id]));
+        }
+        Ok(())
+    }
+
+    pub fn annotations_for_hunk(&self, hunk_index: usize) -> impl Iterator<Item = &Annotation> {
+        self.annotations
+            .iter()
+            .filter(move |a| !a.orphaned && a.hunk_index == hunk_index)
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    fn sample_state() -> ComparisonState {
+        let original = vec!["a".to_string(), "b".to_string()];
+        let modified = vec!["a".to_string(), "c".to_string()];
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        ComparisonState::new("a.txt".to_string(), "b.txt".to_string(), changes)
+    }
+
+    #[test]
+    fn test_annotation_lifecycle() {
+        let mut state = sample_state();
+        let id = state.add_annotation(0, "why b?".to_string()).unwrap();
+        assert!(state.add_annotation(5, "missing".to_string()).is_err());
+
+        state.edit_annotation(id, "why c?".to_string()).unwrap();
+        assert_eq!(state.annotations_for_hunk(0).next().unwrap().text, "why c?");
+
+        state.delete_annotation(id).unwrap();
+        assert!(state.annotations.is_empty());
+        assert!(state.delete_annotation(id).is_err());
+    }
+
+    #[test]
+    fn test_annotations_follow_hunks() {
+        let modified = lines("a\nb\nC\nd\ne");
+        let changes = compute_diff(&lines("a\nb\nc\nd\ne"), &modified, DiffOptions::default());
+        let mut state = ComparisonState::new("a.txt".to_string(), "b.txt".to_string(), changes);
+        state.add_annotation(0, "note".to_string()).unwrap();
+
+        let original = lines("new\na\nb\nc\nd\ne");
+        state.replace_diff_result(compute_diff(&original, &modified, DiffOptions::default()));
+        assert_eq!(state.annotations[0].hunk_index, 1);
+    }
+
+    #[test]
+    fn test_annotation_orphaned_when_hunk_disappears() {
+        let original = lines("a\nb\nc\nd\ne");
+        let modified = lines("a\nB\nc\nd\nE");
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let mut state = ComparisonState::new("a.txt".to_string(), "b.txt".to_string(), changes);
+        state.add_annotation(0, "why B?".to_string()).unwrap();
+
+        // Only the second hunk survives and takes index 0; the note must not follow it there
+        let reverted = lines("a\nb\nc\nd\nE");
+        state.replace_diff_result(compute_diff(&original, &reverted, DiffOptions::default()));
+        assert!(state.annotations[0].orphaned);
+        assert_eq!(state.annotations_for_hunk(0).count(), 0);
+
+        state.replace_diff_result(compute_diff(&original, &modified, DiffOptions::default()));
+        assert!(!state.annotations[0].orphaned);
+        assert_eq!(state.annotations_for_hunk(0).next().unwrap().text, "why B?");
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut state = sample_state();
+        state.add_annotation(0, "note".to_string()).unwrap();
+
+        let restored = ComparisonState::from_json(&state.to_json().unwrap()).unwrap();
+        assert_eq!(restored.annotations, state.annotations);
+        assert_eq!(restored.diff_result.len(), 1);
+    }
+
+    #[test]
+    fn test_option_overrides() {
+        let mut overrides = OptionOverrides::default();
+        let base = DiffOptions::default();
+        assert!(overrides.toggle("ignore_whitespace", &base).unwrap());
+        let options = overrides.apply(&base);
+        assert!(options.ignore_whitespace && !options.ignore_case);
+        assert!(!overrides.toggle("ignore_whitespace", &options).unwrap());
+        assert_eq!(overrides.ignore_whitespace, Some(false));
+        assert!(overrides.toggle("tabs", &base).unwrap_err().starts_with("Unknown option tabs"));
+    }
+}
@@ -0,0 +1,362 @@
+//! Structural comparison of definitions (functions, classes) rather than raw lines.
+//!
+//! Trees come from tree-sitter when the host provides them; `outline` is a heuristic fallback
+//! that recognises common definition keywords and ends blocks by brace depth or indentation.
+
+use std::ops::Range;
+use crate::diff_core::{compute_diff, DiffOptions, LineChange};
+use crate::token_diff::{compute_token_diff, Token};
+
+/// A definition in a syntax tree, with its 0-based, end-exclusive line range
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxNode {
+    /// `function` or `class`, or a tree-sitter node kind
+    pub kind: String,
+    pub name: String,
+    /// The definition's header line, trimmed
+    pub signature: String,
+    pub lines: Range<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub enum StructuralChange {
+    Added(SyntaxNode),
+    Removed(SyntaxNode),
+    /// Present on both sides but in a different order relative to the other definitions
+    Moved { original: SyntaxNode, modified: SyntaxNode },
+    SignatureChanged { original: SyntaxNode, modified: SyntaxNode },
+    /// Line diff of the body, in file line numbers
+    BodyChanged {
+        original: SyntaxNode,
+        modified: SyntaxNode,
+        changes: Vec<LineChange>,
+    },
+}
+
+const DEFINITION_KEYWORDS: &[(&str, &str)] = &[
+    ("fn", "function"),
+    ("def", "function"),
+    ("func", "function"),
+    ("function", "function"),
+    ("class", "class"),
+    ("struct", "class"),
+    ("trait", "class"),
+    ("interface", "class"),
+];
+
+const MODIFIERS: &[&str] = &[
+    "pub", "pub(crate)", "async", "export", "default", "static", "unsafe", "const", "abstract",
+];
+
+/// Top-level definitions found heuristically in `lines`
+pub fn outline(lines: &[String]) -> Vec<SyntaxNode> {
+    let mut nodes = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        match parse_definition(&lines[index]) {
+            Some((kind, name)) => {
+                let end = block_end(lines, index);
+                nodes.push(SyntaxNode {
+                    kind: kind.to_string(),
+                    name,
+                    signature: lines[index].trim().trim_end_matches(['{', ':']).trim_end().to_string(),
+                    lines: index..end,
+                });
+                index = end;
+            }
+            None => index += 1,
+        }
+    }
+    nodes
+}
+
+/// Kind and name of a definition starting on this line
+fn parse_definition(line: &str) -> Option<(&'static str, String)> {
+    let mut words = line.split_whitespace().skip_while(|word| MODIFIERS.contains(word));
+    let keyword = words.next()?;
+    let &(_, kind) = DEFINITION_KEYWORDS.iter().find(|(k, _)| *k == keyword)?;
+    let name: String = words
+        .next()?
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    (!name.is_empty()).then_some((kind, name))
+}
+
+/// End of the block opened at `start`: where braces balance, or for brace-less languages the
+/// next non-blank line indented no deeper than the header
+fn block_end(lines: &[String], start: usize) -> usize {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let header_indent = indent(&lines[start]);
+    let mut depth = 0i64;
+    let mut seen_brace = false;
+
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        for c in line.chars() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    seen_brace = true;
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if seen_brace && depth <= 0 {
+            return index + 1;
+        }
+        if !seen_brace && line.trim_end().ends_with(';') && index == start {
+            return start + 1;
+        }
+        if !seen_brace && index > start && !line.trim().is_empty() && indent(line) <= header_indent {
+            // Trailing blank lines belong to the gap, not the block
+            let mut end = index;
+            while end > start + 1 && lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+            return end;
+        }
+    }
+    lines.len()
+}
+
+/// Match definitions by kind and name, then report additions, removals, moves, and
+/// signature and body changes; bodies fall back to a line diff
+pub fn structural_diff(
+    original: &[SyntaxNode],
+    modified: &[SyntaxNode],
+    original_lines: &[String],
+    modified_lines: &[String],
+    options: &DiffOptions,
+) -> Vec<StructuralChange> {
+    let key = |node: &SyntaxNode| format!("{} {}", node.kind, node.name);
+    let mut matched: Vec<Option<usize>> = vec![None; modified.len()];
+    let mut pairs = Vec::new();
+    let mut changes = Vec::new();
+
+    for (i, node) in original.iter().enumerate() {
+        let found = (0..modified.len()).find(|&j| matched[j].is_none() && key(&modified[j]) == key(node));
+        match found {
+            Some(j) => {
+                matched[j] = Some(i);
+                pairs.push((i, j));
+            }
+            None => changes.push(StructuralChange::Removed(node.clone())),
+        }
+    }
+    for (j, node) in modified.iter().enumerate() {
+        if matched[j].is_none() {
+            changes.push(StructuralChange::Added(node.clone()));
+        }
+    }
+
+    // Definitions outside the longest common ordering of matched pairs have moved
+    let order = |indices: Vec<usize>| -> Vec<Token> {
+        indices.into_iter().map(|i| Token::new(key(&original[i]), i..i + 1)).collect()
+    };
+    let original_order = order(pairs.iter().map(|&(i, _)| i).collect());
+    let mut by_modified = pairs.clone();
+    by_modified.sort_by_key(|&(_, j)| j);
+    let modified_order = order(by_modified.iter().map(|&(i, _)| i).collect());
+    let moved: Vec<usize> = compute_token_diff(&original_order, &modified_order)
+        .iter()
+        .flat_map(|change| change.original.clone())
+        .map(|index| pairs[index].0)
+        .collect();
+
+    for &(i, j) in &pairs {
+        let (a, b) = (&original[i], &modified[j]);
+        if moved.contains(&i) {
+            changes.push(StructuralChange::Moved {
+                original: a.clone(),
+                modified: b.clone(),
+            });
+        }
+        if a.signature != b.signature {
+            changes.push(StructuralChange::SignatureChanged {
+                original: a.clone(),
+                modified: b.clone(),
+            });
+        }
+        let body = |lines: &[String], node: &SyntaxNode| lines[node.lines.start + 1..node.lines.end].to_vec();
+        let mut body_changes = compute_diff(&body(original_lines, a), &body(modified_lines, b), options.clone());
+        if !body_changes.is_empty() {
+            for change in &mut body_changes {
+                change.original_start += a.lines.start + 1;
+                change.original_end += a.lines.start + 1;
+                change.modified_start += b.lines.start + 1;
+                change.modified_end += b.lines.start + 1;
+            }
+            changes.push(StructuralChange::BodyChanged {
+                original: a.clone(),
+                modified: b.clone(),
+                changes: body_changes,
+            });
+        }
+    }
+    changes
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FunctionStatus {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Per-definition totals of a line diff, for orienting in large diffs
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionSummary {
+    pub kind: String,
+    pub name: String,
+    pub status: FunctionStatus,
+    pub added_lines: usize,
+    pub removed_lines: usize,
+}
+
+/// Attribute the lines of a line diff to the definitions containing them
+pub fn summarize_functions(
+    original_lines: &[String],
+    modified_lines: &[String],
+    changes: &[LineChange],
+) -> Vec<FunctionSummary> {
+    let (original, modified) = (outline(original_lines), outline(modified_lines));
+    let key = |node: &SyntaxNode| (node.kind.clone(), node.name.clone());
+    let overlap = |a: &Range<usize>, start: usize, end: usize| end.min(a.end).saturating_sub(start.max(a.start));
+    let removed_in = |node: &SyntaxNode| -> usize {
+        changes.iter().map(|c| overlap(&node.lines, c.original_start, c.original_end)).sum()
+    };
+    let added_in = |node: &SyntaxNode| -> usize {
+        changes.iter().map(|c| overlap(&node.lines, c.modified_start, c.modified_end)).sum()
+    };
+    let summary = |node: &SyntaxNode, status, added_lines, removed_lines| FunctionSummary {
+        kind: node.kind.clone(),
+        name: node.name.clone(),
+        status,
+        added_lines,
+        removed_lines,
+    };
+
+    let mut summaries = Vec::new();
+    for node in &original {
+        match modified.iter().find(|other| key(other) == key(node)) {
+            Some(other) => {
+                let (added, removed) = (added_in(other), removed_in(node));
+                if added + removed > 0 {
+                    summaries.push(summary(node, FunctionStatus::Modified, added, removed));
+                }
+            }
+            None => summaries.push(summary(node, FunctionStatus::Removed, 0, node.lines.len())),
+        }
+    }
+    for node in &modified {
+        if !original.iter().any(|other| key(other) == key(node)) {
+            summaries.push(summary(node, FunctionStatus::Added, node.lines.len(), 0));
+        }
+    }
+    summaries
+}
+
+/// A header block listing changed definitions, or nothing when none were found
+pub fn format_function_summary(summaries: &[FunctionSummary]) -> String {
+    if summaries.is_empty() {
+        return String::new();
+    }
+
+    let mut output = format!("{} definition(s) changed:\n", summaries.len());
+    for summary in summaries {
+        let marker = match summary.status {
+            FunctionStatus::Added => '+',
+            FunctionStatus::Removed => '-',
+            FunctionStatus::Modified => '~',
+        };
+        output.push_str(&format!(
+            "  {} {} {} (+{} -{})\n",
+            marker, summary.kind, summary.name, summary.added_lines, summary.removed_lines
+        ));
+    }
+    output
+}
+
+pub fn format_structural_diff(file1_path: &str, file2_path: &str, changes: &[StructuralChange]) -> String {
+    let mut output = format!("--- {}\n+++ {}\n", file1_path, file2_path);
+    if changes.is_empty() {
+        output.push_str("\nNo structural changes\n");
+        return output;
+    }
+
+    output.push('\n');
+    for change in changes {
+        output.push_str(&match change {
+            StructuralChange::Added(node) => format!("+ {} {} (line {})\n", node.kind, node.name, node.lines.start + 1),
+            StructuralChange::Removed(node) => format!("- {} {} (line {})\n", node.kind, node.name, node.lines.start + 1),
+            StructuralChange::Moved { original, modified } => format!(
+                "> {} {} moved (line {} -> {})\n",
+                original.kind,
+                original.name,
+                original.lines.start + 1,
+                modified.lines.start + 1
+            ),
+            StructuralChange::SignatureChanged { original, modified } => format!(
+                "~ {} {} signature: `{}` -> `{}`\n",
+                original.kind, original.name, original.signature, modified.signature
+            ),
+            StructuralChange::BodyChanged { original, changes, .. } => format!(
+                "~ {} {} body: {} hunk(s)\n",
+                original.kind,
+                original.name,
+                changes.len()
+            ),
+        });
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_outline() {
+        let source = lines("use x;\n\npub fn a() {\n    1\n}\n\ndef b(x):\n    return x\n\nclass C:\n    pass");
+        let nodes = outline(&source);
+        let summary: Vec<_> = nodes.iter().map(|n| (n.name.as_str(), n.lines.clone())).collect();
+        assert_eq!(summary, vec![("a", 2..5), ("b", 6..8), ("C", 9..11)]);
+        assert_eq!(nodes[0].signature, "pub fn a()");
+    }
+
+    #[test]
+    fn test_structural_diff() {
+        let original = lines("fn a() {\n    1\n}\nfn b() {\n    2\n}\nfn gone() {\n}");
+        let modified = lines("fn b() {\n    2\n}\nfn a(x: u8) {\n    1\n}\nfn new() {\n}");
+        let changes = structural_diff(
+            &outline(&original),
+            &outline(&modified),
+            &original,
+            &modified,
+            &DiffOptions::default(),
+        );
+
+        let text = format_structural_diff("x", "y", &changes);
+        assert!(text.contains("- function gone (line 7)"));
+        assert!(text.contains("+ function new (line 7)"));
+        assert_eq!(text.matches(" moved ").count(), 1);
+        assert!(text.contains("~ function a signature: `fn a()` -> `fn a(x: u8)`"));
+        assert!(!text.contains("body"));
+    }
+
+    #[test]
+    fn test_function_summary() {
+        let original = lines("fn a() {\n    1\n}\nfn gone() {\n}");
+        let modified = lines("fn a() {\n    2\n    3\n}\nfn new() {\n    x\n}");
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+
+        let summary = format_function_summary(&summarize_functions(&original, &modified, &changes));
+        assert_eq!(
+            summary,
+            "3 definition(s) changed:\n  ~ function a (+2 -1)\n  - function gone (+0 -2)\n  + function new (+3 -0)\n"
+        );
+    }
+}
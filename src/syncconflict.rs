@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::eol::{detect_line_ending, has_final_newline, join_lines};
+
+/// A copy a file-sync client left next to a file it could not reconcile
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConflictCopy {
+    pub path: PathBuf,
+    pub tool: &'static str,
+    pub modified: Option<SystemTime>,
+}
+
+/// The file name a conflicted copy was made from, and the sync tool whose pattern it matches:
+/// - Dropbox: `report (Ann's conflicted copy 2024-01-02).txt`
+/// - Nextcloud: `report (conflicted copy 2024-01-02 103000).txt`
+/// - ownCloud: `report_conflict-20240102-103000.txt`
+/// - Syncthing: `report.sync-conflict-20240102-103000-ABCDEFG.txt`
+pub fn conflict_copy_origin(name: &str) -> Option<(String, &'static str)> {
+    if let Some(open) = name.find(" (") {
+        let close = open + name[open..].find(')')?;
+        let label = &name[open + 2..close];
+        if label.contains("conflicted copy") || label.contains("Case Conflict") {
+            // Nextcloud stamps a time after the date; Dropbox names the user instead
+            let tool = match label.rsplit(' ').next() {
+                Some(time) if !label.contains("'s ") && time.len() == 6 && time.chars().all(|c| c.is_ascii_digit()) => {
+                    "Nextcloud"
+                }
+                _ => "Dropbox",
+            };
+            return Some((format!("{}{}", &name[..open], &name[close + 1..]), tool));
+        }
+    }
+    if let Some(at) = name.find(".sync-conflict-") {
+        let rest = &name[at + ".sync-conflict-".len()..];
+        let extension = rest.find('.').map_or("", |dot| &rest[dot..]);
+        return Some((format!("{}{}", &name[..at], extension), "Syncthing"));
+    }
+    if let Some(at) = name.find("_conflict-") {
+        let rest = &name[at + "_conflict-".len()..];
+        let extension = rest.find('.').map_or("", |dot| &rest[dot..]);
+        return Some((format!("{}{}", &name[..at], extension), "ownCloud"));
+    }
+    None
+}
+
+/// Conflicted copies of `path` in its directory, newest first
+pub fn find_conflict_copies(path: &Path) -> Vec<ConflictCopy> {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Vec::new();
+    };
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+
+    let mut copies: Vec<ConflictCopy> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let (origin, tool) = conflict_copy_origin(&file_name)?;
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            (origin == name).then(|| ConflictCopy {
+                path: entry.path(),
+                tool,
+                modified: metadata.modified().ok(),
+            })
+        })
+        .collect();
+    copies.sort_by_key(|copy| std::cmp::Reverse(copy.modified));
+    copies
+}
+
+/// The `(original, conflicted copy)` pair for a path naming either one; `choice` picks among
+/// several copies, newest first
+pub fn pair_conflict_copy(path: &Path, choice: usize) -> Result<(PathBuf, PathBuf), String> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    if let Some((origin, _)) = conflict_copy_origin(name) {
+        return Ok((path.with_file_name(origin), path.to_path_buf()));
+    }
+    let copies = find_conflict_copies(path);
+    let copy = copies
+        .get(choice)
+        .ok_or_else(|| format!("No conflicted copy #{} of {} ({} found)", choice + 1, path.display(), copies.len()))?;
+    Ok((path.to_path_buf(), copy.path.clone()))
+}
+
+/// Keep the merged text as the original and delete the conflicted copy; refuses while
+/// conflict markers remain
+pub fn resolve_conflict_copy(original: &Path, copy: &Path, merged: &[String]) -> Result<(), String> {
+    if merged.iter().any(|line| line.starts_with("<<<<<<< ") || line.starts_with(">>>>>>> ")) {
+        return Err("The merged text still contains conflict markers".to_string());
+    }
+    // Keep the original's line endings and whether it ends in one
+    let path = original.to_string_lossy();
+    let text = join_lines(merged, detect_line_ending(&path), has_final_newline(&path));
+    fs::write(original, text).map_err(|e| format!("Failed to write {}: {}", original.display(), e))?;
+    fs::remove_file(copy).map_err(|e| format!("Failed to remove {}: {}", copy.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_conflict_copy_origin() {
+        let origin = |name: &str| conflict_copy_origin(name);
+        assert_eq!(origin("a (Ann's conflicted copy 2024-01-02).txt"), Some(("a.txt".to_string(), "Dropbox")));
+        assert_eq!(origin("a (conflicted copy 2024-01-02 103000).txt"), Some(("a.txt".to_string(), "Nextcloud")));
+        assert_eq!(origin("a.tar.sync-conflict-20240102-103000-ABC.gz"), Some(("a.tar.gz".to_string(), "Syncthing")));
+        assert_eq!(origin("Makefile.sync-conflict-20240102-103000-ABC"), Some(("Makefile".to_string(), "Syncthing")));
+        assert_eq!(origin("a_conflict-20240102-103000.txt"), Some(("a.txt".to_string(), "ownCloud")));
+        assert_eq!(origin("a (copy).txt"), None);
+    }
+
+    #[test]
+    fn test_pair_and_resolve() {
+        let dir = TempDir::new("sync");
+        let original = dir.join("notes.md");
+        let copy = dir.join("notes (Ann's conflicted copy 2024-01-02).md");
+        fs::write(&original, "a\r\n").unwrap();
+        fs::write(&copy, "b\n").unwrap();
+        fs::write(dir.join("other (Ann's conflicted copy 2024-01-02).md"), "c\n").unwrap();
+
+        assert_eq!(find_conflict_copies(&original).len(), 1);
+        assert_eq!(pair_conflict_copy(&copy, 0).unwrap(), (original.clone(), copy.clone()));
+        assert_eq!(pair_conflict_copy(&original, 0).unwrap().1, copy);
+
+        let merged = vec!["<<<<<<< ours".to_string()];
+        assert!(resolve_conflict_copy(&original, &copy, &merged).is_err());
+        resolve_conflict_copy(&original, &copy, &["a".to_string(), "b".to_string()]).unwrap();
+        assert_eq!(fs::read_to_string(&original).unwrap(), "a\r\nb\r\n");
+        assert!(!copy.exists());
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::path::Path;
+use crate::walk::{walk_dir, WalkStep};
+
+/// A test target a change probably affects
+#[derive(Clone, Debug, PartialEq)]
+pub struct AffectedTest {
+    /// File holding the tests
+    pub path: String,
+    pub reason: String,
+    /// Arguments to `cargo test` that run just these tests
+    pub command: String,
+}
+
+/// Rust tests likely affected by changes to `changed` (paths relative to `root`): unit tests in
+/// the changed files or their `tests.rs` submodules, integration tests under `tests/` that
+/// mention a changed module, and changed test files themselves. Non-Rust files are ignored.
+pub fn affected_tests(root: &Path, changed: &[&str]) -> Vec<AffectedTest> {
+    let integration = integration_tests(root);
+    let mut affected: Vec<AffectedTest> = Vec::new();
+    let mut add = |test: AffectedTest| {
+        if !affected.iter().any(|known| known.path == test.path) {
+            affected.push(test);
+        }
+    };
+
+    for path in changed.iter().filter(|path| path.ends_with(".rs")) {
+        if let Some(name) = path.strip_prefix("tests/") {
+            add(integration_test(name, "changed"));
+            continue;
+        }
+        let Some(module) = module_path(path) else {
+            continue;
+        };
+        let source = fs::read_to_string(root.join(path)).unwrap_or_default();
+        if source.contains("#[cfg(test)]") {
+            add(AffectedTest {
+                path: path.to_string(),
+                reason: format!("unit tests in {}", path),
+                command: format!("cargo test {}", test_filter(&module)),
+            });
+        }
+        let submodule = format!("{}/tests.rs", path.trim_end_matches(".rs").trim_end_matches("/mod"));
+        if root.join(&submodule).is_file() {
+            add(AffectedTest {
+                path: submodule,
+                reason: format!("test module of {}", path),
+                command: format!("cargo test {}", test_filter(&module)),
+            });
+        }
+        // Integration tests only see the public API, so look for the module's own name
+        let Some(name) = module.rsplit("::").next().filter(|name| !name.is_empty()) else {
+            continue;
+        };
+        for (test, text) in &integration {
+            if mentions_module(text, name) {
+                add(integration_test(test, &format!("mentions {}", name)));
+            }
+        }
+    }
+    affected
+}
+
+/// `src/a/b.rs` is `a::b`, `src/a/mod.rs` is `a`, and `src/lib.rs` or `src/main.rs` the crate
+/// root (empty); `None` outside `src/`
+fn module_path(path: &str) -> Option<String> {
+    let inner = path.strip_prefix("src/")?.strip_suffix(".rs")?;
+    let inner = inner.strip_suffix("/mod").unwrap_or(inner);
+    if matches!(inner, "lib" | "main") {
+        return Some(String::new());
+    }
+    Some(inner.replace('/', "::"))
+}
+
+fn test_filter(module: &str) -> String {
+    if module.is_empty() {
+        "tests::".to_string()
+    } else {
+        format!("{}::", module)
+    }
+}
+
+fn integration_test(name: &str, reason: &str) -> AffectedTest {
+    // `tests/api/main.rs` and `tests/api.rs` are both the `api` target
+    let target = name.strip_suffix("/main.rs").or_else(|| name.strip_suffix(".rs")).unwrap_or(name);
+    let target = target.split('/').next().unwrap_or(target);
+    AffectedTest {
+        path: format!("tests/{}", name),
+        reason: reason.to_string(),
+        command: format!("cargo test --test {}", target),
+    }
+}
+
+/// Every `.rs` file under `root/tests`, as a path below `tests/` and its contents
+fn integration_tests(root: &Path) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    walk_dir(&root.join("tests"), "", |path, entry, is_dir| {
+        if !is_dir && path.ends_with(".rs") {
+            files.push((path, fs::read_to_string(entry.path()).unwrap_or_default()));
+        }
+        WalkStep::Descend
+    });
+    files.sort();
+    files
+}
+
+/// Whether `text` uses `name` as a path segment, as in `use krate::name` or `name::f()`
+fn mentions_module(text: &str, name: &str) -> bool {
+    text.match_indices(name).any(|(at, _)| {
+        let before = text[..at].chars().next_back();
+        let after = text[at + name.len()..].chars().next();
+        let boundary = |c: Option<char>| !c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        boundary(before) && boundary(after) && (text[..at].ends_with("::") || text[at + name.len()..].starts_with("::"))
+    })
+}
+
+pub fn format_affected_tests(tests: &[AffectedTest]) -> String {
+    if tests.is_empty() {
+        return "No tests found for the changed files\n".to_string();
+    }
+    let mut output = "Tests likely affected:\n".to_string();
+    for test in tests {
+        output.push_str(&format!("  {} ({}): {}\n", test.path, test.reason, test.command));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_affected_tests() {
+        let root = TempDir::new("impact");
+        let files = [
+            ("src/parser.rs", "pub fn parse() {}\n#[cfg(test)]\nmod tests {}\n"),
+            ("src/net/client.rs", "pub fn get() {}\n"),
+            ("src/net/client/tests.rs", "#[test]\nfn t() {}\n"),
+            ("tests/parsing.rs", "use mycrate::parser::parse;\n"),
+            ("tests/cli/main.rs", "fn main_parser() {}\n"),
+            ("tests/http.rs", "use mycrate::net::client;\n"),
+        ];
+        for (path, text) in files {
+            fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            fs::write(root.join(path), text).unwrap();
+        }
+
+        let tests = affected_tests(&root, &["src/parser.rs", "src/net/client.rs", "tests/cli/main.rs", "README.md"]);
+        let summary: Vec<(&str, &str)> = tests.iter().map(|test| (test.path.as_str(), test.command.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("src/parser.rs", "cargo test parser::"),
+                ("tests/parsing.rs", "cargo test --test parsing"),
+                ("src/net/client/tests.rs", "cargo test net::client::"),
+                ("tests/http.rs", "cargo test --test http"),
+                ("tests/cli/main.rs", "cargo test --test cli"),
+            ]
+        );
+        assert!(format_affected_tests(&tests).contains("  tests/parsing.rs (mentions parser): cargo test --test parsing\n"));
+    }
+}
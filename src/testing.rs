@@ -0,0 +1,278 @@
+//! Deterministic random inputs for property tests: file pairs produced by a known edit script.
+
+use crate::diff_core::LineChange;
+use crate::patchmail::unified_hunks;
+
+/// SplitMix64, chosen so a failing seed reproduces identically on every platform
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`; `bound` must be non-zero
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit {
+    Insert { at: usize, lines: Vec<String> },
+    Delete { at: usize, count: usize },
+    /// Remove `count` lines at `from`, then reinsert them at `to` in the shortened file
+    Move { from: usize, count: usize, to: usize },
+}
+
+#[derive(Clone, Debug)]
+pub struct GeneratorOptions {
+    pub lines: usize,
+    pub edits: usize,
+    /// Number of distinct line texts; small values produce many repeated lines
+    pub alphabet: usize,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            lines: 40,
+            edits: 5,
+            alphabet: 8,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FilePair {
+    pub original: Vec<String>,
+    pub modified: Vec<String>,
+    pub edits: Vec<Edit>,
+}
+
+pub fn generate_pair(seed: u64, options: &GeneratorOptions) -> FilePair {
+    let mut rng = Rng::new(seed);
+    let line = |rng: &mut Rng| format!("line {}", rng.below(options.alphabet.max(1)));
+
+    let original: Vec<String> = (0..options.lines).map(|_| line(&mut rng)).collect();
+    let mut current = original.clone();
+    let mut edits = Vec::new();
+
+    for _ in 0..options.edits {
+        let edit = match rng.below(3) {
+            0 => {
+                let count = 1 + rng.below(3);
+                Edit::Insert {
+                    at: rng.below(current.len() + 1),
+                    lines: (0..count).map(|_| line(&mut rng)).collect(),
+                }
+            }
+            1 if !current.is_empty() => {
+                let at = rng.below(current.len());
+                Edit::Delete {
+                    at,
+                    count: 1 + rng.below((current.len() - at).min(3)),
+                }
+            }
+            _ if current.len() >= 2 => {
+                let from = rng.below(current.len());
+                let count = 1 + rng.below((current.len() - from).min(4));
+                Edit::Move {
+                    from,
+                    count,
+                    to: rng.below(current.len() - count + 1),
+                }
+            }
+            _ => continue,
+        };
+        current = apply_edits(&current, std::slice::from_ref(&edit));
+        edits.push(edit);
+    }
+
+    FilePair {
+        original,
+        modified: current,
+        edits,
+    }
+}
+
+pub fn apply_edits(lines: &[String], edits: &[Edit]) -> Vec<String> {
+    let mut lines = lines.to_vec();
+    for edit in edits {
+        match edit {
+            Edit::Insert { at, lines: inserted } => {
+                lines.splice(*at..*at, inserted.iter().cloned());
+            }
+            Edit::Delete { at, count } => {
+                lines.drain(*at..*at + *count);
+            }
+            Edit::Move { from, count, to } => {
+                let moved: Vec<String> = lines.drain(*from..*from + *count).collect();
+                lines.splice(*to..*to, moved);
+            }
+        }
+    }
+    lines
+}
+
+/// Rebuild the modified file from the original by replacing each changed range; equals
+/// `modified` exactly when the change list is a correct diff
+pub fn apply_changes(original: &[String], modified: &[String], changes: &[LineChange]) -> Vec<String> {
+    let mut output = Vec::new();
+    let mut position = 0;
+    for change in changes {
+        output.extend_from_slice(&original[position..change.original_start]);
+        output.extend_from_slice(&modified[change.modified_start..change.modified_end]);
+        position = change.original_end;
+    }
+    output.extend_from_slice(&original[position..]);
+    output
+}
+
+/// A line drawn from text that trips up parsers: diff markers, hunk headers with odd numbers,
+/// quotes, tabs, carriage returns and multi-byte characters
+pub fn random_line(rng: &mut Rng) -> String {
+    const PIECES: &[&str] = &[
+        "", " ", "+", "-", "--- a/", "+++ b/", "@@ -", "@@ +", " @@", ",", "0", "1", "99999999999999999999",
+        "diff --git a/x b/x", "\\ No newline at end of file", "\"", "\\", "\t", "\r", "rename from ",
+        "Binary files ", "é", "日本", "\u{202E}", "x", "fn main() {", "}",
+    ];
+    (0..rng.below(6)).map(|_| PIECES[rng.below(PIECES.len())]).collect()
+}
+
+/// `text` with `count` random line-level corruptions: lines dropped, duplicated, swapped,
+/// inserted, truncated, or with their numbers changed
+pub fn mutate_text(rng: &mut Rng, text: &str, count: usize) -> String {
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    for _ in 0..count {
+        if lines.is_empty() {
+            lines.push(random_line(rng));
+            continue;
+        }
+        let at = rng.below(lines.len());
+        match rng.below(6) {
+            0 => {
+                lines.remove(at);
+            }
+            1 => lines.insert(at, lines[at].clone()),
+            2 => {
+                let other = rng.below(lines.len());
+                lines.swap(at, other);
+            }
+            3 => lines.insert(at, random_line(rng)),
+            4 => {
+                let keep = rng.below(lines[at].chars().count() + 1);
+                lines[at] = lines[at].chars().take(keep).collect();
+            }
+            _ => {
+                let number = rng.below(1000).to_string();
+                lines[at] = lines[at].replacen(|c: char| c.is_ascii_digit(), &number, 1);
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// A `--- a/<path>` / `+++ b/<path>` patch of the change list, as `parse_unified` reads it
+pub fn unified_patch(path: &str, original: &[String], modified: &[String], changes: &[LineChange], context: usize) -> String {
+    format!("--- a/{}\n+++ b/{}\n{}", path, path, unified_hunks(original, modified, changes, context))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use crate::diff_core::{compute_diff, verify, DiffOptions};
+    use crate::outline::outline_patch;
+    use crate::patch::{apply_hunks, parse_unified};
+    use crate::patchview::PatchView;
+    use crate::rebase::{rebase_hunks, RebaseOptions};
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        let options = GeneratorOptions::default();
+        let first = generate_pair(42, &options);
+        let second = generate_pair(42, &options);
+        assert_eq!(first.modified, second.modified);
+        assert_eq!(first.edits, second.edits);
+        assert_eq!(apply_edits(&first.original, &first.edits), first.modified);
+        assert_ne!(generate_pair(43, &options).modified, first.modified);
+    }
+
+    #[test]
+    fn test_diff_round_trips() {
+        let budgets = [
+            DiffOptions::default(),
+            DiffOptions {
+                max_computation_time_ms: 0,
+                ..DiffOptions::default()
+            },
+            DiffOptions {
+                max_memory_bytes: Some(256),
+                ..DiffOptions::default()
+            },
+        ];
+
+        for seed in 0..200 {
+            let pair = generate_pair(seed, &GeneratorOptions::default());
+            for options in &budgets {
+                let changes = compute_diff(&pair.original, &pair.modified, options.clone());
+                assert_eq!(
+                    apply_changes(&pair.original, &pair.modified, &changes),
+                    pair.modified,
+                    "seed {}",
+                    seed
+                );
+                assert_eq!(verify(&pair.original, &pair.modified, &changes), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_patch_round_trips() {
+        let options = GeneratorOptions { alphabet: 4, ..GeneratorOptions::default() };
+        for seed in 0..300 {
+            let pair = generate_pair(seed, &options);
+            let changes = compute_diff(&pair.original, &pair.modified, DiffOptions::default());
+            for context in [0, 1, 3] {
+                let patch = unified_patch("f", &pair.original, &pair.modified, &changes, context);
+                let files = parse_unified(&patch);
+                let hunks = files.first().map(|file| file.hunks.clone()).unwrap_or_default();
+                assert_eq!(apply_hunks(&pair.original, &hunks), Ok(pair.modified.clone()), "seed {} context {}", seed, context);
+            }
+        }
+    }
+
+    /// Garbage and corrupted patches must never panic the parsers, appliers or views
+    #[test]
+    fn test_pathological_inputs() {
+        for seed in 0..300 {
+            let mut rng = Rng::new(seed);
+            let original: Vec<String> = (0..rng.below(30)).map(|_| random_line(&mut rng)).collect();
+            let modified: Vec<String> = (0..rng.below(30)).map(|_| random_line(&mut rng)).collect();
+            let changes = compute_diff(&original, &modified, DiffOptions::default());
+            assert_eq!(apply_changes(&original, &modified, &changes), modified, "seed {}", seed);
+
+            let valid = unified_patch("f", &original, &modified, &changes, 3);
+            let garbage = original.join("\n");
+            for text in [mutate_text(&mut rng, &valid, 1 + seed as usize % 8), garbage] {
+                for file in parse_unified(&text) {
+                    let _ = apply_hunks(&original, &file.hunks);
+                    let _ = rebase_hunks("f", &original, &file.hunks, &RebaseOptions::default());
+                }
+                outline_patch(&text);
+                if let Ok(view) = PatchView::open(&text, Path::new("/nonexistent"), &DiffOptions::default()) {
+                    view.format();
+                }
+            }
+        }
+    }
+}
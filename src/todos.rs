@@ -0,0 +1,111 @@
+use std::ops::Range;
+use crate::diff_core::LineChange;
+
+/// Markers of acknowledged technical debt, matched as whole upper-case words
+pub const DEBT_MARKERS: &[&str] = &["TODO", "FIXME", "HACK"];
+
+/// A TODO, FIXME or HACK comment on a changed line
+#[derive(Clone, Debug, PartialEq)]
+pub struct DebtComment {
+    /// 1-based line in the file it is on: the modified file if added, the original if removed
+    pub line: usize,
+    pub marker: &'static str,
+    /// From the marker to the end of the comment
+    pub text: String,
+}
+
+/// Debt comments the changes introduce and remove
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DebtDelta {
+    pub added: Vec<DebtComment>,
+    pub removed: Vec<DebtComment>,
+}
+
+impl DebtDelta {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// The debt comments on lines `changes` remove and add. A comment that is removed and added
+/// again with the same text was only moved or reindented, and is in neither list.
+pub fn debt_delta(original: &[String], modified: &[String], changes: &[LineChange]) -> DebtDelta {
+    let mut removed = scan(original, changes.iter().map(|c| c.original_start..c.original_end));
+    let mut added = scan(modified, changes.iter().map(|c| c.modified_start..c.modified_end));
+    added.retain(|comment| match removed.iter().position(|old| old.text == comment.text) {
+        Some(at) => {
+            removed.remove(at);
+            false
+        }
+        None => true,
+    });
+    DebtDelta { added, removed }
+}
+
+fn scan(lines: &[String], ranges: impl Iterator<Item = Range<usize>>) -> Vec<DebtComment> {
+    ranges.flatten().filter_map(|index| debt_comment(&lines[index], index + 1)).collect()
+}
+
+fn debt_comment(line: &str, line_number: usize) -> Option<DebtComment> {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    DEBT_MARKERS.iter().find_map(|&marker| {
+        let (at, _) = line.match_indices(marker).find(|(at, _)| {
+            !is_word(line[..*at].chars().next_back()) && !is_word(line[at + marker.len()..].chars().next())
+        })?;
+        let text = line[at..].trim_end();
+        let text = ["*/", "-->", "#}", "--}}"].iter().fold(text, |text, end| text.strip_suffix(end).unwrap_or(text));
+        Some(DebtComment {
+            line: line_number,
+            marker,
+            text: text.trim_end().to_string(),
+        })
+    })
+}
+
+/// New debt followed by resolved debt, or nothing when the changes touch none
+pub fn format_debt_delta(delta: &DebtDelta) -> String {
+    if delta.is_empty() {
+        return String::new();
+    }
+    let mut output = String::new();
+    if !delta.added.is_empty() {
+        output.push_str(&format!("New TODO/FIXME/HACK comments ({}):\n", delta.added.len()));
+        for comment in &delta.added {
+            output.push_str(&format!("  + line {}: {}\n", comment.line, comment.text));
+        }
+    }
+    if !delta.removed.is_empty() {
+        output.push_str(&format!("Resolved TODO/FIXME/HACK comments ({}):\n", delta.removed.len()));
+        for comment in &delta.removed {
+            output.push_str(&format!("  - line {}: {}\n", comment.line, comment.text));
+        }
+    }
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_debt_delta() {
+        let original = lines("fn a() {\n    // FIXME: leaks on error\n    // TODO: cache\n    let todos = 1;\n}");
+        let modified = lines(
+            "fn a() {\n        // TODO: cache\n    /* HACK: retry twice */\n    let TODOS = 1;\n    let s = 1; // TODO(x): later\n}",
+        );
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let delta = debt_delta(&original, &modified, &changes);
+        let added: Vec<(usize, &str)> = delta.added.iter().map(|c| (c.line, c.text.as_str())).collect();
+        assert_eq!(added, vec![(3, "HACK: retry twice"), (5, "TODO(x): later")]);
+        let removed: Vec<(usize, &str)> = delta.removed.iter().map(|c| (c.line, c.marker)).collect();
+        assert_eq!(removed, vec![(2, "FIXME")]);
+        assert_eq!(
+            format_debt_delta(&delta),
+            "New TODO/FIXME/HACK comments (2):\n  + line 3: HACK: retry twice\n  + line 5: TODO(x): later\nResolved TODO/FIXME/HACK comments (1):\n  - line 2: FIXME: leaks on error\n\n"
+        );
+        assert!(format_debt_delta(&DebtDelta::default()).is_empty());
+    }
+}
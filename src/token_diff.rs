@@ -0,0 +1,139 @@
+use std::ops::Range;
+use serde::Serialize;
+use crate::clock::budget_clock;
+use crate::diff_core::{backtrack_changes, coarse_changes, compute_lcs_matrix, ChangeType, DiffOptions};
+use crate::patience::patience_changes;
+
+/// One element of a token stream: its comparable text and its byte span in the source
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub text: String,
+    pub span: Range<usize>,
+}
+
+impl Token {
+    pub fn new(text: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            text: text.into(),
+            span,
+        }
+    }
+}
+
+/// A changed run of tokens, as token index ranges and the source spans they cover
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TokenChange {
+    pub change_type: ChangeType,
+    pub original: Range<usize>,
+    pub modified: Range<usize>,
+    /// Empty at the insertion point for pure additions
+    pub original_span: Range<usize>,
+    /// Empty at the deletion point for pure deletions
+    pub modified_span: Range<usize>,
+}
+
+/// Diff two token streams with the same LCS machinery as line diffs, e.g. for tree-sitter
+/// nodes or lexer output, within the default budgets (see `compute_token_diff_with`).
+pub fn compute_token_diff(original: &[Token], modified: &[Token]) -> Vec<TokenChange> {
+    compute_token_diff_with(original, modified, &DiffOptions::default())
+}
+
+/// `compute_token_diff` within `options`' budgets. Streams whose LCS table would outgrow the
+/// memory budget are aligned on tokens unique to both sides instead, as large line diffs are,
+/// and either way the diff falls back to one coarse change once the time budget runs out.
+pub fn compute_token_diff_with(original: &[Token], modified: &[Token], options: &DiffOptions) -> Vec<TokenChange> {
+    let same = |i: usize, j: usize| original[i].text == modified[j].text;
+    let clock = budget_clock(options.max_computation_time_ms, options.max_operations);
+    let (m, n) = (original.len(), modified.len());
+
+    let word = std::mem::size_of::<usize>() as u64;
+    let table = (m as u64 + 1) * ((n as u64 + 1) * word + std::mem::size_of::<Vec<usize>>() as u64);
+    let changes = if options.max_memory_bytes.is_some_and(|limit| table > limit) {
+        patience_changes(&hash_tokens(original), &hash_tokens(modified), &same, clock.as_ref())
+    } else {
+        match compute_lcs_matrix(m, n, &same, clock.as_ref()) {
+            Some(lcs_matrix) => backtrack_changes(&lcs_matrix, m, n, &same),
+            None => coarse_changes(m, n, &same),
+        }
+    };
+
+    changes
+        .into_iter()
+        .map(|change| TokenChange {
+            original_span: covered_span(original, change.original_start, change.original_end),
+            modified_span: covered_span(modified, change.modified_start, change.modified_end),
+            original: change.original_start..change.original_end,
+            modified: change.modified_start..change.modified_end,
+            change_type: change.change_type,
+        })
+        .collect()
+}
+
+fn hash_tokens(tokens: &[Token]) -> Vec<u64> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    tokens
+        .iter()
+        .map(|token| {
+            let mut hasher = DefaultHasher::new();
+            token.text.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+/// Source span of `tokens[start..end]`, or the empty span where such tokens would sit
+fn covered_span(tokens: &[Token], start: usize, end: usize) -> Range<usize> {
+    if start < end {
+        return tokens[start].span.start..tokens[end - 1].span.end;
+    }
+    let position = match (tokens.get(start), start.checked_sub(1).map(|i| &tokens[i])) {
+        (Some(next), _) => next.span.start,
+        (None, Some(previous)) => previous.span.end,
+        (None, None) => 0,
+    };
+    position..position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whitespace-separated words with their byte spans
+    fn words(text: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut offset = 0;
+        for word in text.split(' ') {
+            tokens.push(Token::new(word, offset..offset + word.len()));
+            offset += word.len() + 1;
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_token_diff_spans() {
+        let changes = compute_token_diff(&words("fn add(a, b)"), &words("fn add(a, b, c)"));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].change_type, ChangeType::Modified);
+        assert_eq!(changes[0].original, 2..3);
+        assert_eq!(changes[0].original_span, 10..12);
+        assert_eq!(changes[0].modified_span, 10..15);
+    }
+
+    #[test]
+    fn test_pure_insertion_has_empty_span() {
+        let changes = compute_token_diff(&words("a c"), &words("a b c"));
+        assert_eq!(changes[0].change_type, ChangeType::Added);
+        assert_eq!(changes[0].original_span, 2..2);
+        assert_eq!(changes[0].modified_span, 2..3);
+    }
+
+    #[test]
+    fn test_memory_budget_anchors_on_unique_tokens() {
+        let options = DiffOptions { max_memory_bytes: Some(64), ..DiffOptions::default() };
+        let changes = compute_token_diff_with(&words("a b c d e"), &words("a b X d e"), &options);
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].original.clone(), changes[0].modified.clone()), (2..3, 2..3));
+    }
+}
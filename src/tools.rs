@@ -0,0 +1,213 @@
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use crate::diff_core::{try_compute_diff, DiffOptions, LineChange};
+use crate::file_handler::read_file_lines;
+use crate::git::file_lines_at_revision;
+use crate::messages::{text, Message};
+
+/// A tool as an assistant or context server lists it: a name, what it does, and a JSON schema
+/// for its arguments
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToolDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub input_schema: Value,
+}
+
+impl ToolDefinition {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "description": self.description,
+            "inputSchema": self.input_schema,
+        })
+    }
+}
+
+/// The diff engine's tools for assistants and agents
+pub fn tool_definitions() -> Vec<ToolDefinition> {
+    let paths = |first: &str, second: &str| {
+        json!({
+            "type": "object",
+            "properties": {
+                first: { "type": "string", "description": "Path of the original file" },
+                second: { "type": "string", "description": "Path of the modified file" },
+                "ignore_whitespace": { "type": "boolean" },
+            },
+            "required": [first, second],
+        })
+    };
+    vec![
+        ToolDefinition {
+            name: "compare_files",
+            description: "Line diff of two files, with hunk positions (0-based, end-exclusive) and diffstat",
+            input_schema: paths("original", "modified"),
+        },
+        ToolDefinition {
+            name: "compare_with_revision",
+            description: "Line diff of a file's committed version at a git revision against its working copy",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "repo": { "type": "string", "description": "Repository root" },
+                    "path": { "type": "string", "description": "File path relative to the repository root" },
+                    "revision": { "type": "string", "description": "Defaults to HEAD" },
+                    "ignore_whitespace": { "type": "boolean" },
+                },
+                "required": ["repo", "path"],
+            }),
+        },
+        ToolDefinition {
+            name: "diffstat",
+            description: "Hunk, insertion and deletion counts between two files",
+            input_schema: paths("original", "modified"),
+        },
+    ]
+}
+
+/// Run tool `name` with its JSON `arguments`, returning a structured result
+pub fn call_tool(name: &str, arguments: &Value) -> Result<Value, String> {
+    let string = |key: &str| {
+        arguments
+            .get(key)
+            .and_then(Value::as_str)
+            .ok_or_else(|| text(Message::ToolNeedsString, [&name, &key]))
+    };
+    let options = DiffOptions {
+        ignore_whitespace: arguments.get("ignore_whitespace").and_then(Value::as_bool).unwrap_or(false),
+        ..DiffOptions::default()
+    };
+    let read = |path: &str| read_file_lines(path).map_err(|e| text(Message::ReadFailed, [&path, &e]));
+
+    match name {
+        "compare_files" | "diffstat" => {
+            let (original, modified) = (string("original")?, string("modified")?);
+            let changes = diff(&read(original)?, &read(modified)?, options)?;
+            let mut result = json!({ "original": original, "modified": modified, "stats": stats(&changes) });
+            if name == "compare_files" {
+                result["hunks"] = json!(changes);
+            }
+            Ok(result)
+        }
+        "compare_with_revision" => {
+            let (repo, path) = (string("repo")?, string("path")?);
+            let revision = arguments.get("revision").and_then(Value::as_str).unwrap_or("HEAD");
+            let original = file_lines_at_revision(Path::new(repo), revision, path)
+                .map_err(|e| text(Message::ReadAtRevisionFailed, [&path, &revision, &e]))?;
+            let modified = read(&Path::new(repo).join(path).to_string_lossy())?;
+            let changes = diff(&original, &modified, options)?;
+            Ok(json!({ "path": path, "revision": revision, "stats": stats(&changes), "hunks": changes }))
+        }
+        _ => Err(text(Message::UnknownTool, [&name])),
+    }
+}
+
+/// MCP revision `serve` speaks
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Answer one JSON-RPC message from a context-server client; notifications, which carry no
+/// `id`, get no answer. Tool failures are results with `isError` set, as MCP asks, so the
+/// assistant sees them.
+pub fn handle_message(message: &Value) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let method = message.get("method").and_then(Value::as_str).unwrap_or_default();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "zed-diff-mcp", "version": env!("CARGO_PKG_VERSION") },
+        }),
+        "ping" => json!({}),
+        "tools/list" => {
+            let tools: Vec<Value> = tool_definitions().iter().map(ToolDefinition::to_json).collect();
+            json!({ "tools": tools })
+        }
+        "tools/call" => {
+            let name = params.get("name").and_then(Value::as_str).unwrap_or_default();
+            let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+            match call_tool(name, &arguments) {
+                Ok(result) => json!({ "content": [{ "type": "text", "text": result.to_string() }], "isError": false }),
+                Err(e) => json!({ "content": [{ "type": "text", "text": e }], "isError": true }),
+            }
+        }
+        _ => {
+            let error = json!({ "code": -32601, "message": format!("Method not found: {}", method) });
+            return Some(json!({ "jsonrpc": "2.0", "id": id, "error": error }));
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+/// Run the tools as an MCP context server over stdio: one JSON-RPC message per line in, one
+/// answer per line out, until `input` closes
+pub fn serve(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let answer = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&message),
+            Err(e) => Some(json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32700, "message": e.to_string() } })),
+        };
+        if let Some(answer) = answer {
+            writeln!(output, "{}", answer)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn diff(original: &[String], modified: &[String], options: DiffOptions) -> Result<Vec<LineChange>, String> {
+    try_compute_diff(original, modified, options).map_err(|e| text(Message::CompareFailed, [&e]))
+}
+
+fn stats(changes: &[LineChange]) -> Value {
+    json!({
+        "hunks": changes.len(),
+        "insertions": changes.iter().map(|c| c.modified_end - c.modified_start).sum::<usize>(),
+        "deletions": changes.iter().map(|c| c.original_end - c.original_start).sum::<usize>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_call_tool() {
+        let dir = TempDir::new("tools");
+        let (a, b) = (dir.join("a.txt"), dir.join("b.txt"));
+        fs::write(&a, "one\ntwo\nthree\n").unwrap();
+        fs::write(&b, "one\n2\nthree\nfour\n").unwrap();
+        let arguments = json!({ "original": a.to_string_lossy(), "modified": b.to_string_lossy() });
+
+        let stat = call_tool("diffstat", &arguments).unwrap();
+        assert_eq!(stat["stats"], json!({ "hunks": 2, "insertions": 2, "deletions": 1 }));
+        assert!(stat.get("hunks").is_none());
+        let result = call_tool("compare_files", &arguments).unwrap();
+        assert_eq!(result["hunks"][1]["modified_start"], 3);
+
+        assert!(call_tool("compare_files", &json!({ "original": "x" })).unwrap_err().contains("\"modified\""));
+        assert!(call_tool("nope", &arguments).is_err());
+        let names: Vec<&str> = tool_definitions().iter().map(|tool| tool.name).collect();
+        assert_eq!(names, vec!["compare_files", "compare_with_revision", "diffstat"]);
+    }
+
+    #[test]
+    fn test_handle_message() {
+        let answer = handle_message(&json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" })).unwrap();
+        assert_eq!(answer["id"], 1);
+        assert_eq!(answer["result"]["tools"][0]["name"], "compare_files");
+
+        let call = json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": { "name": "nope" } });
+        assert_eq!(handle_message(&call).unwrap()["result"]["isError"], true);
+        let unknown = handle_message(&json!({ "jsonrpc": "2.0", "id": 3, "method": "resources/list" })).unwrap();
+        assert_eq!(unknown["error"]["code"], -32601);
+        assert!(handle_message(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).is_none());
+    }
+}
@@ -0,0 +1,177 @@
+use std::cell::RefCell;
+use std::time::Instant;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+}
+
+impl Level {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "debug" => Some(Level::Debug),
+            "info" => Some(Level::Info),
+            "warn" | "warning" => Some(Level::Warn),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+        }
+    }
+}
+
+/// What part of a comparison an event comes from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    /// Reading files and running git or external tools
+    Io,
+    /// Planning and computing the diff
+    Algorithm,
+    /// Rendering, paging and truncating output
+    Format,
+}
+
+impl Category {
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::Io => "io",
+            Category::Algorithm => "algorithm",
+            Category::Format => "format",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEvent {
+    pub level: Level,
+    pub category: Category,
+    pub message: String,
+    /// Since the trace started
+    pub at_ms: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phase {
+    pub name: String,
+    pub category: Category,
+    pub duration_ms: u64,
+}
+
+/// Everything logged while one comparison ran
+#[derive(Clone, Debug)]
+pub struct Trace {
+    pub label: String,
+    pub min_level: Level,
+    pub started: Instant,
+    pub phases: Vec<Phase>,
+    pub events: Vec<TraceEvent>,
+}
+
+thread_local! {
+    /// The comparison being traced, and the last one finished
+    static CURRENT: RefCell<Option<Trace>> = const { RefCell::new(None) };
+    static LAST: RefCell<Option<Trace>> = const { RefCell::new(None) };
+}
+
+/// Finishes its trace when dropped, whichever way the comparison ends
+pub struct TraceGuard(());
+
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        if let Some(trace) = CURRENT.with(|current| current.borrow_mut().take()) {
+            LAST.with(|last| *last.borrow_mut() = Some(trace));
+        }
+    }
+}
+
+/// Start tracing a comparison, keeping events at `min_level` and above; a trace already
+/// running is replaced
+pub fn start(label: &str, min_level: Level) -> TraceGuard {
+    let trace = Trace { label: label.to_string(), min_level, started: Instant::now(), phases: Vec::new(), events: Vec::new() };
+    CURRENT.with(|current| *current.borrow_mut() = Some(trace));
+    TraceGuard(())
+}
+
+/// Record an event in the running trace; without one, or below its level, this does nothing
+pub fn log(level: Level, category: Category, message: impl FnOnce() -> String) {
+    CURRENT.with(|current| {
+        if let Some(trace) = current.borrow_mut().as_mut().filter(|trace| level >= trace.min_level) {
+            let at_ms = trace.started.elapsed().as_millis() as u64;
+            trace.events.push(TraceEvent { level, category, message: message(), at_ms });
+        }
+    });
+}
+
+/// Run `work` as a named phase of the running trace, recording how long it took
+pub fn phase<T>(category: Category, name: &str, work: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = work();
+    let duration_ms = started.elapsed().as_millis() as u64;
+    CURRENT.with(|current| {
+        if let Some(trace) = current.borrow_mut().as_mut() {
+            trace.phases.push(Phase { name: name.to_string(), category, duration_ms });
+        }
+    });
+    result
+}
+
+/// The trace of the last comparison to finish on this thread
+pub fn last_trace() -> Option<Trace> {
+    LAST.with(|last| last.borrow().clone())
+}
+
+pub fn format_trace(trace: &Trace) -> String {
+    let mut text = format!("Diagnostics for {}\n", trace.label);
+    if !trace.phases.is_empty() {
+        text.push_str("\nPhases:\n");
+        for phase in &trace.phases {
+            text.push_str(&format!("  {:<12} {:>6} ms  ({})\n", phase.name, phase.duration_ms, phase.category.label()));
+        }
+    }
+    if !trace.events.is_empty() {
+        text.push_str("\nEvents:\n");
+        for event in &trace.events {
+            text.push_str(&format!(
+                "  [{:>6} ms] {:<5} {}: {}\n",
+                event.at_ms,
+                event.level.label(),
+                event.category.label(),
+                event.message
+            ));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace() {
+        log(Level::Warn, Category::Io, || "dropped: nothing is being traced".to_string());
+        {
+            let _trace = start("a ↔ b", Level::Info);
+            let lines = phase(Category::Io, "read", || 3);
+            log(Level::Debug, Category::Algorithm, || "dropped: below the level".to_string());
+            log(Level::Info, Category::Algorithm, || format!("full diff of {} lines", lines));
+            phase(Category::Format, "format", || ());
+            assert!(last_trace().is_none());
+        }
+
+        let trace = last_trace().unwrap();
+        let phases: Vec<&str> = trace.phases.iter().map(|phase| phase.name.as_str()).collect();
+        assert_eq!(phases, ["read", "format"]);
+        assert_eq!(trace.events.len(), 1);
+        let text = format_trace(&trace);
+        assert!(text.starts_with("Diagnostics for a ↔ b\n\nPhases:\n  read "));
+        assert!(text.contains("] INFO  algorithm: full diff of 3 lines\n"));
+    }
+}
@@ -1,4 +1,75 @@
+use serde::{Deserialize, Serialize};
 use crate::diff_core::{LineChange, ChangeType};
+use crate::git::{BranchComparison, FileConflicts, HistoryStep, HunkAuthors, WorktreeChange};
+use crate::messages::{text, Message};
+use crate::numbering::LineNumbering;
+use crate::patchmail::unified_hunks;
+
+/// Unchanged lines shown around each hunk unless a view or setting says otherwise
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum OutputFormat {
+    Unified,
+    /// Line counts only, no hunks
+    Stat,
+    /// Machine-readable hunk list
+    Json,
+    /// Old lines struck through above their replacements; see `inline::format_inline_diff`
+    Inline,
+    /// Unified hunks with `old:new` line numbers on every line; see `numbered_unified_hunks`
+    Numbered,
+    /// GNU wdiff markup of changed words; see `wdiff::format_wdiff`
+    Wdiff,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unified" => Some(OutputFormat::Unified),
+            "stat" => Some(OutputFormat::Stat),
+            "json" => Some(OutputFormat::Json),
+            "inline" => Some(OutputFormat::Inline),
+            "numbered" => Some(OutputFormat::Numbered),
+            "wdiff" => Some(OutputFormat::Wdiff),
+            _ => None,
+        }
+    }
+}
+
+pub fn format_diff(
+    format: OutputFormat,
+    file1_path: &str,
+    file2_path: &str,
+    changes: &[LineChange],
+) -> String {
+    match format {
+        OutputFormat::Unified => format_unified_diff(file1_path, file2_path, changes),
+        OutputFormat::Stat => format_stat(file1_path, file2_path, changes),
+        OutputFormat::Json => format_json(file1_path, file2_path, changes),
+        // The inline, numbered and wdiff views need both files' lines; without them fall back to
+        // the unified summary
+        OutputFormat::Inline | OutputFormat::Numbered | OutputFormat::Wdiff => {
+            format_unified_diff(file1_path, file2_path, changes)
+        }
+    }
+}
+
+pub fn format_json(file1_path: &str, file2_path: &str, changes: &[LineChange]) -> String {
+    let document = serde_json::json!({
+        "original": file1_path,
+        "modified": file2_path,
+        "hunks": changes,
+    });
+    serde_json::to_string_pretty(&document).unwrap_or_default() + "\n"
+}
+
+pub fn format_stat(file1_path: &str, file2_path: &str, changes: &[LineChange]) -> String {
+    let added: usize = changes.iter().map(|c| c.modified_end - c.modified_start).sum();
+    let deleted: usize = changes.iter().map(|c| c.original_end - c.original_start).sum();
+
+    text(Message::Stat, [&file1_path, &file2_path, &changes.len(), &added, &deleted]) + "\n"
+}
 
 pub fn format_unified_diff(
     file1_path: &str,
@@ -11,7 +82,7 @@ pub fn format_unified_diff(
     output.push_str(&format!("+++ {}\n", file2_path));
 
     if changes.is_empty() {
-        output.push_str("\nFiles are identical\n");
+        output.push_str(&format!("\n{}\n", text(Message::FilesIdentical, [])));
         return output;
     }
 
@@ -19,24 +90,28 @@ pub fn format_unified_diff(
         let original_range = format_range(change.original_start, change.original_end);
         let modified_range = format_range(change.modified_start, change.modified_end);
 
-        output.push_str(&format!("\n@@ -{} +{} @@\n", original_range, modified_range));
+        output.push_str(&format!("\n@@ -{} +{} @@{}\n", original_range, modified_range, format_tags(change)));
+
+        if change.generated {
+            output.push_str(&format!("  {}\n", text(Message::GeneratedCollapsed, [])));
+            continue;
+        }
 
         match change.change_type {
             ChangeType::Added => {
-                output.push_str(&format!("+{} line(s) added\n", 
-                    change.modified_end - change.modified_start));
+                let count = change.modified_end - change.modified_start;
+                output.push_str(&(text(Message::LinesAdded, [&count]) + "\n"));
             }
             ChangeType::Deleted => {
-                output.push_str(&format!("-{} line(s) deleted\n", 
-                    change.original_end - change.original_start));
+                let count = change.original_end - change.original_start;
+                output.push_str(&(text(Message::LinesDeleted, [&count]) + "\n"));
             }
             ChangeType::Modified => {
-                output.push_str(&format!("~{} line(s) modified\n", 
-                    change.original_end - change.original_start));
+                let count = change.original_end - change.original_start;
+                output.push_str(&(text(Message::LinesModified, [&count]) + "\n"));
 
                 if let Some(ref char_changes) = change.char_changes {
-                    output.push_str(&format!("  ({} character-level changes)\n", 
-                        char_changes.len()));
+                    output.push_str(&format!("  {}\n", text(Message::CharacterChanges, [&char_changes.len()])));
                 }
             }
         }
@@ -45,6 +120,272 @@ pub fn format_unified_diff(
     output
 }
 
+/// Both files' headers over numbered hunks
+pub fn format_numbered_diff(
+    file1: &str,
+    file2: &str,
+    original: &[String],
+    modified: &[String],
+    changes: &[LineChange],
+    context_lines: usize,
+    numbering: &LineNumbering,
+) -> String {
+    if changes.is_empty() {
+        return format!("--- {}\n+++ {}\n\n{}\n", file1, file2, text(Message::FilesIdentical, []));
+    }
+    format!("--- {}\n+++ {}\n{}", file1, file2, numbered_unified_hunks(original, modified, changes, context_lines, numbering))
+}
+
+/// The same hunks with every line prefixed by its `old:new` line numbers, blank on the side
+/// the line is absent from, for jumping from a report to either file. Headers keep unified
+/// numbering, but the prefixes mean the result no longer applies as a patch.
+pub fn numbered_unified_hunks(
+    original: &[String],
+    modified: &[String],
+    changes: &[LineChange],
+    context_lines: usize,
+    numbering: &LineNumbering,
+) -> String {
+    write_hunks(original, modified, changes, context_lines, Some(numbering))
+}
+
+/// Unified hunks, with `numbering`'s `old:new` prefixes when given
+pub(crate) fn write_hunks(
+    original: &[String],
+    modified: &[String],
+    changes: &[LineChange],
+    context_lines: usize,
+    numbering: Option<&LineNumbering>,
+) -> String {
+    let width = numbering.map_or(0, |numbering| match numbering.width {
+        0 => numbering.number(original.len().max(modified.len())).to_string().len(),
+        width => width,
+    });
+    let numbers = |old: Option<usize>, new: Option<usize>| match numbering {
+        Some(numbering) => {
+            let show = |index: Option<usize>| index.map(|i| numbering.number(i).to_string()).unwrap_or_default();
+            format!("{:>width$}:{:<width$} ", show(old), show(new))
+        }
+        None => String::new(),
+    };
+    let mut output = String::new();
+    let mut index = 0;
+    while index < changes.len() {
+        // Extend the group while the next change starts within twice the context of this one's end
+        let mut last = index;
+        while last + 1 < changes.len()
+            && changes[last + 1].original_start <= changes[last].original_end + 2 * context_lines
+        {
+            last += 1;
+        }
+        let (first, end) = (&changes[index], &changes[last]);
+        let original_start = first.original_start.saturating_sub(context_lines);
+        let original_end = (end.original_end + context_lines).min(original.len());
+        let modified_start = first.modified_start - (first.original_start - original_start);
+        let modified_end = end.modified_end + (original_end - end.original_end);
+
+        let range = |start: usize, end: usize| {
+            let count = end - start;
+            // An empty side names the line before it, as in `-0,0`
+            format!("{},{}", if count == 0 { start } else { start + 1 }, count)
+        };
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(original_start, original_end),
+            range(modified_start, modified_end)
+        ));
+
+        let mut position = original_start;
+        // Context lines sit this far further down the modified file
+        let mut shift = modified_start as isize - original_start as isize;
+        for change in &changes[index..=last] {
+            for (i, line) in (position..).zip(&original[position..change.original_start]) {
+                let new = (i as isize + shift) as usize;
+                output.push_str(&format!("{} {}\n", numbers(Some(i), Some(new)), line));
+            }
+            for (i, line) in (change.original_start..).zip(&original[change.original_start..change.original_end]) {
+                output.push_str(&format!("{}-{}\n", numbers(Some(i), None), line));
+            }
+            for (j, line) in (change.modified_start..).zip(&modified[change.modified_start..change.modified_end]) {
+                output.push_str(&format!("{}+{}\n", numbers(None, Some(j)), line));
+            }
+            position = change.original_end;
+            shift = change.modified_end as isize - change.original_end as isize;
+        }
+        for (i, line) in (position..).zip(&original[position..original_end]) {
+            let new = (i as isize + shift) as usize;
+            output.push_str(&format!("{} {}\n", numbers(Some(i), Some(new)), line));
+        }
+        index = last + 1;
+    }
+    output
+}
+
+fn format_tags(change: &LineChange) -> String {
+    change.tags.iter().map(|tag| format!(" [{}]", tag.label())).collect()
+}
+
+pub fn format_branch_comparison(comparison: &BranchComparison) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "# {} ({}...{}, merge base {})\n\n",
+        comparison.path, comparison.base_branch, comparison.branch, short_rev(&comparison.merge_base)
+    ));
+    output.push_str(&format!("## {}\n", text(Message::BranchChangesHeading, [])));
+    output.push_str(&format_unified_diff(
+        &format!("{}:{}", short_rev(&comparison.merge_base), comparison.path),
+        &format!("{}:{}", comparison.branch, comparison.path),
+        &comparison.three_dot,
+    ));
+    output.push_str(&format!("\n## {}\n", text(Message::BranchTipsHeading, [])));
+    output.push_str(&format_unified_diff(
+        &format!("{}:{}", comparison.base_branch, comparison.path),
+        &format!("{}:{}", comparison.branch, comparison.path),
+        &comparison.two_dot,
+    ));
+
+    output
+}
+
+pub fn format_file_history(path: &str, steps: &[HistoryStep]) -> String {
+    let mut output = String::new();
+
+    if steps.is_empty() {
+        output.push_str(&format!("{}\n", text(Message::NoCommitsInRange, [&path])));
+        return output;
+    }
+
+    for (index, step) in steps.iter().enumerate() {
+        output.push_str(&format!(
+            "# [{}/{}] {} {}\n",
+            index + 1,
+            steps.len(),
+            short_rev(&step.commit),
+            step.subject
+        ));
+        let before = match &step.parent {
+            Some(parent) => format!("{}:{}", short_rev(parent), path),
+            None => "/dev/null".to_string(),
+        };
+        output.push_str(&format_unified_diff(
+            &before,
+            &format!("{}:{}", short_rev(&step.commit), path),
+            &step.changes,
+        ));
+        output.push('\n');
+    }
+
+    output
+}
+
+pub fn format_conflict_prediction(files: &[FileConflicts]) -> String {
+    if files.is_empty() {
+        return format!("{}\n", text(Message::NoConflictsExpected, []));
+    }
+
+    let mut output = String::new();
+    for file in files {
+        output.push_str(&format!("{}: {} conflicting hunk(s)\n", file.path, file.conflicts.len()));
+        for conflict in &file.conflicts {
+            output.push_str(&format!(
+                "  base -{} (ours) overlaps base -{} (theirs)\n",
+                format_range(conflict.ours.original_start, conflict.ours.original_end),
+                format_range(conflict.theirs.original_start, conflict.theirs.original_end)
+            ));
+        }
+    }
+    output
+}
+
+/// `git diff --stat`-style overview of the working tree: status, path, churn and a bar per
+/// file, then the totals
+pub fn format_worktree_stat(changes: &[WorktreeChange]) -> String {
+    if changes.is_empty() {
+        return format!("{}\n", text(Message::WorktreeClean, []));
+    }
+
+    let counts: Vec<(usize, usize)> = changes
+        .iter()
+        .map(|change| {
+            let added = change.changes.iter().map(|c| c.modified_end - c.modified_start).sum();
+            let deleted = change.changes.iter().map(|c| c.original_end - c.original_start).sum();
+            (added, deleted)
+        })
+        .collect();
+    let name_width = changes.iter().map(|c| c.path.len()).max().unwrap_or(0);
+    let largest = counts.iter().map(|(a, d)| a + d).max().unwrap_or(0);
+    let number_width = largest.to_string().len().max(3);
+    // Bars are scaled to fit 50 columns once any file exceeds that
+    let scale = |n: usize| if largest <= 50 || n == 0 { n } else { (n * 50 / largest).max(1) };
+
+    let mut output = String::new();
+    for (change, (added, deleted)) in changes.iter().zip(&counts) {
+        let churn = if change.submodule {
+            format!("{:>number_width$}", "Sub")
+        } else if change.binary {
+            format!("{:>number_width$}", "Bin")
+        } else {
+            format!(
+                "{:>number_width$} {}{}",
+                added + deleted,
+                "+".repeat(scale(*added)),
+                "-".repeat(scale(*deleted))
+            )
+        };
+        output.push_str(&format!("{} {:<name_width$} | {}\n", change.status, change.path, churn));
+    }
+
+    let (added, deleted) = counts.iter().fold((0, 0), |(a, d), (x, y)| (a + x, d + y));
+    output.push_str(&(text(Message::StatTotals, [&changes.len(), &added, &deleted, &(added + deleted)]) + "\n"));
+    output
+}
+
+/// The diff of one working-tree file against HEAD, with `context_lines` lines of context
+pub fn format_worktree_file(change: &WorktreeChange, context_lines: usize) -> String {
+    let old = if change.status == 'A' || change.status == '?' {
+        "/dev/null".to_string()
+    } else {
+        format!("a/{}", change.path)
+    };
+    let new = if change.status == 'D' { "/dev/null".to_string() } else { format!("b/{}", change.path) };
+
+    let mut output = format!("diff --git a/{} b/{}\n", change.path, change.path);
+    if change.submodule {
+        output.push_str(&format!("{}\n", text(Message::SubmoduleChanged, [&change.path])));
+        return output;
+    }
+    if change.binary {
+        output.push_str(&format!("{}\n", text(Message::BinaryFilesDiffer, [&old, &new])));
+        return output;
+    }
+    output.push_str(&format!("--- {}\n+++ {}\n", old, new));
+    output.push_str(&unified_hunks(&change.original, &change.modified, &change.changes, context_lines));
+    output
+}
+
+/// Who last changed the lines each hunk replaces, as reported by `blame_hunks`
+pub fn format_hunk_authors(blamed: &[HunkAuthors]) -> String {
+    if blamed.is_empty() {
+        return String::new();
+    }
+    let mut output = format!("{}\n", text(Message::LastChangedBy, []));
+    for hunk in blamed {
+        let authors: Vec<String> = hunk.authors.iter().map(|(name, lines)| format!("{} ({})", name, lines)).collect();
+        output.push_str(&format!(
+            "  lines {}-{}: {}\n",
+            hunk.original_start + 1,
+            hunk.original_end,
+            authors.join(", ")
+        ));
+    }
+    output
+}
+
+fn short_rev(rev: &str) -> &str {
+    &rev[..rev.len().min(10)]
+}
+
 fn format_range(start: usize, end: usize) -> String {
     let count = end - start;
     if count == 0 {
@@ -59,6 +400,15 @@ fn format_range(start: usize, end: usize) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_numbered_unified_hunks() {
+        let (original, modified) = (lines("a\nb\nc"), lines("a\nB\nc\nd"));
+        let changes = crate::diff_core::compute_diff(&original, &modified, crate::diff_core::DiffOptions::default());
+        let text = numbered_unified_hunks(&original, &modified, &changes, 1, &LineNumbering::default());
+        assert_eq!(text, "@@ -1,3 +1,4 @@\n1:1  a\n2:  -b\n :2 +B\n3:3  c\n :4 +d\n");
+    }
 
     #[test]
     fn test_format_range() {
@@ -66,4 +416,44 @@ mod tests {
         assert_eq!(format_range(0, 1), "1");
         assert_eq!(format_range(5, 10), "6,5");
     }
+
+    #[test]
+    fn test_format_json() {
+        let changes = vec![LineChange {
+            original_start: 0,
+            original_end: 0,
+            modified_start: 0,
+            modified_end: 1,
+            change_type: ChangeType::Added,
+            char_changes: None,
+            generated: false,
+            tags: Vec::new(),
+            id: "0badc0de".to_string(),
+        }];
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_diff(OutputFormat::Json, "a", "b", &changes)).unwrap();
+        assert_eq!(json["hunks"][0]["id"], "0badc0de");
+        assert_eq!(json["hunks"][0]["change_type"], "Added");
+    }
+
+    #[test]
+    fn test_format_stat() {
+        let changes = vec![LineChange {
+            original_start: 1,
+            original_end: 3,
+            modified_start: 1,
+            modified_end: 2,
+            change_type: ChangeType::Modified,
+            char_changes: None,
+            generated: false,
+            tags: Vec::new(),
+            id: String::new(),
+        }];
+
+        assert_eq!(
+            format_diff(OutputFormat::Stat, "a", "b", &changes),
+            "a -> b: 1 hunk(s), 1 insertion(s)(+), 2 deletion(s)(-)\n"
+        );
+    }
 }
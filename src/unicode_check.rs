@@ -0,0 +1,160 @@
+use serde::Serialize;
+use crate::diff_core::LineChange;
+
+/// Why a character in changed code deserves a reviewer's attention
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum SuspiciousKind {
+    /// Reorders how the surrounding text is displayed (trojan source)
+    Bidi,
+    /// Renders as nothing at all
+    Invisible,
+    /// Looks like an ASCII letter inside an otherwise ASCII word
+    Confusable,
+}
+
+/// A suspicious character on an added or modified line
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SuspiciousChar {
+    /// 1-based line in the modified file
+    pub line: usize,
+    /// 1-based character column
+    pub column: usize,
+    pub character: char,
+    pub kind: SuspiciousKind,
+    pub description: String,
+}
+
+/// Bidi controls, as used by CVE-2021-42574
+const BIDI_CONTROLS: &[(char, &str)] = &[
+    ('\u{061C}', "ARABIC LETTER MARK"),
+    ('\u{200E}', "LEFT-TO-RIGHT MARK"),
+    ('\u{200F}', "RIGHT-TO-LEFT MARK"),
+    ('\u{202A}', "LEFT-TO-RIGHT EMBEDDING"),
+    ('\u{202B}', "RIGHT-TO-LEFT EMBEDDING"),
+    ('\u{202C}', "POP DIRECTIONAL FORMATTING"),
+    ('\u{202D}', "LEFT-TO-RIGHT OVERRIDE"),
+    ('\u{202E}', "RIGHT-TO-LEFT OVERRIDE"),
+    ('\u{2066}', "LEFT-TO-RIGHT ISOLATE"),
+    ('\u{2067}', "RIGHT-TO-LEFT ISOLATE"),
+    ('\u{2068}', "FIRST STRONG ISOLATE"),
+    ('\u{2069}', "POP DIRECTIONAL ISOLATE"),
+];
+
+const INVISIBLE: &[(char, &str)] = &[
+    ('\u{00AD}', "SOFT HYPHEN"),
+    ('\u{180E}', "MONGOLIAN VOWEL SEPARATOR"),
+    ('\u{200B}', "ZERO WIDTH SPACE"),
+    ('\u{200C}', "ZERO WIDTH NON-JOINER"),
+    ('\u{200D}', "ZERO WIDTH JOINER"),
+    ('\u{2060}', "WORD JOINER"),
+    ('\u{FEFF}', "ZERO WIDTH NO-BREAK SPACE"),
+];
+
+/// Cyrillic and Greek letters that render like ASCII ones
+const CONFUSABLES: &[(char, char)] = &[
+    ('а', 'a'), ('в', 'B'), ('е', 'e'), ('к', 'k'), ('м', 'M'), ('н', 'H'), ('о', 'o'), ('р', 'p'),
+    ('с', 'c'), ('т', 'T'), ('у', 'y'), ('х', 'x'), ('і', 'i'), ('ј', 'j'), ('ѕ', 's'), ('А', 'A'),
+    ('В', 'B'), ('Е', 'E'), ('К', 'K'), ('М', 'M'), ('Н', 'H'), ('О', 'O'), ('Р', 'P'), ('С', 'C'),
+    ('Т', 'T'), ('Х', 'X'), ('α', 'a'), ('ο', 'o'), ('ν', 'v'), ('ρ', 'p'), ('Α', 'A'), ('Β', 'B'),
+    ('Ε', 'E'), ('Η', 'H'), ('Ι', 'I'), ('Κ', 'K'), ('Μ', 'M'), ('Ν', 'N'), ('Ο', 'O'), ('Ρ', 'P'),
+    ('Τ', 'T'), ('Χ', 'X'), ('Ζ', 'Z'),
+];
+
+/// Suspicious characters on the modified side of `changes`. Confusables are only reported inside
+/// words that also contain ASCII letters, so genuinely Cyrillic or Greek text is left alone.
+pub fn scan_changes(modified: &[String], changes: &[LineChange]) -> Vec<SuspiciousChar> {
+    changes
+        .iter()
+        .flat_map(|change| change.modified_start..change.modified_end)
+        .flat_map(|index| scan_line(&modified[index], index + 1))
+        .collect()
+}
+
+fn scan_line(line: &str, line_number: usize) -> Vec<SuspiciousChar> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut found = Vec::new();
+    for (index, &c) in chars.iter().enumerate() {
+        let (kind, description) = if let Some((_, name)) = BIDI_CONTROLS.iter().find(|(b, _)| *b == c) {
+            (SuspiciousKind::Bidi, format!("U+{:04X} {}", c as u32, name))
+        } else if let Some((_, name)) = INVISIBLE.iter().find(|(z, _)| *z == c) {
+            // A byte order mark is expected at the very start of a file
+            if c == '\u{FEFF}' && line_number == 1 && index == 0 {
+                continue;
+            }
+            (SuspiciousKind::Invisible, format!("U+{:04X} {}", c as u32, name))
+        } else if let Some((_, ascii)) = CONFUSABLES.iter().find(|(lookalike, _)| *lookalike == c) {
+            if !word_around(&chars, index).any(|w| w.is_ascii_alphabetic()) {
+                continue;
+            }
+            (SuspiciousKind::Confusable, format!("U+{:04X} looks like '{}'", c as u32, ascii))
+        } else {
+            continue;
+        };
+        found.push(SuspiciousChar {
+            line: line_number,
+            column: index + 1,
+            character: c,
+            kind,
+            description,
+        });
+    }
+    found
+}
+
+/// The alphanumeric run containing `chars[index]`
+fn word_around(chars: &[char], index: usize) -> impl Iterator<Item = &char> + Clone {
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+    let start = chars[..index].iter().rposition(|c| !is_word(c)).map_or(0, |at| at + 1);
+    let end = chars[index..].iter().position(|c| !is_word(c)).map_or(chars.len(), |at| index + at);
+    chars[start..end].iter()
+}
+
+/// One warning line per suspicious character, or nothing when there are none
+pub fn format_unicode_warnings(found: &[SuspiciousChar]) -> String {
+    if found.is_empty() {
+        return String::new();
+    }
+    let mut output = format!("⚠ {} suspicious character(s) in changed lines\n", found.len());
+    for item in found {
+        let kind = match item.kind {
+            SuspiciousKind::Bidi => "bidi control",
+            SuspiciousKind::Invisible => "invisible",
+            SuspiciousKind::Confusable => "confusable",
+        };
+        output.push_str(&format!("⚠ line {}, column {}: {} ({})\n", item.line, item.column, item.description, kind));
+    }
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_scan_changes() {
+        let original = lines("let access = user;\nlet x = 1;");
+        let modified = lines("let access = \"user\u{202E} \u{2066}// admin\u{2069}\u{2066}\";\nlet x = 1;\nif аdmin {}\nlet привет = 1;\nzero\u{200B}width");
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let found = scan_changes(&modified, &changes);
+        let summary: Vec<(usize, usize, SuspiciousKind)> = found.iter().map(|f| (f.line, f.column, f.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, 19, SuspiciousKind::Bidi),
+                (1, 21, SuspiciousKind::Bidi),
+                (1, 30, SuspiciousKind::Bidi),
+                (1, 31, SuspiciousKind::Bidi),
+                (3, 4, SuspiciousKind::Confusable),
+                (5, 5, SuspiciousKind::Invisible),
+            ]
+        );
+        let report = format_unicode_warnings(&found);
+        assert!(report.contains("⚠ line 1, column 19: U+202E RIGHT-TO-LEFT OVERRIDE (bidi control)\n"));
+        assert!(report.contains("U+0430 looks like 'a' (confusable)"));
+        let bom = lines("\u{FEFF}fn main() {}");
+        assert!(scan_changes(&bom, &compute_diff(&[], &bom, DiffOptions::default())).is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use crate::diff_core::DiffOptions;
+
+/// Result of comparing two files as multisets of lines
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnorderedDiff {
+    /// Lines present more often in the modified file, one entry per extra occurrence
+    pub added: Vec<String>,
+    /// Lines present more often in the original file, one entry per missing occurrence
+    pub removed: Vec<String>,
+}
+
+/// How often one distinct line occurs in each file
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineFrequency {
+    pub line: String,
+    pub original_count: usize,
+    pub modified_count: usize,
+}
+
+impl LineFrequency {
+    pub fn delta(&self) -> isize {
+        self.modified_count as isize - self.original_count as isize
+    }
+}
+
+/// Compare two files ignoring line order; only occurrence counts matter
+pub fn compare_unordered(
+    original_lines: &[String],
+    modified_lines: &[String],
+    options: &DiffOptions,
+) -> UnorderedDiff {
+    let mut counts: BTreeMap<String, (isize, &String)> = BTreeMap::new();
+
+    for line in original_lines {
+        counts.entry(line_key(line, options)).or_insert((0, line)).0 -= 1;
+    }
+    for line in modified_lines {
+        counts.entry(line_key(line, options)).or_insert((0, line)).0 += 1;
+    }
+
+    let mut diff = UnorderedDiff::default();
+    for (count, line) in counts.into_values() {
+        let target = if count > 0 { &mut diff.added } else { &mut diff.removed };
+        target.extend(std::iter::repeat_n(line.clone(), count.unsigned_abs()));
+    }
+    diff
+}
+
+/// Lines whose occurrence counts differ, largest change first
+pub fn frequency_report(
+    original_lines: &[String],
+    modified_lines: &[String],
+    options: &DiffOptions,
+) -> Vec<LineFrequency> {
+    let mut counts: BTreeMap<String, LineFrequency> = BTreeMap::new();
+
+    for line in original_lines {
+        counts
+            .entry(line_key(line, options))
+            .or_insert_with(|| new_frequency(line))
+            .original_count += 1;
+    }
+    for line in modified_lines {
+        counts
+            .entry(line_key(line, options))
+            .or_insert_with(|| new_frequency(line))
+            .modified_count += 1;
+    }
+
+    let mut report: Vec<LineFrequency> = counts
+        .into_values()
+        .filter(|frequency| frequency.delta() != 0)
+        .collect();
+    // Stable sort keeps ties in key order
+    report.sort_by_key(|frequency| std::cmp::Reverse(frequency.delta().unsigned_abs()));
+    report
+}
+
+fn new_frequency(line: &str) -> LineFrequency {
+    LineFrequency {
+        line: line.to_string(),
+        original_count: 0,
+        modified_count: 0,
+    }
+}
+
+pub fn format_frequency_report(file1_path: &str, file2_path: &str, report: &[LineFrequency]) -> String {
+    let mut output = format!("--- {}\n+++ {}\n(occurrence counts)\n", file1_path, file2_path);
+
+    if report.is_empty() {
+        output.push_str("\nEvery line occurs equally often in both files\n");
+        return output;
+    }
+
+    output.push('\n');
+    for frequency in report {
+        output.push_str(&format!(
+            "{:>+6} ({} -> {})  {}\n",
+            frequency.delta(),
+            frequency.original_count,
+            frequency.modified_count,
+            frequency.line
+        ));
+    }
+    output
+}
+
+fn line_key(line: &str, options: &DiffOptions) -> String {
+    let mut key = if options.ignore_whitespace {
+        line.trim().to_string()
+    } else {
+        line.to_string()
+    };
+    if options.ignore_case {
+        key = key.to_lowercase();
+    }
+    key
+}
+
+pub fn format_unordered_diff(file1_path: &str, file2_path: &str, diff: &UnorderedDiff) -> String {
+    let mut output = format!("--- {}\n+++ {}\n(compared as sorted lines)\n", file1_path, file2_path);
+
+    if diff.added.is_empty() && diff.removed.is_empty() {
+        output.push_str("\nFiles contain the same lines\n");
+        return output;
+    }
+
+    output.push('\n');
+    for line in &diff.removed {
+        output.push_str(&format!("-{}\n", line));
+    }
+    for line in &diff.added {
+        output.push_str(&format!("+{}\n", line));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_order_is_ignored() {
+        let diff = compare_unordered(&lines("b\na\nc"), &lines("c\nb\na"), &DiffOptions::default());
+        assert_eq!(diff, UnorderedDiff::default());
+    }
+
+    #[test]
+    fn test_duplicates_are_counted() {
+        let diff = compare_unordered(
+            &lines("PATH=/bin\nHOME=/root\nHOME=/root"),
+            &lines("HOME=/root\nPATH=/usr/bin\nLANG=C"),
+            &DiffOptions::default(),
+        );
+        assert_eq!(diff.removed, lines("HOME=/root\nPATH=/bin"));
+        assert_eq!(diff.added, lines("LANG=C\nPATH=/usr/bin"));
+    }
+
+    #[test]
+    fn test_frequency_report() {
+        let report = frequency_report(
+            &lines("ok\nok\nerror\nwarn"),
+            &lines("warn\nok\nerror\nerror\nerror"),
+            &DiffOptions::default(),
+        );
+        assert_eq!(report.len(), 2);
+        assert_eq!((report[0].line.as_str(), report[0].delta()), ("error", 2));
+        assert_eq!((report[1].line.as_str(), report[1].delta()), ("ok", -1));
+
+        let text = format_frequency_report("a", "b", &report);
+        assert!(text.contains("    +2 (1 -> 3)  error"));
+    }
+}
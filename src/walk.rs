@@ -0,0 +1,61 @@
+use std::fs::{self, DirEntry};
+use std::path::Path;
+
+/// What `walk_dir` does after visiting an entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WalkStep {
+    /// Go on, into the entry too if it is a directory
+    Descend,
+    /// Go on, but leave this directory's contents out
+    Skip,
+    /// End the walk
+    Stop,
+}
+
+/// Call `visit` with the `/`-separated path below `root` of every entry under `root/prefix`,
+/// and whether it is a directory. Directories are kept on an explicit stack rather than recursed
+/// into, so no tree is deep enough to overflow a WASM stack, and symlinked directories count as
+/// files, so a link cycle cannot hang the walk. Unreadable directories are passed over.
+pub fn walk_dir(root: &Path, prefix: &str, mut visit: impl FnMut(String, &DirEntry, bool) -> WalkStep) {
+    let mut pending = vec![prefix.to_string()];
+    while let Some(prefix) = pending.pop() {
+        let Ok(read_dir) = fs::read_dir(root.join(&prefix)) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            match visit(path.clone(), &entry, is_dir) {
+                WalkStep::Descend if is_dir => pending.push(path),
+                WalkStep::Stop => return,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+
+    #[test]
+    fn test_walk_dir() {
+        let root = TempDir::new("walk");
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::create_dir_all(root.join("skipped")).unwrap();
+        fs::write(root.join("a/b/deep.txt"), "").unwrap();
+        fs::write(root.join("skipped/hidden.txt"), "").unwrap();
+
+        let mut visited = Vec::new();
+        walk_dir(&root, "", |path, _, is_dir| {
+            let step = if path == "skipped" { WalkStep::Skip } else { WalkStep::Descend };
+            visited.push((path, is_dir));
+            step
+        });
+        visited.sort();
+        let expected = [("a", true), ("a/b", true), ("a/b/deep.txt", false), ("skipped", true)];
+        assert_eq!(visited, expected.map(|(path, is_dir)| (path.to_string(), is_dir)));
+    }
+}
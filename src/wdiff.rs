@@ -0,0 +1,76 @@
+use crate::diff_core::DiffOptions;
+use crate::token_diff::{compute_token_diff_with, Token};
+
+/// GNU wdiff's output: the modified text in full, with deleted words shown as `[-…-]` and
+/// inserted words as `{+…+}` where they occur. Words are runs of non-whitespace compared across
+/// line breaks, so reflowed prose only marks the words that changed. `options` supplies the time
+/// and memory budgets.
+pub fn format_wdiff(original: &[String], modified: &[String], options: &DiffOptions) -> String {
+    let (old_text, new_text) = (join(original), join(modified));
+    let changes = compute_token_diff_with(&words(&old_text), &words(&new_text), options);
+
+    let mut output = String::new();
+    let mut position = 0;
+    for change in &changes {
+        output.push_str(&new_text[position..change.modified_span.start]);
+        let (removed, added) = (&old_text[change.original_span.clone()], &new_text[change.modified_span.clone()]);
+        if !removed.is_empty() {
+            // A deleted word keeps a space either side, as it had in the original
+            if !output.is_empty() && !output.ends_with(char::is_whitespace) {
+                output.push(' ');
+            }
+            output.push_str(&format!("[-{}-]", removed));
+            let next = new_text[change.modified_span.start..].chars().next();
+            if !added.is_empty() || next.is_some_and(|c| !c.is_whitespace()) {
+                output.push(' ');
+            }
+        }
+        if !added.is_empty() {
+            output.push_str(&format!("{{+{}+}}", added));
+        }
+        position = change.modified_span.end;
+    }
+    output.push_str(&new_text[position..]);
+    output
+}
+
+fn join(lines: &[String]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Runs of non-whitespace with their byte spans
+fn words(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                tokens.push(Token::new(&text[s..i], s..i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_format_wdiff() {
+        let original = lines("The quick brown fox\njumps over the dog.");
+        let modified = lines("The slow brown fox\njumps over the lazy dog.");
+        assert_eq!(
+            format_wdiff(&original, &modified, &DiffOptions::default()),
+            "The [-quick-] {+slow+} brown fox\njumps over the {+lazy+} dog.\n"
+        );
+
+        let text = format_wdiff(&lines("keep this word and that"), &lines("keep word and"), &DiffOptions::default());
+        assert_eq!(text, "keep [-this-] word and [-that-]\n");
+        assert_eq!(format_wdiff(&original, &original, &DiffOptions::default()), "The quick brown fox\njumps over the dog.\n");
+    }
+}
@@ -0,0 +1,153 @@
+use std::ops::Range;
+use serde::Serialize;
+use crate::align::align_lines;
+use crate::diff_core::{ChangeType, LineChange};
+
+/// Columns a tab advances to the next multiple of, when the renderer does not say
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// One display row of a wrapped line
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WrapSegment {
+    /// Character range of the line shown on this row
+    pub range: Range<usize>,
+    /// Whether the row continues the line above rather than starting it
+    pub continuation: bool,
+}
+
+/// One side of a split-view row: a logical line and the display rows it wraps to
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WrappedLine {
+    /// Index into its file
+    pub line: usize,
+    pub segments: Vec<WrapSegment>,
+}
+
+/// Lines shown side by side in a split view. The shorter side is padded with `height` minus its
+/// own row count filler rows, so the next pair starts level on both sides.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SplitRow {
+    pub original: Option<WrappedLine>,
+    pub modified: Option<WrappedLine>,
+    /// Display rows the pair takes, the larger of the two sides
+    pub height: usize,
+}
+
+/// Where `line` breaks when wrapped at `width` columns: tabs advance to the next multiple of
+/// `tab_width`, and East Asian wide characters take two columns. A line is never split inside a
+/// character, so a wide character on a one-column display still gets a row of its own.
+pub fn wrap_line(line: &str, width: usize, tab_width: usize) -> Vec<WrapSegment> {
+    let width = width.max(1);
+    let mut segments = Vec::new();
+    let (mut start, mut column) = (0, 0);
+    for (i, c) in line.chars().enumerate() {
+        let mut advance = columns(c, column, tab_width);
+        if column > 0 && column + advance > width {
+            segments.push(WrapSegment { range: start..i, continuation: !segments.is_empty() });
+            start = i;
+            column = 0;
+            advance = columns(c, column, tab_width);
+        }
+        column += advance;
+    }
+    segments.push(WrapSegment { range: start..line.chars().count(), continuation: !segments.is_empty() });
+    segments
+}
+
+/// Columns `c` takes when it starts at `column`
+fn columns(c: char, column: usize, tab_width: usize) -> usize {
+    match c {
+        '\t' => tab_width.max(1) - column % tab_width.max(1),
+        c if is_wide(c) => 2,
+        _ => 1,
+    }
+}
+
+fn is_wide(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F | 0x2E80..=0x303E | 0x3041..=0x33FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6 | 0x1F300..=0x1F64F | 0x1F900..=0x1F9FF | 0x20000..=0x3FFFD)
+}
+
+/// Every line of both files as split-view rows, each side wrapped at `width` columns. Unchanged
+/// lines pair with their copies; a modified hunk's lines pair by similarity, as in the inline view.
+pub fn split_rows(
+    original: &[String],
+    modified: &[String],
+    changes: &[LineChange],
+    width: usize,
+    tab_width: usize,
+) -> Vec<SplitRow> {
+    let side = |lines: &[String], index: Option<usize>| {
+        index.map(|line| WrappedLine { line, segments: wrap_line(&lines[line], width, tab_width) })
+    };
+    let mut rows = Vec::new();
+    let mut push = |i: Option<usize>, j: Option<usize>| {
+        let (original, modified) = (side(original, i), side(modified, j));
+        let height = [&original, &modified].iter().flat_map(|s| s.as_ref()).map(|s| s.segments.len()).max().unwrap_or(0);
+        rows.push(SplitRow { original, modified, height });
+    };
+
+    let (mut i, mut j) = (0, 0);
+    for change in changes {
+        while i < change.original_start {
+            push(Some(i), Some(j));
+            i += 1;
+            j += 1;
+        }
+        let (old, new) = (change.original_start..change.original_end, change.modified_start..change.modified_end);
+        if change.change_type == ChangeType::Modified {
+            for (a, b) in align_lines(&original[old.clone()], &modified[new.clone()]) {
+                push(a.map(|a| old.start + a), b.map(|b| new.start + b));
+            }
+        } else {
+            old.clone().for_each(|a| push(Some(a), None));
+            new.clone().for_each(|b| push(None, Some(b)));
+        }
+        (i, j) = (old.end, new.end);
+    }
+    while i < original.len() && j < modified.len() {
+        push(Some(i), Some(j));
+        i += 1;
+        j += 1;
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_core::{compute_diff, DiffOptions};
+    use crate::test_helpers::lines;
+
+    #[test]
+    fn test_wrap_line() {
+        let ranges = |line: &str, width: usize| -> Vec<Range<usize>> {
+            wrap_line(line, width, DEFAULT_TAB_WIDTH).into_iter().map(|segment| segment.range).collect()
+        };
+        assert_eq!(ranges("abcdefg", 3), vec![0..3, 3..6, 6..7]);
+        assert_eq!(ranges("", 3), vec![0..0]);
+        assert_eq!(ranges("a\tb", 4), vec![0..2, 2..3]);
+        assert_eq!(ranges("日本語", 4), vec![0..2, 2..3]);
+        assert!(wrap_line("abcd", 2, DEFAULT_TAB_WIDTH)[1].continuation);
+    }
+
+    #[test]
+    fn test_split_rows() {
+        let original = lines("same\nshorter line\ngone");
+        let modified = lines("same\nshorter line, wrapped\nextra\nnew");
+        let changes = compute_diff(&original, &modified, DiffOptions::default());
+        let rows = split_rows(&original, &modified, &changes, 10, DEFAULT_TAB_WIDTH);
+
+        let shape: Vec<(Option<usize>, Option<usize>, usize)> = rows
+            .iter()
+            .map(|row| (row.original.as_ref().map(|s| s.line), row.modified.as_ref().map(|s| s.line), row.height))
+            .collect();
+        assert_eq!(shape[0], (Some(0), Some(0), 1));
+        assert_eq!(shape[1], (Some(1), Some(1), 3));
+        assert_eq!(rows[1].original.as_ref().unwrap().segments.len(), 2);
+        assert_eq!(shape.iter().filter(|(a, _, _)| a.is_some()).count(), 3);
+        assert_eq!(shape.iter().filter(|(_, b, _)| b.is_some()).count(), 4);
+    }
+}
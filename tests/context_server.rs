@@ -0,0 +1,50 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use serde_json::{json, Value};
+
+/// Drive the context server binary the way an MCP client does: initialize, list the tools, then
+/// call one on real files
+#[test]
+fn test_context_server_session() {
+    let dir = std::env::temp_dir().join(format!("zed-diff-mcp-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (a, b) = (dir.join("a.txt"), dir.join("b.txt"));
+    fs::write(&a, "one\ntwo\n").unwrap();
+    fs::write(&b, "one\n2\n").unwrap();
+
+    let mut server = Command::new(env!("CARGO_BIN_EXE_zed-diff-mcp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut input = server.stdin.take().unwrap();
+    let mut output = BufReader::new(server.stdout.take().unwrap());
+    let mut request = move |message: Value| -> Option<Value> {
+        writeln!(input, "{}", message).unwrap();
+        message.get("id")?;
+        let mut line = String::new();
+        output.read_line(&mut line).unwrap();
+        Some(serde_json::from_str(&line).unwrap())
+    };
+
+    let init = request(json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} })).unwrap();
+    assert!(init["result"]["capabilities"]["tools"].is_object());
+    assert!(request(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).is_none());
+
+    let list = request(json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" })).unwrap();
+    let names: Vec<&str> = list["result"]["tools"].as_array().unwrap().iter().filter_map(|t| t["name"].as_str()).collect();
+    assert_eq!(names, vec!["compare_files", "compare_with_revision", "diffstat"]);
+
+    let arguments = json!({ "original": a.to_string_lossy(), "modified": b.to_string_lossy() });
+    let call = json!({ "jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": { "name": "diffstat", "arguments": arguments } });
+    let result = request(call).unwrap();
+    assert_eq!(result["result"]["isError"], false);
+    let stats: Value = serde_json::from_str(result["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(stats["stats"], json!({ "hunks": 1, "insertions": 1, "deletions": 1 }));
+
+    // Closing stdin ends the session
+    drop(request);
+    assert!(server.wait().unwrap().success());
+    fs::remove_dir_all(&dir).unwrap();
+}